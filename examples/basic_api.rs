@@ -1,4 +1,4 @@
-use soundlooper::audio::{AudioConfig, LayerCommand, LooperEngine};
+use soundlooper::audio::{AudioConfig, LayerCommand, LooperEngine, TransportTime};
 use std::thread;
 use std::time::Duration;
 
@@ -39,6 +39,23 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("Stopping all layers...");
     looper.send_command(LayerCommand::StopAll)?;
 
+    // Schedule a command at an exact transport position
+    println!("Scheduling mute of layer 0 at beat 2...");
+    looper.send_command(LayerCommand::Scheduled {
+        at: TransportTime::Beat(2),
+        cmd: Box::new(LayerCommand::Mute(0)),
+    })?;
+
+    // Drive the engine by hand (the audio stream normally does this)
+    let input = vec![0.0; 512];
+    let mut output = vec![0.0; 512];
+    for _ in 0..100 {
+        looper.process_audio(&input, &mut output);
+    }
+    if let Some(layer) = looper.get_layer(0) {
+        println!("Layer 0 muted: {}", layer.lock().unwrap().is_muted);
+    }
+
     // Clear the layer
     println!("Clearing layer 0...");
     looper.send_command(LayerCommand::Clear(0))?;
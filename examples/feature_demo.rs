@@ -8,9 +8,9 @@
 // - Metronome
 
 use anyhow::Result;
-use crossbeam::channel;
 use soundlooper::audio::{
-    AudioConfig, AudioEvent, AudioStream, LayerCommand, LooperEngine, import_wav,
+    AudioConfig, AudioEvent, AudioStream, ChannelStats, ExportNormalization, ImportMode,
+    LayerCommand, LooperEngine, command_channel, event_channel, import_wav,
 };
 use std::sync::Arc;
 use std::thread;
@@ -51,18 +51,20 @@ fn main() -> Result<()> {
 
     // Step 3: Set up communication channels
     println!("3. Setting up communication channels...");
-    let (command_sender, command_receiver) = channel::unbounded::<LayerCommand>();
-    let (event_sender, event_receiver) = channel::unbounded::<AudioEvent>();
+    let stats = ChannelStats::new();
+    let (command_sender, command_receiver) = command_channel(&stats);
+    let (event_sender, event_receiver) = event_channel(&stats);
 
     // Step 4: Start audio streams
     println!("4. Starting audio streams...");
     let looper_clone = Arc::clone(&looper_engine);
-    let (_input_stream, _output_stream) = audio_stream.start_audio_looper(
+    let streams = audio_stream.build_audio_looper(
         looper_clone,
         command_receiver,
         event_sender.clone(),
         false,
     )?;
+    streams.play()?;
     println!("   Audio streams active\n");
 
     // Step 5: Event monitoring thread
@@ -77,13 +79,13 @@ fn main() -> Result<()> {
                 Ok(event) => {
                     last_event = Instant::now();
                     match event {
-                        AudioEvent::LayerRecording(id) => {
+                        AudioEvent::LayerRecording(id, _) => {
                             println!("   → Layer {} started recording", id + 1);
                         }
                         AudioEvent::LayerStopped(id) => {
                             println!("   → Layer {} stopped", id + 1);
                         }
-                        AudioEvent::LayerPlaying(id) => {
+                        AudioEvent::LayerPlaying(id, _) => {
                             println!("   → Layer {} playing", id + 1);
                         }
                         AudioEvent::BpmChanged(bpm) => {
@@ -121,6 +123,7 @@ fn main() -> Result<()> {
         command_sender.send(LayerCommand::ImportWav(
             0,
             "assets/metronome.wav".to_string(),
+            ImportMode::Replace,
         ))?;
         thread::sleep(Duration::from_millis(500));
         println!("   Layer 1 now contains metronome sample\n");
@@ -183,7 +186,10 @@ fn main() -> Result<()> {
 
     // Demo 12: Export composition
     println!("Demo 12: Exporting composition");
-    command_sender.send(LayerCommand::ExportWav("demo_output.wav".to_string()))?;
+    command_sender.send(LayerCommand::ExportWav(
+        "demo_output.wav".to_string(),
+        ExportNormalization::Peak,
+    ))?;
     thread::sleep(Duration::from_millis(500));
 
     // Demo 13: Count-in mode
@@ -220,8 +226,7 @@ fn main() -> Result<()> {
     thread::sleep(Duration::from_millis(500));
 
     // Drop streams
-    drop(_input_stream);
-    drop(_output_stream);
+    drop(streams);
 
     // Wait for event monitor
     drop(command_sender);
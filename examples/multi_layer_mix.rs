@@ -6,8 +6,10 @@
 // - Export the final mix
 
 use anyhow::Result;
-use crossbeam::channel;
-use soundlooper::audio::{AudioConfig, AudioEvent, AudioStream, LayerCommand, LooperEngine};
+use soundlooper::audio::{
+    AudioConfig, AudioEvent, AudioStream, ChannelStats, ExportNormalization, LayerCommand,
+    LooperEngine, command_channel, event_channel,
+};
 use std::io::{self, Write};
 use std::sync::Arc;
 use std::thread;
@@ -30,12 +32,14 @@ fn main() -> Result<()> {
     let looper = Arc::new(LooperEngine::new(runtime_config.clone()));
 
     // Channels
-    let (cmd_tx, cmd_rx) = channel::unbounded();
-    let (evt_tx, evt_rx) = channel::unbounded();
+    let stats = ChannelStats::new();
+    let (cmd_tx, cmd_rx) = command_channel(&stats);
+    let (evt_tx, evt_rx) = event_channel(&stats);
 
     // Start audio
-    let (_input, _output) =
-        audio_stream.start_audio_looper(Arc::clone(&looper), cmd_rx, evt_tx.clone(), false)?;
+    let streams =
+        audio_stream.build_audio_looper(Arc::clone(&looper), cmd_rx, evt_tx.clone(), false)?;
+    streams.play()?;
 
     // Event monitor
     let event_thread = thread::spawn(move || {
@@ -48,10 +52,10 @@ fn main() -> Result<()> {
                 Ok(event) => {
                     last_event = Instant::now();
                     match event {
-                        AudioEvent::LayerRecording(id) => {
+                        AudioEvent::LayerRecording(id, _) => {
                             println!("  🔴 Recording Layer {}...", id + 1);
                         }
-                        AudioEvent::LayerPlaying(id) => {
+                        AudioEvent::LayerPlaying(id, _) => {
                             println!("  ▶️  Layer {} playing", id + 1);
                         }
                         AudioEvent::LayerMuted(id) => {
@@ -171,7 +175,11 @@ fn main() -> Result<()> {
     println!("\n=== EXPORT PHASE ===\n");
 
     println!("Exporting final mix to 'my_composition.wav'...");
-    cmd_tx.send(LayerCommand::ExportWav("my_composition.wav".to_string()))?;
+    // Streaming-style loudness rather than peak normalization
+    cmd_tx.send(LayerCommand::ExportWav(
+        "my_composition.wav".to_string(),
+        ExportNormalization::Loudness(-14.0),
+    ))?;
     thread::sleep(Duration::from_millis(500));
 
     println!("\n=== Composition Complete! ===\n");
@@ -190,8 +198,7 @@ fn main() -> Result<()> {
     println!("  • Keep lead elements at ~90-100%\n");
 
     // Cleanup
-    drop(_input);
-    drop(_output);
+    drop(streams);
     drop(cmd_tx);
     drop(evt_tx); // Close the event sender to signal the thread to exit
     let _ = event_thread.join();
@@ -6,8 +6,10 @@
 // 4. Export to WAV
 
 use anyhow::Result;
-use crossbeam::channel;
-use soundlooper::audio::{AudioConfig, AudioEvent, AudioStream, LayerCommand, LooperEngine};
+use soundlooper::audio::{
+    AudioConfig, AudioEvent, AudioStream, ChannelStats, ExportNormalization, LayerCommand,
+    LooperEngine, command_channel, event_channel,
+};
 use std::io::{self, Write};
 use std::sync::Arc;
 use std::thread;
@@ -39,12 +41,14 @@ fn main() -> Result<()> {
     let looper = Arc::new(LooperEngine::new(runtime_config.clone()));
 
     // Set up channels
-    let (cmd_tx, cmd_rx) = channel::unbounded();
-    let (evt_tx, evt_rx) = channel::unbounded();
+    let stats = ChannelStats::new();
+    let (cmd_tx, cmd_rx) = command_channel(&stats);
+    let (evt_tx, evt_rx) = event_channel(&stats);
 
     // Start audio
-    let (_input, _output) =
-        audio_stream.start_audio_looper(Arc::clone(&looper), cmd_rx, evt_tx.clone(), false)?;
+    let streams =
+        audio_stream.build_audio_looper(Arc::clone(&looper), cmd_rx, evt_tx.clone(), false)?;
+    streams.play()?;
 
     println!("Audio streams started.\n");
 
@@ -59,13 +63,13 @@ fn main() -> Result<()> {
                 Ok(event) => {
                     last_event = Instant::now();
                     match event {
-                        AudioEvent::LayerRecording(id) => {
+                        AudioEvent::LayerRecording(id, _) => {
                             println!("🔴 Recording on Layer {}...", id + 1);
                         }
                         AudioEvent::LayerStopped(id) => {
                             println!("⏹️  Layer {} stopped", id + 1);
                         }
-                        AudioEvent::LayerPlaying(id) => {
+                        AudioEvent::LayerPlaying(id, _) => {
                             println!("▶️  Layer {} playing", id + 1);
                         }
                         AudioEvent::WavExported(path) => {
@@ -115,7 +119,10 @@ fn main() -> Result<()> {
 
     // Step 3: Export to WAV
     println!("\n💾 Exporting to 'my_loop.wav'...");
-    cmd_tx.send(LayerCommand::ExportWav("my_loop.wav".to_string()))?;
+    cmd_tx.send(LayerCommand::ExportWav(
+        "my_loop.wav".to_string(),
+        ExportNormalization::Peak,
+    ))?;
     thread::sleep(Duration::from_millis(500));
 
     println!("\n=== Example Complete! ===");
@@ -126,8 +133,7 @@ fn main() -> Result<()> {
     println!("  - Use it in your music production software\n");
 
     // Cleanup
-    drop(_input);
-    drop(_output);
+    drop(streams);
     drop(cmd_tx);
     drop(evt_tx); // Close the event sender to signal the thread to exit
     let _ = event_thread.join();
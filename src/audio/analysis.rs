@@ -0,0 +1,295 @@
+// src/audio/analysis.rs
+// Loudness and coarse spectrum of the master mix, computed off the audio thread,
+// plus the whole-mix measurements used to normalize exports and check imports
+
+use std::collections::VecDeque;
+use std::f32::consts::{PI, SQRT_2};
+use std::fmt;
+
+use super::biquad::Biquad;
+
+pub const ANALYSIS_BANDS: usize = 8;
+
+/// Octave band centres in Hz
+pub const BAND_CENTRES: [f32; ANALYSIS_BANDS] =
+    [63.0, 125.0, 250.0, 500.0, 1000.0, 2000.0, 4000.0, 8000.0];
+
+/// Floor for loudness and band levels (digital silence)
+pub const SILENCE_DB: f32 = -70.0;
+
+// One history column
+const FRAME_SECS: f32 = 0.1;
+// Loudness is averaged over 400 ms, like EBU R128 momentary loudness
+const MOMENTARY_FRAMES: usize = 4;
+// Gating blocks for integrated loudness (400 ms, 75% overlap)
+const GATE_BLOCK_SECS: f32 = 0.4;
+const GATE_BLOCK_HOPS: usize = 4;
+// True peak interpolation: 4x oversampling, 12 taps per phase
+const OVERSAMPLE: usize = 4;
+const INTERPOLATION_HALF_TAPS: isize = 6;
+// Import checks: a flat top is this many samples in a row at full scale, and a file
+// with this many of them was clipped; a mean past DC_OFFSET_LIMIT is a DC offset
+const CLIP_LEVEL: f32 = 0.999;
+const FLAT_TOP_SAMPLES: usize = 3;
+const CLIPPED_FLAT_TOPS: usize = 10;
+const DC_OFFSET_LIMIT: f32 = 0.01; // -40 dBFS
+
+/// Something wrong with an imported file worth telling the user about
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ImportIssue {
+    Silent,
+    Clipped(usize), // flat tops found
+    DcOffset(f32),  // mean level, linear
+}
+
+impl fmt::Display for ImportIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Silent => write!(f, "is silent"),
+            Self::Clipped(flat_tops) => write!(f, "is clipped in {} places", flat_tops),
+            Self::DcOffset(offset) => write!(f, "has a DC offset of {:+.1}%", offset * 100.0),
+        }
+    }
+}
+
+/// One analysis step of the master mix
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnalysisFrame {
+    pub loudness: f32,                // LUFS-ish (K-weighted, mono, no gating)
+    pub bands: [f32; ANALYSIS_BANDS], // dB per octave band
+}
+
+/// Turns blocks of the master mix into `AnalysisFrame`s
+pub struct MixAnalyzer {
+    sample_rate: f32,
+    k_weighting: [Biquad; 2],
+    bands: Vec<Biquad>,
+    recent_power: VecDeque<f64>, // K-weighted mean square of the last frames
+}
+
+impl MixAnalyzer {
+    pub fn new(sample_rate: u32) -> Self {
+        let sample_rate = sample_rate as f32;
+        Self {
+            sample_rate,
+            k_weighting: Biquad::k_weighting(sample_rate),
+            // Octave-wide bands
+            bands: BAND_CENTRES
+                .iter()
+                .map(|&centre| Biquad::band_pass(sample_rate, centre, SQRT_2))
+                .collect(),
+            recent_power: VecDeque::with_capacity(MOMENTARY_FRAMES),
+        }
+    }
+
+    /// Samples per frame (100 ms)
+    pub fn frame_len(&self) -> usize {
+        (self.sample_rate * FRAME_SECS) as usize
+    }
+
+    pub fn process(&mut self, block: &[f32]) -> AnalysisFrame {
+        let mut weighted = 0.0f64;
+        let mut band_power = [0.0f64; ANALYSIS_BANDS];
+        for &sample in block {
+            let k = self
+                .k_weighting
+                .iter_mut()
+                .fold(sample, |x, filter| filter.process(x));
+            weighted += (k * k) as f64;
+            for (power, filter) in band_power.iter_mut().zip(self.bands.iter_mut()) {
+                let y = filter.process(sample);
+                *power += (y * y) as f64;
+            }
+        }
+        let count = block.len().max(1) as f64;
+
+        if self.recent_power.len() == MOMENTARY_FRAMES {
+            self.recent_power.pop_front();
+        }
+        self.recent_power.push_back(weighted / count);
+        let momentary = self.recent_power.iter().sum::<f64>() / self.recent_power.len() as f64;
+
+        let nyquist = self.sample_rate / 2.0;
+        let mut bands = [SILENCE_DB; ANALYSIS_BANDS];
+        for (band, (power, centre)) in bands
+            .iter_mut()
+            .zip(band_power.iter().zip(BAND_CENTRES.iter()))
+        {
+            // Bands the sample rate can't carry stay silent
+            if *centre < nyquist * 0.9 {
+                *band = to_db(power / count, 0.0);
+            }
+        }
+        AnalysisFrame {
+            loudness: to_db(momentary, -0.691),
+            bands,
+        }
+    }
+}
+
+/// Integrated loudness of a whole mix in LUFS (BS.1770 gating: absolute gate
+/// at -70 LUFS, then a relative gate 10 LU below); None if the mix is silent
+pub fn integrated_loudness(samples: &[f32], sample_rate: u32) -> Option<f32> {
+    let mut k_weighting = Biquad::k_weighting(sample_rate as f32);
+    // Running sum of squares so each block's power is one subtraction
+    let mut energy = Vec::with_capacity(samples.len() + 1);
+    energy.push(0.0f64);
+    for &sample in samples {
+        let k = k_weighting
+            .iter_mut()
+            .fold(sample, |x, filter| filter.process(x));
+        energy.push(energy[energy.len() - 1] + (k * k) as f64);
+    }
+
+    // Mixes shorter than one block are measured as a single block
+    let block = ((sample_rate as f32 * GATE_BLOCK_SECS) as usize).clamp(1, samples.len().max(1));
+    let hop = (block / GATE_BLOCK_HOPS).max(1);
+    let powers: Vec<f64> = (0..=samples.len().saturating_sub(block))
+        .step_by(hop)
+        .map(|start| (energy[start + block] - energy[start]) / block as f64)
+        .collect();
+
+    let loudness = |power: f64| -0.691 + 10.0 * power.log10();
+    let gated_mean = |threshold: f64| {
+        let gated: Vec<f64> = powers
+            .iter()
+            .copied()
+            .filter(|&power| power > 0.0 && loudness(power) > threshold)
+            .collect();
+        (!gated.is_empty()).then(|| gated.iter().sum::<f64>() / gated.len() as f64)
+    };
+    let absolute = gated_mean(SILENCE_DB as f64)?;
+    let relative = gated_mean(loudness(absolute) - 10.0)?;
+    Some(loudness(relative) as f32)
+}
+
+/// Highest level of the waveform including between samples (4x oversampled
+/// as in BS.1770), as a linear amplitude
+pub fn true_peak(samples: &[f32]) -> f32 {
+    let mut peak = samples.iter().fold(0.0f32, |peak, &s| peak.max(s.abs()));
+    for phase in 1..OVERSAMPLE {
+        // Hann-windowed sinc taps for the point `phase / OVERSAMPLE` past each sample
+        let offset = phase as f32 / OVERSAMPLE as f32;
+        let taps: Vec<(isize, f32)> = (1 - INTERPOLATION_HALF_TAPS..=INTERPOLATION_HALF_TAPS)
+            .map(|j| {
+                let t = offset - j as f32;
+                let sinc = (PI * t).sin() / (PI * t);
+                let window = 0.5 * (1.0 + (PI * t / INTERPOLATION_HALF_TAPS as f32).cos());
+                (j, sinc * window)
+            })
+            .collect();
+        for n in 0..samples.len() as isize {
+            let value: f32 = taps
+                .iter()
+                .filter_map(|&(j, tap)| {
+                    usize::try_from(n + j)
+                        .ok()
+                        .and_then(|i| samples.get(i))
+                        .map(|&s| s * tap)
+                })
+                .sum();
+            peak = peak.max(value.abs());
+        }
+    }
+    peak
+}
+
+/// Silence, clipping and DC offset in decoded audio; a silent file reports nothing else
+pub fn check_import(samples: &[f32]) -> Vec<ImportIssue> {
+    let peak = samples.iter().fold(0.0f32, |peak, &s| peak.max(s.abs()));
+    if peak < 10f32.powf(SILENCE_DB / 20.0) {
+        return vec![ImportIssue::Silent];
+    }
+    let mut issues = Vec::new();
+    let mut flat_tops = 0;
+    let mut run = 0;
+    for &sample in samples {
+        if sample.abs() >= CLIP_LEVEL {
+            run += 1;
+            if run == FLAT_TOP_SAMPLES {
+                flat_tops += 1;
+            }
+        } else {
+            run = 0;
+        }
+    }
+    if flat_tops >= CLIPPED_FLAT_TOPS {
+        issues.push(ImportIssue::Clipped(flat_tops));
+    }
+    let offset = dc_offset(samples);
+    if offset.abs() > DC_OFFSET_LIMIT {
+        issues.push(ImportIssue::DcOffset(offset));
+    }
+    issues
+}
+
+/// Mean level of the waveform
+pub fn dc_offset(samples: &[f32]) -> f32 {
+    (samples.iter().map(|&s| s as f64).sum::<f64>() / samples.len().max(1) as f64) as f32
+}
+
+fn to_db(mean_square: f64, offset: f64) -> f32 {
+    if mean_square <= 0.0 {
+        return SILENCE_DB;
+    }
+    ((offset + 10.0 * mean_square.log10()) as f32).max(SILENCE_DB)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sine_loudness_and_band() {
+        let sample_rate = 48000;
+        let mut analyzer = MixAnalyzer::new(sample_rate);
+        let len = analyzer.frame_len();
+        let mut frame = None;
+        for block in 0..MOMENTARY_FRAMES {
+            let sine: Vec<f32> = (0..len)
+                .map(|i| {
+                    let t = (block * len + i) as f32 / sample_rate as f32;
+                    (2.0 * PI * 1000.0 * t).sin()
+                })
+                .collect();
+            frame = Some(analyzer.process(&sine));
+        }
+        let frame = frame.unwrap();
+        // A full-scale 1 kHz sine reads about -3 LUFS
+        assert!(
+            (-4.5..=-2.0).contains(&frame.loudness),
+            "{}",
+            frame.loudness
+        );
+        let loudest = (0..ANALYSIS_BANDS)
+            .max_by(|&a, &b| frame.bands[a].total_cmp(&frame.bands[b]))
+            .unwrap();
+        assert_eq!(BAND_CENTRES[loudest], 1000.0);
+
+        let silence = MixAnalyzer::new(sample_rate).process(&vec![0.0; len]);
+        assert_eq!(silence.loudness, SILENCE_DB);
+        assert!(silence.bands.iter().all(|&band| band == SILENCE_DB));
+    }
+
+    #[test]
+    fn test_check_import() {
+        let sine: Vec<f32> = (0..48000)
+            .map(|i| 0.5 * (2.0 * PI * 440.0 * i as f32 / 48000.0).sin())
+            .collect();
+        assert!(check_import(&sine).is_empty());
+        assert_eq!(check_import(&[0.0; 4800]), vec![ImportIssue::Silent]);
+
+        // Overdriven to flat tops and shifted up
+        let broken: Vec<f32> = sine
+            .iter()
+            .map(|s| (s * 4.0).clamp(-1.0, 1.0) + 0.05)
+            .collect();
+        let issues = check_import(&broken);
+        assert!(
+            matches!(issues[0], ImportIssue::Clipped(n) if n >= 400),
+            "{:?}",
+            issues
+        );
+        assert!(matches!(issues[1], ImportIssue::DcOffset(o) if (o - 0.05).abs() < 1e-3));
+    }
+}
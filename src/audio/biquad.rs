@@ -0,0 +1,104 @@
+// src/audio/biquad.rs
+// Second-order IIR filters shared by the mix analysis and the input high-pass
+
+use std::f32::consts::{FRAC_1_SQRT_2, PI};
+
+/// Second-order IIR filter (RBJ cookbook coefficients)
+#[derive(Debug, Clone)]
+pub struct Biquad {
+    b0: f32,
+    b1: f32,
+    b2: f32,
+    a1: f32,
+    a2: f32,
+    z1: f32,
+    z2: f32,
+}
+
+impl Biquad {
+    pub fn new(b: [f32; 3], a: [f32; 3]) -> Self {
+        Self {
+            b0: b[0] / a[0],
+            b1: b[1] / a[0],
+            b2: b[2] / a[0],
+            a1: a[1] / a[0],
+            a2: a[2] / a[0],
+            z1: 0.0,
+            z2: 0.0,
+        }
+    }
+
+    pub fn high_shelf(sample_rate: f32, freq: f32, gain_db: f32, q: f32) -> Self {
+        let a = 10f32.powf(gain_db / 40.0);
+        let w0 = 2.0 * PI * freq / sample_rate;
+        let (sin, cos) = w0.sin_cos();
+        let alpha = sin / (2.0 * q);
+        let root = 2.0 * a.sqrt() * alpha;
+        Self::new(
+            [
+                a * ((a + 1.0) + (a - 1.0) * cos + root),
+                -2.0 * a * ((a - 1.0) + (a + 1.0) * cos),
+                a * ((a + 1.0) + (a - 1.0) * cos - root),
+            ],
+            [
+                (a + 1.0) - (a - 1.0) * cos + root,
+                2.0 * ((a - 1.0) - (a + 1.0) * cos),
+                (a + 1.0) - (a - 1.0) * cos - root,
+            ],
+        )
+    }
+
+    pub fn high_pass(sample_rate: f32, freq: f32, q: f32) -> Self {
+        let w0 = 2.0 * PI * freq / sample_rate;
+        let (sin, cos) = w0.sin_cos();
+        let alpha = sin / (2.0 * q);
+        Self::new(
+            [(1.0 + cos) / 2.0, -(1.0 + cos), (1.0 + cos) / 2.0],
+            [1.0 + alpha, -2.0 * cos, 1.0 - alpha],
+        )
+    }
+
+    /// Unity gain at the centre frequency
+    pub fn band_pass(sample_rate: f32, freq: f32, q: f32) -> Self {
+        let w0 = 2.0 * PI * freq / sample_rate;
+        let (sin, cos) = w0.sin_cos();
+        let alpha = sin / (2.0 * q);
+        Self::new([alpha, 0.0, -alpha], [1.0 + alpha, -2.0 * cos, 1.0 - alpha])
+    }
+
+    /// ITU-R BS.1770 pre-filter: head shelf then RLB high-pass
+    pub fn k_weighting(sample_rate: f32) -> [Self; 2] {
+        [
+            Self::high_shelf(sample_rate, 1681.97, 4.0, FRAC_1_SQRT_2),
+            Self::high_pass(sample_rate, 38.13, 0.5003),
+        ]
+    }
+
+    pub fn process(&mut self, x: f32) -> f32 {
+        let y = self.b0 * x + self.z1;
+        self.z1 = self.b1 * x - self.a1 * y + self.z2;
+        self.z2 = self.b2 * x - self.a2 * y;
+        y
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn level_after(filter: &mut Biquad, freq: f32) -> f32 {
+        let sample_rate = 48_000.0;
+        (0..48_000)
+            .map(|i| filter.process((2.0 * PI * freq * i as f32 / sample_rate).sin()))
+            .skip(24_000) // settled
+            .fold(0.0, |peak: f32, y| peak.max(y.abs()))
+    }
+
+    #[test]
+    fn test_high_pass_cuts_rumble_and_keeps_voice() {
+        let mut filter = Biquad::high_pass(48_000.0, 80.0, FRAC_1_SQRT_2);
+        assert!(level_after(&mut filter, 20.0) < 0.1);
+        let mut filter = Biquad::high_pass(48_000.0, 80.0, FRAC_1_SQRT_2);
+        assert!((level_after(&mut filter, 1000.0) - 1.0).abs() < 0.01);
+    }
+}
@@ -0,0 +1,103 @@
+// src/audio/block_adapter.rs
+// Runs the engine in fixed-size blocks whatever the device callback size, so
+// beat crossings and scheduled commands land on the same grid on every device
+
+/// The last rendered block per bus; what the device hasn't taken yet is
+/// handed out on the next callback
+#[derive(Debug, Clone)]
+pub struct BlockAdapter {
+    main: Vec<f32>,
+    cue: Vec<f32>,   // empty when the cue bus isn't rendered separately
+    click: Vec<f32>, // empty when the click isn't rendered separately
+    position: usize, // next sample to hand out (block length = used up)
+}
+
+impl BlockAdapter {
+    pub fn new(block_frames: usize, cue: bool, click: bool) -> Self {
+        let block_frames = block_frames.max(1);
+        let bus = |rendered: bool| vec![0.0; if rendered { block_frames } else { 0 }];
+        Self {
+            main: vec![0.0; block_frames],
+            cue: bus(cue),
+            click: bus(click),
+            position: block_frames,
+        }
+    }
+
+    pub fn block_frames(&self) -> usize {
+        self.main.len()
+    }
+
+    /// Fill `main` (and `cue`/`click` where rendered) from whole blocks, calling
+    /// `render` for each new one; adds up to one block of latency
+    pub fn fill(
+        &mut self,
+        main: &mut [f32],
+        mut cue: Option<&mut [f32]>,
+        mut click: Option<&mut [f32]>,
+        mut render: impl FnMut(&mut [f32], Option<&mut [f32]>, Option<&mut [f32]>),
+    ) {
+        let mut filled = 0;
+        while filled < main.len() {
+            if self.position == self.main.len() {
+                render(
+                    &mut self.main,
+                    (!self.cue.is_empty()).then_some(&mut self.cue[..]),
+                    (!self.click.is_empty()).then_some(&mut self.click[..]),
+                );
+                self.position = 0;
+            }
+
+            let count = (main.len() - filled).min(self.main.len() - self.position);
+            let from = self.position..self.position + count;
+            let to = filled..filled + count;
+            main[to.clone()].copy_from_slice(&self.main[from.clone()]);
+            if let Some(cue) = cue.as_deref_mut()
+                && !self.cue.is_empty()
+            {
+                cue[to.clone()].copy_from_slice(&self.cue[from.clone()]);
+            }
+            if let Some(click) = click.as_deref_mut()
+                && !self.click.is_empty()
+            {
+                click[to].copy_from_slice(&self.click[from]);
+            }
+            self.position += count;
+            filled += count;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fixed_blocks_for_any_callback_size() {
+        let mut adapter = BlockAdapter::new(128, true, false);
+        let mut next = 0.0f32;
+        let mut blocks = 0;
+        let mut played = Vec::new();
+
+        for callback in [100, 300, 7, 1024, 0, 57] {
+            let mut main = vec![0.0; callback];
+            let mut cue = vec![0.0; callback];
+            adapter.fill(&mut main, Some(&mut cue), None, |out, cue, click| {
+                assert_eq!(out.len(), 128);
+                assert!(cue.is_some() && click.is_none());
+                for (sample, cue) in out.iter_mut().zip(cue.unwrap().iter_mut()) {
+                    *sample = next;
+                    *cue = -next;
+                    next += 1.0;
+                }
+                blocks += 1;
+            });
+            assert!(main.iter().zip(&cue).all(|(m, c)| *m == -*c));
+            played.extend(main);
+        }
+
+        // 1488 frames played from 12 whole blocks, without gaps or repeats
+        assert_eq!(blocks, 12);
+        assert!(played.iter().enumerate().all(|(i, &s)| s == i as f32));
+    }
+}
@@ -0,0 +1,174 @@
+// src/audio/channels.rs
+// Bounded UI <-> engine channels with explicit overflow policies
+
+use crossbeam::channel::{self, Receiver, Sender, TrySendError};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Weak};
+
+use super::{AudioEvent, LayerCommand, LooperError};
+
+pub const COMMAND_CAPACITY: usize = 256;
+pub const EVENT_CAPACITY: usize = 4096;
+
+/// Overflow counters shared by both channels (shown by the UI when non-zero)
+#[derive(Debug, Default)]
+pub struct ChannelStats {
+    dropped_events: AtomicU64,
+    rejected_commands: AtomicU64,
+}
+
+impl ChannelStats {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self::default())
+    }
+
+    pub fn dropped_events(&self) -> u64 {
+        self.dropped_events.load(Ordering::Relaxed)
+    }
+
+    pub fn rejected_commands(&self) -> u64 {
+        self.rejected_commands.load(Ordering::Relaxed)
+    }
+}
+
+/// Command side: rejects instead of blocking when the engine falls behind
+#[derive(Debug, Clone)]
+pub struct CommandSender {
+    tx: Sender<LayerCommand>,
+    stats: Arc<ChannelStats>,
+}
+
+impl CommandSender {
+    pub fn send(&self, command: LayerCommand) -> Result<(), LooperError> {
+        match self.tx.try_send(command) {
+            Ok(()) => Ok(()),
+            Err(TrySendError::Full(_)) => {
+                self.stats.rejected_commands.fetch_add(1, Ordering::Relaxed);
+                Err(LooperError::BufferFull("Command queue"))
+            }
+            Err(TrySendError::Disconnected(_)) => Err(LooperError::EngineStopped),
+        }
+    }
+}
+
+/// Event side: periodic status updates give way once the queue is half full, so
+/// they can't crowd out errors and acks (the next update carries the same state)
+#[derive(Debug, Clone)]
+pub struct EventSender {
+    tx: Sender<AudioEvent>,
+    receiver: Weak<()>, // gone once every EventReceiver is dropped
+    stats: Arc<ChannelStats>,
+}
+
+impl EventSender {
+    /// Never blocks; a full queue drops the event and counts it
+    pub fn try_send(&self, event: AudioEvent) -> Result<(), TrySendError<AudioEvent>> {
+        if !self.is_connected() {
+            return Err(TrySendError::Disconnected(event));
+        }
+        if event.is_periodic() && self.tx.len() >= EVENT_CAPACITY / 2 {
+            self.stats.dropped_events.fetch_add(1, Ordering::Relaxed);
+            return Err(TrySendError::Full(event));
+        }
+        let result = self.tx.try_send(event);
+        if let Err(TrySendError::Full(_)) = result {
+            self.stats.dropped_events.fetch_add(1, Ordering::Relaxed);
+        }
+        result
+    }
+
+    /// False once the UI has dropped its receiver
+    pub fn is_connected(&self) -> bool {
+        self.receiver.strong_count() > 0
+    }
+}
+
+/// UI side of the event channel; dropping every clone disconnects the engine's senders
+#[derive(Debug, Clone)]
+pub struct EventReceiver {
+    rx: Receiver<AudioEvent>,
+    _alive: Arc<()>,
+}
+
+impl std::ops::Deref for EventReceiver {
+    type Target = Receiver<AudioEvent>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.rx
+    }
+}
+
+pub fn command_channel(stats: &Arc<ChannelStats>) -> (CommandSender, Receiver<LayerCommand>) {
+    let (tx, rx) = channel::bounded(COMMAND_CAPACITY);
+    let sender = CommandSender {
+        tx,
+        stats: Arc::clone(stats),
+    };
+    (sender, rx)
+}
+
+pub fn event_channel(stats: &Arc<ChannelStats>) -> (EventSender, EventReceiver) {
+    let (tx, rx) = channel::bounded(EVENT_CAPACITY);
+    let alive = Arc::new(());
+    let sender = EventSender {
+        tx,
+        receiver: Arc::downgrade(&alive),
+        stats: Arc::clone(stats),
+    };
+    (sender, EventReceiver { rx, _alive: alive })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_full_command_queue_rejects() {
+        let stats = ChannelStats::new();
+        let (sender, _rx) = command_channel(&stats);
+        for _ in 0..COMMAND_CAPACITY {
+            sender.send(LayerCommand::StopAll).unwrap();
+        }
+        assert_eq!(
+            sender.send(LayerCommand::StopAll),
+            Err(LooperError::BufferFull("Command queue"))
+        );
+        assert_eq!(stats.rejected_commands(), 1);
+    }
+
+    #[test]
+    fn test_status_updates_leave_room_for_other_events() {
+        let stats = ChannelStats::new();
+        let (sender, rx) = event_channel(&stats);
+        while sender
+            .try_send(AudioEvent::LayerStatusBatch(Vec::new()))
+            .is_ok()
+        {}
+        assert_eq!(rx.len(), EVENT_CAPACITY / 2);
+        assert_eq!(stats.dropped_events(), 1);
+
+        for bpm in rx.len()..EVENT_CAPACITY {
+            sender.try_send(AudioEvent::BpmChanged(bpm as f64)).unwrap();
+        }
+        assert!(matches!(
+            sender.try_send(AudioEvent::BpmChanged(0.0)),
+            Err(TrySendError::Full(_))
+        ));
+        assert_eq!(stats.dropped_events(), 2);
+        assert_eq!(rx.try_recv(), Ok(AudioEvent::LayerStatusBatch(Vec::new())));
+    }
+
+    #[test]
+    fn test_dropped_receiver_disconnects() {
+        let (sender, rx) = event_channel(&ChannelStats::new());
+        let clone = rx.clone();
+        drop(rx);
+        assert!(sender.is_connected());
+        drop(clone);
+        assert!(!sender.is_connected());
+        assert!(matches!(
+            sender.try_send(AudioEvent::BpmChanged(120.0)),
+            Err(TrySendError::Disconnected(_))
+        ));
+    }
+}
@@ -0,0 +1,132 @@
+// src/audio/click.rs
+// Metronome click sounds: the bundled sample, a custom WAV or a synthesized flavor
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::f32::consts::TAU;
+use std::path::PathBuf;
+
+use super::import_wav;
+
+/// Bundled click, used unless the config picks another sound
+pub const DEFAULT_CLICK_PATH: &str = "assets/metronome.wav";
+
+/// Built-in clicks generated at the engine's sample rate
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClickFlavor {
+    Beep, // 1 kHz sine
+    Wood, // pitched knock with a noisy attack
+    Tick, // short bright noise burst
+}
+
+impl ClickFlavor {
+    // Peak level of synthesized clicks (the bundled sample peaks around here too)
+    const LEVEL: f32 = 0.6;
+
+    /// Render the click: an oscillator and/or noise under a fast-attack exponential decay
+    pub fn synthesize(self, sample_rate: u32) -> Vec<f32> {
+        let (length_ms, decay_ms, tone_hz, tone_mix) = match self {
+            Self::Beep => (40.0, 8.0, 1000.0, 1.0),
+            Self::Wood => (30.0, 5.0, 1800.0, 0.7),
+            Self::Tick => (10.0, 1.5, 0.0, 0.0),
+        };
+        let rate = sample_rate as f32;
+        let length = (rate * length_ms / 1000.0) as usize;
+        let attack = (rate / 1000.0).max(1.0); // 1 ms, avoids a click on the click
+        let decay = rate * decay_ms / 1000.0;
+
+        // xorshift: deterministic noise without pulling in a RNG crate
+        let mut seed = 0x2545_f491_u32;
+        let mut previous_noise = 0.0;
+        (0..length)
+            .map(|i| {
+                let t = i as f32;
+                seed ^= seed << 13;
+                seed ^= seed >> 17;
+                seed ^= seed << 5;
+                let noise = seed as f32 / u32::MAX as f32 * 2.0 - 1.0;
+                // First difference tilts the noise towards the highs
+                let bright_noise = (noise - previous_noise) * 0.5;
+                previous_noise = noise;
+
+                let tone = (TAU * tone_hz * t / rate).sin();
+                let envelope = (t / attack).min(1.0) * (-t / decay).exp();
+                (tone * tone_mix + bright_noise * (1.0 - tone_mix)) * envelope * Self::LEVEL
+            })
+            .collect()
+    }
+}
+
+/// Click chosen in the config file: `"sample"`, `"beep"`, `"wood"`, `"tick"` or a WAV path
+#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+#[serde(from = "String", into = "String")]
+pub enum ClickSound {
+    #[default]
+    Sample, // the bundled assets/metronome.wav
+    Synth(ClickFlavor),
+    Wav(PathBuf),
+}
+
+impl From<String> for ClickSound {
+    fn from(name: String) -> Self {
+        match name.to_lowercase().as_str() {
+            "sample" => Self::Sample,
+            "beep" => Self::Synth(ClickFlavor::Beep),
+            "wood" => Self::Synth(ClickFlavor::Wood),
+            "tick" => Self::Synth(ClickFlavor::Tick),
+            _ => Self::Wav(PathBuf::from(name)),
+        }
+    }
+}
+
+impl From<ClickSound> for String {
+    fn from(sound: ClickSound) -> Self {
+        match sound {
+            ClickSound::Sample => "sample".to_string(),
+            ClickSound::Synth(ClickFlavor::Beep) => "beep".to_string(),
+            ClickSound::Synth(ClickFlavor::Wood) => "wood".to_string(),
+            ClickSound::Synth(ClickFlavor::Tick) => "tick".to_string(),
+            ClickSound::Wav(path) => path.to_string_lossy().into_owned(),
+        }
+    }
+}
+
+impl ClickSound {
+    /// Samples for the engine, resampled to its rate
+    pub fn render(&self, sample_rate: u32) -> Result<Vec<f32>> {
+        match self {
+            Self::Sample => import_wav(DEFAULT_CLICK_PATH, sample_rate),
+            Self::Synth(flavor) => Ok(flavor.synthesize(sample_rate)),
+            Self::Wav(path) => import_wav(path, sample_rate),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_synthesized_clicks() {
+        for flavor in [ClickFlavor::Beep, ClickFlavor::Wood, ClickFlavor::Tick] {
+            let click = flavor.synthesize(48000);
+            assert!(!click.is_empty());
+            let peak = click.iter().fold(0.0f32, |peak, s| peak.max(s.abs()));
+            assert!(peak > 0.05 && peak <= ClickFlavor::LEVEL);
+            // Decays to silence so consecutive clicks don't smear
+            assert!(click.last().unwrap().abs() < 0.01);
+        }
+    }
+
+    #[test]
+    fn test_click_sound_names() {
+        assert_eq!(
+            ClickSound::from("Wood".to_string()),
+            ClickSound::Synth(ClickFlavor::Wood)
+        );
+        assert_eq!(
+            ClickSound::from("clicks/cowbell.wav".to_string()),
+            ClickSound::Wav(PathBuf::from("clicks/cowbell.wav"))
+        );
+    }
+}
@@ -0,0 +1,128 @@
+// src/audio/daw_project.rs
+// Minimal Reaper project (.rpp) referencing per-layer stems, for follow-up work in a DAW
+
+use anyhow::{Context, Result};
+use std::fmt::Write as _;
+use std::path::Path;
+
+/// One layer placed on its own track
+#[derive(Debug, Clone, PartialEq)]
+pub struct DawTrack {
+    pub name: String,
+    pub file: String,  // stem path relative to the project file
+    pub length: usize, // stem length in samples
+    pub volume: f32,
+    pub muted: bool,
+    pub solo: bool,
+}
+
+/// Project skeleton: tempo, sample rate and one looping item per track
+#[derive(Debug, Clone, PartialEq)]
+pub struct DawProject {
+    pub sample_rate: u32,
+    pub bpm: f64,
+    pub beats_per_measure: u32,
+    pub tracks: Vec<DawTrack>,
+}
+
+impl DawProject {
+    pub fn new(sample_rate: u32, bpm: f64, beats_per_measure: u32) -> Self {
+        Self {
+            sample_rate,
+            bpm,
+            beats_per_measure,
+            tracks: Vec::new(),
+        }
+    }
+
+    /// Render the project in Reaper's text format
+    /// Every item spans the longest stem; shorter loops repeat to fill it
+    pub fn to_rpp(&self) -> String {
+        let sample_rate = self.sample_rate.max(1) as f64;
+        let span = self.tracks.iter().map(|t| t.length).max().unwrap_or(0) as f64 / sample_rate;
+
+        let mut rpp = String::new();
+        let _ = writeln!(rpp, "<REAPER_PROJECT 0.1 \"6.0\" 0");
+        let _ = writeln!(rpp, "  TEMPO {} {} 4", self.bpm, self.beats_per_measure);
+        let _ = writeln!(rpp, "  SAMPLERATE {} 0 0", self.sample_rate);
+        for track in &self.tracks {
+            let _ = writeln!(rpp, "  <TRACK");
+            let _ = writeln!(rpp, "    NAME {}", quote(&track.name));
+            let _ = writeln!(rpp, "    VOLPAN {} 0 -1 -1 1", track.volume);
+            let _ = writeln!(
+                rpp,
+                "    MUTESOLO {} {} 0",
+                track.muted as u8,
+                if track.solo { 2 } else { 0 } // 2 = solo in place
+            );
+            let _ = writeln!(rpp, "    <ITEM");
+            let _ = writeln!(rpp, "      POSITION 0");
+            let _ = writeln!(rpp, "      LENGTH {}", span);
+            let _ = writeln!(rpp, "      LOOP 1");
+            let _ = writeln!(rpp, "      NAME {}", quote(&track.name));
+            let _ = writeln!(rpp, "      <SOURCE WAVE");
+            let _ = writeln!(rpp, "        FILE {}", quote(&track.file));
+            let _ = writeln!(rpp, "      >");
+            let _ = writeln!(rpp, "    >");
+            let _ = writeln!(rpp, "  >");
+        }
+        let _ = writeln!(rpp, ">");
+        rpp
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        std::fs::write(&path, self.to_rpp())
+            .with_context(|| format!("Failed to write {}", path.as_ref().display()))
+    }
+}
+
+/// Reaper strings can't escape quotes; switch to single quotes when needed
+fn quote(text: &str) -> String {
+    if text.contains('"') {
+        format!("'{}'", text.replace('\'', "`"))
+    } else {
+        format!("\"{}\"", text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rpp_has_tempo_and_one_track_per_layer() {
+        let mut project = DawProject::new(48000, 96.0, 3);
+        project.tracks.push(DawTrack {
+            name: "Layer 1".to_string(),
+            file: "set_layer01.wav".to_string(),
+            length: 96000,
+            volume: 0.5,
+            muted: false,
+            solo: true,
+        });
+        project.tracks.push(DawTrack {
+            name: "Layer 3".to_string(),
+            file: "set_layer03.wav".to_string(),
+            length: 48000,
+            volume: 1.0,
+            muted: true,
+            solo: false,
+        });
+
+        let rpp = project.to_rpp();
+        assert!(rpp.contains("TEMPO 96 3 4"));
+        assert!(rpp.contains("SAMPLERATE 48000 0 0"));
+        assert_eq!(rpp.matches("<TRACK").count(), 2);
+        assert!(rpp.contains("FILE \"set_layer03.wav\""));
+        assert!(rpp.contains("MUTESOLO 0 2 0"));
+        assert!(rpp.contains("MUTESOLO 1 0 0"));
+        // Both items span the longest stem (2 seconds)
+        assert_eq!(rpp.matches("LENGTH 2\n").count(), 2);
+    }
+
+    #[test]
+    fn test_quote() {
+        assert_eq!(quote("Layer 1"), "\"Layer 1\"");
+        assert_eq!(quote("say \"hi\""), "'say \"hi\"'");
+    }
+}
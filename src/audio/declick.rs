@@ -0,0 +1,98 @@
+// src/audio/declick.rs
+// Offline click / pop removal: short spikes that stick out of the surrounding
+// audio (cable plugged in mid-take, a dropout) are interpolated over
+
+// Half-width of the window the local level is measured over
+const LEVEL_WINDOW: usize = 256;
+// A sample is a click when it strays from its neighbours this many times the local level
+const THRESHOLD: f32 = 10.0;
+// ...and by at least this much, so quiet passages aren't "repaired"
+const MIN_SPIKE: f32 = 0.05;
+// Longer disturbances are real transients, not clicks
+const MAX_CLICK: usize = 32;
+// Flagged samples this close together are one pop (a flat-topped burst only
+// stands out at its edges)
+const MERGE_GAP: usize = 8;
+
+/// Interpolate over clicks in place; returns how many were repaired
+pub fn declick(samples: &mut [f32]) -> usize {
+    let n = samples.len();
+    if n < 3 {
+        return 0;
+    }
+
+    // How far each sample is from the line through its neighbours
+    let mut residual = vec![0.0f32; n];
+    for i in 1..n - 1 {
+        residual[i] = (samples[i] - (samples[i - 1] + samples[i + 1]) * 0.5).abs();
+    }
+    let mut prefix = vec![0.0f64; n + 1];
+    for (i, r) in residual.iter().enumerate() {
+        prefix[i + 1] = prefix[i] + *r as f64;
+    }
+
+    let flagged: Vec<bool> = (0..n)
+        .map(|i| {
+            let (lo, hi) = (i.saturating_sub(LEVEL_WINDOW), (i + LEVEL_WINDOW).min(n));
+            let level = ((prefix[hi] - prefix[lo]) / (hi - lo) as f64) as f32;
+            residual[i] > THRESHOLD * level + MIN_SPIKE
+        })
+        .collect();
+
+    let mut repaired = 0;
+    let mut i = 1;
+    while i < n - 1 {
+        if !flagged[i] {
+            i += 1;
+            continue;
+        }
+        let mut end = i + 1;
+        while let Some(next) = (end..(end + MERGE_GAP).min(n)).find(|&k| flagged[k]) {
+            end = next + 1;
+        }
+        // The spike's neighbours are pulled off the line too, so widen the run by one
+        let (start, stop) = (i - 1, (end + 1).min(n - 1));
+        if start > 0 && stop - start <= MAX_CLICK {
+            let (before, after) = (samples[start - 1], samples[stop]);
+            let span = (stop - start + 1) as f32;
+            for (k, sample) in samples[start..stop].iter_mut().enumerate() {
+                *sample = before + (after - before) * (k + 1) as f32 / span;
+            }
+            repaired += 1;
+        }
+        i = stop + 1;
+    }
+    repaired
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::PI;
+
+    #[test]
+    fn test_declick_repairs_spikes_only() {
+        let tone: Vec<f32> = (0..8_000)
+            .map(|i| 0.5 * (2.0 * PI * 220.0 * i as f32 / 48_000.0).sin())
+            .collect();
+
+        // Clean audio is left alone
+        let mut clean = tone.clone();
+        assert_eq!(declick(&mut clean), 0);
+        assert_eq!(clean, tone);
+
+        // Two pops, one a single sample and one a short burst
+        let mut popped = tone.clone();
+        popped[1_000] = 0.95;
+        for sample in popped[5_000..5_004].iter_mut() {
+            *sample = -0.9;
+        }
+        assert_eq!(declick(&mut popped), 2);
+        let worst = popped
+            .iter()
+            .zip(&tone)
+            .map(|(a, b)| (a - b).abs())
+            .fold(0.0, f32::max);
+        assert!(worst < 0.02, "worst error {}", worst);
+    }
+}
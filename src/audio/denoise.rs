@@ -0,0 +1,195 @@
+// src/audio/denoise.rs
+// Offline spectral noise reduction: a noise print is learned from a capture of
+// room tone, then each layer bin quieter than it is gated down
+
+use std::f32::consts::PI;
+
+// STFT frame (power of two) and hop; 75% overlap keeps the gating smooth
+const FRAME: usize = 2048;
+const HOP: usize = FRAME / 4;
+// Bins below the noise print times this are gated
+const OVER_SUBTRACTION: f32 = 2.0;
+// Most a gated bin is turned down (-24 dB), so the result doesn't sound hollow
+const GAIN_FLOOR: f32 = 0.063;
+// Per-frame release of a bin's gain, so gating doesn't warble between frames
+const GAIN_RELEASE: f32 = 0.7;
+// Sum of squared Hann windows at 75% overlap (analysis and synthesis windows)
+const OVERLAP_GAIN: f32 = 1.5;
+
+/// Average spectrum of the room noise a layer was recorded with
+#[derive(Debug, Clone, PartialEq)]
+pub struct NoiseProfile {
+    magnitudes: Vec<f32>, // per bin, 0..=FRAME / 2
+}
+
+impl NoiseProfile {
+    /// Learn from a capture of room tone; None if it's shorter than one frame
+    pub fn learn(noise: &[f32]) -> Option<Self> {
+        let window = hann_window();
+        let mut magnitudes = vec![0.0; FRAME / 2 + 1];
+        let mut frames = 0;
+        let (mut re, mut im) = (vec![0.0; FRAME], vec![0.0; FRAME]);
+        for start in (0..noise.len().checked_sub(FRAME)? + 1).step_by(HOP) {
+            for (i, slot) in re.iter_mut().enumerate() {
+                *slot = noise[start + i] * window[i];
+            }
+            im.fill(0.0);
+            fft(&mut re, &mut im, false);
+            for (bin, magnitude) in magnitudes.iter_mut().enumerate() {
+                *magnitude += re[bin].hypot(im[bin]);
+            }
+            frames += 1;
+        }
+        for magnitude in magnitudes.iter_mut() {
+            *magnitude /= frames as f32;
+        }
+        Some(Self { magnitudes })
+    }
+
+    /// Gate `samples` against the noise print; `progress` gets 0.0..=1.0 as it goes
+    /// The result has the same length, so loop points and markers still apply
+    pub fn denoise(&self, samples: &[f32], mut progress: impl FnMut(f32)) -> Vec<f32> {
+        let window = hann_window();
+        // Pad a frame of silence at both ends so every sample gets full overlap
+        let mut padded = vec![0.0; samples.len() + 2 * FRAME];
+        padded[FRAME..FRAME + samples.len()].copy_from_slice(samples);
+        let mut output = vec![0.0; padded.len()];
+
+        let mut gains = vec![1.0f32; FRAME / 2 + 1];
+        let (mut re, mut im) = (vec![0.0; FRAME], vec![0.0; FRAME]);
+        let starts = (padded.len() - FRAME) / HOP + 1;
+        for frame in 0..starts {
+            let start = frame * HOP;
+            for (i, slot) in re.iter_mut().enumerate() {
+                *slot = padded[start + i] * window[i];
+            }
+            im.fill(0.0);
+            fft(&mut re, &mut im, false);
+
+            for (bin, gain) in gains.iter_mut().enumerate() {
+                let magnitude = re[bin].hypot(im[bin]);
+                let target = if magnitude > 0.0 {
+                    (1.0 - OVER_SUBTRACTION * self.magnitudes[bin] / magnitude).max(GAIN_FLOOR)
+                } else {
+                    GAIN_FLOOR
+                };
+                // Open at once, close gradually
+                *gain = target.max(*gain * GAIN_RELEASE);
+                re[bin] *= *gain;
+                im[bin] *= *gain;
+                // Mirror bin of the real signal's spectrum
+                if bin > 0 && bin < FRAME / 2 {
+                    re[FRAME - bin] *= *gain;
+                    im[FRAME - bin] *= *gain;
+                }
+            }
+
+            fft(&mut re, &mut im, true);
+            for (i, sample) in re.iter().enumerate() {
+                output[start + i] += sample * window[i] / OVERLAP_GAIN;
+            }
+            if frame % 64 == 0 {
+                progress(frame as f32 / starts as f32);
+            }
+        }
+        progress(1.0);
+        output.drain(..FRAME);
+        output.truncate(samples.len());
+        output
+    }
+}
+
+fn hann_window() -> Vec<f32> {
+    (0..FRAME)
+        .map(|i| 0.5 - 0.5 * (2.0 * PI * i as f32 / FRAME as f32).cos())
+        .collect()
+}
+
+/// In-place radix-2 FFT (length must be a power of two); the inverse is scaled by 1/n
+fn fft(re: &mut [f32], im: &mut [f32], inverse: bool) {
+    let n = re.len();
+    // Bit-reversal permutation
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+
+    let sign = if inverse { 1.0 } else { -1.0 };
+    let mut len = 2;
+    while len <= n {
+        let angle = sign * 2.0 * PI / len as f32;
+        for start in (0..n).step_by(len) {
+            for k in 0..len / 2 {
+                let (sin, cos) = (angle * k as f32).sin_cos();
+                let (a, b) = (start + k, start + k + len / 2);
+                let t_re = re[b] * cos - im[b] * sin;
+                let t_im = re[b] * sin + im[b] * cos;
+                re[b] = re[a] - t_re;
+                im[b] = im[a] - t_im;
+                re[a] += t_re;
+                im[a] += t_im;
+            }
+        }
+        len <<= 1;
+    }
+
+    if inverse {
+        for (r, i) in re.iter_mut().zip(im.iter_mut()) {
+            *r /= n as f32;
+            *i /= n as f32;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Deterministic white-ish noise
+    fn noise(len: usize, level: f32) -> Vec<f32> {
+        let mut state = 0x1234_5678u32;
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                (state as f32 / u32::MAX as f32 * 2.0 - 1.0) * level
+            })
+            .collect()
+    }
+
+    fn rms(samples: &[f32]) -> f32 {
+        (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt()
+    }
+
+    #[test]
+    fn test_denoise_gates_noise_and_keeps_tone() {
+        assert!(NoiseProfile::learn(&[0.0; FRAME - 1]).is_none());
+        let profile = NoiseProfile::learn(&noise(48_000, 0.01)).unwrap();
+
+        // Noise alone is turned well down
+        let hiss = noise(24_000, 0.01);
+        let mut last = 0.0;
+        let cleaned = profile.denoise(&hiss, |p| last = p);
+        assert_eq!((cleaned.len(), last), (hiss.len(), 1.0));
+        assert!(rms(&cleaned) < rms(&hiss) * 0.3);
+
+        // A loud tone comes through at about its level
+        let tone: Vec<f32> = (0..24_000)
+            .map(|i| 0.5 * (2.0 * PI * 440.0 * i as f32 / 48_000.0).sin())
+            .collect();
+        let noisy: Vec<f32> = tone.iter().zip(&hiss).map(|(t, n)| t + n).collect();
+        let cleaned = profile.denoise(&noisy, |_| {});
+        let middle = 4_000..20_000;
+        assert!((rms(&cleaned[middle.clone()]) / rms(&tone[middle]) - 1.0).abs() < 0.05);
+    }
+}
@@ -0,0 +1,101 @@
+// src/audio/drift.rs
+// Clock drift between input and output devices, estimated from sample counts
+
+/// Compares samples produced by the input clock with samples the output clock
+/// consumes; the ratio is used to micro-resample the output path
+#[derive(Debug, Clone)]
+pub struct DriftEstimator {
+    warmup: f64, // samples ignored while streams settle
+    settle: f64, // samples measured before correcting
+    consumed: f64,
+    baseline: Option<(u64, f64)>, // (produced, consumed) when measuring started
+    ratio: f64,
+}
+
+impl DriftEstimator {
+    /// Largest correction applied (0.5%); anything beyond that isn't clock drift
+    pub const MAX_CORRECTION: f64 = 0.005;
+    // Fraction of the remaining error corrected per update (avoids audible pitch steps)
+    const SMOOTHING: f64 = 0.01;
+
+    pub fn new(sample_rate: u32) -> Self {
+        Self {
+            warmup: sample_rate as f64 * 2.0,
+            settle: sample_rate as f64 * 10.0,
+            consumed: 0.0,
+            baseline: None,
+            ratio: 1.0,
+        }
+    }
+
+    /// Feed the input device's running sample count and the samples just consumed
+    /// Returns the correction ratio (input samples per nominal output-side sample)
+    pub fn update(&mut self, produced_total: u64, consumed: f64) -> f64 {
+        self.consumed += consumed;
+        if self.consumed < self.warmup {
+            return self.ratio;
+        }
+
+        let (base_produced, base_consumed) =
+            *self.baseline.get_or_insert((produced_total, self.consumed));
+        let measured_consumed = self.consumed - base_consumed;
+        if measured_consumed < self.settle {
+            return self.ratio;
+        }
+
+        // Long-run totals average out callback jitter
+        let measured = produced_total.saturating_sub(base_produced) as f64 / measured_consumed;
+        let target = measured.clamp(1.0 - Self::MAX_CORRECTION, 1.0 + Self::MAX_CORRECTION);
+        self.ratio += (target - self.ratio) * Self::SMOOTHING;
+        self.ratio
+    }
+
+    pub fn ratio(&self) -> f64 {
+        self.ratio
+    }
+
+    /// Estimated drift in parts per million (positive = input clock runs fast)
+    pub fn drift_ppm(&self) -> f64 {
+        (self.ratio - 1.0) * 1_000_000.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimates_fast_input_clock() {
+        let mut drift = DriftEstimator::new(48000);
+        let mut produced = 0.0;
+
+        // 60s of 512-sample callbacks with the input clock 200ppm fast
+        for _ in 0..(48000 * 60 / 512) {
+            produced += 512.0 * 1.0002;
+            drift.update(produced as u64, 512.0);
+        }
+        assert!((drift.drift_ppm() - 200.0).abs() < 20.0);
+    }
+
+    #[test]
+    fn test_no_correction_while_settling() {
+        let mut drift = DriftEstimator::new(48000);
+        let mut produced = 0u64;
+        for _ in 0..(48000 * 5 / 512) {
+            produced += 530;
+            drift.update(produced, 512.0);
+        }
+        assert_eq!(drift.ratio(), 1.0);
+    }
+
+    #[test]
+    fn test_correction_is_capped() {
+        let mut drift = DriftEstimator::new(48000);
+        let mut produced = 0u64;
+        for _ in 0..(48000 * 120 / 512) {
+            produced += 1024; // a broken clock, not drift
+            drift.update(produced, 512.0);
+        }
+        assert!(drift.ratio() <= 1.0 + DriftEstimator::MAX_CORRECTION);
+    }
+}
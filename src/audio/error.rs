@@ -0,0 +1,179 @@
+// src/audio/error.rs
+// Typed engine errors, returned from commands and reported to the UI
+
+use std::fmt;
+
+use super::LayerPhase;
+use super::io::TooLarge;
+
+/// Failure reported by the looper engine
+/// Clone + PartialEq so it can travel inside `AudioEvent::Error`
+#[derive(Debug, Clone, PartialEq)]
+pub enum LooperError {
+    InvalidLayer(usize),
+    EmptyLayer(usize),
+    LayerInMix(usize), // already playing in the main mix (can't be cued)
+    LayerLocked(usize),
+    InvalidTransition {
+        layer_id: usize,
+        from: LayerPhase,
+        to: LayerPhase,
+    },
+    LayerCapturing {
+        layer_id: usize,      // recording or overdubbing
+        action: &'static str, // e.g. "import"
+    },
+    LayerChanged {
+        layer_id: usize,      // edited while a background job processed it
+        action: &'static str, // e.g. "freeze"
+    },
+    NoMasterLoop,
+    CannotFit(usize),       // too far from the master loop to varispeed into it
+    NothingToFreeze(usize), // not varispeeded, so it already plays as recorded
+    NoNoiseProfile,         // denoise needs a learned noise print first
+    NoiseTooShort(usize),   // room tone shorter than one analysis frame
+    MarkerNotFound {
+        layer_id: usize,
+        name: String,
+    },
+    NoAudition,
+    NoReplacement(usize), // no new take to keep or revert
+    TakeNotFound {
+        layer_id: usize,
+        take: usize,
+    },
+    NoOtherTakes(usize),      // nothing for keep-take to drop
+    Busy(&'static str),       // shared state locked by another thread; try again
+    BufferFull(&'static str), // which queue or buffer
+    DeviceLost(&'static str), // "input" or "output"
+    EngineStopped,            // command channel closed
+    IoFailed {
+        action: &'static str, // e.g. "import WAV"
+        path: String,
+        source: String, // full error chain as text
+    },
+    TooLarge {
+        path: String,
+        seconds: f64,
+        limit_seconds: u32,
+    },
+}
+
+impl LooperError {
+    /// Wrap a file operation failure; files over the import cap get their own variant
+    pub fn io(action: &'static str, path: impl Into<String>, error: &anyhow::Error) -> Self {
+        if let Some(too_large) = error.downcast_ref::<TooLarge>() {
+            return Self::TooLarge {
+                path: path.into(),
+                seconds: too_large.seconds,
+                limit_seconds: too_large.limit_seconds,
+            };
+        }
+        Self::IoFailed {
+            action,
+            path: path.into(),
+            source: format!("{:#}", error),
+        }
+    }
+}
+
+impl fmt::Display for LooperError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidLayer(id) => write!(f, "Layer {} out of range", id + 1),
+            Self::EmptyLayer(id) => write!(f, "Layer {} is empty", id + 1),
+            Self::LayerInMix(id) => write!(f, "Layer {} is already playing", id + 1),
+            Self::LayerLocked(id) => write!(f, "Layer {} is locked", id + 1),
+            Self::InvalidTransition { layer_id, from, to } => write!(
+                f,
+                "Layer {} is {}; it can't go to {}",
+                layer_id + 1,
+                from,
+                to
+            ),
+            Self::LayerCapturing { layer_id, action } => write!(
+                f,
+                "Layer {} is recording; can't {} until it stops",
+                layer_id + 1,
+                action
+            ),
+            Self::LayerChanged { layer_id, action } => write!(
+                f,
+                "Layer {} changed while it was being processed; {} again",
+                layer_id + 1,
+                action
+            ),
+            Self::NoMasterLoop => write!(f, "No master loop set"),
+            Self::NothingToFreeze(id) => {
+                write!(f, "Layer {} isn't fitted to the master loop", id + 1)
+            }
+            Self::NoNoiseProfile => write!(f, "No noise print learned yet"),
+            Self::NoiseTooShort(id) => {
+                write!(f, "Layer {} is too short to learn noise from", id + 1)
+            }
+            Self::CannotFit(id) => write!(
+                f,
+                "Layer {} is too far from the master loop length to fit",
+                id + 1
+            ),
+            Self::MarkerNotFound { layer_id, name } => {
+                write!(f, "No marker named {} on layer {}", name, layer_id + 1)
+            }
+            Self::NoAudition => write!(f, "No audition to confirm"),
+            Self::NoReplacement(id) => write!(f, "Layer {} has no new take", id + 1),
+            Self::TakeNotFound { layer_id, take } => {
+                write!(f, "Layer {} has no take {}", layer_id + 1, take + 1)
+            }
+            Self::NoOtherTakes(id) => write!(f, "Layer {} has no other takes", id + 1),
+            Self::Busy(what) => write!(f, "{} busy, try again", what),
+            Self::BufferFull(what) => write!(f, "{} full", what),
+            Self::DeviceLost(direction) => write!(f, "Lost {} device", direction),
+            Self::EngineStopped => write!(f, "Audio engine is not running"),
+            Self::IoFailed {
+                action,
+                path,
+                source,
+            } => write!(f, "Failed to {} {}: {}", action, path, source),
+            Self::TooLarge {
+                path,
+                seconds,
+                limit_seconds,
+            } => write!(
+                f,
+                "{} is too long ({:.0} s, limit {} s)",
+                path, seconds, limit_seconds
+            ),
+        }
+    }
+}
+
+impl std::error::Error for LooperError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        assert_eq!(
+            LooperError::InvalidLayer(16).to_string(),
+            "Layer 17 out of range"
+        );
+        let err = LooperError::LayerCapturing {
+            layer_id: 1,
+            action: "import",
+        };
+        assert_eq!(
+            err.to_string(),
+            "Layer 2 is recording; can't import until it stops"
+        );
+        let err = LooperError::io("load session", "set.toml", &anyhow::anyhow!("missing"));
+        assert_eq!(err.to_string(), "Failed to load session set.toml: missing");
+        let too_large = anyhow::Error::from(TooLarge {
+            seconds: 725.4,
+            limit_seconds: 600,
+        });
+        let err = LooperError::io("import WAV", "set.wav", &too_large);
+        assert_eq!(err.to_string(), "set.wav is too long (725 s, limit 600 s)");
+    }
+}
@@ -0,0 +1,124 @@
+// src/audio/event_log.rs
+// Session event log: every command sent to the engine and every event it sends
+// back, one JSON object per line, for working out what happened after a gig
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Instant, SystemTime};
+
+use super::io::utc_timestamp;
+use super::{AudioEvent, LayerCommand};
+
+/// Lines look like `{"t":12.345678,"kind":"command","script":"play 1","data":"Play(0)"}`;
+/// `t` is seconds since the log opened on a monotonic clock, so it survives clock
+/// changes, and `script` (commands only) is the control protocol line to replay
+pub struct EventLog {
+    writer: BufWriter<File>,
+    path: PathBuf,
+    started: Instant,
+}
+
+impl EventLog {
+    /// Open `session_YYYY-MM-DD_HH-MM-SS.jsonl` (UTC) in `directory`
+    pub fn create(directory: &Path, time: SystemTime) -> io::Result<Self> {
+        std::fs::create_dir_all(directory)?;
+        let path = directory.join(format!("session_{}.jsonl", utc_timestamp(time)));
+        let mut log = Self {
+            writer: BufWriter::new(File::create(&path)?),
+            path,
+            started: Instant::now(),
+        };
+        // Wall-clock start, so `t` can be lined up with other logs
+        log.write("start", None, &utc_timestamp(time))?;
+        log.flush()?;
+        Ok(log)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// `script` is None for commands the control protocol can't express
+    pub fn log_command(&mut self, command: &LayerCommand, script: Option<&str>) -> io::Result<()> {
+        self.write("command", script, &format!("{:?}", command))
+    }
+
+    pub fn log_event(&mut self, event: &AudioEvent) -> io::Result<()> {
+        self.write("event", None, &format!("{:?}", event))
+    }
+
+    /// Lines are buffered; flush after a batch so a crash loses at most that batch
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    fn write(&mut self, kind: &str, script: Option<&str>, data: &str) -> io::Result<()> {
+        let script = script
+            .map(|line| format!("\"script\":\"{}\",", json_escape(line)))
+            .unwrap_or_default();
+        writeln!(
+            self.writer,
+            "{{\"t\":{:.6},\"kind\":\"{}\",{}\"data\":\"{}\"}}",
+            self.started.elapsed().as_secs_f64(),
+            kind,
+            script,
+            json_escape(data)
+        )
+    }
+}
+
+/// Escape for the inside of a JSON string
+fn json_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    #[test]
+    fn test_log_lines() {
+        let dir = std::env::temp_dir().join("soundlooper_test_event_log");
+        let _ = std::fs::remove_dir_all(&dir);
+        let time = UNIX_EPOCH + Duration::from_secs(1_709_210_096);
+        let mut log = EventLog::create(&dir, time).unwrap();
+        assert_eq!(
+            log.path(),
+            dir.join("session_2024-02-29_12-34-56.jsonl").as_path()
+        );
+
+        log.log_command(&LayerCommand::LoadSession("a \"b\"\\c.sls".into()), None)
+            .unwrap();
+        log.log_command(&LayerCommand::Play(0), Some("play 1"))
+            .unwrap();
+        log.log_event(&AudioEvent::PeaksReset).unwrap();
+        log.flush().unwrap();
+
+        let text = std::fs::read_to_string(log.path()).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[0].ends_with(r#""kind":"start","data":"2024-02-29_12-34-56"}"#));
+        assert!(lines[1].starts_with(r#"{"t":"#));
+        assert!(
+            lines[1]
+                .ends_with(r#""kind":"command","data":"LoadSession(\"a \\\"b\\\"\\\\c.sls\")"}"#)
+        );
+        assert!(lines[2].ends_with(r#""kind":"command","script":"play 1","data":"Play(0)"}"#));
+        assert!(lines[3].ends_with(r#""kind":"event","data":"PeaksReset"}"#));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,217 @@
+// src/audio/export_hook.rs
+// Post-export hook: hands each finished WAV to a shell command or uploads it
+// with an HTTP PUT, so takes land in a synced folder or on a collaboration server
+
+use anyhow::{Context, Result, bail};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::Path;
+use std::process::Command;
+use std::time::Duration;
+
+// Body is written in chunks so progress can be reported
+const UPLOAD_CHUNK: usize = 64 * 1024;
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+const IO_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExportHook {
+    Command(String), // run by the shell; {file} becomes the quoted path
+    HttpPut(String), // http:// URL; {name} becomes the file name
+}
+
+impl ExportHook {
+    /// Run the hook for a finished export; `progress` gets (bytes sent, total)
+    /// during uploads
+    pub fn run(&self, file_path: &str, progress: impl FnMut(u64, u64)) -> Result<()> {
+        match self {
+            Self::Command(template) => run_command(template, file_path),
+            Self::HttpPut(url) => http_put(url, file_path, progress),
+        }
+    }
+}
+
+/// Shell command for a file; the path is appended if the template has no {file}
+fn command_line(template: &str, file_path: &str) -> String {
+    let quoted = shell_quote(file_path);
+    if template.contains("{file}") {
+        template.replace("{file}", &quoted)
+    } else {
+        format!("{} {}", template, quoted)
+    }
+}
+
+#[cfg(unix)]
+fn shell_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', r"'\''"))
+}
+
+#[cfg(not(unix))]
+fn shell_quote(text: &str) -> String {
+    format!("\"{}\"", text)
+}
+
+fn run_command(template: &str, file_path: &str) -> Result<()> {
+    let line = command_line(template, file_path);
+    #[cfg(unix)]
+    let output = Command::new("sh").arg("-c").arg(&line).output();
+    #[cfg(not(unix))]
+    let output = Command::new("cmd").arg("/C").arg(&line).output();
+    let output = output.with_context(|| format!("Failed to run `{}`", line))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("`{}` failed ({}): {}", line, output.status, stderr.trim());
+    }
+    Ok(())
+}
+
+/// Host, port and request path of a plain http:// URL
+fn parse_http_url(url: &str) -> Result<(String, u16, String)> {
+    if url.starts_with("https://") {
+        bail!("HTTPS uploads aren't built in; use a hook command such as `curl -T {{file}} URL`");
+    }
+    let rest = url
+        .strip_prefix("http://")
+        .with_context(|| format!("Upload URL must start with http://: {}", url))?;
+    let (authority, path) = match rest.find('/') {
+        Some(slash) => (&rest[..slash], &rest[slash..]),
+        None => (rest, "/"),
+    };
+    let (host, port) = match authority.rsplit_once(':') {
+        Some((host, port)) => (
+            host,
+            port.parse()
+                .with_context(|| format!("Invalid port in upload URL: {}", url))?,
+        ),
+        None => (authority, 80),
+    };
+    if host.is_empty() {
+        bail!("Upload URL has no host: {}", url);
+    }
+    Ok((host.to_string(), port, path.to_string()))
+}
+
+/// Percent-encode a file name for use in a URL path
+fn encode_name(name: &str) -> String {
+    name.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+fn http_put(url: &str, file_path: &str, mut progress: impl FnMut(u64, u64)) -> Result<()> {
+    let name = Path::new(file_path)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let url = url.replace("{name}", &encode_name(&name));
+    let (host, port, path) = parse_http_url(&url)?;
+    let body = std::fs::read(file_path).with_context(|| format!("Failed to read {}", file_path))?;
+
+    let address = (host.as_str(), port)
+        .to_socket_addrs()
+        .with_context(|| format!("Failed to resolve {}", host))?
+        .next()
+        .with_context(|| format!("No address for {}", host))?;
+    let mut stream = TcpStream::connect_timeout(&address, CONNECT_TIMEOUT)
+        .with_context(|| format!("Failed to connect to {}:{}", host, port))?;
+    stream.set_read_timeout(Some(IO_TIMEOUT))?;
+    stream.set_write_timeout(Some(IO_TIMEOUT))?;
+
+    write!(
+        stream,
+        "PUT {} HTTP/1.1\r\nHost: {}\r\nContent-Type: audio/wav\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        path,
+        host,
+        body.len()
+    )?;
+    let total = body.len() as u64;
+    let mut sent = 0;
+    progress(0, total);
+    for chunk in body.chunks(UPLOAD_CHUNK) {
+        stream.write_all(chunk)?;
+        sent += chunk.len() as u64;
+        progress(sent, total);
+    }
+    stream.flush()?;
+
+    let mut status_line = String::new();
+    BufReader::new(&stream)
+        .read_line(&mut status_line)
+        .context("No response from the upload server")?;
+    let status = status_line.trim();
+    match status.split_whitespace().nth(1) {
+        Some(code) if code.starts_with('2') => Ok(()),
+        Some(_) => bail!("Upload rejected: {}", status),
+        None => bail!("Unexpected upload response: {:?}", status),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use std::net::TcpListener;
+
+    #[test]
+    fn test_command_and_http_put() {
+        #[cfg(unix)]
+        assert_eq!(
+            command_line("rclone copy {file} remote:loops", "take 1.wav"),
+            "rclone copy 'take 1.wav' remote:loops"
+        );
+        #[cfg(unix)]
+        assert_eq!(command_line("echo", "it's.wav"), r"echo 'it'\''s.wav'");
+        assert!(parse_http_url("https://example.com/").is_err());
+        assert_eq!(
+            parse_http_url("http://nas.local:8080/dav").unwrap(),
+            ("nas.local".to_string(), 8080, "/dav".to_string())
+        );
+
+        let dir = std::env::temp_dir().join(format!("soundlooper-hook-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("take 1.wav");
+        std::fs::write(&file, vec![7u8; UPLOAD_CHUNK + 10]).unwrap();
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 8192];
+            // Headers, then the whole body
+            loop {
+                let n = stream.read(&mut buf).unwrap();
+                request.extend_from_slice(&buf[..n]);
+                let head_end = request.windows(4).position(|w| w == b"\r\n\r\n");
+                if head_end.is_some_and(|end| request.len() >= end + 4 + UPLOAD_CHUNK + 10) {
+                    break;
+                }
+            }
+            stream.write_all(b"HTTP/1.1 201 Created\r\n\r\n").unwrap();
+            request
+        });
+
+        let hook = ExportHook::HttpPut(format!("http://127.0.0.1:{}/loops/{{name}}", port));
+        let mut reports = Vec::new();
+        hook.run(file.to_str().unwrap(), |sent, total| {
+            reports.push((sent, total))
+        })
+        .unwrap();
+        let request = server.join().unwrap();
+        let head = String::from_utf8_lossy(&request[..200]);
+        assert!(head.starts_with("PUT /loops/take%201.wav HTTP/1.1\r\n"));
+        assert!(head.contains(&format!("Content-Length: {}", UPLOAD_CHUNK + 10)));
+        let total = (UPLOAD_CHUNK + 10) as u64;
+        assert_eq!(
+            reports,
+            vec![(0, total), (UPLOAD_CHUNK as u64, total), (total, total)]
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,420 @@
+// src/audio/flac.rs
+// Minimal FLAC encoder for sharing exports: mono, fixed-size blocks, fixed
+// linear predictors (orders 0-4) with partitioned Rice coding of the residual
+
+use std::io::{self, Write};
+
+// Samples per frame (the reference encoder's default)
+const BLOCK_SIZE: usize = 4096;
+// Highest Rice partition order tried (2^8 partitions per block)
+const MAX_PARTITION_ORDER: u32 = 8;
+// Highest fixed predictor order the format defines
+const MAX_FIXED_ORDER: usize = 4;
+// Subframe types (FIXED carries the predictor order in its low bits)
+const SUBFRAME_CONSTANT: u64 = 0b00_0000;
+const SUBFRAME_VERBATIM: u64 = 0b00_0001;
+const SUBFRAME_FIXED: u64 = 0b00_1000;
+
+/// Encode mono integer samples (`bits_per_sample` wide) as a FLAC stream;
+/// `bpm` is written as a `BPM` Vorbis comment so players and DJ tools see the tempo
+pub fn encode_flac<W: Write>(
+    mut sink: W,
+    samples: &[i32],
+    sample_rate: u32,
+    bits_per_sample: u32,
+    bpm: Option<f64>,
+) -> io::Result<()> {
+    if !(4..=24).contains(&bits_per_sample) || !(1..=655_350).contains(&sample_rate) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "FLAC can't hold {}-bit audio at {} Hz",
+                bits_per_sample, sample_rate
+            ),
+        ));
+    }
+
+    sink.write_all(b"fLaC")?;
+    let mut info = BitWriter::default();
+    info.write(BLOCK_SIZE as u64, 16); // minimum block size
+    info.write(BLOCK_SIZE as u64, 16); // maximum block size
+    info.write(0, 24); // minimum frame size (unknown)
+    info.write(0, 24); // maximum frame size (unknown)
+    info.write(sample_rate as u64, 20);
+    info.write(0, 3); // channels - 1
+    info.write(bits_per_sample as u64 - 1, 5);
+    info.write(samples.len() as u64, 36);
+    info.write(0, 64); // MD5 of the audio: all zeros means not computed
+    info.write(0, 64);
+    write_metadata_block(&mut sink, 0, &info.into_bytes(), bpm.is_none())?;
+
+    if let Some(bpm) = bpm {
+        let vendor = "soundlooper";
+        let tag = format!("BPM={}", (bpm * 100.0).round() / 100.0);
+        let mut comment = Vec::new();
+        comment.extend_from_slice(&(vendor.len() as u32).to_le_bytes());
+        comment.extend_from_slice(vendor.as_bytes());
+        comment.extend_from_slice(&1u32.to_le_bytes());
+        comment.extend_from_slice(&(tag.len() as u32).to_le_bytes());
+        comment.extend_from_slice(tag.as_bytes());
+        write_metadata_block(&mut sink, 4, &comment, true)?;
+    }
+
+    for (number, block) in samples.chunks(BLOCK_SIZE).enumerate() {
+        sink.write_all(&encode_frame(block, number as u64, bits_per_sample))?;
+    }
+    sink.flush()
+}
+
+fn write_metadata_block<W: Write>(
+    sink: &mut W,
+    kind: u8,
+    body: &[u8],
+    last: bool,
+) -> io::Result<()> {
+    sink.write_all(&[(last as u8) << 7 | kind])?;
+    sink.write_all(&(body.len() as u32).to_be_bytes()[1..])?;
+    sink.write_all(body)
+}
+
+fn encode_frame(block: &[i32], number: u64, bits_per_sample: u32) -> Vec<u8> {
+    let mut frame = BitWriter::default();
+    frame.write(0b11_1111_1111_1110, 14); // sync code
+    frame.write(0, 1); // reserved
+    frame.write(0, 1); // fixed block size, so the header carries the frame number
+    frame.write(0b0111, 4); // block size - 1 follows as 16 bits
+    frame.write(0, 4); // sample rate from STREAMINFO
+    frame.write(0, 4); // mono
+    let size_code = match bits_per_sample {
+        8 => 0b001,
+        12 => 0b010,
+        16 => 0b100,
+        20 => 0b101,
+        24 => 0b110,
+        _ => 0b000, // from STREAMINFO
+    };
+    frame.write(size_code, 3);
+    frame.write(0, 1); // reserved
+    frame.write_utf8(number);
+    frame.write(block.len() as u64 - 1, 16);
+    let crc = crc8(frame.bytes());
+    frame.write(crc as u64, 8);
+
+    write_subframe(&mut frame, block, bits_per_sample);
+    frame.pad_to_byte();
+    let crc = crc16(frame.bytes());
+    frame.write(crc as u64, 16);
+    frame.into_bytes()
+}
+
+/// The cheapest of constant, fixed prediction and verbatim for this block
+fn write_subframe(out: &mut BitWriter, block: &[i32], bits_per_sample: u32) {
+    if block.iter().all(|&s| s == block[0]) {
+        write_subframe_header(out, SUBFRAME_CONSTANT);
+        out.write_signed(block[0] as i64, bits_per_sample);
+        return;
+    }
+
+    let verbatim_bits = block.len() as u64 * bits_per_sample as u64;
+    let best = (0..=MAX_FIXED_ORDER.min(block.len() - 1))
+        .map(|order| {
+            let residual = fixed_residual(block, order);
+            let coding = RiceCoding::choose(&residual, block.len(), order);
+            (order, residual, coding)
+        })
+        .min_by_key(|(order, _, coding)| coding.bits + (*order as u64 * bits_per_sample as u64));
+
+    match best {
+        Some((order, residual, coding))
+            if coding.bits + (order as u64 * bits_per_sample as u64) < verbatim_bits =>
+        {
+            write_subframe_header(out, SUBFRAME_FIXED | order as u64);
+            for &warm_up in &block[..order] {
+                out.write_signed(warm_up as i64, bits_per_sample);
+            }
+            coding.write(out, &residual);
+        }
+        _ => {
+            write_subframe_header(out, SUBFRAME_VERBATIM);
+            for &sample in block {
+                out.write_signed(sample as i64, bits_per_sample);
+            }
+        }
+    }
+}
+
+// Zero padding bit, 6-bit type, no wasted bits
+fn write_subframe_header(out: &mut BitWriter, kind: u64) {
+    out.write(kind << 1, 8);
+}
+
+/// Prediction error of the order-`order` polynomial (the format's fixed predictors)
+fn fixed_residual(block: &[i32], order: usize) -> Vec<i64> {
+    let s = |i: usize| block[i] as i64;
+    (order..block.len())
+        .map(|i| match order {
+            0 => s(i),
+            1 => s(i) - s(i - 1),
+            2 => s(i) - 2 * s(i - 1) + s(i - 2),
+            3 => s(i) - 3 * s(i - 1) + 3 * s(i - 2) - s(i - 3),
+            _ => s(i) - 4 * s(i - 1) + 6 * s(i - 2) - 4 * s(i - 3) + s(i - 4),
+        })
+        .collect()
+}
+
+/// Partition order and per-partition Rice parameters for a residual
+struct RiceCoding {
+    order: usize, // predictor order; the first partition is that much shorter
+    partition_order: u32,
+    parameters: Vec<u32>,
+    wide: bool, // 5-bit parameters (RICE2), needed past 14
+    bits: u64,  // size of the residual section (an upper bound)
+}
+
+impl RiceCoding {
+    /// Sizes come from partition sums (sum >> k bounds the quotient bits), so every
+    /// partition order is costed from one pass over the residual
+    fn choose(residual: &[i64], block_len: usize, order: usize) -> Self {
+        // Finest split: partitions must divide the block and outlast the warm-up
+        let mut finest = 0;
+        while finest < MAX_PARTITION_ORDER
+            && block_len.is_multiple_of(1 << (finest + 1))
+            && block_len >> (finest + 1) > order
+        {
+            finest += 1;
+        }
+        let mut sums: Vec<u64> = vec![0; 1 << finest];
+        let mut lengths: Vec<u64> = vec![(block_len >> finest) as u64; 1 << finest];
+        lengths[0] -= order as u64;
+        let mut values = residual.iter().map(|&r| zigzag(r));
+        for (sum, &length) in sums.iter_mut().zip(&lengths) {
+            *sum = values.by_ref().take(length as usize).sum();
+        }
+
+        let mut best: Option<Self> = None;
+        for partition_order in (0..=finest).rev() {
+            let mut parameters = Vec::with_capacity(sums.len());
+            let mut bits = 2 + 4; // coding method, partition order
+            for (&sum, &length) in sums.iter().zip(&lengths) {
+                let (parameter, cost) = (0..=30)
+                    .map(|k| (k, length * (k as u64 + 1) + (sum >> k)))
+                    .min_by_key(|&(_, cost)| cost)
+                    .unwrap();
+                parameters.push(parameter);
+                bits += cost;
+            }
+            let wide = parameters.iter().any(|&k| k > 14);
+            bits += parameters.len() as u64 * if wide { 5 } else { 4 };
+            if best.as_ref().is_none_or(|best| bits < best.bits) {
+                best = Some(Self {
+                    order,
+                    partition_order,
+                    parameters,
+                    wide,
+                    bits,
+                });
+            }
+            // Merge neighbours for the next coarser split
+            sums = sums.chunks(2).map(|pair| pair.iter().sum()).collect();
+            lengths = lengths.chunks(2).map(|pair| pair.iter().sum()).collect();
+        }
+        best.expect("partition order 0 always fits")
+    }
+
+    fn write(&self, out: &mut BitWriter, residual: &[i64]) {
+        out.write(self.wide as u64, 2);
+        out.write(self.partition_order as u64, 4);
+        let per_partition = (residual.len() + self.order) >> self.partition_order;
+        let mut values = residual.iter().map(|&r| zigzag(r));
+        for (partition, &k) in self.parameters.iter().enumerate() {
+            out.write(k as u64, if self.wide { 5 } else { 4 });
+            let length = per_partition - if partition == 0 { self.order } else { 0 };
+            for value in values.by_ref().take(length) {
+                out.write_unary(value >> k);
+                out.write(value & ((1 << k) - 1), k);
+            }
+        }
+    }
+}
+
+/// Signed to unsigned, small magnitudes first (0, -1, 1, -2, ...)
+fn zigzag(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+/// MSB-first bit packing
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    accumulator: u64,
+    pending: u32, // bits in the accumulator, always under 8 between writes
+}
+
+impl BitWriter {
+    fn write(&mut self, value: u64, bits: u32) {
+        // Split so the accumulator never holds more than 7 + 32 bits
+        if bits > 32 {
+            self.write(value >> 32, bits - 32);
+            self.write(value & 0xFFFF_FFFF, 32);
+            return;
+        }
+        let mask = if bits == 64 {
+            u64::MAX
+        } else {
+            (1 << bits) - 1
+        };
+        self.accumulator = self.accumulator << bits | (value & mask);
+        self.pending += bits;
+        while self.pending >= 8 {
+            self.pending -= 8;
+            self.bytes.push((self.accumulator >> self.pending) as u8);
+        }
+        self.accumulator &= (1 << self.pending) - 1;
+    }
+
+    fn write_signed(&mut self, value: i64, bits: u32) {
+        self.write(value as u64, bits);
+    }
+
+    /// `value` zeros then a one
+    fn write_unary(&mut self, mut value: u64) {
+        while value >= 32 {
+            self.write(0, 32);
+            value -= 32;
+        }
+        self.write(1, value as u32 + 1);
+    }
+
+    /// Frame numbers use UTF-8's variable-length layout, extended to 36 bits
+    fn write_utf8(&mut self, value: u64) {
+        if value < 0x80 {
+            self.write(value, 8);
+            return;
+        }
+        let continuation = match value {
+            0..0x800 => 1,
+            0x800..0x1_0000 => 2,
+            0x1_0000..0x20_0000 => 3,
+            0x20_0000..0x400_0000 => 4,
+            0x400_0000..0x8000_0000 => 5,
+            _ => 6,
+        };
+        let lead_mask = (0xFF00u64 >> (continuation + 1)) & 0xFF;
+        self.write(lead_mask | value >> (6 * continuation), 8);
+        for shift in (0..continuation).rev() {
+            self.write(0x80 | (value >> (6 * shift)) & 0x3F, 8);
+        }
+    }
+
+    fn pad_to_byte(&mut self) {
+        if self.pending > 0 {
+            self.write(0, 8 - self.pending);
+        }
+    }
+
+    /// Whole bytes written so far
+    fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    fn into_bytes(mut self) -> Vec<u8> {
+        self.pad_to_byte();
+        self.bytes
+    }
+}
+
+fn crc8(bytes: &[u8]) -> u8 {
+    bytes.iter().fold(0u8, |mut crc, &byte| {
+        crc ^= byte;
+        for _ in 0..8 {
+            crc = if crc & 0x80 != 0 {
+                crc << 1 ^ 0x07
+            } else {
+                crc << 1
+            };
+        }
+        crc
+    })
+}
+
+fn crc16(bytes: &[u8]) -> u16 {
+    bytes.iter().fold(0u16, |mut crc, &byte| {
+        crc ^= (byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                crc << 1 ^ 0x8005
+            } else {
+                crc << 1
+            };
+        }
+        crc
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use symphonia::core::audio::SampleBuffer;
+    use symphonia::core::codecs::DecoderOptions;
+    use symphonia::core::formats::FormatOptions;
+    use symphonia::core::io::MediaSourceStream;
+    use symphonia::core::meta::MetadataOptions;
+    use symphonia::core::probe::Hint;
+
+    /// Decode with an independent FLAC decoder (which checks every frame CRC)
+    fn decode(bytes: Vec<u8>) -> (Vec<i32>, u32) {
+        let stream = MediaSourceStream::new(Box::new(io::Cursor::new(bytes)), Default::default());
+        let mut hint = Hint::new();
+        hint.with_extension("flac");
+        let mut format = symphonia::default::get_probe()
+            .format(
+                &hint,
+                stream,
+                &FormatOptions::default(),
+                &MetadataOptions::default(),
+            )
+            .unwrap()
+            .format;
+        let track = format.default_track().unwrap();
+        let sample_rate = track.codec_params.sample_rate.unwrap();
+        let mut decoder = symphonia::default::get_codecs()
+            .make(&track.codec_params, &DecoderOptions { verify: true })
+            .unwrap();
+        let mut samples = Vec::new();
+        while let Ok(packet) = format.next_packet() {
+            let decoded = decoder.decode(&packet).unwrap();
+            let mut buffer = SampleBuffer::<i32>::new(decoded.capacity() as u64, *decoded.spec());
+            buffer.copy_interleaved_ref(decoded);
+            samples.extend_from_slice(buffer.samples());
+        }
+        (samples, sample_rate)
+    }
+
+    #[test]
+    fn test_round_trip_is_lossless() {
+        // A decaying tone with noise: fixed prediction, odd last block, silence at the end
+        let mut noise = 12_345u32;
+        let mut samples: Vec<i32> = (0..3 * BLOCK_SIZE + 1234)
+            .map(|i| {
+                noise = noise.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+                let tone = (i as f64 * 0.05).sin() * 6_000_000.0 * (-(i as f64) / 8000.0).exp();
+                tone as i32 + (noise >> 24) as i32 - 128
+            })
+            .collect();
+        samples.extend(std::iter::repeat_n(0, BLOCK_SIZE));
+        samples.extend([-8_388_608, 8_388_607, 0, 1]); // full scale, verbatim-sized
+
+        for bits in [16, 24] {
+            let samples: Vec<i32> = samples.iter().map(|&s| s >> (24 - bits)).collect();
+            let mut bytes = Vec::new();
+            encode_flac(&mut bytes, &samples, 44_100, bits, Some(120.0)).unwrap();
+            assert!(bytes.len() < samples.len() * bits as usize / 8);
+            let (decoded, sample_rate) = decode(bytes);
+            assert_eq!(sample_rate, 44_100);
+            assert_eq!(decoded.len(), samples.len());
+            // Decoded samples come back scaled to 32 bits
+            let shift = 32 - bits;
+            assert!(decoded.iter().zip(&samples).all(|(&d, &s)| d >> shift == s));
+        }
+    }
+}
@@ -0,0 +1,77 @@
+// src/audio/input_capture.rs
+// Input callback processing: downmix to mono and convert to the engine rate
+// through preallocated buffers, so callbacks of any size are captured whole
+
+use super::RateConverter;
+
+/// Upper bound on the preallocated chunk; bigger callbacks take several chunks
+pub const MAX_CHUNK_FRAMES: usize = 16384;
+
+#[derive(Debug, Clone)]
+pub struct InputCapture {
+    channels: usize,
+    mono: Vec<f32>,
+    converter: RateConverter,
+    converted: Vec<f32>,
+}
+
+impl InputCapture {
+    /// `max_frames` is the largest callback the device is expected to deliver
+    pub fn new(channels: u16, max_frames: usize, converter: RateConverter) -> Self {
+        let chunk_frames = max_frames.clamp(1, MAX_CHUNK_FRAMES);
+        Self {
+            channels: channels.max(1) as usize,
+            mono: vec![0.0; chunk_frames],
+            converted: vec![0.0; converter.max_output(chunk_frames)],
+            converter,
+        }
+    }
+
+    /// Downmix one callback's interleaved samples and hand them on at the
+    /// engine rate; returns how many samples were delivered
+    pub fn process(&mut self, data: &[f32], mut deliver: impl FnMut(&[f32])) -> usize {
+        let mut delivered = 0;
+        for block in data.chunks(self.mono.len() * self.channels) {
+            let frames = block.len() / self.channels;
+            for (mono, frame) in self.mono.iter_mut().zip(block.chunks_exact(self.channels)) {
+                *mono = frame.iter().sum::<f32>() / self.channels as f32;
+            }
+
+            let samples = if self.converter.is_passthrough() {
+                &self.mono[..frames]
+            } else {
+                let written = self
+                    .converter
+                    .process(&self.mono[..frames], &mut self.converted);
+                &self.converted[..written]
+            };
+            deliver(samples);
+            delivered += samples.len();
+        }
+        delivered
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_large_callbacks_are_captured_whole() {
+        // 8192 stereo frames, left = frame index, right = 0
+        let data: Vec<f32> = (0..8192).flat_map(|i| [i as f32 * 2.0, 0.0]).collect();
+
+        for max_frames in [8192, 4096, 0] {
+            let mut capture = InputCapture::new(2, max_frames, RateConverter::new(48000, 48000));
+            let mut captured = Vec::new();
+            let delivered = capture.process(&data, |samples| captured.extend_from_slice(samples));
+            assert_eq!(delivered, 8192);
+            assert!(captured.iter().enumerate().all(|(i, &s)| s == i as f32));
+        }
+
+        // Converted to a lower engine rate across chunk boundaries
+        let mut capture = InputCapture::new(2, 4096, RateConverter::new(48000, 24000));
+        let delivered = capture.process(&data, |_| {});
+        assert!((delivered as i64 - 4096).abs() <= 1);
+    }
+}
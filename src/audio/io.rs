@@ -1,40 +1,144 @@
-use anyhow::{Result, anyhow};
+use anyhow::{Result, bail};
 use hound::{SampleFormat, WavReader, WavSpec, WavWriter};
 use rubato::{
     Resampler, SincFixedIn, SincInterpolationParameters, SincInterpolationType, WindowFunction,
 };
+use std::cell::Cell;
+use std::fs::{File, OpenOptions};
+use std::io::{BufReader, IsTerminal, Read, Seek, SeekFrom, Write};
 use std::path::Path;
+use std::rc::Rc;
+use std::time::{SystemTime, UNIX_EPOCH};
 
+use super::ExportNormalization;
+use super::analysis::{integrated_loudness, true_peak};
+use super::flac::encode_flac;
+
+// Longest file `import_wav` decodes (10 minutes is about 115 MB of mono samples at 48 kHz)
+pub const DEFAULT_MAX_IMPORT_SECONDS: u32 = 600;
+
+/// A WAV longer than the import cap, refused from its header before any decoding
+#[derive(Debug, Clone, PartialEq)]
+pub struct TooLarge {
+    pub seconds: f64,
+    pub limit_seconds: u32,
+}
+
+impl std::fmt::Display for TooLarge {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:.0} s long; imports are limited to {} s",
+            self.seconds, self.limit_seconds
+        )
+    }
+}
+
+impl std::error::Error for TooLarge {}
+
+/// Decode a WAV to mono at `target_sample_rate`, refusing files over the default cap
 pub fn import_wav<P: AsRef<Path>>(path: P, target_sample_rate: u32) -> Result<Vec<f32>> {
-    let mut reader = WavReader::open(&path)?;
+    import_wav_capped(path, target_sample_rate, Some(DEFAULT_MAX_IMPORT_SECONDS))
+}
+
+/// `import_wav` with a chosen cap (None for none); over the cap fails with `TooLarge`
+pub fn import_wav_capped<P: AsRef<Path>>(
+    path: P,
+    target_sample_rate: u32,
+    max_seconds: Option<u32>,
+) -> Result<Vec<f32>> {
+    let reader = WavReader::open(&path)?;
+    let spec = reader.spec();
+    // The header gives the length, so oversized files fail before they use any memory
+    if spec.sample_rate > 0
+        && let Some(limit_seconds) = max_seconds
+    {
+        let seconds = reader.duration() as f64 / spec.sample_rate as f64;
+        if seconds > limit_seconds as f64 {
+            return Err(TooLarge {
+                seconds,
+                limit_seconds,
+            }
+            .into());
+        }
+    }
+    decode_wav(reader, target_sample_rate, max_seconds, None)
+}
+
+/// Import path that reads the WAV from standard input instead of a file
+pub const STDIN_PATH: &str = "-";
+/// Export path that writes the WAV to standard output instead of a file
+pub const STDOUT_PATH: &str = "-";
+
+/// Decode a WAV from a pipe or other stream that can't seek, such as standard input
+/// Streams from capture tools carry a placeholder length, so the data runs to the
+/// end of the stream and the cap is checked as it's read
+pub fn import_wav_from_reader<R: Read>(
+    reader: R,
+    target_sample_rate: u32,
+    max_seconds: Option<u32>,
+) -> Result<Vec<f32>> {
+    let ended = Rc::new(Cell::new(false));
+    let reader = WavReader::new(BufReader::new(StreamEnd {
+        inner: reader,
+        ended: Rc::clone(&ended),
+    }))?;
+    decode_wav(reader, target_sample_rate, max_seconds, Some(&ended))
+}
+
+/// Flags when the wrapped stream runs dry (hound reports that as a plain read error)
+struct StreamEnd<R> {
+    inner: R,
+    ended: Rc<Cell<bool>>,
+}
+
+impl<R: Read> Read for StreamEnd<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        if read == 0 && !buf.is_empty() {
+            self.ended.set(true);
+        }
+        Ok(read)
+    }
+}
+
+/// Mono samples at `target_sample_rate`; with `stream_end`, running out of data
+/// there is the end of the audio rather than a truncated file
+fn decode_wav<R: Read>(
+    mut reader: WavReader<R>,
+    target_sample_rate: u32,
+    max_seconds: Option<u32>,
+    stream_end: Option<&Cell<bool>>,
+) -> Result<Vec<f32>> {
     let spec = reader.spec();
+    if spec.sample_rate == 0 || spec.channels == 0 {
+        bail!(
+            "Invalid WAV format: {} Hz, {} channels",
+            spec.sample_rate,
+            spec.channels
+        );
+    }
+    let limit = max_seconds.map(|limit_seconds| (limit_seconds, spec.sample_rate));
 
-    // Read samples as f32 in interleaved order
-    let raw_samples: Vec<f32> = match spec.sample_format {
-        SampleFormat::Float => reader
-            .samples::<f32>()
-            .collect::<std::result::Result<Vec<_>, _>>()?,
+    let mono_samples = match spec.sample_format {
+        SampleFormat::Float => downmix(reader.samples::<f32>(), spec.channels, limit, stream_end)?,
         SampleFormat::Int => {
+            if !(1..=32).contains(&spec.bits_per_sample) {
+                bail!("Unsupported bit depth: {}", spec.bits_per_sample);
+            }
             // Convert integer samples to float in [-1.0, 1.0]
-            let max_value = 2_i32.pow((spec.bits_per_sample - 1) as u32) as f32;
-            reader
-                .samples::<i32>()
-                .map(|s| s.unwrap_or(0) as f32 / max_value)
-                .collect()
+            let max_value = (1i64 << (spec.bits_per_sample - 1)) as f32;
+            downmix(
+                reader
+                    .samples::<i32>()
+                    .map(|s| s.map(|s| s as f32 / max_value)),
+                spec.channels,
+                limit,
+                stream_end,
+            )?
         }
     };
 
-    // Downmix to mono if needed by averaging channels per frame
-    let mono_samples: Vec<f32> = if spec.channels > 1 {
-        let ch = spec.channels as usize;
-        raw_samples
-            .chunks(ch)
-            .map(|frame| frame.iter().sum::<f32>() / ch as f32)
-            .collect()
-    } else {
-        raw_samples
-    };
-
     // If sample rates match, return mono as-is
     if spec.sample_rate == target_sample_rate {
         return Ok(mono_samples);
@@ -44,72 +148,564 @@ pub fn import_wav<P: AsRef<Path>>(path: P, target_sample_rate: u32) -> Result<Ve
     resample_audio(&mono_samples, spec.sample_rate, target_sample_rate, 1)
 }
 
-pub fn export_wav<P: AsRef<Path>>(path: P, samples: &[f32], sample_rate: u32) -> Result<()> {
+/// Average interleaved samples into mono frames as they're read, so the interleaved
+/// copy is never held; a trailing partial frame is averaged over the full channel
+/// count. `limit` is (seconds, sample rate) and stops the read once it's passed
+fn downmix(
+    samples: impl Iterator<Item = hound::Result<f32>>,
+    channels: u16,
+    limit: Option<(u32, u32)>,
+    stream_end: Option<&Cell<bool>>,
+) -> Result<Vec<f32>> {
+    let channels = channels as usize;
+    let mut mono = Vec::new();
+    let (mut sum, mut in_frame) = (0.0f32, 0);
+    for sample in samples {
+        let sample = match sample {
+            Ok(sample) => sample,
+            Err(hound::Error::IoError(_)) if stream_end.is_some_and(Cell::get) => break,
+            Err(e) => return Err(e.into()),
+        };
+        sum += sample;
+        in_frame += 1;
+        if in_frame == channels {
+            mono.push(sum / channels as f32);
+            (sum, in_frame) = (0.0, 0);
+            if let Some((limit_seconds, rate)) = limit
+                && mono.len() > limit_seconds as usize * rate as usize
+            {
+                return Err(TooLarge {
+                    seconds: mono.len() as f64 / rate as f64,
+                    limit_seconds,
+                }
+                .into());
+            }
+        }
+    }
+    if in_frame > 0 {
+        mono.push(sum / channels as f32);
+    }
+    Ok(mono)
+}
+
+// smpl / acid / fmt bodies are small; anything bigger is skipped unread
+const MAX_METADATA_CHUNK: u32 = 64 * 1024;
+// ACID flag for one-shots, whose tempo means nothing for looping
+const ACID_ONE_SHOT: u32 = 0x01;
+// ACID flag asking the host to time-stretch the loop to the project tempo
+const ACID_STRETCH: u32 = 0x04;
+// MIDI note written as the root / unity note (middle C)
+const ROOT_NOTE: u32 = 60;
+
+/// Loop and tempo metadata that sample-library and ACID-style WAVs carry
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct WavLoopInfo {
+    pub loop_points: Option<(usize, usize)>, // first smpl loop: start, end (exclusive)
+    pub bpm: Option<f64>,                    // ACID tempo
+}
+
+/// Read the smpl loop and ACID tempo of a WAV; loop points are converted to
+/// `target_sample_rate` to match the samples `import_wav` returns
+pub fn read_loop_info<P: AsRef<Path>>(path: P, target_sample_rate: u32) -> Result<WavLoopInfo> {
+    let mut file = BufReader::new(File::open(path)?);
+    let mut header = [0u8; 12];
+    file.read_exact(&mut header)?;
+    if &header[0..4] != b"RIFF" || &header[8..12] != b"WAVE" {
+        bail!("Not a RIFF WAVE file");
+    }
+
+    let mut source_rate = None;
+    let mut loop_frames = None;
+    let mut bpm = None;
+    let mut chunk = [0u8; 8];
+    while file.read_exact(&mut chunk).is_ok() {
+        let size = u32::from_le_bytes([chunk[4], chunk[5], chunk[6], chunk[7]]);
+        // Chunks are padded to an even length
+        let padded = size as i64 + (size % 2) as i64;
+        let id = &chunk[0..4];
+        if !matches!(id, b"fmt " | b"smpl" | b"acid") || size > MAX_METADATA_CHUNK {
+            file.seek_relative(padded)?;
+            continue;
+        }
+        let mut body = vec![0u8; size as usize];
+        file.read_exact(&mut body)?;
+        file.seek_relative(padded - size as i64)?;
+        match id {
+            b"fmt " => source_rate = le_u32(&body, 4),
+            b"smpl" => {
+                // 36-byte header, then 24-byte loops: id, type, start, end (inclusive), ...
+                if le_u32(&body, 28).is_some_and(|loops| loops > 0)
+                    && let (Some(start), Some(end)) = (le_u32(&body, 44), le_u32(&body, 48))
+                    && start <= end
+                {
+                    loop_frames = Some((start as u64, end as u64 + 1));
+                }
+            }
+            _ => {
+                // flags, root note, two reserved fields, beats, meter, then the tempo
+                let one_shot = le_u32(&body, 0).is_some_and(|flags| flags & ACID_ONE_SHOT != 0);
+                let tempo = le_u32(&body, 20).map(f32::from_bits);
+                if !one_shot && let Some(tempo) = tempo.filter(|t| t.is_finite() && *t > 0.0) {
+                    bpm = Some(tempo as f64);
+                }
+            }
+        }
+    }
+
+    let scale = target_sample_rate as f64 / source_rate.unwrap_or(target_sample_rate).max(1) as f64;
+    let rescale = |frame: u64| (frame as f64 * scale).round() as usize;
+    Ok(WavLoopInfo {
+        loop_points: loop_frames.map(|(start, end)| (rescale(start), rescale(end))),
+        bpm,
+    })
+}
+
+/// Tempo and meter written into exported loops
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LoopTempo {
+    pub bpm: f64,
+    pub beats_per_measure: u32,
+}
+
+/// Append a smpl chunk looping the whole file and an ACID chunk with its tempo
+/// and length in beats to a finished WAV, so DAWs and samplers treat it as a loop
+pub fn write_loop_chunks<P: AsRef<Path>>(
+    path: P,
+    frames: usize,
+    sample_rate: u32,
+    tempo: LoopTempo,
+) -> Result<()> {
+    let mut file = OpenOptions::new().read(true).write(true).open(path)?;
+    append_loop_chunks(&mut file, frames, sample_rate, tempo)
+}
+
+/// `write_loop_chunks` on an open WAV stream
+fn append_loop_chunks<S: Write + Seek>(
+    stream: &mut S,
+    frames: usize,
+    sample_rate: u32,
+    tempo: LoopTempo,
+) -> Result<()> {
+    if frames == 0 {
+        return Ok(());
+    }
+    let mut smpl = Vec::with_capacity(60);
+    for field in [
+        0,                                  // manufacturer
+        0,                                  // product
+        1_000_000_000 / sample_rate.max(1), // sample period in ns
+        ROOT_NOTE,
+        0, // pitch fraction
+        0, // SMPTE format
+        0, // SMPTE offset
+        1, // loops
+        0, // sampler data bytes
+        // The loop: cue id, forward, start, end (inclusive), fraction, play forever
+        0,
+        0,
+        0,
+        frames as u32 - 1,
+        0,
+        0,
+    ] {
+        smpl.extend_from_slice(&field.to_le_bytes());
+    }
+
+    let samples_per_beat = sample_rate as f64 * 60.0 / tempo.bpm;
+    let beats = (frames as f64 / samples_per_beat).round().max(1.0) as u32;
+    let mut acid = Vec::with_capacity(24);
+    acid.extend_from_slice(&ACID_STRETCH.to_le_bytes());
+    acid.extend_from_slice(&(ROOT_NOTE as u16).to_le_bytes());
+    acid.extend_from_slice(&[0u8; 6]); // reserved
+    acid.extend_from_slice(&beats.to_le_bytes());
+    acid.extend_from_slice(&4u16.to_le_bytes()); // meter denominator
+    acid.extend_from_slice(&(tempo.beats_per_measure as u16).to_le_bytes());
+    acid.extend_from_slice(&(tempo.bpm as f32).to_le_bytes());
+
+    let mut end = stream.seek(SeekFrom::End(0))?;
+    // Chunks start on an even offset
+    if end % 2 == 1 {
+        stream.write_all(&[0])?;
+        end += 1;
+    }
+    for (id, body) in [(b"smpl", &smpl), (b"acid", &acid)] {
+        stream.write_all(id)?;
+        stream.write_all(&(body.len() as u32).to_le_bytes())?;
+        stream.write_all(body)?;
+        end += 8 + body.len() as u64;
+    }
+    stream.seek(SeekFrom::Start(4))?;
+    stream.write_all(&((end - 8) as u32).to_le_bytes())?;
+    Ok(())
+}
+
+fn le_u32(bytes: &[u8], offset: usize) -> Option<u32> {
+    let field = bytes.get(offset..offset + 4)?;
+    Some(u32::from_le_bytes([field[0], field[1], field[2], field[3]]))
+}
+
+/// Sample format of written WAV files
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BitDepth {
+    Int16,
+    Int24,
+    #[default]
+    Float32, // lossless; sessions are always saved this way
+}
+
+impl BitDepth {
+    /// From `--bit-depth` / the `bit_depth` config key
+    pub fn from_bits(bits: u16) -> Option<Self> {
+        match bits {
+            16 => Some(Self::Int16),
+            24 => Some(Self::Int24),
+            32 => Some(Self::Float32),
+            _ => None,
+        }
+    }
+}
+
+/// Triangular (TPDF) dither: the sum of two uniform values, peaking at ±1 LSB
+/// Turns the distortion of rounding quiet material into a constant noise floor
+struct TpdfDither {
+    state: u32,
+}
+
+impl TpdfDither {
+    fn new() -> Self {
+        Self { state: 0x9E37_79B9 }
+    }
+
+    // xorshift32, uniform in [-0.5, 0.5)
+    fn uniform(&mut self) -> f64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 17;
+        self.state ^= self.state << 5;
+        self.state as f64 / 4_294_967_296.0 - 0.5
+    }
+
+    fn next(&mut self) -> f64 {
+        self.uniform() + self.uniform()
+    }
+}
+
+pub fn export_wav<P: AsRef<Path>>(
+    path: P,
+    samples: &[f32],
+    sample_rate: u32,
+    bit_depth: BitDepth,
+) -> Result<()> {
+    let file = std::io::BufWriter::new(File::create(path)?);
+    write_wav(file, samples, sample_rate, bit_depth)
+}
+
+/// `export_wav` into any seekable writer (the header's lengths are filled in last)
+fn write_wav<W: Write + Seek>(
+    sink: W,
+    samples: &[f32],
+    sample_rate: u32,
+    bit_depth: BitDepth,
+) -> Result<()> {
+    let (bits_per_sample, sample_format) = match bit_depth {
+        BitDepth::Int16 => (16, SampleFormat::Int),
+        BitDepth::Int24 => (24, SampleFormat::Int),
+        BitDepth::Float32 => (32, SampleFormat::Float),
+    };
     let spec = WavSpec {
         channels: 1, // Mono
         sample_rate,
-        bits_per_sample: 32,
-        sample_format: SampleFormat::Float,
+        bits_per_sample,
+        sample_format,
     };
 
-    let mut writer = WavWriter::create(&path, spec)?;
+    let mut writer = WavWriter::new(sink, spec)?;
 
-    for &sample in samples {
-        writer.write_sample(sample)?;
+    if sample_format == SampleFormat::Float {
+        for &sample in samples {
+            writer.write_sample(sample)?;
+        }
+    } else {
+        for value in quantize(samples, bits_per_sample) {
+            writer.write_sample(value)?;
+        }
     }
 
     writer.finalize()?;
     Ok(())
 }
 
-pub fn export_mixed_wav<P: AsRef<Path>>(
+/// Integer samples `bits` wide, with dither instead of plain rounding
+fn quantize(samples: &[f32], bits: u16) -> impl Iterator<Item = i32> + '_ {
+    let max = (1i32 << (bits - 1)) as f64 - 1.0;
+    let mut dither = TpdfDither::new();
+    samples.iter().map(move |&sample| {
+        let value = (sample as f64 * max + dither.next()).round();
+        value.clamp(-max - 1.0, max) as i32
+    })
+}
+
+/// FLAC is integer only, so 32-bit float exports are written as 24-bit
+pub fn export_flac<P: AsRef<Path>>(
     path: P,
-    layers: &[Vec<f32>],
+    samples: &[f32],
     sample_rate: u32,
+    bit_depth: BitDepth,
+    bpm: Option<f64>,
 ) -> Result<()> {
-    if layers.is_empty() {
-        return Err(anyhow!("No layers to export"));
+    let bits = match bit_depth {
+        BitDepth::Int16 => 16,
+        BitDepth::Int24 | BitDepth::Float32 => 24,
+    };
+    let quantized: Vec<i32> = quantize(samples, bits).collect();
+    let file = std::io::BufWriter::new(File::create(path)?);
+    encode_flac(file, &quantized, sample_rate, bits as u32, bpm)?;
+    Ok(())
+}
+
+/// Normalize a finished mix, soft clip it and write it
+pub fn export_normalized_wav<P: AsRef<Path>>(
+    path: P,
+    mut mixed: Vec<f32>,
+    sample_rate: u32,
+    normalization: ExportNormalization,
+    bit_depth: BitDepth,
+    tempo: Option<LoopTempo>, // tags the file as a loop at this tempo
+) -> Result<()> {
+    normalize(&mut mixed, sample_rate, normalization);
+    export_wav(&path, &mixed, sample_rate, bit_depth)?;
+    match tempo {
+        Some(tempo) => write_loop_chunks(&path, mixed.len(), sample_rate, tempo),
+        None => Ok(()),
+    }
+}
+
+/// `export_normalized_wav`, or FLAC (tagged with the tempo) for a `.flac` path
+pub fn export_normalized<P: AsRef<Path>>(
+    path: P,
+    mut mixed: Vec<f32>,
+    sample_rate: u32,
+    normalization: ExportNormalization,
+    bit_depth: BitDepth,
+    tempo: Option<LoopTempo>,
+) -> Result<()> {
+    let extension = path.as_ref().extension().unwrap_or_default();
+    if is_flac(&path) {
+        normalize(&mut mixed, sample_rate, normalization);
+        let bpm = tempo.map(|tempo| tempo.bpm);
+        export_flac(path, &mixed, sample_rate, bit_depth, bpm)
+    } else if extension.eq_ignore_ascii_case("opus") || extension.eq_ignore_ascii_case("ogg") {
+        bail!("Opus isn't supported; export .flac for a smaller file")
+    } else {
+        export_normalized_wav(path, mixed, sample_rate, normalization, bit_depth, tempo)
     }
+}
+
+/// Whether an export path asks for FLAC
+pub fn is_flac<P: AsRef<Path>>(path: P) -> bool {
+    path.as_ref()
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("flac"))
+}
 
-    // Find the maximum length
-    let max_length = layers.iter().map(|layer| layer.len()).max().unwrap_or(0);
+/// `export_normalized_wav` into a writer that can't seek, such as a pipe; the
+/// file is assembled in memory first since the header comes out last
+pub fn export_normalized_wav_to<W: Write>(
+    mut sink: W,
+    mut mixed: Vec<f32>,
+    sample_rate: u32,
+    normalization: ExportNormalization,
+    bit_depth: BitDepth,
+    tempo: Option<LoopTempo>,
+) -> Result<()> {
+    normalize(&mut mixed, sample_rate, normalization);
+    let mut wav = std::io::Cursor::new(Vec::new());
+    write_wav(&mut wav, &mixed, sample_rate, bit_depth)?;
+    if let Some(tempo) = tempo {
+        append_loop_chunks(&mut wav, mixed.len(), sample_rate, tempo)?;
+    }
+    sink.write_all(wav.get_ref())?;
+    sink.flush()?;
+    Ok(())
+}
 
-    if max_length == 0 {
-        return Err(anyhow!("All layers are empty"));
+/// Standard output for an export to `STDOUT_PATH`, unless it's the terminal
+pub fn stdout_sink() -> Result<std::io::StdoutLock<'static>> {
+    let stdout = std::io::stdout();
+    if stdout.is_terminal() {
+        bail!("standard output is a terminal; redirect it to a file or program");
     }
+    Ok(stdout.lock())
+}
 
-    // Mix all layers
-    let mut mixed = vec![0.0; max_length];
+fn normalize(mixed: &mut [f32], sample_rate: u32, normalization: ExportNormalization) {
+    let normalization_factor = normalization_gain(mixed, sample_rate, normalization);
+    for sample in mixed.iter_mut() {
+        *sample *= normalization_factor;
+        *sample = sample.clamp(-1.0, 1.0); // Soft clipping
+    }
+}
 
-    for layer in layers {
-        for (i, &sample) in layer.iter().enumerate() {
-            if i < max_length {
-                mixed[i] += sample;
+/// Gain that brings a mix to the export target (1.0 for silence)
+fn normalization_gain(mixed: &[f32], sample_rate: u32, target: ExportNormalization) -> f32 {
+    let from_db = |db: f32| 10f32.powf(db / 20.0);
+    match target {
+        ExportNormalization::Peak => {
+            let max_amplitude = mixed.iter().map(|&s| s.abs()).fold(0.0f32, |a, b| a.max(b));
+            if max_amplitude > 0.0 {
+                0.95 / max_amplitude // Leave some headroom
+            } else {
+                1.0
+            }
+        }
+        ExportNormalization::TruePeak(dbtp) => {
+            let peak = true_peak(mixed);
+            if peak > 0.0 {
+                from_db(dbtp) / peak
+            } else {
+                1.0
             }
         }
+        ExportNormalization::Loudness(lufs) => match integrated_loudness(mixed, sample_rate) {
+            // Quiet targets on dense mixes would otherwise clip
+            Some(current) => from_db(lufs - current).min(from_db(-1.0) / true_peak(mixed)),
+            None => 1.0,
+        },
     }
+}
 
-    // Normalize and apply soft clipping
-    let max_amplitude = mixed.iter().map(|&s| s.abs()).fold(0.0f32, |a, b| a.max(b));
+/// Snapshot exports are named `take-NNN_YYYY-MM-DD_HH-MM-SS.wav` (UTC)
+const SNAPSHOT_PREFIX: &str = "take-";
 
-    if max_amplitude > 0.0 {
-        let normalization_factor = 0.95 / max_amplitude; // Leave some headroom
-        for sample in &mut mixed {
-            *sample *= normalization_factor;
-            *sample = sample.clamp(-1.0, 1.0); // Soft clipping
-        }
+/// Number for the next snapshot in `directory`: one past the highest there (1 if none)
+pub fn next_snapshot_number(directory: &Path) -> u32 {
+    let Ok(entries) = std::fs::read_dir(directory) else {
+        return 1;
+    };
+    entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let digits = name.strip_prefix(SNAPSHOT_PREFIX)?.split('_').next()?;
+            digits.parse::<u32>().ok()
+        })
+        .max()
+        .map_or(1, |n| n + 1)
+}
+
+pub fn snapshot_file_name(number: u32, time: SystemTime) -> String {
+    format!(
+        "{}{:03}_{}.wav",
+        SNAPSHOT_PREFIX,
+        number,
+        utc_timestamp(time)
+    )
+}
+
+/// Values for the tokens of an export name template
+#[derive(Debug, Clone, PartialEq)]
+pub struct NameTokens {
+    pub session: String, // session title; "session" when it has none
+    pub bpm: f64,
+    pub time: SystemTime,
+}
+
+/// Expand `{session}`, `{bpm}`, `{date}` and `{time}` (UTC) in an export path, and
+/// `{n}` to the lowest number from 001 whose file doesn't exist yet; that file is
+/// created empty to claim it, so exports rendering at the same time don't share a
+/// number (`release_file_name` gives it back). Unknown tokens are left as they are
+pub fn expand_file_name(template: &str, tokens: &NameTokens) -> String {
+    if !template.contains('{') {
+        return template.to_string();
+    }
+    let session: String = match tokens.session.trim() {
+        "" => "session".to_string(),
+        title => title
+            .chars()
+            .map(|c| if "/\\:*?\"<>|".contains(c) { '-' } else { c })
+            .collect(),
+    };
+    let timestamp = utc_timestamp(tokens.time);
+    let (date, time) = timestamp.split_once('_').unwrap_or((&timestamp, ""));
+    let expanded = template
+        .replace("{session}", &session)
+        .replace("{bpm}", &format!("{}", (tokens.bpm * 10.0).round() / 10.0))
+        .replace("{date}", date)
+        .replace("{time}", time);
+    if !expanded.contains("{n}") {
+        return expanded;
+    }
+    (1..)
+        .map(|n| expanded.replace("{n}", &format!("{:03}", n)))
+        .find(|name| {
+            // Other failures (a missing directory) are left for the export to report
+            !matches!(
+                OpenOptions::new().write(true).create_new(true).open(name),
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists
+            )
+        })
+        .expect("some number is free")
+}
+
+/// Remove the file `expand_file_name` claimed for `path` (a `{n}` template),
+/// so an export that failed doesn't leave it behind; a named file is the
+/// user's own and is left alone
+pub fn release_file_name(template: &str, path: &str) {
+    if template.contains("{n}") {
+        let _ = std::fs::remove_file(path);
     }
+}
+
+/// `YYYY-MM-DD_HH-MM-SS` in UTC, for file names that sort by time
+pub fn utc_timestamp(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let (days, secs_of_day) = ((secs / 86_400) as i64, secs % 86_400);
 
-    export_wav(path, &mixed, sample_rate)
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}_{:02}-{:02}-{:02}",
+        year,
+        month,
+        day,
+        secs_of_day / 3600,
+        secs_of_day / 60 % 60,
+        secs_of_day % 60
+    )
 }
 
-fn resample_audio(
+// Beyond this either way a rate is corrupt, not a format (and the output would be huge)
+const MAX_RESAMPLE_RATIO: f64 = 256.0;
+
+/// Resample interleaved audio (no-op if the rates match)
+pub fn resample_audio(
     samples: &[f32],
     input_rate: u32,
     output_rate: u32,
     channels: usize,
 ) -> Result<Vec<f32>> {
-    if input_rate == output_rate {
+    let ratio = output_rate as f64 / input_rate as f64;
+    if channels == 0 || !(1.0 / MAX_RESAMPLE_RATIO..=MAX_RESAMPLE_RATIO).contains(&ratio) {
+        bail!(
+            "Cannot resample {} channels from {} Hz to {} Hz",
+            channels,
+            input_rate,
+            output_rate
+        );
+    }
+    if input_rate == output_rate || samples.len() < channels {
         return Ok(samples.to_vec());
     }
 
@@ -122,20 +718,28 @@ fn resample_audio(
         window: WindowFunction::BlackmanHarris2,
     };
 
-    let mut resampler = SincFixedIn::<f32>::new(
-        output_rate as f64 / input_rate as f64,
-        2.0, // Max ratio
-        params,
-        samples.len() / channels,
-        channels,
-    )?;
+    let frames = samples.len() / channels;
+    // 2.0: max relative ratio (the ratio is fixed here)
+    let mut resampler = SincFixedIn::<f32>::new(ratio, 2.0, params, frames, channels)?;
 
-    // Resample
-    let input = vec![samples];
+    // Resample each channel (a trailing partial frame is dropped)
+    let input: Vec<Vec<f32>> = (0..channels)
+        .map(|channel| {
+            samples[channel..]
+                .iter()
+                .step_by(channels)
+                .take(frames)
+                .copied()
+                .collect()
+        })
+        .collect();
     let output = resampler.process(&input, None)?;
 
-    // Flatten the output
-    Ok(output.into_iter().flatten().collect())
+    // Interleave the output
+    let length = output.first().map_or(0, Vec::len);
+    Ok((0..length)
+        .flat_map(|frame| output.iter().map(move |channel| channel[frame]))
+        .collect())
 }
 
 #[cfg(test)]
@@ -150,7 +754,7 @@ mod tests {
 
         // Export to temporary file
         let temp_path = "test_roundtrip.wav";
-        export_wav(temp_path, &original_samples, sample_rate).unwrap();
+        export_wav(temp_path, &original_samples, sample_rate, BitDepth::Float32).unwrap();
 
         // Import back
         let imported_samples = import_wav(temp_path, sample_rate).unwrap();
@@ -164,4 +768,414 @@ mod tests {
             assert!((orig - imp).abs() < 0.001);
         }
     }
+
+    #[test]
+    fn test_import_cap_and_downmix() {
+        // Two seconds of stereo at 8 kHz, left and right averaging to 0.25
+        let path = std::env::temp_dir().join("soundlooper_test_import_cap.wav");
+        let spec = WavSpec {
+            channels: 2,
+            sample_rate: 8_000,
+            bits_per_sample: 32,
+            sample_format: SampleFormat::Float,
+        };
+        let mut writer = WavWriter::create(&path, spec).unwrap();
+        for _ in 0..16_000 {
+            writer.write_sample(0.5f32).unwrap();
+            writer.write_sample(0.0f32).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        let error = import_wav_capped(&path, 8_000, Some(1)).unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<TooLarge>(),
+            Some(&TooLarge {
+                seconds: 2.0,
+                limit_seconds: 1
+            })
+        );
+        let samples = import_wav_capped(&path, 8_000, None).unwrap();
+        let _ = fs::remove_file(&path);
+        assert_eq!(samples.len(), 16_000);
+        assert!(samples.iter().all(|&s| s == 0.25));
+    }
+
+    #[test]
+    fn test_import_from_stream() {
+        // What `arecord` writes to a pipe: 16-bit mono with a placeholder data length
+        let spec = WavSpec {
+            channels: 1,
+            sample_rate: 8_000,
+            bits_per_sample: 16,
+            sample_format: SampleFormat::Int,
+        };
+        let mut bytes = std::io::Cursor::new(Vec::new());
+        let mut writer = WavWriter::new(&mut bytes, spec).unwrap();
+        for _ in 0..16_000 {
+            writer.write_sample(16_384i16).unwrap();
+        }
+        writer.finalize().unwrap();
+        let mut bytes = bytes.into_inner();
+        let data = bytes.windows(4).position(|id| id == b"data").unwrap();
+        bytes[data + 4..data + 8].copy_from_slice(&0x7fff_fffeu32.to_le_bytes());
+
+        // A byte slice reads but can't seek
+        let samples = import_wav_from_reader(&bytes[..], 8_000, None).unwrap();
+        assert_eq!(samples.len(), 16_000);
+        assert!(samples.iter().all(|&s| s == 0.5));
+
+        let error = import_wav_from_reader(&bytes[..], 8_000, Some(1)).unwrap_err();
+        assert_eq!(error.downcast_ref::<TooLarge>().unwrap().limit_seconds, 1);
+    }
+
+    #[test]
+    fn test_16_bit_export_is_dithered() {
+        let sample_rate = 44100;
+        let lsb = 1.0 / 32767.0;
+        // Half an LSB: plain rounding would write nothing but zeros
+        let quiet: Vec<f32> = (0..sample_rate)
+            .map(|i| {
+                0.5 * lsb
+                    * (2.0 * std::f32::consts::PI * 100.0 * i as f32 / sample_rate as f32).sin()
+            })
+            .collect();
+        let temp_path = "test_dither.wav";
+        export_wav(temp_path, &quiet, sample_rate, BitDepth::Int16).unwrap();
+        let written: Vec<i32> = WavReader::open(temp_path)
+            .unwrap()
+            .samples::<i32>()
+            .map(|s| s.unwrap())
+            .collect();
+        let imported = import_wav(temp_path, sample_rate).unwrap();
+        let _ = fs::remove_file(temp_path);
+
+        assert_eq!(written.len(), quiet.len());
+        assert!(written.iter().all(|s| s.abs() <= 2));
+        // The signal survives in the average of the dithered samples
+        let correlation: f32 = quiet.iter().zip(&written).map(|(q, &w)| q * w as f32).sum();
+        assert!(correlation > 0.0);
+        for (orig, imp) in quiet.iter().zip(imported.iter()) {
+            assert!((orig - imp).abs() <= 2.5 * lsb);
+        }
+        assert_eq!(BitDepth::from_bits(16), Some(BitDepth::Int16));
+        assert_eq!(BitDepth::from_bits(8), None);
+    }
+
+    #[test]
+    fn test_read_smpl_and_acid_chunks() {
+        let temp_path = "test_loop_info.wav";
+        export_wav(temp_path, &[0.0; 1000], 22050, BitDepth::Int16).unwrap();
+        let mut bytes = fs::read(temp_path).unwrap();
+
+        let mut smpl = vec![0u8; 36 + 24];
+        smpl[28..32].copy_from_slice(&1u32.to_le_bytes());
+        smpl[44..48].copy_from_slice(&100u32.to_le_bytes());
+        smpl[48..52].copy_from_slice(&899u32.to_le_bytes());
+        let mut acid = vec![0u8; 24];
+        acid[20..24].copy_from_slice(&128.0f32.to_le_bytes());
+        for (id, body) in [(b"smpl", smpl), (b"acid", acid)] {
+            bytes.extend_from_slice(id);
+            bytes.extend_from_slice(&(body.len() as u32).to_le_bytes());
+            bytes.extend_from_slice(&body);
+        }
+        let riff_size = (bytes.len() - 8) as u32;
+        bytes[4..8].copy_from_slice(&riff_size.to_le_bytes());
+        fs::write(temp_path, &bytes).unwrap();
+
+        // Imported at twice the file's rate, so the loop doubles too
+        let info = read_loop_info(temp_path, 44100).unwrap();
+        let plain = read_loop_info(temp_path, 22050).unwrap();
+        let samples = import_wav(temp_path, 22050).unwrap();
+        let _ = fs::remove_file(temp_path);
+
+        assert_eq!(info.loop_points, Some((200, 1800)));
+        assert_eq!(info.bpm, Some(128.0));
+        assert_eq!(plain.loop_points, Some((100, 900)));
+        assert_eq!(samples.len(), 1000);
+
+        let none_path = "test_loop_info_none.wav";
+        export_wav(none_path, &[0.0; 10], 44100, BitDepth::Float32).unwrap();
+        let none = read_loop_info(none_path, 44100).unwrap();
+        let _ = fs::remove_file(none_path);
+        assert_eq!(none, WavLoopInfo::default());
+    }
+
+    #[test]
+    fn test_exported_loop_chunks_read_back() {
+        let temp_path = "test_loop_chunks.wav";
+        let tempo = LoopTempo {
+            bpm: 90.0,
+            beats_per_measure: 3,
+        };
+        // Odd data length (24-bit mono) exercises the chunk padding
+        let samples = vec![0.25; 44101];
+        export_wav(temp_path, &samples, 44100, BitDepth::Int24).unwrap();
+        write_loop_chunks(temp_path, samples.len(), 44100, tempo).unwrap();
+        let info = read_loop_info(temp_path, 44100).unwrap();
+        let imported = import_wav(temp_path, 44100).unwrap();
+        let _ = fs::remove_file(temp_path);
+
+        assert_eq!(info.loop_points, Some((0, samples.len())));
+        assert_eq!(info.bpm, Some(90.0));
+        assert_eq!(imported.len(), samples.len());
+    }
+
+    #[test]
+    fn test_stream_export_matches_file() {
+        let temp_path = "test_stream_export.wav";
+        let tempo = Some(LoopTempo {
+            bpm: 120.0,
+            beats_per_measure: 4,
+        });
+        let mix: Vec<f32> = (0..4801).map(|i| (i as f32 * 0.01).sin() * 0.3).collect();
+        export_normalized_wav(
+            temp_path,
+            mix.clone(),
+            48_000,
+            ExportNormalization::Peak,
+            BitDepth::Float32,
+            tempo,
+        )
+        .unwrap();
+        let file = fs::read(temp_path).unwrap();
+        let _ = fs::remove_file(temp_path);
+
+        let mut streamed = Vec::new();
+        export_normalized_wav_to(
+            &mut streamed,
+            mix.clone(),
+            48_000,
+            ExportNormalization::Peak,
+            BitDepth::Float32,
+            tempo,
+        )
+        .unwrap();
+        assert_eq!(streamed, file);
+    }
+
+    #[test]
+    fn test_expand_file_name() {
+        let dir = std::env::temp_dir().join("soundlooper_test_name_template");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let tokens = NameTokens {
+            session: "Friday: Jam".to_string(),
+            bpm: 92.5,
+            time: UNIX_EPOCH + std::time::Duration::from_secs(1_709_210_096),
+        };
+        let template = dir.join("{session}_{bpm}bpm_{date}_{time}_{n}{x}.flac");
+        let template = template.to_string_lossy();
+
+        let first = expand_file_name(&template, &tokens);
+        assert_eq!(
+            first,
+            dir.join("Friday- Jam_92.5bpm_2024-02-29_12-34-56_001{x}.flac")
+                .to_string_lossy()
+        );
+        // The first name is claimed, so the next export gets 002
+        let second = expand_file_name(&template, &tokens);
+        assert!(second.ends_with("_002{x}.flac"), "{}", second);
+        // A failed export gives its number back
+        release_file_name(&template, &second);
+        assert!(!std::path::Path::new(&second).exists());
+        assert_eq!(expand_file_name(&template, &tokens), second);
+        let named = dir.join("named.wav").to_string_lossy().to_string();
+        fs::write(&named, b"RIFF").unwrap();
+        release_file_name(&named, &named);
+        assert!(std::path::Path::new(&named).exists());
+
+        let untitled = NameTokens {
+            session: " ".to_string(),
+            bpm: 120.0,
+            ..tokens
+        };
+        assert_eq!(
+            expand_file_name("{session}_{bpm}.wav", &untitled),
+            "session_120.wav"
+        );
+        assert_eq!(expand_file_name("plain.wav", &untitled), "plain.wav");
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_snapshot_names() {
+        let time = UNIX_EPOCH + std::time::Duration::from_secs(1_709_210_096);
+        assert_eq!(
+            snapshot_file_name(7, time),
+            "take-007_2024-02-29_12-34-56.wav"
+        );
+
+        let dir = std::env::temp_dir().join("soundlooper_test_snapshots");
+        let _ = fs::remove_dir_all(&dir);
+        assert_eq!(next_snapshot_number(&dir), 1);
+        fs::create_dir_all(&dir).unwrap();
+        for name in [
+            "take-002_2024-02-29_12-00-00.wav",
+            "take-010_2024-02-29_12-05-00.wav",
+            "mix.wav",
+        ] {
+            fs::write(dir.join(name), b"").unwrap();
+        }
+        assert_eq!(next_snapshot_number(&dir), 11);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_export_normalization() {
+        let sample_rate = 48000;
+        let sine: Vec<f32> = (0..sample_rate * 2)
+            .map(|i| {
+                0.1 * (2.0 * std::f32::consts::PI * 1000.0 * i as f32 / sample_rate as f32).sin()
+            })
+            .collect();
+        let normalized = |target: &str| {
+            let gain = normalization_gain(&sine, sample_rate, target.parse().unwrap());
+            sine.iter().map(|s| s * gain).collect::<Vec<f32>>()
+        };
+
+        let loud = normalized("-20 LUFS");
+        let lufs = integrated_loudness(&loud, sample_rate).unwrap();
+        assert!((lufs + 20.0).abs() < 0.1, "{}", lufs);
+        assert!((true_peak(&normalized("-1 dBTP")) - 0.891).abs() < 0.01);
+        // -3 LUFS would need a peak above -1 dBTP
+        assert!(true_peak(&normalized("-3")) <= 0.892);
+        assert!((true_peak(&normalized("peak")) - 0.95).abs() < 0.01);
+
+        // Samples at ±0.707 of a sine whose crest falls between them
+        let between: Vec<f32> = (0..64)
+            .map(|i| (std::f32::consts::FRAC_PI_2 * i as f32 + std::f32::consts::FRAC_PI_4).sin())
+            .collect();
+        assert!(true_peak(&between) > 0.95);
+
+        assert_eq!(integrated_loudness(&[0.0; 100], sample_rate), None);
+        assert!("-14 dBFS".parse::<ExportNormalization>().is_err());
+        assert!("loud".parse::<ExportNormalization>().is_err());
+    }
+
+    // Corrupt and unusual files must come back as errors, never panics
+    mod fuzz {
+        use super::*;
+        use proptest::prelude::*;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        /// A valid WAV in the given format, as bytes
+        fn wav_bytes(
+            channels: u16,
+            sample_rate: u32,
+            bits: u16,
+            float: bool,
+            frames: usize,
+        ) -> Vec<u8> {
+            // hound won't write every rate a file can claim; patched in below
+            let spec = WavSpec {
+                channels,
+                sample_rate: 44_100,
+                bits_per_sample: bits,
+                sample_format: if float {
+                    SampleFormat::Float
+                } else {
+                    SampleFormat::Int
+                },
+            };
+            let mut bytes = std::io::Cursor::new(Vec::new());
+            let mut writer = WavWriter::new(&mut bytes, spec).unwrap();
+            for i in 0..frames * channels as usize {
+                if float {
+                    writer.write_sample((i as f32 * 0.37).sin()).unwrap();
+                } else {
+                    let max = (1i64 << (bits - 1)) - 1;
+                    writer
+                        .write_sample((i as i64 * 7919 % (2 * max) - max) as i32)
+                        .unwrap();
+                }
+            }
+            writer.finalize().unwrap();
+            let mut bytes = bytes.into_inner();
+            // fmt chunk: sample rate, then byte rate
+            let block_align = channels as u32 * bits as u32 / 8;
+            bytes[24..28].copy_from_slice(&sample_rate.to_le_bytes());
+            bytes[28..32].copy_from_slice(&sample_rate.wrapping_mul(block_align).to_le_bytes());
+            bytes
+        }
+
+        /// Import `bytes` from a file of their own (cases run on several threads)
+        fn import_bytes(bytes: &[u8], target_rate: u32) -> (Result<Vec<f32>>, Result<WavLoopInfo>) {
+            static CASE: AtomicUsize = AtomicUsize::new(0);
+            let path = std::env::temp_dir().join(format!(
+                "soundlooper_fuzz_{}_{}.wav",
+                std::process::id(),
+                CASE.fetch_add(1, Ordering::Relaxed)
+            ));
+            fs::write(&path, bytes).unwrap();
+            let result = (
+                import_wav(&path, target_rate),
+                read_loop_info(&path, target_rate),
+            );
+            let _ = fs::remove_file(&path);
+            result
+        }
+
+        fn format() -> impl Strategy<Value = (u16, bool)> {
+            prop_oneof![
+                Just((8, false)),
+                Just((16, false)),
+                Just((24, false)),
+                Just((32, false)),
+                Just((32, true)),
+            ]
+        }
+
+        proptest! {
+            #![proptest_config(ProptestConfig::with_cases(64))]
+
+            #[test]
+            fn valid_files_import_at_any_rate(
+                channels in 1u16..=12,
+                sample_rate in prop_oneof![1u32..=1000, 8000u32..=384_000, Just(u32::MAX)],
+                (bits, float) in format(),
+                frames in 0usize..600,
+                target_rate in prop_oneof![Just(44_100u32), Just(48_000), 8000u32..=192_000],
+            ) {
+                let bytes = wav_bytes(channels, sample_rate, bits, float, frames);
+                let (samples, _) = import_bytes(&bytes, target_rate);
+                if let Ok(samples) = samples {
+                    prop_assert!(samples.iter().all(|s| s.is_finite()));
+                }
+            }
+
+            #[test]
+            fn corrupt_files_fail_cleanly(
+                channels in 1u16..=8,
+                (bits, float) in format(),
+                frames in 0usize..300,
+                edits in prop::collection::vec((any::<prop::sample::Index>(), any::<u8>()), 1..16),
+                cut in any::<prop::sample::Index>(),
+            ) {
+                let mut bytes = wav_bytes(channels, 44_100, bits, float, frames);
+                // Headers are where the damage matters, so aim most edits there
+                for (index, value) in edits {
+                    let at = index.index(bytes.len().min(64));
+                    bytes[at] = value;
+                }
+                bytes.truncate(cut.index(bytes.len() + 1));
+                let _ = import_bytes(&bytes, 48_000);
+            }
+
+            #[test]
+            fn resampler_handles_any_rates(
+                samples in prop::collection::vec(-1.0f32..1.0, 0..2000),
+                input_rate in prop_oneof![0u32..=10, 8000u32..=384_000, Just(u32::MAX)],
+                output_rate in prop_oneof![0u32..=10, 8000u32..=384_000, Just(u32::MAX)],
+                channels in 0usize..=4,
+            ) {
+                if let Ok(output) = resample_audio(&samples, input_rate, output_rate, channels) {
+                    prop_assert!(output.iter().all(|s| s.is_finite()));
+                    // Matching rates pass the samples through untouched
+                    if input_rate != output_rate {
+                        prop_assert_eq!(output.len() % channels, 0);
+                    }
+                }
+            }
+        }
+    }
 }
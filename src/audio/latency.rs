@@ -0,0 +1,190 @@
+// src/audio/latency.rs
+// Callback timing for the latency tuning assistant (--tune-latency) and the
+// device delays used to warn about (and compensate for) high-latency devices
+
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// Round trips above this (typical of Bluetooth) make overdubs land audibly late
+pub const HIGH_LATENCY: Duration = Duration::from_millis(80);
+
+/// Buffer sizes tried in frames, smallest (lowest latency) first
+pub const TRIAL_BUFFER_SIZES: [u32; 5] = [64, 128, 256, 512, 1024];
+
+/// How long each buffer size runs
+pub const TRIAL_DURATION: Duration = Duration::from_secs(3);
+
+// Callbacks right after starting arrive in bursts while the device fills its buffers
+const WARMUP: Duration = Duration::from_millis(500);
+
+/// Gaps between output callbacks; one more than twice the buffer period most
+/// likely means the device ran dry (an xrun)
+#[derive(Debug, Clone)]
+pub struct CallbackTimer {
+    period: Duration,
+    started: Instant,
+    last: Option<Instant>,
+    pub callbacks: u64,
+    pub late_callbacks: u64,
+    pub worst_gap: Duration,
+}
+
+impl CallbackTimer {
+    pub fn new(frames: u32, sample_rate: u32, started: Instant) -> Self {
+        Self {
+            period: Duration::from_secs_f64(frames as f64 / sample_rate.max(1) as f64),
+            started,
+            last: None,
+            callbacks: 0,
+            late_callbacks: 0,
+            worst_gap: Duration::ZERO,
+        }
+    }
+
+    pub fn tick(&mut self, now: Instant) {
+        if now.duration_since(self.started) < WARMUP {
+            return;
+        }
+        if let Some(last) = self.last {
+            let gap = now.duration_since(last);
+            self.worst_gap = self.worst_gap.max(gap);
+            if gap > self.period * 2 {
+                self.late_callbacks += 1;
+            }
+        }
+        self.last = Some(now);
+        self.callbacks += 1;
+    }
+}
+
+/// Result of running the devices at one buffer size
+#[derive(Debug, Clone, PartialEq)]
+pub struct BufferTrial {
+    pub frames: u32, // after the devices clamp the requested size
+    pub sample_rate: u32,
+    pub callbacks: u64,
+    pub late_callbacks: u64,
+    pub stream_errors: u64,
+    pub worst_gap: Duration,
+}
+
+impl BufferTrial {
+    pub fn from_timer(frames: u32, sample_rate: u32, timer: &CallbackTimer, errors: u64) -> Self {
+        Self {
+            frames,
+            sample_rate,
+            callbacks: timer.callbacks,
+            late_callbacks: timer.late_callbacks,
+            stream_errors: errors,
+            worst_gap: timer.worst_gap,
+        }
+    }
+
+    /// Input plus output buffering in milliseconds
+    pub fn latency_ms(&self) -> f64 {
+        2.0 * self.frames as f64 * 1000.0 / self.sample_rate.max(1) as f64
+    }
+
+    /// Ran without device errors or dropouts
+    pub fn is_stable(&self) -> bool {
+        self.callbacks > 0 && self.stream_errors == 0 && self.late_callbacks == 0
+    }
+}
+
+impl fmt::Display for BufferTrial {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} frames (~{:.1} ms): {} callbacks, {} late, {} errors, worst gap {:.1} ms - {}",
+            self.frames,
+            self.latency_ms(),
+            self.callbacks,
+            self.late_callbacks,
+            self.stream_errors,
+            self.worst_gap.as_secs_f64() * 1000.0,
+            if self.is_stable() {
+                "stable"
+            } else {
+                "unstable"
+            }
+        )
+    }
+}
+
+/// Capture and playback delays reported by the stream callbacks
+#[derive(Debug, Default)]
+pub struct DeviceLatency {
+    input_us: AtomicU64,
+    output_us: AtomicU64,
+}
+
+impl DeviceLatency {
+    /// Time from the input reaching the device to its callback
+    pub fn record_input(&self, delay: Duration) {
+        self.input_us
+            .store(delay.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    /// Time from the output callback to the audio being heard
+    pub fn record_output(&self, delay: Duration) {
+        self.output_us
+            .store(delay.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    /// Output heard late plus input captured late: how far behind the playback a
+    /// recording lands (zero if the backend reports no timestamps)
+    pub fn round_trip(&self) -> Duration {
+        Duration::from_micros(
+            self.input_us.load(Ordering::Relaxed) + self.output_us.load(Ordering::Relaxed),
+        )
+    }
+}
+
+/// Smallest buffer size that ran cleanly
+pub fn recommend(trials: &[BufferTrial]) -> Option<u32> {
+    trials
+        .iter()
+        .filter(|trial| trial.is_stable())
+        .map(|trial| trial.frames)
+        .min()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_late_callbacks_and_recommendation() {
+        let start = Instant::now();
+        // 480 frames at 48 kHz = 10 ms per callback
+        let mut timer = CallbackTimer::new(480, 48000, start);
+        timer.tick(start + Duration::from_millis(100)); // still warming up
+        for ms in [600, 610, 620, 650, 660] {
+            timer.tick(start + Duration::from_millis(ms));
+        }
+        assert_eq!(timer.callbacks, 5);
+        assert_eq!(timer.late_callbacks, 1);
+        assert_eq!(timer.worst_gap, Duration::from_millis(30));
+
+        let unstable = BufferTrial::from_timer(480, 48000, &timer, 0);
+        assert!(!unstable.is_stable());
+        let stable = BufferTrial {
+            frames: 960,
+            late_callbacks: 0,
+            ..unstable.clone()
+        };
+        let larger = BufferTrial {
+            frames: 1920,
+            ..stable.clone()
+        };
+        assert_eq!(recommend(&[larger, unstable, stable]), Some(960));
+        assert_eq!(recommend(&[]), None);
+
+        let latency = DeviceLatency::default();
+        latency.record_input(Duration::from_millis(40));
+        latency.record_output(Duration::from_millis(150));
+        assert!(latency.round_trip() > HIGH_LATENCY);
+        assert_eq!(latency.round_trip(), Duration::from_millis(190));
+    }
+}
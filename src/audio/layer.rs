@@ -1,3 +1,51 @@
+use std::borrow::Cow;
+use std::ops::Range;
+use std::sync::{Arc, Weak};
+
+use serde::{Deserialize, Serialize};
+
+use super::undo_history::QuantizedBuffer;
+use super::{LayerPhase, LoopRatio, OutputBus, TakeLanes};
+
+/// Named position inside a layer's buffer (e.g. "A", "B", "verse")
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Marker {
+    pub name: String,
+    pub position: usize,
+}
+
+/// A parked layer's audio: 16-bit, shared with its undo history, plus a weak
+/// link to the full-precision buffer it was encoded from so that a job which
+/// started on that buffer still sees the layer as unchanged
+#[derive(Debug, Clone)]
+struct ParkedAudio {
+    quantized: Arc<QuantizedBuffer>,
+    source: Weak<Vec<f32>>,
+}
+
+/// A layer's audio as playback reads it (no allocation either way)
+#[derive(Clone, Copy)]
+enum Samples<'a> {
+    Full(&'a [f32]),
+    Parked(&'a QuantizedBuffer),
+}
+
+impl Samples<'_> {
+    fn len(self) -> usize {
+        match self {
+            Samples::Full(samples) => samples.len(),
+            Samples::Parked(quantized) => quantized.len(),
+        }
+    }
+
+    fn get(self, index: usize) -> f32 {
+        match self {
+            Samples::Full(samples) => samples[index],
+            Samples::Parked(quantized) => quantized.sample(index),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct AudioLayer {
     pub id: usize,
@@ -12,9 +60,34 @@ pub struct AudioLayer {
     pub loop_end: usize,
     pub undo_history: crate::audio::undo_history::UndoHistory,
     pub meter: crate::audio::peak_meter::PeakMeter,
+    pub record_meter: crate::audio::peak_meter::PeakMeter, // input written while capturing
+    silent_input: usize, // samples in a row under SILENT_INPUT while capturing
+    pub markers: Vec<Marker>,
+    pub output_bus: OutputBus,
+    pub is_cueing: bool,    // pre-listen: plays on the cue bus only
+    pub is_locked: bool,    // refuses record/clear/import/volume commands
+    pub is_solo_safe: bool, // keeps playing while other layers are soloed
+    // Fit to master: varispeed so one pass lasts exactly this many samples
+    pub fit_length: Option<usize>,
+    fit_phase: f64, // fraction of a sample between reads while varispeeding
+    // Replace with monitoring: a new take captured while the old one plays on
+    // the cue bus; it only becomes the layer's audio once confirmed
+    pub replacement: Option<Vec<f32>>,
+    pub is_replacing: bool, // input is going into `replacement`
+    pub takes: TakeLanes,   // record passes, for picking the best one
+    // Parked (idle layer): the audio is only kept as a 16-bit copy, shared with
+    // the undo history's current state, and playback decodes it as it reads
+    parked: Option<ParkedAudio>,
 }
 
 impl AudioLayer {
+    /// Level of a cued layer when it has to share the main output (-12 dB)
+    pub const CUE_PREVIEW_GAIN: f32 = 0.25;
+    /// Largest speed change fit to master applies (about a semitone)
+    pub const MAX_FIT_CHANGE: f64 = 0.06;
+    /// Input below this is taken as no signal at all (about -90 dBFS, under 16-bit dither)
+    pub const SILENT_INPUT: f32 = 3.2e-5;
+
     pub fn new(id: usize) -> Self {
         let mut layer = Self {
             id,
@@ -29,20 +102,55 @@ impl AudioLayer {
             loop_end: 0,
             undo_history: crate::audio::undo_history::UndoHistory::new(),
             meter: crate::audio::peak_meter::PeakMeter::new(),
+            record_meter: crate::audio::peak_meter::PeakMeter::new(),
+            silent_input: 0,
+            markers: Vec::new(),
+            output_bus: OutputBus::Main,
+            is_cueing: false,
+            is_locked: false,
+            is_solo_safe: false,
+            fit_length: None,
+            fit_phase: 0.0,
+            replacement: None,
+            is_replacing: false,
+            takes: TakeLanes::new(),
+            parked: None,
         };
 
         // Save initial empty state to history
-        layer.save_state_to_history();
+        layer.checkpoint();
         layer
     }
 
+    /// Copy for offline rendering (exports): same audio and mix settings, no undo
+    /// history, playing from the loop start
+    pub fn playback_copy(&self) -> Self {
+        let mut copy = Self::new(self.id);
+        copy.buffer = self.audio().into_owned();
+        copy.volume = self.volume;
+        copy.is_muted = self.is_muted;
+        copy.is_solo = self.is_solo;
+        copy.is_solo_safe = self.is_solo_safe;
+        copy.loop_start = self.loop_start;
+        copy.loop_end = self.loop_end;
+        copy.output_bus = self.output_bus;
+        copy.fit_length = self.fit_length;
+        copy.start_playing();
+        copy
+    }
+
     pub fn start_recording(&mut self) {
         self.is_recording = true;
         self.is_playing = false;
+        self.is_cueing = false;
 
         // Save current state to undo history before starting recording
-        self.save_state_to_history();
+        self.checkpoint();
+        self.save_take();
+        self.reset_input_tracking();
 
+        // The new take starts empty, so a parked copy isn't brought back
+        self.parked = None;
         self.buffer.clear();
         self.playback_position = 0;
         self.loop_start = 0;
@@ -50,28 +158,84 @@ impl AudioLayer {
     }
 
     pub fn stop_recording(&mut self) {
+        if !self.is_recording {
+            return;
+        }
         self.is_recording = false;
+        if self.is_playing {
+            // Overdub: the loop keeps its length and loop points
+            self.checkpoint();
+            return;
+        }
         if !self.buffer.is_empty() {
             self.loop_end = self.buffer.len();
             self.is_playing = true;
 
             // Save the recorded state to history after recording stops
-            self.save_state_to_history();
+            self.checkpoint();
+            self.save_take();
+        }
+    }
+
+    /// Shift a take earlier by the device round trip (call before stop_recording);
+    /// the late start wraps to the end so the loop length is kept
+    pub fn compensate_latency(&mut self, samples: usize) {
+        if self.phase() == LayerPhase::Recording && samples > 0 && !self.buffer.is_empty() {
+            let shift = samples % self.buffer.len();
+            self.buffer.rotate_left(shift);
         }
     }
 
+    /// A take in progress is ended with stop_recording, not by playing over it
+    /// A parked layer plays straight from the undo history's copy (no copy made here)
     pub fn start_playing(&mut self) {
-        if !self.buffer.is_empty() {
+        if !self.is_empty() && !self.is_recording {
             self.is_playing = true;
+            self.is_cueing = false;
             self.playback_position = self.loop_start;
         }
     }
 
     pub fn stop_playing(&mut self) {
+        if self.is_recording {
+            return;
+        }
         self.is_playing = false;
+        self.is_cueing = false;
         self.playback_position = self.loop_start;
     }
 
+    /// Pre-listen from the loop start without bringing the layer into the main mix
+    pub fn start_cue(&mut self) {
+        if !self.is_empty() {
+            self.start_playing();
+            self.is_cueing = true;
+        }
+    }
+
+    /// Gain the layer is mixed at on `bus` (None = one combined output), or None if it
+    /// isn't heard there. Cued layers ignore solo and bus settings; with no separate
+    /// cue output they are mixed in quietly
+    pub fn bus_gain(&self, bus: Option<OutputBus>, has_solo: bool) -> Option<f32> {
+        if !self.is_playing || self.is_muted {
+            return None;
+        }
+        if self.is_cueing {
+            return match bus {
+                Some(OutputBus::Cue) => Some(1.0),
+                Some(OutputBus::Main) => None,
+                None => Some(Self::CUE_PREVIEW_GAIN),
+            };
+        }
+        if has_solo && !self.is_solo && !self.is_solo_safe {
+            return None;
+        }
+        match bus {
+            Some(bus) if bus != self.output_bus => None,
+            _ => Some(1.0),
+        }
+    }
+
     pub fn toggle_mute(&mut self) {
         self.is_muted = !self.is_muted;
     }
@@ -84,35 +248,369 @@ impl AudioLayer {
         self.volume = volume.clamp(0.0, 1.0);
     }
 
+    /// Start layering input onto the loop while it keeps playing
+    /// Returns false unless the layer is playing
+    pub fn start_overdub(&mut self) -> bool {
+        if self.phase() != LayerPhase::Playing {
+            return false;
+        }
+        // Undo goes back to the loop as it was before the overdub
+        self.checkpoint();
+        // Normally already done by the parking thread once the layer played
+        self.unpark();
+        self.reset_input_tracking();
+        self.is_recording = true;
+        true
+    }
+
+    /// Start a new take while the old one plays from the top on the cue bus
+    /// Returns false unless the layer has audio and isn't capturing already
+    pub fn start_replacement(&mut self) -> bool {
+        if self.is_empty() || self.is_recording || self.is_replacing {
+            return false;
+        }
+        self.replacement = Some(Vec::new());
+        self.reset_input_tracking();
+        self.is_replacing = true;
+        self.start_cue();
+        true
+    }
+
+    pub fn append_replacement(&mut self, samples: &[f32]) {
+        if let Some(take) = self.replacement.as_mut() {
+            take.extend_from_slice(samples);
+        }
+    }
+
+    /// End the capture, shifting the new take earlier by `latency` samples; the
+    /// old take plays on in the mix. Returns false (dropping the take) if it's empty
+    pub fn stop_replacement(&mut self, latency: usize) -> bool {
+        if !self.is_replacing {
+            return false;
+        }
+        self.is_replacing = false;
+        self.is_cueing = false;
+        match self.replacement.as_mut() {
+            Some(take) if !take.is_empty() => {
+                let shift = latency % take.len();
+                take.rotate_left(shift);
+                true
+            }
+            _ => {
+                self.replacement = None;
+                false
+            }
+        }
+    }
+
+    /// Swap a finished new take in as an undoable change (undo brings the old
+    /// take back); it plays from the top. Returns false if there's none
+    pub fn confirm_replacement(&mut self) -> bool {
+        if self.is_replacing {
+            return false;
+        }
+        let Some(take) = self.replacement.take() else {
+            return false;
+        };
+        self.checkpoint();
+        self.save_take();
+        self.parked = None;
+        self.buffer = take;
+        self.loop_start = 0;
+        self.loop_end = self.buffer.len();
+        self.playback_position = 0;
+        let len = self.buffer.len();
+        self.markers.retain(|m| m.position < len);
+        self.checkpoint();
+        self.save_take();
+        true
+    }
+
+    /// Play take `index` in place of the current audio as an undoable step;
+    /// the playhead stays put if it's inside the take's loop
+    pub fn select_take(&mut self, index: usize) -> bool {
+        if self.is_recording || self.is_replacing || index >= self.takes.len() {
+            return false;
+        }
+        self.unpark();
+        // Edits since the pass (e.g. an overdub) stay with the take they were made on
+        self.checkpoint();
+        if !self.buffer.is_empty()
+            && let Some(now) = self.undo_history.get_current()
+        {
+            self.takes.update_current(now);
+        }
+
+        let Some(take) = self.takes.select(index) else {
+            return false;
+        };
+        self.buffer = take.buffer.as_ref().clone();
+        self.loop_start = take.loop_start;
+        self.loop_end = take.loop_end;
+        self.markers = take.markers.clone();
+        if !(self.loop_start..self.loop_end).contains(&self.playback_position) {
+            self.playback_position = self.loop_start;
+        }
+        self.checkpoint();
+        true
+    }
+
+    /// Swap in a processed render of the audio (frozen varispeed, denoise) as an
+    /// undoable step; loop points and markers are scaled to its length
+    pub fn replace_processed(&mut self, samples: Vec<f32>) {
+        self.checkpoint();
+        self.unpark();
+        let scale = samples.len() as f64 / self.buffer.len().max(1) as f64;
+        let rescale = |position: usize| ((position as f64 * scale) as usize).min(samples.len());
+        self.loop_start = rescale(self.loop_start);
+        self.loop_end = rescale(self.loop_end).max(self.loop_start);
+        self.playback_position = rescale(self.playback_position);
+        for marker in self.markers.iter_mut() {
+            marker.position = rescale(marker.position).min(samples.len().saturating_sub(1));
+        }
+        self.buffer = samples;
+        self.fit_phase = 0.0;
+        self.checkpoint();
+    }
+
+    /// Comp: keep the take that's playing and forget the others
+    pub fn keep_take(&mut self) -> bool {
+        !self.is_recording && self.takes.keep_current()
+    }
+
+    /// Keep the layer's audio as a take (shared with the undo history's current state)
+    fn save_take(&mut self) {
+        if !self.buffer.is_empty()
+            && let Some(take) = self.undo_history.get_current()
+        {
+            self.takes.push(take);
+        }
+    }
+
+    /// Drop the new take, finished or still capturing; the old take is untouched
+    /// Returns false if there was none
+    pub fn revert_replacement(&mut self) -> bool {
+        if self.is_replacing {
+            self.is_replacing = false;
+            self.is_cueing = false;
+        }
+        self.replacement.take().is_some()
+    }
+
+    pub fn phase(&self) -> LayerPhase {
+        LayerPhase::from_flags(self.is_recording, self.is_playing, self.is_empty())
+    }
+
+    /// The layer's audio, parked or not; read this rather than `buffer`
+    /// Decodes (allocates) for a parked layer: not for the audio thread
+    pub fn audio(&self) -> Cow<'_, [f32]> {
+        match &self.parked {
+            Some(parked) => Cow::Owned(parked.quantized.decode()),
+            None => Cow::Borrowed(&self.buffer),
+        }
+    }
+
+    /// `audio()` without decoding, from the fields alone so playback can move
+    /// the playhead while reading it
+    fn source<'a>(buffer: &'a [f32], parked: &'a Option<ParkedAudio>) -> Samples<'a> {
+        match parked {
+            Some(parked) => Samples::Parked(&parked.quantized),
+            None => Samples::Full(buffer),
+        }
+    }
+
+    pub fn is_parked(&self) -> bool {
+        self.parked.is_some()
+    }
+
+    fn is_idle(&self) -> bool {
+        !self.is_playing
+            && !self.is_recording
+            && !self.is_replacing
+            && !self.is_solo
+            && self.replacement.is_none()
+    }
+
+    /// The audio of an idle layer (stopped, not soloed, no take in progress)
+    /// that can be parked, for encoding without holding the layer; hand the
+    /// 16-bit copy to `park`
+    pub fn park_source(&self) -> Option<Arc<Vec<f32>>> {
+        if self.parked.is_some() || !self.is_idle() || self.buffer.is_empty() {
+            return None;
+        }
+        self.undo_history
+            .current()
+            .map(|current| Arc::clone(&current.buffer))
+    }
+
+    /// Keep an idle layer's audio only as `quantized`, the 16-bit copy of
+    /// `source`, freeing both full-precision copies; it's brought back on play
+    /// or edit. Lossy: the layer comes back as 16-bit audio
+    /// Only parks when `source` is still both the working and the undo
+    /// history's current audio
+    pub fn park(&mut self, source: &Arc<Vec<f32>>, quantized: Arc<QuantizedBuffer>) -> bool {
+        if self.parked.is_some()
+            || !self.is_idle()
+            || self.buffer.as_slice() != source.as_slice()
+            || !self
+                .undo_history
+                .park_current(source, Arc::clone(&quantized))
+        {
+            return false;
+        }
+        self.buffer = Vec::new();
+        self.parked = Some(ParkedAudio {
+            quantized,
+            source: Arc::downgrade(source),
+        });
+        true
+    }
+
+    /// The 16-bit audio a parked layer reads; None unless parked
+    pub fn parked_audio(&self) -> Option<Arc<QuantizedBuffer>> {
+        self.parked
+            .as_ref()
+            .map(|parked| Arc::clone(&parked.quantized))
+    }
+
+    /// Unpark with `audio`, decoded from `parked` without holding the layer,
+    /// and a working copy of it. Returns false if the layer's audio changed
+    /// since `parked_audio` gave it
+    pub fn unpark_from(
+        &mut self,
+        parked: &Arc<QuantizedBuffer>,
+        audio: Arc<Vec<f32>>,
+        copy: Vec<f32>,
+    ) -> bool {
+        if self
+            .parked
+            .as_ref()
+            .is_none_or(|current| !Arc::ptr_eq(&current.quantized, parked))
+        {
+            return false;
+        }
+        self.parked = None;
+        self.buffer = copy;
+        self.undo_history.unpark_current(audio);
+        true
+    }
+
+    /// The audio as it is now, shared with the undo history, for a job that
+    /// processes it without holding the layer; check `is_unchanged_since`
+    /// before writing its result back. A parked layer is unparked first
+    pub fn edit_source(&mut self) -> Arc<Vec<f32>> {
+        self.unpark();
+        self.checkpoint();
+        self.undo_history
+            .current()
+            .map(|current| Arc::clone(&current.buffer))
+            .unwrap_or_default()
+    }
+
+    /// False if the audio moved on from `source` (a take, undo, import or
+    /// other edit landed after `edit_source` gave it); parking since doesn't count
+    pub fn is_unchanged_since(&self, source: &Arc<Vec<f32>>) -> bool {
+        match &self.parked {
+            Some(parked) => std::ptr::eq(parked.source.as_ptr(), Arc::as_ptr(source)),
+            None => {
+                self.undo_history
+                    .current()
+                    .is_some_and(|current| Arc::ptr_eq(&current.buffer, source))
+                    && self.buffer.as_slice() == source.as_slice()
+            }
+        }
+    }
+
+    /// Decode a parked layer's audio back into its working buffer
+    /// Allocates: prefer `unpark_from` off the audio thread
+    pub fn unpark(&mut self) {
+        let Some(parked) = self.parked.take() else {
+            return;
+        };
+        let audio = Arc::new(parked.quantized.decode());
+        self.buffer = audio.as_ref().clone();
+        self.undo_history.unpark_current(audio);
+    }
+
+    fn reset_input_tracking(&mut self) {
+        self.record_meter.reset();
+        self.silent_input = 0;
+    }
+
+    /// REAL-TIME SAFE: Meter input on its way into the layer; true once per silent
+    /// stretch, when `window` samples in a row have carried no signal
+    pub fn track_input(&mut self, samples: &[f32], window: usize) -> bool {
+        self.record_meter.update(samples);
+        let was_silent = self.silent_input;
+        match samples.iter().rposition(|s| s.abs() > Self::SILENT_INPUT) {
+            Some(last_signal) => self.silent_input = samples.len() - 1 - last_signal,
+            None => self.silent_input += samples.len(),
+        }
+        was_silent < window && self.silent_input >= window
+    }
+
     pub fn append_samples(&mut self, samples: &[f32]) {
         self.buffer.extend_from_slice(samples);
     }
 
+    /// REAL-TIME SAFE: Mix input into the loop where it's about to play,
+    /// wrapping like playback does
+    pub fn overdub_samples(&mut self, samples: &[f32]) {
+        let buffer_len = self.buffer.len();
+        let mut position = self.playback_position;
+        for &sample in samples {
+            if position >= buffer_len {
+                position = self.loop_start.min(buffer_len.saturating_sub(1));
+            }
+            if let Some(existing) = self.buffer.get_mut(position) {
+                *existing = (*existing + sample).clamp(-1.0, 1.0);
+            }
+            position += 1;
+        }
+    }
+
     /// REAL-TIME SAFE: Zero allocations, writes to existing buffer
     pub fn fill_next_samples(&mut self, output: &mut [f32]) {
+        if self.fill_next_samples_unmetered(output) {
+            // Update peak meter (no allocations)
+            self.meter.update(output);
+        }
+    }
+
+    /// REAL-TIME SAFE: Same as fill_next_samples() but skips meter updates
+    /// Used by the reduced-quality path under CPU pressure
+    /// Returns false if the layer produced silence without advancing
+    pub fn fill_next_samples_unmetered(&mut self, output: &mut [f32]) -> bool {
         let count = output.len();
 
         // Fast path: silent or not playing
-        if !self.is_playing || self.buffer.is_empty() {
+        if !self.is_playing || self.is_empty() {
             output.fill(0.0);
-            return;
+            return false;
         }
 
-        let buffer_len = self.buffer.len();
+        let buffer_len = self.get_buffer_length();
         let loop_len = self.loop_end - self.loop_start;
 
         if loop_len == 0 {
             output.fill(0.0);
-            return;
+            return false;
+        }
+
+        let rate = self.playback_rate();
+        if rate != 1.0 {
+            self.fill_varispeed(output, rate);
+            return true;
         }
 
         // Generate samples directly into output buffer
+        let audio = Self::source(&self.buffer, &self.parked);
         for output_sample in output.iter_mut().take(count) {
             if self.playback_position >= buffer_len {
                 self.playback_position = self.loop_start;
             }
 
-            let sample = self.buffer[self.playback_position];
+            let sample = audio.get(self.playback_position);
             let volume_sample = if self.is_muted {
                 0.0
             } else {
@@ -123,8 +621,79 @@ impl AudioLayer {
             self.playback_position += 1;
         }
 
-        // Update peak meter (no allocations)
-        self.meter.update(output);
+        true
+    }
+
+    /// REAL-TIME SAFE: Add the next samples at `gain` into `output`, without a
+    /// scratch buffer (no varispeed or meter update)
+    pub fn mix_next_samples(&mut self, output: &mut [f32], gain: f32) {
+        let audio = Self::source(&self.buffer, &self.parked);
+        let buffer_len = audio.len();
+        if self.loop_end <= self.loop_start || buffer_len == 0 {
+            return;
+        }
+
+        for output_sample in output.iter_mut() {
+            if self.playback_position >= buffer_len {
+                self.playback_position = self.loop_start;
+            }
+
+            *output_sample += audio.get(self.playback_position) * self.volume * gain;
+            self.playback_position += 1;
+        }
+    }
+
+    /// Source samples read per output sample (1.0 unless fitted to the master loop)
+    pub fn playback_rate(&self) -> f64 {
+        match self.fit_length {
+            Some(target) if target > 0 => {
+                let rate = self.get_buffer_length() as f64 / target as f64;
+                // A re-recorded or edited layer may no longer be close enough
+                if (rate - 1.0).abs() <= Self::MAX_FIT_CHANGE {
+                    rate
+                } else {
+                    1.0
+                }
+            }
+            _ => 1.0,
+        }
+    }
+
+    /// Length this layer would be fitted to: the nearest ratio of the master loop,
+    /// or None if reaching it needs more than MAX_FIT_CHANGE
+    pub fn fit_target(&self, master_length: usize) -> Option<usize> {
+        let length = self.get_buffer_length();
+        let target = LoopRatio::nearest(length, master_length).target_length(master_length);
+        let change = (length as f64 / target.max(1) as f64 - 1.0).abs();
+        (length > 0 && change <= Self::MAX_FIT_CHANGE).then_some(target)
+    }
+
+    /// REAL-TIME SAFE: Linear-interpolated playback at `rate`
+    fn fill_varispeed(&mut self, output: &mut [f32], rate: f64) {
+        let audio = Self::source(&self.buffer, &self.parked);
+        let buffer_len = audio.len();
+        let gain = if self.is_muted { 0.0 } else { self.volume };
+
+        for output_sample in output.iter_mut() {
+            if self.playback_position >= buffer_len {
+                // Carry the overshoot so the loop keeps its exact length
+                self.playback_position =
+                    (self.loop_start + self.playback_position - buffer_len).min(buffer_len - 1);
+            }
+            let next = if self.playback_position + 1 < buffer_len {
+                self.playback_position + 1
+            } else {
+                self.loop_start
+            };
+            let frac = self.fit_phase as f32;
+            let sample = audio.get(self.playback_position) * (1.0 - frac) + audio.get(next) * frac;
+            *output_sample = sample * gain;
+
+            self.fit_phase += rate;
+            let whole = self.fit_phase.floor();
+            self.playback_position += whole as usize;
+            self.fit_phase -= whole;
+        }
     }
 
     /// DEPRECATED: Use fill_next_samples() instead for real-time safety
@@ -140,13 +709,57 @@ impl AudioLayer {
     }
 
     pub fn set_loop_points(&mut self, start: usize, end: usize) {
-        self.loop_start = start.min(self.buffer.len());
-        self.loop_end = end.min(self.buffer.len());
+        self.loop_start = start.min(self.get_buffer_length());
+        self.loop_end = end.min(self.get_buffer_length());
         if self.loop_start >= self.loop_end {
             self.loop_end = self.loop_start + 1;
         }
     }
 
+    /// Add or move a named marker; markers are kept sorted by position
+    pub fn set_marker(&mut self, name: &str, position: usize) {
+        let position = position.min(self.get_buffer_length().saturating_sub(1));
+        self.markers.retain(|m| m.name != name);
+        self.markers.push(Marker {
+            name: name.to_string(),
+            position,
+        });
+        self.markers.sort_by_key(|m| m.position);
+    }
+
+    pub fn remove_marker(&mut self, name: &str) -> bool {
+        let before = self.markers.len();
+        self.markers.retain(|m| m.name != name);
+        self.markers.len() != before
+    }
+
+    pub fn marker_position(&self, name: &str) -> Option<usize> {
+        self.markers
+            .iter()
+            .find(|m| m.name == name)
+            .map(|m| m.position)
+    }
+
+    /// First marker after the playback position, wrapping to the first marker
+    pub fn next_marker(&self) -> Option<&Marker> {
+        self.markers
+            .iter()
+            .find(|m| m.position > self.playback_position)
+            .or_else(|| self.markers.first())
+    }
+
+    /// Move playback to a buffer position, clamped to the loop region
+    pub fn jump_to(&mut self, position: usize) {
+        if self.is_empty() {
+            return;
+        }
+        let end = self
+            .loop_end
+            .min(self.get_buffer_length())
+            .max(self.loop_start + 1);
+        self.playback_position = position.clamp(self.loop_start, end - 1);
+    }
+
     pub fn undo(&mut self) -> bool {
         if let Some(snapshot) = self.undo_history.undo() {
             self.apply_snapshot(snapshot);
@@ -175,7 +788,8 @@ impl AudioLayer {
 
     pub fn clear(&mut self) {
         // Save state before clearing
-        self.save_state_to_history();
+        self.checkpoint();
+        self.parked = None;
 
         self.buffer.clear();
         self.is_recording = false;
@@ -184,31 +798,142 @@ impl AudioLayer {
         self.loop_start = 0;
         self.loop_end = 0;
         self.meter.reset();
+        self.markers.clear();
+        self.replacement = None;
+        self.is_replacing = false;
+        self.takes.clear();
+
+        // Save cleared state so undo returns to the audio that was cleared
+        self.checkpoint();
     }
 
-    /// Save current layer state to undo history
-    fn save_state_to_history(&mut self) {
+    /// Replace the layer audio (e.g. WAV import) as an undoable operation
+    pub fn replace_buffer(&mut self, samples: Vec<f32>) {
+        self.checkpoint();
+        self.parked = None;
+        self.buffer = samples;
+        self.loop_end = self.buffer.len();
+        self.checkpoint();
+    }
+
+    /// Insert audio at a buffer position as an undoable operation
+    /// Loop points and markers after the insertion point move with the audio
+    pub fn insert_buffer(&mut self, position: usize, samples: &[f32]) {
+        self.checkpoint();
+        self.unpark();
+        let position = position.min(self.buffer.len());
+        let loop_covers_end = self.loop_end == 0 || self.loop_end >= self.buffer.len();
+
+        self.buffer
+            .splice(position..position, samples.iter().copied());
+
+        if self.loop_start > position {
+            self.loop_start += samples.len();
+        }
+        if loop_covers_end {
+            self.loop_end = self.buffer.len();
+        } else if self.loop_end > position {
+            self.loop_end += samples.len();
+        }
+        for marker in self.markers.iter_mut().filter(|m| m.position >= position) {
+            marker.position += samples.len();
+        }
+        self.checkpoint();
+    }
+
+    /// Add imported audio according to the import mode
+    pub fn import_samples(&mut self, samples: Vec<f32>, mode: crate::audio::ImportMode) {
+        match mode {
+            crate::audio::ImportMode::Replace => self.replace_buffer(samples),
+            crate::audio::ImportMode::Append => {
+                self.insert_buffer(self.get_buffer_length(), &samples)
+            }
+            crate::audio::ImportMode::InsertAtPlayhead => {
+                self.insert_buffer(self.playback_position, &samples)
+            }
+        }
+    }
+
+    /// Save current layer state to undo history if it changed since the last snapshot
+    /// Call before and after destructive operations so they are always undoable
+    /// Returns true if a new snapshot was stored
+    pub fn checkpoint(&mut self) -> bool {
+        // Copy-on-write: unchanged audio shares the previous snapshot's buffer
+        // (a parked layer's audio is the current state's 16-bit copy)
+        let buffer = match self.undo_history.current() {
+            Some(current) if self.parked.is_some() => Arc::clone(&current.buffer),
+            _ => self.undo_history.share_buffer(&self.buffer),
+        };
+        self.save_checkpoint(buffer)
+    }
+
+    /// The undo history's current audio, for checking the working audio
+    /// against it a range at a time (`audio_matches`) instead of all at once
+    pub fn checkpoint_source(&self) -> Option<Arc<Vec<f32>>> {
+        self.undo_history
+            .current()
+            .map(|current| Arc::clone(&current.buffer))
+    }
+
+    /// Whether the working audio in `range` is the same as `source`'s; None if
+    /// `source` is no longer the undo history's current audio
+    pub fn audio_matches(&self, source: &Arc<Vec<f32>>, range: Range<usize>) -> Option<bool> {
+        if self
+            .undo_history
+            .current()
+            .is_none_or(|current| !Arc::ptr_eq(&current.buffer, source))
+        {
+            return None;
+        }
+        Some(
+            self.parked.is_some()
+                || (self.buffer.len() == source.len()
+                    && self.buffer.get(range.clone()) == source.get(range)),
+        )
+    }
+
+    /// `checkpoint` for audio already found to match `source` (`audio_matches`),
+    /// so nothing is compared or copied; false if `source` is no longer current
+    pub fn checkpoint_unchanged(&mut self, source: &Arc<Vec<f32>>) -> bool {
+        if self
+            .undo_history
+            .current()
+            .is_none_or(|current| !Arc::ptr_eq(&current.buffer, source))
+        {
+            return false;
+        }
+        self.save_checkpoint(Arc::clone(source))
+    }
+
+    fn save_checkpoint(&mut self, buffer: Arc<Vec<f32>>) -> bool {
         let snapshot = crate::audio::undo_history::LayerSnapshot {
-            buffer: self.buffer.clone(),
+            buffer,
             volume: self.volume,
             loop_start: self.loop_start,
             loop_end: self.loop_end,
             playback_position: self.playback_position,
             is_muted: self.is_muted,
             is_solo: self.is_solo,
+            markers: self.markers.clone(),
         };
+        if self.undo_history.matches_current(&snapshot) {
+            return false;
+        }
         self.undo_history.save_state(snapshot);
+        true
     }
 
     /// Apply a snapshot to the current layer state
     fn apply_snapshot(&mut self, snapshot: crate::audio::undo_history::LayerSnapshot) {
-        self.buffer = snapshot.buffer;
+        self.parked = None;
+        self.buffer = snapshot.buffer.as_ref().clone();
         self.volume = snapshot.volume;
         self.loop_start = snapshot.loop_start;
         self.loop_end = snapshot.loop_end;
         self.playback_position = snapshot.playback_position;
         self.is_muted = snapshot.is_muted;
         self.is_solo = snapshot.is_solo;
+        self.markers = snapshot.markers;
 
         // Update playback state based on buffer
         if self.buffer.is_empty() {
@@ -218,11 +943,11 @@ impl AudioLayer {
     }
 
     pub fn is_empty(&self) -> bool {
-        self.buffer.is_empty()
+        self.get_buffer_length() == 0
     }
 
     pub fn get_buffer_length(&self) -> usize {
-        self.buffer.len()
+        Self::source(&self.buffer, &self.parked).len()
     }
 }
 
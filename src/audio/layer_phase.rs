@@ -0,0 +1,83 @@
+// src/audio/layer_phase.rs
+// Record/play state machine for a layer, so commands are checked against the
+// layer's current state instead of combining recording and playing flags ad hoc
+
+use std::fmt;
+
+/// Where a layer is in its record/play cycle
+/// (`LayerState` is the saved per-layer settings in a session file)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LayerPhase {
+    Empty,
+    Recording,   // capturing a new take (replaces the buffer)
+    Playing,     // in the mix
+    Overdubbing, // playing while input is layered onto the loop
+    Stopped,     // has audio but isn't playing
+}
+
+impl LayerPhase {
+    /// Phase for a layer's flags
+    pub fn from_flags(is_recording: bool, is_playing: bool, is_empty: bool) -> Self {
+        match (is_recording, is_playing) {
+            (true, true) => Self::Overdubbing,
+            (true, false) => Self::Recording,
+            (false, true) if !is_empty => Self::Playing,
+            _ if is_empty => Self::Empty,
+            _ => Self::Stopped,
+        }
+    }
+
+    /// Whether a layer in this phase may move to `next`; restarting a
+    /// recording or playback from the top counts as a valid transition
+    pub fn can_enter(self, next: Self) -> bool {
+        use LayerPhase::*;
+        match (self, next) {
+            // Clearing is always allowed
+            (_, Empty) => true,
+            (Empty, Recording) => true,
+            // A take that captured nothing goes back to Empty
+            (Recording, Recording | Playing | Stopped) => true,
+            (Playing, Recording | Playing | Overdubbing | Stopped) => true,
+            (Overdubbing, Playing | Stopped) => true,
+            (Stopped, Recording | Playing) => true,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for LayerPhase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Empty => "empty",
+            Self::Recording => "recording",
+            Self::Playing => "playing",
+            Self::Overdubbing => "overdubbing",
+            Self::Stopped => "stopped",
+        };
+        f.write_str(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_phases_and_transitions() {
+        use LayerPhase::*;
+        assert_eq!(LayerPhase::from_flags(false, false, true), Empty);
+        assert_eq!(LayerPhase::from_flags(true, false, true), Recording);
+        assert_eq!(LayerPhase::from_flags(false, true, false), Playing);
+        assert_eq!(LayerPhase::from_flags(true, true, false), Overdubbing);
+        assert_eq!(LayerPhase::from_flags(false, false, false), Stopped);
+
+        assert!(Empty.can_enter(Recording));
+        assert!(!Empty.can_enter(Playing));
+        assert!(!Empty.can_enter(Overdubbing));
+        assert!(!Recording.can_enter(Overdubbing));
+        assert!(Playing.can_enter(Overdubbing));
+        assert!(!Stopped.can_enter(Overdubbing));
+        assert!(!Overdubbing.can_enter(Recording));
+        assert!(Overdubbing.can_enter(Empty));
+    }
+}
@@ -0,0 +1,122 @@
+// src/audio/layer_stats.rs
+// Per-layer statistics for the info overlay: length, levels and an estimated
+// tempo, measured off the audio thread
+
+use super::PeakMeter;
+
+// Onset envelope resolution (about 11 ms at 48 kHz)
+const ENVELOPE_HOP: usize = 512;
+// Tempo range searched by the autocorrelation
+const MIN_BPM: f64 = 60.0;
+const MAX_BPM: f64 = 180.0;
+
+/// Measurements of a layer's loop region
+#[derive(Debug, Clone, PartialEq)]
+pub struct LayerStats {
+    pub seconds: f64,
+    pub bars: f64, // at the session tempo
+    pub peak_db: f32,
+    pub rms_db: f32,
+    pub crest_db: f32,      // peak over RMS; low means heavily compressed
+    pub tempo: Option<f64>, // BPM from the onset autocorrelation; None if no beat stands out
+}
+
+impl LayerStats {
+    pub fn measure(samples: &[f32], sample_rate: u32, bpm: f64, beats_per_measure: u32) -> Self {
+        let seconds = samples.len() as f64 / sample_rate as f64;
+        let peak = samples.iter().fold(0.0f32, |peak, s| peak.max(s.abs()));
+        let rms = if samples.is_empty() {
+            0.0
+        } else {
+            (samples.iter().map(|&s| s as f64 * s as f64).sum::<f64>() / samples.len() as f64)
+                .sqrt() as f32
+        };
+        let (peak_db, rms_db) = (PeakMeter::to_db(peak), PeakMeter::to_db(rms));
+        Self {
+            seconds,
+            bars: seconds * bpm / 60.0 / beats_per_measure.max(1) as f64,
+            peak_db,
+            rms_db,
+            crest_db: if rms > 0.0 { peak_db - rms_db } else { 0.0 },
+            tempo: estimate_tempo(samples, sample_rate),
+        }
+    }
+}
+
+/// Tempo from the autocorrelation of the onset envelope (rises in level)
+fn estimate_tempo(samples: &[f32], sample_rate: u32) -> Option<f64> {
+    let hops_per_sec = sample_rate as f64 / ENVELOPE_HOP as f64;
+    let min_lag = (60.0 / MAX_BPM * hops_per_sec).floor() as usize;
+    let max_lag = (60.0 / MIN_BPM * hops_per_sec).ceil() as usize;
+
+    let envelope: Vec<f32> = samples
+        .chunks_exact(ENVELOPE_HOP)
+        .map(|hop| (hop.iter().map(|s| s * s).sum::<f32>() / ENVELOPE_HOP as f32).sqrt())
+        .collect();
+    // At least two beats at the slowest tempo
+    if envelope.len() < 2 * max_lag + 1 {
+        return None;
+    }
+    let mut onsets: Vec<f32> = envelope
+        .windows(2)
+        .map(|pair| (pair[1] - pair[0]).max(0.0))
+        .collect();
+    let mean = onsets.iter().sum::<f32>() / onsets.len() as f32;
+    for onset in onsets.iter_mut() {
+        *onset -= mean;
+    }
+
+    // Unnormalized sums favour the shorter of two related lags (the beat over the half-bar)
+    let correlation =
+        |lag: usize| -> f32 { onsets.iter().zip(&onsets[lag..]).map(|(a, b)| a * b).sum() };
+    let scores: Vec<f32> = (min_lag - 1..=max_lag + 1).map(correlation).collect();
+    let (best, &score) = scores[1..scores.len() - 1]
+        .iter()
+        .enumerate()
+        .max_by(|a, b| a.1.total_cmp(b.1))?;
+    if score <= 0.0 {
+        return None;
+    }
+
+    // Parabolic fit around the peak for a fractional lag
+    let (before, after) = (scores[best], scores[best + 2]);
+    let curvature = before - 2.0 * score + after;
+    let offset = if curvature < 0.0 {
+        (0.5 * (before - after) / curvature) as f64
+    } else {
+        0.0
+    };
+    let lag = (min_lag + best) as f64 + offset;
+    Some(60.0 * hops_per_sec / lag)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_measure_levels_and_tempo() {
+        // Eight seconds of 10 ms clicks at 120 BPM over quiet hiss
+        let sample_rate = 48_000;
+        let beat = sample_rate as usize / 2;
+        let samples: Vec<f32> = (0..sample_rate as usize * 8)
+            .map(|i| {
+                let hiss = if i % 7 < 3 { 0.01 } else { -0.01 };
+                if i % beat < 480 { 0.5 } else { hiss }
+            })
+            .collect();
+
+        let stats = LayerStats::measure(&samples, sample_rate, 120.0, 4);
+        assert_eq!((stats.seconds, stats.bars), (8.0, 4.0));
+        assert!((stats.peak_db - PeakMeter::to_db(0.5)).abs() < 0.01);
+        assert!(stats.crest_db > 10.0);
+        let tempo = stats.tempo.unwrap();
+        assert!((tempo - 120.0).abs() < 1.0, "estimated {}", tempo);
+
+        // Too short to find a beat
+        assert_eq!(
+            LayerStats::measure(&samples[..beat], sample_rate, 120.0, 4).tempo,
+            None
+        );
+    }
+}
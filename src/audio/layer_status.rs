@@ -0,0 +1,311 @@
+// src/audio/layer_status.rs
+// Lock-free layer state published by the audio thread for the UI
+
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU8, AtomicU16, AtomicU32, AtomicUsize, Ordering};
+
+use super::layer::{AudioLayer, Marker};
+use super::{LayerPhase, OutputBus};
+
+/// Plain copy of a layer's display state
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct LayerStatusSnapshot {
+    pub is_recording: bool,
+    pub is_playing: bool,
+    pub is_muted: bool,
+    pub is_solo: bool,
+    pub is_cueing: bool,
+    pub is_locked: bool,
+    pub is_solo_safe: bool,
+    pub is_fitted: bool,       // varispeeding to fit the master loop
+    pub is_replacing: bool,    // capturing a re-take
+    pub has_replacement: bool, // a finished re-take waits to be kept or reverted
+    pub takes: usize,          // record passes kept in the take lanes
+    pub current_take: usize,   // take playing (from 0)
+    pub volume: f32,
+    pub buffer_len: usize,
+    pub playback_position: usize,
+    pub loop_start: usize,
+    pub loop_end: usize,
+    pub peak: f32,
+    pub peak_hold: f32,
+    pub max_peak: f32, // since the meters were last reset
+    pub rms: f32,
+    pub record_peak: f32, // input reaching the layer while it captures
+    pub output_bus: OutputBus,
+    pub queued: QueuedActions,
+}
+
+/// Beat-synced actions waiting for the next boundary
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct QueuedActions {
+    pub play: bool,
+    pub stop: bool,
+    pub record: bool,    // includes a count-in that's running
+    pub beats_left: u32, // until the next measure, when they take effect
+}
+
+impl QueuedActions {
+    pub fn any(&self) -> bool {
+        self.play || self.stop || self.record
+    }
+}
+
+impl LayerStatusSnapshot {
+    pub fn is_empty(&self) -> bool {
+        self.buffer_len == 0
+    }
+
+    pub fn phase(&self) -> LayerPhase {
+        LayerPhase::from_flags(self.is_recording, self.is_playing, self.is_empty())
+    }
+}
+
+/// Layer state mirrored into atomics so readers never touch the layer mutex
+#[derive(Debug, Default)]
+pub struct LayerStatus {
+    flags: AtomicU16,
+    volume: AtomicU32, // f32 bits
+    buffer_len: AtomicUsize,
+    playback_position: AtomicUsize,
+    loop_start: AtomicUsize,
+    loop_end: AtomicUsize,
+    peak: AtomicU32,        // f32 bits
+    peak_hold: AtomicU32,   // f32 bits
+    max_peak: AtomicU32,    // f32 bits
+    rms: AtomicU32,         // f32 bits
+    record_peak: AtomicU32, // f32 bits
+    queued: AtomicU8,
+    queued_beats_left: AtomicU32,
+    takes: AtomicU8,
+    current_take: AtomicU8,
+    // Markers change rarely; only copied when they differ
+    markers: Mutex<Vec<Marker>>,
+}
+
+impl LayerStatus {
+    const RECORDING: u16 = 1;
+    const PLAYING: u16 = 1 << 1;
+    const MUTED: u16 = 1 << 2;
+    const SOLO: u16 = 1 << 3;
+    const CUE: u16 = 1 << 4; // routed to the cue bus
+    const CUEING: u16 = 1 << 5; // pre-listening
+    const LOCKED: u16 = 1 << 6;
+    const SOLO_SAFE: u16 = 1 << 7;
+    const FITTED: u16 = 1 << 8;
+    const REPLACING: u16 = 1 << 9;
+    const REPLACEMENT: u16 = 1 << 10; // a re-take exists (capturing or finished)
+
+    const QUEUED_PLAY: u8 = 1;
+    const QUEUED_STOP: u8 = 1 << 1;
+    const QUEUED_RECORD: u8 = 1 << 2;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Copy the layer's state (call from the audio thread while holding the layer)
+    /// Returns the new phase when it differs from the last published one
+    pub fn publish(&self, layer: &AudioLayer) -> Option<LayerPhase> {
+        let previous = self.phase();
+        let mut flags = 0;
+        if layer.is_recording {
+            flags |= Self::RECORDING;
+        }
+        if layer.is_playing {
+            flags |= Self::PLAYING;
+        }
+        if layer.is_muted {
+            flags |= Self::MUTED;
+        }
+        if layer.is_solo {
+            flags |= Self::SOLO;
+        }
+        if layer.output_bus == OutputBus::Cue {
+            flags |= Self::CUE;
+        }
+        if layer.is_cueing {
+            flags |= Self::CUEING;
+        }
+        if layer.is_locked {
+            flags |= Self::LOCKED;
+        }
+        if layer.is_solo_safe {
+            flags |= Self::SOLO_SAFE;
+        }
+        if layer.fit_length.is_some() {
+            flags |= Self::FITTED;
+        }
+        if layer.is_replacing {
+            flags |= Self::REPLACING;
+        }
+        if layer.replacement.is_some() {
+            flags |= Self::REPLACEMENT;
+        }
+        self.flags.store(flags, Ordering::Relaxed);
+        self.volume.store(layer.volume.to_bits(), Ordering::Relaxed);
+        self.buffer_len
+            .store(layer.get_buffer_length(), Ordering::Relaxed);
+        self.playback_position
+            .store(layer.playback_position, Ordering::Relaxed);
+        self.loop_start.store(layer.loop_start, Ordering::Relaxed);
+        self.loop_end.store(layer.loop_end, Ordering::Relaxed);
+        self.peak
+            .store(layer.meter.get_peak().to_bits(), Ordering::Relaxed);
+        self.peak_hold
+            .store(layer.meter.get_peak_hold().to_bits(), Ordering::Relaxed);
+        self.max_peak
+            .store(layer.meter.get_max_peak().to_bits(), Ordering::Relaxed);
+        self.rms
+            .store(layer.meter.get_rms().to_bits(), Ordering::Relaxed);
+        self.record_peak
+            .store(layer.record_meter.get_peak().to_bits(), Ordering::Relaxed);
+        self.takes.store(layer.takes.len() as u8, Ordering::Relaxed);
+        self.current_take
+            .store(layer.takes.current().unwrap_or(0) as u8, Ordering::Relaxed);
+
+        // Skip if the UI is reading; the next cycle will catch up
+        if let Ok(mut markers) = self.markers.try_lock()
+            && *markers != layer.markers
+        {
+            markers.clone_from(&layer.markers);
+        }
+
+        let phase = layer.phase();
+        (phase != previous).then_some(phase)
+    }
+
+    /// Copy the layer's pending sync actions (kept by the engine, not the layer)
+    pub fn publish_queued(&self, queued: QueuedActions) {
+        let mut bits = 0;
+        if queued.play {
+            bits |= Self::QUEUED_PLAY;
+        }
+        if queued.stop {
+            bits |= Self::QUEUED_STOP;
+        }
+        if queued.record {
+            bits |= Self::QUEUED_RECORD;
+        }
+        self.queued.store(bits, Ordering::Relaxed);
+        self.queued_beats_left
+            .store(queued.beats_left, Ordering::Relaxed);
+    }
+
+    fn phase(&self) -> LayerPhase {
+        let flags = self.flags.load(Ordering::Relaxed);
+        LayerPhase::from_flags(
+            flags & Self::RECORDING != 0,
+            flags & Self::PLAYING != 0,
+            self.buffer_len.load(Ordering::Relaxed) == 0,
+        )
+    }
+
+    pub fn snapshot(&self) -> LayerStatusSnapshot {
+        let flags = self.flags.load(Ordering::Relaxed);
+        let queued = self.queued.load(Ordering::Relaxed);
+        LayerStatusSnapshot {
+            is_recording: flags & Self::RECORDING != 0,
+            is_playing: flags & Self::PLAYING != 0,
+            is_muted: flags & Self::MUTED != 0,
+            is_solo: flags & Self::SOLO != 0,
+            is_cueing: flags & Self::CUEING != 0,
+            is_locked: flags & Self::LOCKED != 0,
+            is_solo_safe: flags & Self::SOLO_SAFE != 0,
+            is_fitted: flags & Self::FITTED != 0,
+            is_replacing: flags & Self::REPLACING != 0,
+            has_replacement: flags & Self::REPLACEMENT != 0 && flags & Self::REPLACING == 0,
+            volume: f32::from_bits(self.volume.load(Ordering::Relaxed)),
+            buffer_len: self.buffer_len.load(Ordering::Relaxed),
+            playback_position: self.playback_position.load(Ordering::Relaxed),
+            loop_start: self.loop_start.load(Ordering::Relaxed),
+            loop_end: self.loop_end.load(Ordering::Relaxed),
+            peak: f32::from_bits(self.peak.load(Ordering::Relaxed)),
+            peak_hold: f32::from_bits(self.peak_hold.load(Ordering::Relaxed)),
+            max_peak: f32::from_bits(self.max_peak.load(Ordering::Relaxed)),
+            rms: f32::from_bits(self.rms.load(Ordering::Relaxed)),
+            record_peak: f32::from_bits(self.record_peak.load(Ordering::Relaxed)),
+            takes: self.takes.load(Ordering::Relaxed) as usize,
+            current_take: self.current_take.load(Ordering::Relaxed) as usize,
+            output_bus: if flags & Self::CUE != 0 {
+                OutputBus::Cue
+            } else {
+                OutputBus::Main
+            },
+            queued: QueuedActions {
+                play: queued & Self::QUEUED_PLAY != 0,
+                stop: queued & Self::QUEUED_STOP != 0,
+                record: queued & Self::QUEUED_RECORD != 0,
+                beats_left: self.queued_beats_left.load(Ordering::Relaxed),
+            },
+        }
+    }
+
+    pub fn markers(&self) -> Vec<Marker> {
+        self.markers.lock().unwrap().clone()
+    }
+
+    /// Make the marker copy readable again after the audio thread panicked
+    pub fn clear_poison(&self) {
+        self.markers.clear_poison();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_publish_and_snapshot() {
+        let mut layer = AudioLayer::new(0);
+        layer.buffer = vec![0.5; 100];
+        layer.is_playing = true;
+        layer.is_solo = true;
+        layer.volume = 0.25;
+        layer.playback_position = 40;
+        layer.set_marker("A", 10);
+        layer.set_marker("B", 60);
+
+        let status = LayerStatus::new();
+        assert_eq!(status.publish(&layer), Some(LayerPhase::Playing));
+        assert_eq!(status.publish(&layer), None);
+        let snapshot = status.snapshot();
+
+        assert!(snapshot.is_playing && snapshot.is_solo);
+        assert!(!snapshot.is_recording && !snapshot.is_muted);
+        assert_eq!(snapshot.volume, 0.25);
+        assert_eq!(snapshot.buffer_len, 100);
+        assert_eq!(status.markers(), layer.markers);
+
+        assert!(layer.start_overdub());
+        layer.record_meter.update(&[0.5; 64]);
+        assert_eq!(status.publish(&layer), Some(LayerPhase::Overdubbing));
+        assert_eq!(status.snapshot().phase(), LayerPhase::Overdubbing);
+        assert_eq!(status.snapshot().record_peak, 0.5);
+
+        assert!(!status.snapshot().queued.any());
+        status.publish_queued(QueuedActions {
+            record: true,
+            beats_left: 3,
+            ..Default::default()
+        });
+        let queued = status.snapshot().queued;
+        assert!(queued.record && !queued.play && !queued.stop);
+        assert_eq!(queued.beats_left, 3);
+
+        // A re-take is flagged while capturing, then until it's kept or reverted
+        layer.stop_recording();
+        assert!(layer.start_replacement());
+        layer.append_replacement(&[0.5; 30]);
+        status.publish(&layer);
+        let snapshot = status.snapshot();
+        assert!(snapshot.is_replacing && snapshot.is_cueing && !snapshot.has_replacement);
+        assert!(layer.stop_replacement(10));
+        status.publish(&layer);
+        assert!(status.snapshot().has_replacement && !status.snapshot().is_replacing);
+        assert!(layer.confirm_replacement());
+        assert_eq!((layer.get_buffer_length(), layer.markers.len()), (30, 1));
+        assert!(layer.undo());
+        assert_eq!(layer.get_buffer_length(), 100);
+    }
+}
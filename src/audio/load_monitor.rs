@@ -0,0 +1,132 @@
+// src/audio/load_monitor.rs
+// DSP load tracking with hysteresis for adaptive quality degradation
+
+/// Quality transition reported by the load monitor
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QualityChange {
+    Degraded,
+    Restored,
+}
+
+/// Tracks smoothed DSP load (processing time / buffer duration)
+/// and decides when to switch to cheaper processing
+#[derive(Debug, Clone)]
+pub struct DspLoadMonitor {
+    smoothed_load: f32,
+    degraded: bool,
+    degrade_threshold: f32,
+    restore_threshold: f32,
+}
+
+impl DspLoadMonitor {
+    const DEFAULT_DEGRADE_THRESHOLD: f32 = 0.8; // 80% of the callback budget
+    const DEFAULT_RESTORE_THRESHOLD: f32 = 0.5; // Hysteresis to avoid flapping
+    const SMOOTHING: f32 = 0.1;
+
+    pub fn new() -> Self {
+        Self::with_thresholds(
+            Self::DEFAULT_DEGRADE_THRESHOLD,
+            Self::DEFAULT_RESTORE_THRESHOLD,
+        )
+    }
+
+    pub fn with_thresholds(degrade_threshold: f32, restore_threshold: f32) -> Self {
+        Self {
+            smoothed_load: 0.0,
+            degraded: false,
+            degrade_threshold,
+            restore_threshold: restore_threshold.min(degrade_threshold),
+        }
+    }
+
+    /// Record one processing cycle (call from audio thread, no allocations)
+    /// Returns a transition if the quality mode changed
+    pub fn update(&mut self, elapsed_secs: f64, budget_secs: f64) -> Option<QualityChange> {
+        if budget_secs <= 0.0 {
+            return None;
+        }
+
+        let load = (elapsed_secs / budget_secs) as f32;
+        self.smoothed_load += (load - self.smoothed_load) * Self::SMOOTHING;
+
+        if !self.degraded && self.smoothed_load > self.degrade_threshold {
+            self.degraded = true;
+            Some(QualityChange::Degraded)
+        } else if self.degraded && self.smoothed_load < self.restore_threshold {
+            self.degraded = false;
+            Some(QualityChange::Restored)
+        } else {
+            None
+        }
+    }
+
+    /// Smoothed load (1.0 = the whole buffer duration was spent processing)
+    pub fn load(&self) -> f32 {
+        self.smoothed_load
+    }
+
+    pub fn is_degraded(&self) -> bool {
+        self.degraded
+    }
+}
+
+impl Default for DspLoadMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_degrades_under_sustained_load() {
+        let mut monitor = DspLoadMonitor::new();
+
+        let mut change = None;
+        for _ in 0..100 {
+            if let Some(c) = monitor.update(0.95, 1.0) {
+                change = Some(c);
+            }
+        }
+
+        assert_eq!(change, Some(QualityChange::Degraded));
+        assert!(monitor.is_degraded());
+    }
+
+    #[test]
+    fn test_single_spike_does_not_degrade() {
+        let mut monitor = DspLoadMonitor::new();
+
+        for _ in 0..50 {
+            monitor.update(0.1, 1.0);
+        }
+        assert_eq!(monitor.update(2.0, 1.0), None);
+        assert!(!monitor.is_degraded());
+    }
+
+    #[test]
+    fn test_restores_with_hysteresis() {
+        let mut monitor = DspLoadMonitor::new();
+        for _ in 0..100 {
+            monitor.update(0.95, 1.0);
+        }
+        assert!(monitor.is_degraded());
+
+        // Load between thresholds keeps degraded mode
+        for _ in 0..100 {
+            monitor.update(0.6, 1.0);
+        }
+        assert!(monitor.is_degraded());
+
+        let mut change = None;
+        for _ in 0..100 {
+            if let Some(c) = monitor.update(0.1, 1.0) {
+                change = Some(c);
+            }
+        }
+        assert_eq!(change, Some(QualityChange::Restored));
+        assert!(!monitor.is_degraded());
+    }
+}
@@ -120,6 +120,20 @@ impl SharedLockFreeBuffer {
             false
         }
     }
+
+    /// Make the buffer usable again after a panic while it was locked
+    pub fn clear_poison(&self) {
+        self.buffer.clear_poison();
+    }
+
+    /// Grow to at least `capacity`, dropping anything queued (blocks; not for
+    /// the audio thread, which only ever try_locks and skips a cycle)
+    pub fn reserve(&self, capacity: usize) {
+        let mut buf = self.buffer.lock().unwrap_or_else(|e| e.into_inner());
+        if buf.capacity() < capacity {
+            *buf = LockFreeAudioBuffer::new(capacity);
+        }
+    }
 }
 
 /// Bidirectional lock-free audio buffer pair for input/output
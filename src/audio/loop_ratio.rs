@@ -0,0 +1,69 @@
+// src/audio/loop_ratio.rs
+// How a layer's length relates to the master loop, and fixes for layers that don't fit
+
+use std::fmt;
+
+/// Layer length as a whole-number ratio of the master loop: `loops:1` for layers
+/// spanning several master loops, `1:parts` for layers that divide it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LoopRatio {
+    pub loops: u32,
+    pub parts: u32,
+}
+
+impl LoopRatio {
+    /// Longest multiple and finest division considered
+    pub const MAX_TERM: u32 = 8;
+
+    /// The ratio whose target length is closest to `length`
+    pub fn nearest(length: usize, master_length: usize) -> Self {
+        let multiples = (1..=Self::MAX_TERM).map(|loops| Self { loops, parts: 1 });
+        let divisions = (2..=Self::MAX_TERM).map(|parts| Self { loops: 1, parts });
+        multiples
+            .chain(divisions)
+            .min_by_key(|ratio| ratio.target_length(master_length).abs_diff(length))
+            .unwrap_or(Self { loops: 1, parts: 1 })
+    }
+
+    /// Length in samples a layer with this ratio should have
+    pub fn target_length(self, master_length: usize) -> usize {
+        (master_length * self.loops as usize + self.parts as usize / 2) / self.parts as usize
+    }
+}
+
+impl fmt::Display for LoopRatio {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.loops, self.parts)
+    }
+}
+
+/// Quick fixes offered when a layer doesn't fit the master loop
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoopFix {
+    Trim,      // cut (or pad with silence) to the nearest ratio
+    Stretch,   // resample to the nearest ratio (changes pitch slightly)
+    SetMaster, // keep the layer as is and make its length the new master
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nearest_ratio() {
+        let master = 48000;
+        assert_eq!(
+            LoopRatio::nearest(96500, master),
+            LoopRatio { loops: 2, parts: 1 }
+        );
+        assert_eq!(
+            LoopRatio::nearest(11900, master),
+            LoopRatio { loops: 1, parts: 4 }
+        );
+        let third = LoopRatio { loops: 1, parts: 3 };
+        assert_eq!(third.target_length(master), 16000);
+        assert_eq!(third.to_string(), "1:3");
+        // Far beyond the longest multiple still snaps to it
+        assert_eq!(LoopRatio::nearest(10 * master, master).loops, 8);
+    }
+}
@@ -1,20 +1,84 @@
-use crossbeam::channel::{Receiver, Sender};
-use std::sync::{Arc, Mutex};
+use crossbeam::channel::{Receiver, TrySendError};
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
 
+use super::analysis::MixAnalyzer;
+use super::biquad::Biquad;
+use super::io::{
+    DEFAULT_MAX_IMPORT_SECONDS, NameTokens, STDIN_PATH, STDOUT_PATH, expand_file_name,
+    release_file_name,
+};
+use super::undo_history::QuantizedBuffer;
 use super::{
-    AudioConfig, AudioEvent, AudioLayer, LayerCommand, SharedLockFreeBuffer, SimdMixer, TempoEngine,
+    AudioConfig, AudioEvent, AudioLayer, BitDepth, ClickFlavor, ClickSettings, CommandScheduler,
+    DspLoadMonitor, EventSender, ExportHook, ExportNormalization, ExportRegion, ImportMode,
+    LayerCommand, LayerHistory, LayerPhase, LayerSnapshot, LayerStats, LayerStatus, LayerVersion,
+    LoopFix, LoopRatio, LoopTempo, LooperError, MAX_STEREO_WIDTH, MetronomeState, MixQuantize,
+    NoiseProfile, OutputBus, PeakMeter, QualityChange, QueuedActions, SessionMetadata,
+    SharedLockFreeBuffer, SimdMixer, TempoEngine, Transaction, TransactionLog, TransportPosition,
+    TransportStamp, UndoHistory, WavLoopInfo, read_loop_info,
 };
-// use super::io::import_wav;
+
+/// Candidate import looped against the running layers until confirmed or cancelled
+struct Audition {
+    layer_id: usize,
+    file_path: String,
+    samples: Vec<f32>,
+    loop_info: WavLoopInfo, // applied if the candidate replaces the layer
+    position: usize,
+    remaining: usize,          // samples left to play
+    waiting_for_measure: bool, // starts on the next measure boundary when synced
+}
+
+/// What a saved session carries besides tempo and layer settings
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SessionContent {
+    Template,                // no audio
+    Audio { history: bool }, // plus undo states and takes
+}
+
+/// A layer's undo states and takes, copied out to be written after its lock is
+/// released (the buffers are shared, not copied)
+struct LayerVersions {
+    undo: Vec<LayerSnapshot>,
+    undo_position: usize,
+    takes: Vec<LayerSnapshot>,
+    current_take: usize,
+}
+
+impl LayerVersions {
+    fn of(layer: &AudioLayer) -> Self {
+        let (undo, undo_position) = layer.undo_history.snapshots();
+        Self {
+            undo,
+            undo_position,
+            takes: layer.takes.takes().to_vec(),
+            current_take: layer.takes.current().unwrap_or(0),
+        }
+    }
+}
+
+/// Mute or solo toggle waiting for a quantize boundary
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MixChange {
+    Mute,
+    Solo,
+}
 
 pub struct LooperEngine {
     layers: Arc<Vec<Arc<Mutex<AudioLayer>>>>,
+    // Lock-free copy of layer state for the UI
+    layer_status: Arc<Vec<LayerStatus>>,
+    status_publisher_active: Arc<AtomicBool>,
     config: AudioConfig,
     master_loop_length: Arc<Mutex<Option<usize>>>,
     input_buffer: SharedLockFreeBuffer,
     is_recording: Arc<Mutex<bool>>,
     recording_layer: Arc<Mutex<Option<usize>>>,
     command_receiver: Arc<Mutex<Option<Receiver<LayerCommand>>>>,
-    event_sender: Arc<Mutex<Option<Sender<AudioEvent>>>>,
+    event_sender: Arc<Mutex<Option<EventSender>>>,
     debug_mode: Arc<Mutex<bool>>,
     // Tempo / sync
     tempo: Arc<Mutex<TempoEngine>>,
@@ -22,32 +86,131 @@ pub struct LooperEngine {
     pending_play: Arc<Mutex<Vec<usize>>>,
     pending_stop: Arc<Mutex<Vec<usize>>>,
     pending_record: Arc<Mutex<Option<usize>>>,
+    pending_jumps: Arc<Mutex<Vec<(usize, usize)>>>, // (layer_id, marker position)
+    mix_quantize: Arc<Mutex<MixQuantize>>,
+    pending_mix: Arc<Mutex<Vec<(usize, MixChange)>>>,
+    // Samples new takes are shifted earlier by (device round trip)
+    latency_compensation: Arc<Mutex<usize>>,
+    // Commands waiting for an exact transport position
+    scheduler: Arc<Mutex<CommandScheduler>>,
+    // Transport position of the work being processed (timestamps events)
+    transport_clock: Arc<Mutex<TransportStamp>>,
+    // Bar:beat:tick for the status publisher, copied after each block
+    transport_position: Arc<Mutex<TransportPosition>>,
     // Metronome
     metronome_enabled: Arc<Mutex<bool>>,
     metronome_sample: Arc<Mutex<Vec<f32>>>,
     metronome_playhead: Arc<Mutex<Option<usize>>>,
+    metronome_accent: Arc<Mutex<bool>>, // the playing click is accented
+    // File preview (cue) channel
+    preview_sample: Arc<Mutex<Vec<f32>>>,
+    preview_playhead: Arc<Mutex<Option<usize>>>,
+    audition: Arc<Mutex<Option<Audition>>>,
     // Count-in mode
     count_in_mode: Arc<Mutex<bool>>,
+    count_in_measures: Arc<Mutex<u32>>,
+    // Count-out: keep clicking for a bar after recording stops
+    count_out_enabled: Arc<Mutex<bool>>,
+    count_out_remaining: Arc<Mutex<u32>>, // beats
+    // Pre-count: click the rest of the bar while a synced record is armed
+    pre_count_enabled: Arc<Mutex<bool>>,
     // SIMD mixer
     simd_mixer: Arc<Mutex<SimdMixer>>,
     // Preallocated scratch buffer for fallback mixing
     scratch_buffer: Arc<Mutex<Vec<f32>>>,
     // Preallocated scratch buffer for recording
     recording_scratch: Arc<Mutex<Vec<f32>>>,
+    // Adaptive quality under CPU pressure
+    load_monitor: Arc<Mutex<DspLoadMonitor>>,
+    // Periodic undo autosnapshots (None = disabled)
+    autosnapshot_interval: Arc<Mutex<Option<Duration>>>,
+    autosnapshot_active: Arc<Mutex<bool>>,
+    // Global undo across layers
+    transaction_log: Arc<Mutex<TransactionLog>>,
+    // Title, author, notes and tags saved with the session
+    metadata: Arc<Mutex<SessionMetadata>>,
+    // Sessions also keep undo states and takes (more audio files)
+    save_history: Arc<Mutex<bool>>,
+    // Bus the metronome / count-in click plays on
+    click_bus: Arc<Mutex<OutputBus>>,
+    click: Arc<Mutex<ClickSettings>>,
+    // Level of the live input (for the input check screen)
+    input_meter: Arc<PeakMeter>,
+    // Sample format of WAV exports and stems
+    export_bit_depth: Arc<Mutex<BitDepth>>,
+    // Upload / copy step run after each WAV export
+    export_hook: Arc<Mutex<Option<ExportHook>>>,
+    // Resample imports with an ACID tempo to the session BPM
+    import_tempo_match: Arc<Mutex<bool>>,
+    // Longest WAV imported, previewed or auditioned, in seconds (None: no limit)
+    import_limit: Arc<Mutex<Option<u32>>>,
+    // Copy of the master mix for the analysis worker (loudness / spectrogram)
+    analysis_enabled: Arc<AtomicBool>,
+    analysis_active: Arc<Mutex<bool>>,
+    analysis_tap: Arc<SharedLockFreeBuffer>,
+    // Idle parking: stopped layers drop their working copy in the background
+    park_idle_layers: Arc<AtomicBool>,
+    parking_active: Arc<Mutex<bool>>,
+    // Undo compression: inactive undo states of long loops quantized in the background
+    compress_undo: Arc<AtomicBool>,
+    compaction_active: Arc<Mutex<bool>>,
+    // Room tone spectrum for denoising, learned from a layer
+    noise_profile: Arc<Mutex<Option<NoiseProfile>>>,
+    // Rumble filter on the recorded input (None = off)
+    input_high_pass: Arc<Mutex<Option<Biquad>>>,
+    // Mid/side width the output applies to the main bus (1.0 = as mixed)
+    stereo_width: Arc<Mutex<f32>>,
 }
 
 impl LooperEngine {
+    // Previews are mixed quietly so they can be auditioned over a running set
+    const PREVIEW_GAIN: f32 = 0.3;
+    // Accented clicks (last count-in beat) play an octave up and louder
+    const ACCENT_GAIN: f32 = 1.5;
+    // Length of an import audition in measures
+    const AUDITION_MEASURES: usize = 2;
+    // How often LayerStatusBatch events are sent (~30 fps)
+    const STATUS_INTERVAL: Duration = Duration::from_millis(33);
+    // How often idle layers are looked for when idle parking is on
+    const PARK_INTERVAL: Duration = Duration::from_secs(5);
+    // Samples of a layer compared per lock when autosnapshotting
+    const AUTOSNAPSHOT_CHUNK: usize = 48_000;
+    // Range of the input high-pass cutoff in Hz
+    const INPUT_HIGH_PASS_MIN: f32 = 40.0;
+    const INPUT_HIGH_PASS_MAX: f32 = 120.0;
+    // Block size of offline renders (exports)
+    const RENDER_BLOCK: usize = 1024;
+    // Input blocks taken whole until reserve_input asks for more
+    const MAX_INPUT_FRAMES: usize = 8192;
+    // Saving undo states and takes warns past this much extra audio
+    const HISTORY_WARNING_BYTES: usize = 256 * 1024 * 1024;
+
     pub fn new(config: AudioConfig) -> Self {
         let mut layers = Vec::with_capacity(config.max_layers);
         for i in 0..config.max_layers {
             layers.push(Arc::new(Mutex::new(AudioLayer::new(i))));
         }
+        let layer_status: Vec<LayerStatus> = layers
+            .iter()
+            .map(|layer| {
+                let status = LayerStatus::new();
+                status.publish(&layer.lock().unwrap());
+                status
+            })
+            .collect();
+        let max_input = config.buffer_size.max(Self::MAX_INPUT_FRAMES);
+        let initial_revisions = layers
+            .iter()
+            .map(|layer| layer.lock().unwrap().undo_history.revision())
+            .collect();
 
         Self {
             layers: Arc::new(layers),
+            layer_status: Arc::new(layer_status),
+            status_publisher_active: Arc::new(AtomicBool::new(false)),
             config: config.clone(),
             master_loop_length: Arc::new(Mutex::new(None)),
-            input_buffer: SharedLockFreeBuffer::new(config.buffer_size * 4), // 4x capacity for safety
+            input_buffer: SharedLockFreeBuffer::new(max_input * 4), // 4x capacity for safety
             is_recording: Arc::new(Mutex::new(false)),
             recording_layer: Arc::new(Mutex::new(None)),
             command_receiver: Arc::new(Mutex::new(None)),
@@ -58,18 +221,403 @@ impl LooperEngine {
             pending_play: Arc::new(Mutex::new(Vec::with_capacity(config.max_layers))),
             pending_stop: Arc::new(Mutex::new(Vec::with_capacity(config.max_layers))),
             pending_record: Arc::new(Mutex::new(None)),
+            pending_jumps: Arc::new(Mutex::new(Vec::with_capacity(config.max_layers))),
+            mix_quantize: Arc::new(Mutex::new(MixQuantize::Off)),
+            pending_mix: Arc::new(Mutex::new(Vec::with_capacity(config.max_layers * 2))),
+            latency_compensation: Arc::new(Mutex::new(0)),
+            scheduler: Arc::new(Mutex::new(CommandScheduler::new())),
+            transport_clock: Arc::new(Mutex::new(TransportStamp::new(0, Instant::now()))),
+            transport_position: Arc::new(Mutex::new(TransportPosition::default())),
             metronome_enabled: Arc::new(Mutex::new(false)),
-            metronome_sample: Arc::new(Mutex::new(Vec::new())),
+            // Synthesized until a sample is set
+            metronome_sample: Arc::new(Mutex::new(
+                ClickFlavor::Beep.synthesize(config.sample_rate),
+            )),
             metronome_playhead: Arc::new(Mutex::new(None)),
+            metronome_accent: Arc::new(Mutex::new(false)),
+            preview_sample: Arc::new(Mutex::new(Vec::new())),
+            preview_playhead: Arc::new(Mutex::new(None)),
+            audition: Arc::new(Mutex::new(None)),
             count_in_mode: Arc::new(Mutex::new(false)),
+            count_in_measures: Arc::new(Mutex::new(1)),
+            count_out_enabled: Arc::new(Mutex::new(false)),
+            count_out_remaining: Arc::new(Mutex::new(0)),
+            pre_count_enabled: Arc::new(Mutex::new(false)),
             simd_mixer: Arc::new(Mutex::new(SimdMixer::new(config.buffer_size * 2))),
             // Preallocate scratch buffer for fallback mixing
             // 4x headroom to prevent resize() in RT callback (must never resize)
             scratch_buffer: Arc::new(Mutex::new(vec![0.0; config.buffer_size * 4])),
-            // Preallocate recording buffer to max size (4096 samples max expected)
+            // Preallocate recording buffer for the largest input block
             // Avoids resize() calls in audio callback
-            recording_scratch: Arc::new(Mutex::new(vec![0.0; 4096])),
+            recording_scratch: Arc::new(Mutex::new(vec![0.0; max_input])),
+            load_monitor: Arc::new(Mutex::new(DspLoadMonitor::new())),
+            autosnapshot_interval: Arc::new(Mutex::new(None)),
+            autosnapshot_active: Arc::new(Mutex::new(false)),
+            transaction_log: Arc::new(Mutex::new(TransactionLog::with_revisions(
+                initial_revisions,
+            ))),
+            metadata: Arc::new(Mutex::new(SessionMetadata::default())),
+            save_history: Arc::new(Mutex::new(false)),
+            input_meter: Arc::new(PeakMeter::new()),
+            click_bus: Arc::new(Mutex::new(OutputBus::Main)),
+            click: Arc::new(Mutex::new(ClickSettings::default())),
+            export_bit_depth: Arc::new(Mutex::new(BitDepth::default())),
+            export_hook: Arc::new(Mutex::new(None)),
+            import_tempo_match: Arc::new(Mutex::new(false)),
+            import_limit: Arc::new(Mutex::new(Some(DEFAULT_MAX_IMPORT_SECONDS))),
+            analysis_enabled: Arc::new(AtomicBool::new(false)),
+            analysis_active: Arc::new(Mutex::new(false)),
+            // One second of headroom in case the worker falls behind
+            analysis_tap: Arc::new(SharedLockFreeBuffer::new(config.sample_rate as usize)),
+            park_idle_layers: Arc::new(AtomicBool::new(false)),
+            parking_active: Arc::new(Mutex::new(false)),
+            compress_undo: Arc::new(AtomicBool::new(false)),
+            compaction_active: Arc::new(Mutex::new(false)),
+            noise_profile: Arc::new(Mutex::new(None)),
+            input_high_pass: Arc::new(Mutex::new(None)),
+            stereo_width: Arc::new(Mutex::new(1.0)),
+        }
+    }
+
+    /// Periodically snapshot changed layers into their undo history
+    /// Runs on a background thread (snapshots allocate); None disables it
+    /// A layer's audio is compared a chunk at a time, so the audio thread never
+    /// finds it held for a whole buffer; only audio that changed gets copied
+    pub fn set_autosnapshot_interval(&self, interval: Option<Duration>) {
+        *self.autosnapshot_interval.lock().unwrap() = interval;
+
+        let mut active = self.autosnapshot_active.lock().unwrap();
+        if interval.is_none() || *active {
+            return;
+        }
+        *active = true;
+
+        let layers = Arc::clone(&self.layers);
+        let interval_setting = Arc::clone(&self.autosnapshot_interval);
+        let active_flag = Arc::clone(&self.autosnapshot_active);
+        let transaction_log = Arc::clone(&self.transaction_log);
+        std::thread::spawn(move || {
+            loop {
+                let interval = {
+                    let mut active = active_flag.lock().unwrap();
+                    match *interval_setting.lock().unwrap() {
+                        Some(interval) => interval,
+                        None => {
+                            *active = false;
+                            break;
+                        }
+                    }
+                };
+                std::thread::sleep(interval);
+
+                // A layer that is busy, recording or edited meanwhile waits for
+                // the next tick
+                'layers: for layer_arc in layers.iter() {
+                    let Some(source) = layer_arc
+                        .try_lock()
+                        .ok()
+                        .filter(|layer| !layer.is_recording)
+                        .and_then(|layer| layer.checkpoint_source())
+                    else {
+                        continue;
+                    };
+                    let mut changed = false;
+                    for start in (0..source.len().max(1)).step_by(Self::AUTOSNAPSHOT_CHUNK) {
+                        let range = start..(start + Self::AUTOSNAPSHOT_CHUNK).min(source.len());
+                        let matches = match layer_arc.try_lock() {
+                            Ok(layer) if !layer.is_recording => layer.audio_matches(&source, range),
+                            _ => None,
+                        };
+                        match matches {
+                            Some(true) => {}
+                            Some(false) => {
+                                changed = true;
+                                break;
+                            }
+                            None => continue 'layers,
+                        }
+                    }
+                    if let Ok(mut layer) = layer_arc.try_lock()
+                        && !layer.is_recording
+                    {
+                        if changed {
+                            layer.checkpoint();
+                        } else {
+                            layer.checkpoint_unchanged(&source);
+                        }
+                    }
+                }
+                if let Ok(mut log) = transaction_log.lock() {
+                    Self::record_transactions(&layers, &mut log);
+                }
+            }
+        });
+    }
+
+    /// Analyse the master mix on a background thread, sending a MixAnalysis
+    /// event every 100 ms until disabled
+    fn set_mix_analysis(&self, enabled: bool) {
+        self.analysis_enabled.store(enabled, Ordering::Relaxed);
+
+        let mut active = self.analysis_active.lock().unwrap();
+        if !enabled || *active {
+            return;
+        }
+        *active = true;
+
+        let enabled_flag = Arc::clone(&self.analysis_enabled);
+        let active_flag = Arc::clone(&self.analysis_active);
+        let tap = Arc::clone(&self.analysis_tap);
+        let event_sender = Arc::clone(&self.event_sender);
+        let mut analyzer = MixAnalyzer::new(self.config.sample_rate);
+        std::thread::spawn(move || {
+            let mut block = vec![0.0; analyzer.frame_len()];
+            let mut filled = 0;
+            loop {
+                {
+                    let mut active = active_flag.lock().unwrap();
+                    if !enabled_flag.load(Ordering::Relaxed) {
+                        *active = false;
+                        break;
+                    }
+                }
+                filled += tap.try_read(&mut block[filled..]);
+                if filled < block.len() {
+                    std::thread::sleep(Duration::from_millis(20));
+                    continue;
+                }
+                filled = 0;
+                let frame = analyzer.process(&block);
+                if let Ok(sender) = event_sender.try_lock()
+                    && let Some(ref tx) = *sender
+                {
+                    let _ = tx.try_send(AudioEvent::MixAnalysis(frame));
+                }
+            }
+        });
+    }
+
+    /// Park idle layers on a background thread (encoding and freeing whole
+    /// buffers is too slow for the audio thread); the 16-bit copy is encoded
+    /// without holding the layer. Parked layers that are playing get their
+    /// working copy back here, decoded the same way. Disabling brings them all back
+    fn set_idle_parking(&self, enabled: bool) {
+        self.park_idle_layers.store(enabled, Ordering::Relaxed);
+
+        let mut active = self.parking_active.lock().unwrap();
+        if !enabled || *active {
+            return;
+        }
+        *active = true;
+
+        let layers = Arc::clone(&self.layers);
+        let enabled_flag = Arc::clone(&self.park_idle_layers);
+        let active_flag = Arc::clone(&self.parking_active);
+        std::thread::spawn(move || {
+            loop {
+                std::thread::sleep(Self::PARK_INTERVAL);
+                {
+                    let mut active = active_flag.lock().unwrap();
+                    if !enabled_flag.load(Ordering::Relaxed) {
+                        *active = false;
+                        break;
+                    }
+                }
+                for layer_arc in layers.iter() {
+                    // A layer the audio thread holds is in use anyway
+                    let (source, parked) = match layer_arc.try_lock() {
+                        Ok(layer) if !layer.is_playing => (layer.park_source(), None),
+                        Ok(layer) => (None, layer.parked_audio()),
+                        Err(_) => (None, None),
+                    };
+                    if let Some(source) = source {
+                        // Dropped if the layer changed or started playing meanwhile
+                        let quantized = Arc::new(QuantizedBuffer::encode(&source));
+                        if let Ok(mut layer) = layer_arc.try_lock() {
+                            layer.park(&source, quantized);
+                        }
+                    }
+                    if let Some(quantized) = parked {
+                        Self::unpark_unlocked(layer_arc, &quantized);
+                    }
+                }
+            }
+            for layer_arc in layers.iter() {
+                let parked = layer_arc.lock().ok().and_then(|layer| layer.parked_audio());
+                if let Some(quantized) = parked {
+                    Self::unpark_unlocked(layer_arc, &quantized);
+                }
+            }
+        });
+    }
+
+    /// Give a parked layer its audio back; it's decoded unlocked, so a playing
+    /// layer never drops out of the mix for it (retried next round if the
+    /// layer is busy or its audio changed meanwhile)
+    fn unpark_unlocked(layer_arc: &Mutex<AudioLayer>, quantized: &Arc<QuantizedBuffer>) {
+        let audio = Arc::new(quantized.decode());
+        let copy = audio.as_ref().clone();
+        if let Ok(mut layer) = layer_arc.try_lock() {
+            layer.unpark_from(quantized, audio, copy);
+        }
+    }
+
+    /// Quantize inactive undo states of long loops on a background thread; each
+    /// layer is only held to pick its buffers and to swap the 16-bit copies in,
+    /// never while encoding. Disabling brings every state back to full precision
+    fn set_undo_compression(&self, enabled: bool) {
+        self.compress_undo.store(enabled, Ordering::Relaxed);
+
+        let mut active = self.compaction_active.lock().unwrap();
+        if !enabled || *active {
+            return;
+        }
+        *active = true;
+
+        let layers = Arc::clone(&self.layers);
+        let enabled_flag = Arc::clone(&self.compress_undo);
+        let active_flag = Arc::clone(&self.compaction_active);
+        std::thread::spawn(move || {
+            for layer_arc in layers.iter() {
+                if let Ok(mut layer) = layer_arc.lock() {
+                    layer.undo_history.set_compression_threshold(Some(
+                        UndoHistory::DEFAULT_COMPRESSION_THRESHOLD,
+                    ));
+                }
+            }
+            loop {
+                std::thread::sleep(Self::PARK_INTERVAL);
+                {
+                    let mut active = active_flag.lock().unwrap();
+                    if !enabled_flag.load(Ordering::Relaxed) {
+                        *active = false;
+                        break;
+                    }
+                }
+                for layer_arc in layers.iter() {
+                    // A layer the audio thread holds is in use anyway
+                    let candidates = match layer_arc.try_lock() {
+                        Ok(layer) => layer.undo_history.compaction_candidates(),
+                        Err(_) => continue,
+                    };
+                    for buffer in candidates {
+                        let quantized = Arc::new(QuantizedBuffer::encode(&buffer));
+                        if let Ok(mut layer) = layer_arc.try_lock() {
+                            layer.undo_history.install_compacted(&buffer, quantized);
+                        }
+                    }
+                }
+            }
+            for layer_arc in layers.iter() {
+                if let Ok(mut layer) = layer_arc.lock() {
+                    layer.undo_history.set_compression_threshold(None);
+                }
+            }
+        });
+    }
+
+    /// Fold undo snapshots saved since the last call into one transaction
+    /// Uses try_lock on layers, so it is safe to call from the audio thread
+    fn record_transactions(layers: &[Arc<Mutex<AudioLayer>>], log: &mut TransactionLog) {
+        log.sync(layers.iter().enumerate().filter_map(|(layer_id, layer)| {
+            layer
+                .try_lock()
+                .ok()
+                .map(|layer| (layer_id, layer.undo_history.revision()))
+        }));
+    }
+
+    fn sync_transaction_log(&self) {
+        if let Ok(mut log) = self.transaction_log.try_lock() {
+            Self::record_transactions(&self.layers, &mut log);
+        }
+    }
+
+    /// Lock every layer a transaction touches before any step runs, so a busy
+    /// or locked layer refuses the whole transaction instead of leaving it half
+    /// applied
+    fn lock_transaction(
+        &self,
+        transaction: &Transaction,
+    ) -> Result<Vec<(MutexGuard<'_, AudioLayer>, u32)>, LooperError> {
+        transaction
+            .steps
+            .iter()
+            .map(|&(layer_id, count)| {
+                let layer = self.layers[layer_id]
+                    .try_lock()
+                    .map_err(|_| LooperError::Busy("Layer"))?;
+                Self::check_unlocked(&layer, layer_id)?;
+                Ok((layer, count))
+            })
+            .collect()
+    }
+
+    /// Undo the most recent action across all layers; returns affected layers
+    fn apply_global_undo(&self) -> Result<Vec<usize>, LooperError> {
+        let mut log = self
+            .transaction_log
+            .try_lock()
+            .map_err(|_| LooperError::Busy("Transaction log"))?;
+
+        // Skip transactions whose snapshots already fell out of the layer histories
+        while let Some(transaction) = log.take_undo() {
+            let mut layers = match self.lock_transaction(&transaction) {
+                Ok(layers) => layers,
+                Err(e) => {
+                    log.push_undo(transaction);
+                    return Err(e);
+                }
+            };
+            let mut applied = false;
+            for (layer, count) in &mut layers {
+                for _ in 0..*count {
+                    applied |= layer.undo();
+                }
+            }
+            drop(layers);
+            if applied {
+                let layer_ids = transaction.layer_ids();
+                log.push_redo(transaction);
+                return Ok(layer_ids);
+            }
+        }
+        Ok(Vec::new())
+    }
+
+    /// Redo the most recently undone global action; returns affected layers
+    fn apply_global_redo(&self) -> Result<Vec<usize>, LooperError> {
+        let mut log = self
+            .transaction_log
+            .try_lock()
+            .map_err(|_| LooperError::Busy("Transaction log"))?;
+
+        while let Some(transaction) = log.take_redo() {
+            let mut layers = match self.lock_transaction(&transaction) {
+                Ok(layers) => layers,
+                Err(e) => {
+                    log.push_redo(transaction);
+                    return Err(e);
+                }
+            };
+            let mut applied = false;
+            for (layer, count) in &mut layers {
+                for _ in 0..*count {
+                    applied |= layer.redo();
+                }
+            }
+            drop(layers);
+            if applied {
+                let layer_ids = transaction.layer_ids();
+                log.push_undo(transaction);
+                return Ok(layer_ids);
+            }
         }
+        Ok(Vec::new())
+    }
+
+    pub fn get_autosnapshot_interval(&self) -> Option<Duration> {
+        *self.autosnapshot_interval.lock().unwrap()
     }
 
     pub fn set_metronome_sample(&self, samples: Vec<f32>) {
@@ -79,9 +627,44 @@ impl LooperEngine {
     }
 
     pub fn process_audio(&self, input: &[f32], output: &mut [f32]) {
+        self.process_audio_routed(input, output, None, None);
+    }
+
+    /// Width for the output to apply to the main bus (non-blocking)
+    pub fn stereo_width(&self) -> Option<f32> {
+        self.stereo_width.try_lock().ok().map(|width| *width)
+    }
+
+    /// Bus and settings of the click, for rendering it separately (non-blocking)
+    pub fn click_output(&self) -> Option<(OutputBus, ClickSettings)> {
+        let bus = self.click_bus.try_lock().ok()?;
+        let click = self.click.try_lock().ok()?;
+        Some((*bus, *click))
+    }
+
+    /// Like `process_audio`, but layers and the click routed to the cue bus are
+    /// rendered into `cue` instead of `output` (the main bus)
+    /// With `click`, the click is rendered there alone so the caller can pan it
+    pub fn process_audio_routed(
+        &self,
+        input: &[f32],
+        output: &mut [f32],
+        mut cue: Option<&mut [f32]>,
+        click: Option<&mut [f32]>,
+    ) {
         // REMOVED: File I/O in audio thread is not real-time safe
         // Debug logging should be done via lock-free channel to separate thread
         // For now, removed to prevent blocking
+        let started = Instant::now();
+
+        // Transport position at the start of this block
+        let block_start = self
+            .tempo
+            .try_lock()
+            .map(|t| t.global_position)
+            .unwrap_or(0);
+        let block_stamp = TransportStamp::new(block_start, started);
+        self.set_transport_clock(block_stamp);
 
         // Write input to lock-free buffer (non-blocking)
         // Silently drop if buffer is full (avoid eprintln! in audio thread)
@@ -90,36 +673,116 @@ impl LooperEngine {
         // Process commands from UI thread
         self.process_commands();
 
+        // A transport reset among the commands moves the block start
+        let (block_start, transport_paused) = self
+            .tempo
+            .try_lock()
+            .map(|t| (t.global_position, t.paused))
+            .unwrap_or((block_start, false));
+        let block_stamp = TransportStamp::new(block_start, started);
+
         // Record input if any layer is recording (zero allocations)
         if let Ok(recording_layer) = self.recording_layer.try_lock()
             && let Some(layer_id) = *recording_layer
             && let Ok(mut layer) = self.layers[layer_id].try_lock()
-            && layer.is_recording
+            && (layer.is_recording || layer.is_replacing)
         {
             // Try to get recording scratch buffer
-            // Buffer is preallocated to max size (4096) to avoid resize() in RT callback
+            // Buffer is preallocated to the largest input block to avoid resize() in RT callback
             if let Ok(mut temp_buffer) = self.recording_scratch.try_lock() {
                 let read_len = input.len().min(temp_buffer.len());
                 let read_count = self.input_buffer.try_read(&mut temp_buffer[..read_len]);
-                if read_count > 0 {
+                // Rumble and handling noise never reach the layer
+                if let Ok(mut high_pass) = self.input_high_pass.try_lock()
+                    && let Some(filter) = high_pass.as_mut()
+                {
+                    for sample in temp_buffer[..read_count].iter_mut() {
+                        *sample = filter.process(*sample);
+                    }
+                }
+                // A dead cable or muted interface is caught a second in, not after the take
+                let window = self.config.sample_rate as usize;
+                if layer.track_input(&temp_buffer[..read_count], window) {
+                    self.send_event(AudioEvent::SilentInput(layer_id));
+                }
+                if read_count > 0 && layer.is_replacing {
+                    layer.append_replacement(&temp_buffer[..read_count]);
+                } else if read_count > 0 && layer.is_playing {
+                    layer.overdub_samples(&temp_buffer[..read_count]);
+                } else if read_count > 0 {
                     layer.append_samples(&temp_buffer[..read_count]);
                 }
             }
             // If we can't get the scratch buffer, skip this cycle (rare)
         }
 
-        // Mix all layers using SIMD acceleration
-        if let Ok(mut mixer) = self.simd_mixer.try_lock() {
-            mixer.mix_layers(&self.layers, output);
-        } else {
-            // Fallback to scalar mixing if SIMD mixer is locked
-            Self::mix_layers_static(&self.layers, output, &self.scratch_buffer);
+        // Under CPU pressure use the cheap scalar path (no meters)
+        let degraded = self
+            .load_monitor
+            .try_lock()
+            .map(|m| m.is_degraded())
+            .unwrap_or(false);
+
+        // Mix in sub-blocks split at scheduled command positions so they land sample-accurately
+        // (scheduled commands wait while the transport is paused)
+        let mut offset = 0;
+        while offset < output.len() {
+            self.set_transport_clock(
+                block_stamp.at_position(block_start + offset, self.config.sample_rate),
+            );
+            if !transport_paused {
+                self.run_due_commands(block_start + offset);
+            }
+            let end = self
+                .scheduler
+                .try_lock()
+                .ok()
+                .filter(|_| !transport_paused)
+                .and_then(|s| s.next_at())
+                .map(|at| {
+                    at.saturating_sub(block_start)
+                        .clamp(offset + 1, output.len())
+                })
+                .unwrap_or(output.len());
+            match cue.as_deref_mut() {
+                Some(cue) => {
+                    self.mix_block(&mut output[offset..end], degraded, Some(OutputBus::Main));
+                    self.mix_block(&mut cue[offset..end], degraded, Some(OutputBus::Cue));
+                }
+                None => self.mix_block(&mut output[offset..end], degraded, None),
+            }
+            offset = end;
+        }
+
+        // Master mix without the click for the analysis pane
+        if self.analysis_enabled.load(Ordering::Relaxed) {
+            let _ = self.analysis_tap.try_write(output);
         }
 
         // Mix metronome if active
-        self.mix_metronome(output);
+        let click_bus = self
+            .click_bus
+            .try_lock()
+            .map(|b| *b)
+            .unwrap_or(OutputBus::Main);
+        let volume = self.click.try_lock().map(|c| c.volume).unwrap_or(1.0);
+        match (click, cue) {
+            (Some(click), _) => {
+                click.fill(0.0);
+                self.mix_metronome(click, volume);
+            }
+            (None, Some(cue)) if click_bus == OutputBus::Cue => self.mix_metronome(cue, volume),
+            _ => self.mix_metronome(output, volume),
+        }
+
+        // Mix file preview if one is playing
+        self.mix_preview(output);
 
-        // Only process tempo if beat sync or metronome is enabled
+        // Mix import candidate under audition
+        self.mix_audition(output);
+
+        // The transport always runs (scheduled commands follow it);
+        // beat/measure actions only fire if beat sync or metronome is enabled
         let (beat_sync_enabled, metronome_enabled) = (
             self.beat_sync_enabled
                 .try_lock()
@@ -131,150 +794,586 @@ impl LooperEngine {
                 .unwrap_or(false),
         );
 
-        if beat_sync_enabled || metronome_enabled {
-            let processed_samples = input.len();
+        let count_out_active = self
+            .count_out_remaining
+            .try_lock()
+            .map(|beats| *beats > 0)
+            .unwrap_or(false);
+        let tempo_active = beat_sync_enabled || metronome_enabled || count_out_active;
+        let processed_samples = output.len();
 
-            // Get state BEFORE advancing
-            let (prev_measure, prev_beat_number) = {
-                if let Ok(tempo) = self.tempo.try_lock() {
-                    (
-                        tempo.get_current_measure(),
-                        tempo.global_position / tempo.samples_per_beat,
-                    )
-                } else {
-                    (0, 0)
-                }
-            };
+        // Get state BEFORE advancing
+        let (prev_measure, prev_beat_number) = {
+            if let Ok(tempo) = self.tempo.try_lock() {
+                (
+                    tempo.get_current_measure(),
+                    tempo.global_position / tempo.samples_per_beat,
+                )
+            } else {
+                (0, 0)
+            }
+        };
 
-            // Advance tempo and check for crossings
-            let (crossed_measure, crossed_beat, count_in_data) = {
-                if let Ok(mut tempo) = self.tempo.try_lock() {
-                    tempo.advance(processed_samples);
+        // Advance tempo and check for crossings
+        let (crossed_measure, crossed_beat, crossed_offbeat, count_in_data, beat_data, new_beat) = {
+            if let Ok(mut tempo) = self.tempo.try_lock() {
+                // A running count-in clicks even with sync and metronome off
+                let tempo_active = tempo_active || tempo.count_in_active;
+                let prev_position = tempo.global_position;
+                tempo.advance(processed_samples);
 
-                    let curr_measure = tempo.get_current_measure();
-                    let curr_beat_number = tempo.global_position / tempo.samples_per_beat;
+                let curr_measure = tempo.get_current_measure();
+                let curr_beat_number = tempo.global_position / tempo.samples_per_beat;
 
-                    let crossed_measure = curr_measure != prev_measure;
-                    let crossed_beat = curr_beat_number > prev_beat_number;
+                // The transport always runs, so the UI's beat display follows it even
+                // with sync and the metronome off; beat actions only run when active
+                let new_beat = curr_beat_number > prev_beat_number;
+                let crossed_measure = tempo_active && curr_measure != prev_measure;
+                let crossed_beat = tempo_active && new_beat;
+                let crossed_offbeat = tempo_active
+                    && tempo
+                        .offbeat_between(prev_position, tempo.global_position)
+                        .is_some();
 
-                    let count_in_data =
-                        if tempo.count_in_active && tempo.count_in_remaining_beats > 0 {
-                            tempo
-                                .count_in_layer
-                                .map(|layer_id| (layer_id, tempo.count_in_remaining_beats))
-                        } else {
-                            None
-                        };
-                    (crossed_measure, crossed_beat, count_in_data)
-                } else {
-                    (false, false, None)
-                }
-            };
+                // (beat in measure, measure, boundary position) of the latest beat crossed
+                let beat_data = (
+                    tempo.get_current_beat(),
+                    curr_measure,
+                    curr_beat_number * tempo.samples_per_beat,
+                );
 
-            if crossed_measure {
-                self.run_scheduled_actions();
-                // Trigger metronome ONLY on measure boundaries (downbeat)
-                self.trigger_metronome_click();
+                let count_in_data = if tempo.count_in_active && tempo.count_in_remaining_beats > 0 {
+                    tempo
+                        .count_in_layer
+                        .map(|layer_id| (layer_id, tempo.count_in_remaining_beats))
+                } else {
+                    None
+                };
+                (
+                    crossed_measure,
+                    crossed_beat,
+                    crossed_offbeat,
+                    count_in_data,
+                    beat_data,
+                    new_beat,
+                )
+            } else {
+                (false, false, false, None, (1, 0, block_start), false)
             }
+        };
 
-            if crossed_beat {
-                // Emit count-in event (but don't trigger metronome on every beat)
-                if let Some((layer_id, remaining_beats)) = count_in_data {
-                    self.send_event(AudioEvent::CountInTick {
-                        layer_id,
-                        remaining_beats,
-                    });
+        let (beat, measure, beat_position) = beat_data;
+        let beat_stamp = block_stamp.at_position(beat_position, self.config.sample_rate);
+
+        if crossed_measure {
+            self.run_downbeat(beat_stamp);
+        }
+
+        if new_beat {
+            self.send_event(AudioEvent::Beat(beat, measure, beat_stamp));
+        }
+
+        if crossed_beat {
+            // Marker jumps are quantized to the beat
+            self.run_pending_jumps();
+            self.run_pending_mix(MixQuantize::Beat);
+
+            // Count-in, pre-count and count-out click on every beat, metronome or not
+            if let Some((layer_id, remaining_beats)) = count_in_data {
+                // The last click before the entry is accented
+                if remaining_beats == 1 {
+                    self.start_accented_click();
+                } else {
+                    self.start_click();
                 }
+                self.send_event(AudioEvent::CountInTick {
+                    layer_id,
+                    remaining_beats,
+                });
+            } else if self.pre_count_enabled.try_lock().is_ok_and(|on| *on)
+                && self.pending_record.try_lock().is_ok_and(|r| r.is_some())
+            {
+                // Armed record starts on the coming downbeat
+                self.start_click();
+            } else if let Ok(mut count_out) = self.count_out_remaining.try_lock()
+                && *count_out > 0
+            {
+                *count_out -= 1;
+                self.start_click();
             }
         }
 
-        // Check if we need to set master loop length
-        if let Ok(recording_layer) = self.recording_layer.try_lock()
-            && let Some(layer_id) = *recording_layer
-            && let Ok(layer) = self.layers[layer_id].try_lock()
-            && layer.is_recording
-            && !layer.buffer.is_empty()
-            && let Ok(mut master_len) = self.master_loop_length.try_lock()
-            && master_len.is_none()
-        {
-            // This is the first layer recording, set it as master
-            *master_len = Some(layer.buffer.len());
+        if crossed_offbeat {
+            // Swing: the off-beat clicks and takes beat-quantized actions too
+            self.trigger_metronome_click();
+            self.run_pending_jumps();
         }
+
+        self.publish_layer_status();
+
+        self.update_dsp_load(started.elapsed(), output.len());
     }
 
-    fn trigger_metronome_click(&self) {
-        if let Ok(enabled) = self.metronome_enabled.try_lock()
-            && *enabled
-            && let Ok(mut playhead) = self.metronome_playhead.try_lock()
+    /// Actions that wait for a measure boundary
+    fn run_downbeat(&self, stamp: TransportStamp) {
+        // Actions started here are stamped at the downbeat
+        self.set_transport_clock(stamp);
+        if let Ok(mut audition) = self.audition.try_lock()
+            && let Some(audition) = audition.as_mut()
         {
-            *playhead = Some(0);
+            audition.waiting_for_measure = false;
         }
+        self.run_scheduled_actions();
+        self.run_pending_mix(MixQuantize::Measure);
+        self.sync_transaction_log();
+        // Trigger metronome ONLY on measure boundaries (downbeat)
+        self.trigger_metronome_click();
     }
 
-    fn mix_metronome(&self, output_buf: &mut [f32]) {
-        let enabled = self
-            .metronome_enabled
-            .try_lock()
-            .map(|b| *b)
-            .unwrap_or(false);
-        if !enabled {
-            return;
+    /// Mirror layer state into the lock-free status (layers busy elsewhere keep their last state)
+    fn publish_layer_status(&self) {
+        for (layer_id, (layer_arc, status)) in
+            self.layers.iter().zip(self.layer_status.iter()).enumerate()
+        {
+            if let Ok(layer) = layer_arc.try_lock()
+                && let Some(phase) = status.publish(&layer)
+            {
+                drop(layer);
+                self.send_event(AudioEvent::LayerPhaseChanged(layer_id, phase));
+            }
         }
-        let mut playhead_lock = match self.metronome_playhead.try_lock() {
-            Ok(lock) => lock,
-            Err(_) => return, // Skip if locked
-        };
-        let Some(mut playhead) = *playhead_lock else {
+        self.publish_queued_actions();
+        if let Ok(tempo) = self.tempo.try_lock()
+            && let Ok(mut position) = self.transport_position.try_lock()
+        {
+            *position = tempo.position();
+        }
+    }
+
+    /// Mirror the pending sync actions per layer (skipped if any list is busy)
+    fn publish_queued_actions(&self) {
+        let (Ok(to_play), Ok(to_stop), Ok(to_record), Ok(tempo)) = (
+            self.pending_play.try_lock(),
+            self.pending_stop.try_lock(),
+            self.pending_record.try_lock(),
+            self.tempo.try_lock(),
+        ) else {
             return;
         };
-        let sample = match self.metronome_sample.try_lock() {
-            Ok(lock) => lock,
-            Err(_) => {
-                *playhead_lock = None;
-                return;
+        let counting_in = tempo.count_in_layer.filter(|_| tempo.count_in_active);
+        // Queued actions run on the next downbeat
+        let beats_left = tempo.beats_per_measure + 1 - tempo.get_current_beat();
+        for (layer_id, status) in self.layer_status.iter().enumerate() {
+            status.publish_queued(QueuedActions {
+                play: to_play.contains(&layer_id),
+                stop: to_stop.contains(&layer_id),
+                record: *to_record == Some(layer_id) || counting_in == Some(layer_id),
+                beats_left,
+            });
+        }
+    }
+
+    /// Drop sync actions still waiting for a boundary (None = every layer),
+    /// so a stop or clear isn't undone by a play or record queued before it
+    fn cancel_pending(&self, layer_id: Option<usize>) {
+        let matches = |id: usize| layer_id.is_none_or(|target| target == id);
+        let mut cancelled = false;
+        for pending in [&self.pending_play, &self.pending_stop] {
+            if let Ok(mut layers) = pending.try_lock() {
+                let queued = layers.len();
+                layers.retain(|&id| !matches(id));
+                cancelled |= layers.len() != queued;
             }
-        };
-        if sample.is_empty() {
-            *playhead_lock = None;
-            return;
         }
-        let remaining = sample.len().saturating_sub(playhead);
-        if remaining == 0 {
-            *playhead_lock = None;
-            return;
+        if let Ok(mut pending_rec) = self.pending_record.try_lock()
+            && pending_rec.is_some_and(matches)
+        {
+            *pending_rec = None;
+            cancelled = true;
+        }
+        if let Ok(mut jumps) = self.pending_jumps.try_lock() {
+            let queued = jumps.len();
+            jumps.retain(|&(id, _)| !matches(id));
+            cancelled |= jumps.len() != queued;
+        }
+        if let Ok(mut tempo) = self.tempo.try_lock()
+            && tempo.count_in_active
+            && tempo.count_in_layer.is_some_and(matches)
+        {
+            tempo.cancel_count_in();
+            cancelled = true;
+        }
+        if cancelled {
+            self.send_event(AudioEvent::PendingCancelled(layer_id));
         }
+    }
 
-        let to_mix = remaining.min(output_buf.len());
-        for i in 0..to_mix {
-            output_buf[i] = (output_buf[i] + sample[playhead + i]).clamp(-1.0, 1.0);
+    fn layer_phase(&self, layer_id: usize) -> Option<LayerPhase> {
+        self.layers[layer_id]
+            .try_lock()
+            .ok()
+            .map(|layer| layer.phase())
+    }
+
+    /// Stop sending input to `layer_id` if it was the layer capturing
+    fn clear_recording_layer(&self, layer_id: usize) {
+        if let Ok(mut recording_layer) = self.recording_layer.try_lock()
+            && *recording_layer == Some(layer_id)
+        {
+            *recording_layer = None;
         }
-        playhead += to_mix;
-        if playhead >= sample.len() {
-            *playhead_lock = None;
-        } else {
-            *playhead_lock = Some(playhead);
+        if let Ok(mut is_recording) = self.is_recording.try_lock() {
+            *is_recording = false;
         }
     }
 
-    fn run_scheduled_actions(&self) {
-        // Count-in complete: only auto-start recording if count-in mode is enabled
-        if let Ok(mut tempo) = self.tempo.try_lock()
-            && !tempo.count_in_active
-            && tempo.count_in_layer.is_some()
-            && tempo.count_in_remaining_beats == 0
-            && let Some(layer_id) = tempo.count_in_layer.take()
+    /// End whatever take, overdub or re-take is capturing input
+    fn finish_current_take(&self) {
+        let current_layer = self.recording_layer.try_lock().ok().and_then(|r| *r);
+        if let Some(current_layer) = current_layer
+            && let Ok(mut layer) = self.layers[current_layer].try_lock()
+            && (layer.is_recording || layer.is_replacing)
         {
-            self.send_event(AudioEvent::CountInFinished { layer_id });
-            let start_on_boundary = self.count_in_mode.try_lock().map(|g| *g).unwrap_or(false);
-            if start_on_boundary && let Ok(mut layer) = self.layers[layer_id].try_lock() {
-                layer.start_recording();
-                if let Ok(mut recording_layer) = self.recording_layer.try_lock() {
-                    *recording_layer = Some(layer_id);
-                }
-                if let Ok(mut is_recording) = self.is_recording.try_lock() {
-                    *is_recording = true;
+            if layer.is_replacing {
+                let ready = layer.stop_replacement(self.latency_offset());
+                drop(layer);
+                if ready {
+                    self.send_event(AudioEvent::ReplacementReady(current_layer));
                 }
-                self.send_event(AudioEvent::LayerRecording(layer_id));
+                return;
+            }
+            let fresh_take = layer.phase() == LayerPhase::Recording;
+            layer.compensate_latency(self.latency_offset());
+            layer.stop_recording();
+            drop(layer);
+            if fresh_take {
+                self.check_loop_length(current_layer);
+            }
+        }
+    }
+
+    /// Refuse a command that would move a layer into a phase it can't reach
+    /// from its current one (a busy layer is left to the command's own handling)
+    fn check_transition(&self, layer_id: usize, to: LayerPhase) -> Result<(), LooperError> {
+        let Ok(layer) = self.layers[layer_id].try_lock() else {
+            return Ok(());
+        };
+        let from = layer.phase();
+        if from.can_enter(to) {
+            Ok(())
+        } else {
+            Err(LooperError::InvalidTransition { layer_id, from, to })
+        }
+    }
+
+    fn set_transport_clock(&self, stamp: TransportStamp) {
+        if let Ok(mut clock) = self.transport_clock.try_lock() {
+            *clock = stamp;
+        }
+    }
+
+    /// Timestamp for an event raised by the work currently being processed
+    fn transport_stamp(&self) -> TransportStamp {
+        match self.transport_clock.try_lock() {
+            Ok(clock) => *clock,
+            Err(_) => TransportStamp::new(
+                self.tempo
+                    .try_lock()
+                    .map(|t| t.global_position)
+                    .unwrap_or(0),
+                Instant::now(),
+            ),
+        }
+    }
+
+    /// Mix the layers on `bus` (all layers if None)
+    fn mix_block(&self, output: &mut [f32], degraded: bool, bus: Option<OutputBus>) {
+        // Mix all layers using SIMD acceleration
+        if degraded {
+            Self::mix_layers_reduced(&self.layers, output, &self.scratch_buffer, bus);
+        } else if let Ok(mut mixer) = self.simd_mixer.try_lock() {
+            mixer.mix_bus(&self.layers, output, bus);
+        } else {
+            // Fallback to scalar mixing if SIMD mixer is locked
+            Self::mix_layers_static(&self.layers, output, &self.scratch_buffer, bus);
+        }
+    }
+
+    /// Execute scheduled commands due at or before a transport position
+    fn run_due_commands(&self, position: usize) {
+        let mut executed = false;
+        loop {
+            let command = match self.scheduler.try_lock() {
+                Ok(mut scheduler) => scheduler.pop_due(position),
+                Err(_) => None,
+            };
+            let Some(command) = command else {
+                break;
+            };
+            if let Err(e) = self.send_command(command) {
+                self.send_event(AudioEvent::Error(e));
+            }
+            executed = true;
+        }
+        if executed {
+            self.sync_transaction_log();
+        }
+    }
+
+    /// Feed the load monitor and notify the UI on quality mode changes
+    fn update_dsp_load(&self, elapsed: Duration, frames: usize) {
+        let budget_secs = frames as f64 / self.config.sample_rate as f64;
+        let change = match self.load_monitor.try_lock() {
+            Ok(mut monitor) => monitor
+                .update(elapsed.as_secs_f64(), budget_secs)
+                .map(|change| (change, monitor.load())),
+            Err(_) => None,
+        };
+
+        match change {
+            Some((QualityChange::Degraded, load)) => {
+                self.send_event(AudioEvent::QualityDegraded(load));
+            }
+            Some((QualityChange::Restored, _)) => {
+                self.send_event(AudioEvent::QualityRestored);
+            }
+            None => {}
+        }
+    }
+
+    /// Refuse content and volume changes on a locked layer; a layer held
+    /// elsewhere can't be checked, so it is refused as busy
+    fn ensure_unlocked(&self, layer_id: usize) -> Result<(), LooperError> {
+        let layer = self.layers[layer_id]
+            .try_lock()
+            .map_err(|_| LooperError::Busy("Layer"))?;
+        Self::check_unlocked(&layer, layer_id)
+    }
+
+    fn check_unlocked(layer: &AudioLayer, layer_id: usize) -> Result<(), LooperError> {
+        if layer.is_locked {
+            return Err(LooperError::LayerLocked(layer_id));
+        }
+        Ok(())
+    }
+
+    /// Apply a background edit under the same guard that checks the layer is
+    /// still unlocked and not capturing (either may have changed while the
+    /// edit was processed)
+    fn edit_layer<T>(
+        layers: &[Arc<Mutex<AudioLayer>>],
+        layer_id: usize,
+        action: &'static str,
+        edit: impl FnOnce(&mut AudioLayer) -> T,
+    ) -> Result<T, LooperError> {
+        let mut layer = layers
+            .get(layer_id)
+            .ok_or(LooperError::InvalidLayer(layer_id))?
+            .lock()
+            .map_err(|_| LooperError::Busy("Layer"))?;
+        Self::check_unlocked(&layer, layer_id)?;
+        Self::check_not_capturing(&layer, layer_id, action)?;
+        Ok(edit(&mut layer))
+    }
+
+    /// `edit_layer` for a job that processed `source` (from `edit_source`) without
+    /// the lock: refused if the layer's audio moved on meanwhile, so the edit
+    /// that landed isn't overwritten
+    fn edit_layer_from<T>(
+        layers: &[Arc<Mutex<AudioLayer>>],
+        layer_id: usize,
+        action: &'static str,
+        source: &Arc<Vec<f32>>,
+        edit: impl FnOnce(&mut AudioLayer) -> T,
+    ) -> Result<T, LooperError> {
+        Self::edit_layer(layers, layer_id, action, |layer| {
+            if !layer.is_unchanged_since(source) {
+                return Err(LooperError::LayerChanged { layer_id, action });
+            }
+            Ok(edit(layer))
+        })?
+    }
+
+    /// Refuse buffer swaps (import, clear) on a layer that is capturing input,
+    /// so the recording path and mixer never see the buffer replaced mid-take;
+    /// a layer held elsewhere can't be checked, so it is refused as busy
+    fn ensure_not_capturing(
+        &self,
+        layer_id: usize,
+        action: &'static str,
+    ) -> Result<(), LooperError> {
+        let layer = self.layers[layer_id]
+            .try_lock()
+            .map_err(|_| LooperError::Busy("Layer"))?;
+        Self::check_not_capturing(&layer, layer_id, action)
+    }
+
+    fn check_not_capturing(
+        layer: &AudioLayer,
+        layer_id: usize,
+        action: &'static str,
+    ) -> Result<(), LooperError> {
+        if layer.is_recording || layer.is_replacing {
+            return Err(LooperError::LayerCapturing { layer_id, action });
+        }
+        Ok(())
+    }
+
+    /// Exports render every layer; wait until no take is in progress
+    fn ensure_none_capturing(&self, action: &'static str) -> Result<(), LooperError> {
+        (0..self.layers.len()).try_for_each(|layer_id| self.ensure_not_capturing(layer_id, action))
+    }
+
+    fn trigger_metronome_click(&self) {
+        let record_only = self.click.try_lock().is_ok_and(|c| c.record_only);
+        if let Ok(enabled) = self.metronome_enabled.try_lock()
+            && *enabled
+            && (!record_only || self.is_recording.try_lock().is_ok_and(|r| *r))
+        {
+            self.start_click();
+        }
+    }
+
+    /// Start the click sample regardless of the metronome toggle
+    fn start_click(&self) {
+        self.start_click_with_accent(false);
+    }
+
+    fn start_accented_click(&self) {
+        self.start_click_with_accent(true);
+    }
+
+    fn start_click_with_accent(&self, accent: bool) {
+        if let Ok(mut playhead) = self.metronome_playhead.try_lock()
+            && let Ok(mut accented) = self.metronome_accent.try_lock()
+        {
+            *playhead = Some(0);
+            *accented = accent;
+        }
+    }
+
+    /// Click through one bar after a recording stops (if count-out is on)
+    fn start_count_out(&self) {
+        if let Ok(enabled) = self.count_out_enabled.try_lock()
+            && *enabled
+            && let Ok(tempo) = self.tempo.try_lock()
+            && let Ok(mut remaining) = self.count_out_remaining.try_lock()
+        {
+            *remaining = tempo.beats_per_measure;
+        }
+    }
+
+    // Clicks only start when enabled (metronome, count-in or count-out)
+    fn mix_metronome(&self, output_buf: &mut [f32], volume: f32) {
+        let mut playhead_lock = match self.metronome_playhead.try_lock() {
+            Ok(lock) => lock,
+            Err(_) => return, // Skip if locked
+        };
+        let Some(mut playhead) = *playhead_lock else {
+            return;
+        };
+        let sample = match self.metronome_sample.try_lock() {
+            Ok(lock) => lock,
+            Err(_) => {
+                *playhead_lock = None;
+                return;
+            }
+        };
+        if sample.is_empty() {
+            *playhead_lock = None;
+            return;
+        }
+        // Accents skip every other sample to sound an octave up
+        let accent = self.metronome_accent.try_lock().is_ok_and(|a| *a);
+        let (step, gain) = if accent {
+            (2, volume * Self::ACCENT_GAIN)
+        } else {
+            (1, volume)
+        };
+
+        for out in output_buf.iter_mut() {
+            if playhead >= sample.len() {
+                break;
+            }
+            *out = (*out + sample[playhead] * gain).clamp(-1.0, 1.0);
+            playhead += step;
+        }
+        if playhead >= sample.len() {
+            *playhead_lock = None;
+        } else {
+            *playhead_lock = Some(playhead);
+        }
+    }
+
+    fn mix_preview(&self, output_buf: &mut [f32]) {
+        let mut playhead_lock = match self.preview_playhead.try_lock() {
+            Ok(lock) => lock,
+            Err(_) => return, // Skip if locked
+        };
+        let Some(playhead) = *playhead_lock else {
+            return;
+        };
+        let sample = match self.preview_sample.try_lock() {
+            Ok(lock) => lock,
+            Err(_) => return, // Being replaced by a new preview
+        };
+
+        let remaining = sample.len().saturating_sub(playhead);
+        let to_mix = remaining.min(output_buf.len());
+        for i in 0..to_mix {
+            output_buf[i] =
+                (output_buf[i] + sample[playhead + i] * Self::PREVIEW_GAIN).clamp(-1.0, 1.0);
+        }
+
+        if playhead + to_mix >= sample.len() {
+            *playhead_lock = None;
+            self.send_event(AudioEvent::PreviewStopped);
+        } else {
+            *playhead_lock = Some(playhead + to_mix);
+        }
+    }
+
+    fn mix_audition(&self, output_buf: &mut [f32]) {
+        let Ok(mut audition) = self.audition.try_lock() else {
+            return;
+        };
+        let Some(audition) = audition.as_mut() else {
+            return;
+        };
+        if audition.waiting_for_measure || audition.remaining == 0 || audition.samples.is_empty() {
+            return;
+        }
+
+        let to_mix = audition.remaining.min(output_buf.len());
+        for sample in output_buf.iter_mut().take(to_mix) {
+            *sample = (*sample + audition.samples[audition.position]).clamp(-1.0, 1.0);
+            audition.position = (audition.position + 1) % audition.samples.len();
+        }
+        audition.remaining -= to_mix;
+    }
+
+    fn run_scheduled_actions(&self) {
+        // Count-in complete: only auto-start recording if count-in mode is enabled
+        if let Ok(mut tempo) = self.tempo.try_lock()
+            && !tempo.count_in_active
+            && tempo.count_in_layer.is_some()
+            && tempo.count_in_remaining_beats == 0
+            && let Some(layer_id) = tempo.count_in_layer.take()
+        {
+            self.send_event(AudioEvent::CountInFinished { layer_id });
+            let start_on_boundary = self.count_in_mode.try_lock().map(|g| *g).unwrap_or(false);
+            if start_on_boundary
+                && let Ok(mut layer) = self.layers[layer_id].try_lock()
+                && layer.phase().can_enter(LayerPhase::Recording)
+            {
+                layer.start_recording();
+                if let Ok(mut recording_layer) = self.recording_layer.try_lock() {
+                    *recording_layer = Some(layer_id);
+                }
+                if let Ok(mut is_recording) = self.is_recording.try_lock() {
+                    *is_recording = true;
+                }
+                self.send_event(AudioEvent::LayerRecording(layer_id, self.transport_stamp()));
             }
         }
 
@@ -282,10 +1381,10 @@ impl LooperEngine {
         if let Ok(mut to_play) = self.pending_play.try_lock() {
             while let Some(layer_id) = to_play.pop() {
                 if let Ok(mut layer) = self.layers[layer_id].try_lock()
-                    && !layer.buffer.is_empty()
+                    && !layer.is_empty()
                 {
                     layer.start_playing();
-                    self.send_event(AudioEvent::LayerPlaying(layer_id));
+                    self.send_event(AudioEvent::LayerPlaying(layer_id, self.transport_stamp()));
                 }
             }
         }
@@ -304,6 +1403,7 @@ impl LooperEngine {
         if let Ok(mut pending_rec) = self.pending_record.try_lock()
             && let Some(layer_id) = pending_rec.take()
             && let Ok(mut layer) = self.layers[layer_id].try_lock()
+            && layer.phase().can_enter(LayerPhase::Recording)
         {
             layer.start_recording();
             if let Ok(mut recording_layer) = self.recording_layer.try_lock() {
@@ -312,7 +1412,323 @@ impl LooperEngine {
             if let Ok(mut is_recording) = self.is_recording.try_lock() {
                 *is_recording = true;
             }
-            self.send_event(AudioEvent::LayerRecording(layer_id));
+            self.send_event(AudioEvent::LayerRecording(layer_id, self.transport_stamp()));
+        }
+    }
+
+    fn run_pending_jumps(&self) {
+        if let Ok(mut jumps) = self.pending_jumps.try_lock() {
+            while let Some((layer_id, position)) = jumps.pop() {
+                if let Ok(mut layer) = self.layers[layer_id].try_lock() {
+                    layer.jump_to(position);
+                    self.send_event(AudioEvent::MarkerJumped { layer_id, position });
+                }
+            }
+        }
+    }
+
+    /// Samples a finished take is shifted by to line up with what was heard
+    fn latency_offset(&self) -> usize {
+        self.latency_compensation
+            .try_lock()
+            .map(|samples| *samples)
+            .unwrap_or(0)
+    }
+
+    fn export_bit_depth(&self) -> BitDepth {
+        self.export_bit_depth
+            .try_lock()
+            .map(|depth| *depth)
+            .unwrap_or_default()
+    }
+
+    /// Put imported audio into a layer; replace imports also take the file's
+    /// embedded loop and tempo. Returns the event describing what was applied
+    /// Decoding takes a while, so a take started meanwhile is checked for again here
+    fn import_into_layer(
+        layers: &[Arc<Mutex<AudioLayer>>],
+        layer_id: usize,
+        samples: Vec<f32>,
+        mode: ImportMode,
+        info: WavLoopInfo,
+        target_bpm: Option<f64>,
+    ) -> Result<Option<AudioEvent>, LooperError> {
+        // Embedded loops only apply when the file becomes the whole layer
+        let info = if mode == ImportMode::Replace {
+            info
+        } else {
+            WavLoopInfo::default()
+        };
+        let (samples, loop_start, stretched_to) = Self::apply_loop_info(samples, info, target_bpm);
+        Self::edit_layer(layers, layer_id, "import", |layer| {
+            layer.import_samples(samples, mode);
+            if let Some(start) = loop_start {
+                let end = layer.get_buffer_length();
+                layer.set_loop_points(start, end);
+                layer.checkpoint();
+            }
+        })?;
+        Ok(
+            (info != WavLoopInfo::default()).then_some(AudioEvent::ImportLoopInfo {
+                layer_id,
+                info,
+                stretched_to,
+            }),
+        )
+    }
+
+    /// Shape an import by its embedded metadata: audio after the smpl loop end
+    /// is dropped (layers wrap at the end of their buffer) and, given a
+    /// `target_bpm`, the file is resampled from its ACID tempo (varispeed, so the
+    /// pitch follows). Returns the samples, the loop start and the BPM stretched to
+    fn apply_loop_info(
+        mut samples: Vec<f32>,
+        info: WavLoopInfo,
+        target_bpm: Option<f64>,
+    ) -> (Vec<f32>, Option<usize>, Option<f64>) {
+        let mut loop_start = None;
+        if let Some((start, end)) = info.loop_points
+            && start < end
+            && end <= samples.len()
+        {
+            samples.truncate(end);
+            loop_start = Some(start);
+        }
+
+        if let (Some(file_bpm), Some(target)) = (info.bpm, target_bpm)
+            && (file_bpm - target).abs() > 0.01
+            && !samples.is_empty()
+        {
+            let ratio = file_bpm / target;
+            let length = (samples.len() as f64 * ratio).round() as usize;
+            // Sample counts stand in for rates, as for LoopFix::Stretch
+            if let Ok(mut stretched) =
+                super::io::resample_audio(&samples, samples.len() as u32, length as u32, 1)
+            {
+                stretched.resize(length, 0.0);
+                let loop_start = loop_start.map(|start| (start as f64 * ratio).round() as usize);
+                return (stretched, loop_start, Some(target));
+            }
+        }
+        (samples, loop_start, None)
+    }
+
+    /// Session tempo tagged onto exported loops (smpl / acid chunks)
+    fn loop_tempo(&self) -> Option<LoopTempo> {
+        self.tempo.try_lock().ok().map(|t| LoopTempo {
+            bpm: t.bpm,
+            beats_per_measure: t.beats_per_measure,
+        })
+    }
+
+    /// Values for `{session}`, `{bpm}` and the date in export names, taken now
+    fn name_tokens(&self) -> NameTokens {
+        NameTokens {
+            session: self
+                .metadata
+                .try_lock()
+                .map(|m| m.title.clone())
+                .unwrap_or_default(),
+            bpm: self.tempo.try_lock().map_or(120.0, |t| t.bpm),
+            time: std::time::SystemTime::now(),
+        }
+    }
+
+    fn export_hook(&self) -> Option<ExportHook> {
+        self.export_hook
+            .try_lock()
+            .ok()
+            .and_then(|hook| hook.clone())
+    }
+
+    /// Write a rendered mix to `file_path`, or to standard output for `-`
+    fn write_export(
+        file_path: &str,
+        mix: Vec<f32>,
+        sample_rate: u32,
+        normalization: ExportNormalization,
+        bit_depth: BitDepth,
+        tempo: Option<LoopTempo>,
+    ) -> anyhow::Result<()> {
+        if file_path == STDOUT_PATH {
+            let stdout = super::io::stdout_sink()?;
+            return super::io::export_normalized_wav_to(
+                stdout,
+                mix,
+                sample_rate,
+                normalization,
+                bit_depth,
+                tempo,
+            );
+        }
+        super::io::export_normalized(file_path, mix, sample_rate, normalization, bit_depth, tempo)
+    }
+
+    /// Report a background WAV export, then run the post-export hook on the
+    /// file (still off the audio thread)
+    fn finish_export(
+        event_sender: &Mutex<Option<EventSender>>,
+        action: &'static str,
+        file_path: String,
+        result: anyhow::Result<()>,
+        hook: Option<ExportHook>,
+    ) {
+        let send = |event| {
+            if let Ok(sender) = event_sender.try_lock()
+                && let Some(ref tx) = *sender
+            {
+                let _ = tx.try_send(event);
+            }
+        };
+        if let Err(e) = result {
+            send(AudioEvent::Error(LooperError::io(action, file_path, &e)));
+            return;
+        }
+        send(AudioEvent::WavExported(file_path.clone()));
+        // A piped export has no file to hand on
+        let Some(hook) = hook.filter(|_| file_path != STDOUT_PATH) else {
+            return;
+        };
+
+        // Progress in 10% steps so a large upload doesn't flood the channel
+        let mut reported = None;
+        let upload = hook.run(&file_path, |sent, total| {
+            let percent = (sent * 100 / total.max(1)) as u8;
+            if reported.is_none_or(|last| percent >= last + 10 || (percent == 100 && last < 100)) {
+                reported = Some(percent);
+                send(AudioEvent::UploadProgress {
+                    file_path: file_path.clone(),
+                    percent,
+                });
+            }
+        });
+        match upload {
+            Ok(()) => send(AudioEvent::UploadFinished(file_path)),
+            Err(e) => send(AudioEvent::Error(LooperError::io("upload", file_path, &e))),
+        }
+    }
+
+    /// Called when recording on a layer ends: the first recording sets the master
+    /// loop, later ones are checked against it so layers don't silently drift apart
+    fn check_loop_length(&self, layer_id: usize) {
+        let Ok(layer) = self.layers[layer_id].try_lock() else {
+            return;
+        };
+        let length = layer.get_buffer_length();
+        drop(layer);
+        let Ok(master) = self.master_loop_length.try_lock().map(|m| *m) else {
+            return;
+        };
+        let Some(master_length) = master else {
+            if length > 0 {
+                self.set_master_length(Some(length));
+            }
+            return;
+        };
+        if length == 0 {
+            return;
+        }
+
+        let ratio = LoopRatio::nearest(length, master_length);
+        let target = ratio.target_length(master_length);
+        // Up to a millisecond per pass is inaudible
+        if target.abs_diff(length) > self.config.sample_rate as usize / 1000 {
+            self.send_event(AudioEvent::LoopLengthMismatch {
+                layer_id,
+                ratio,
+                length,
+                target,
+            });
+        }
+    }
+
+    /// None means the next finished recording sets the master loop
+    fn set_master_length(&self, length: Option<usize>) {
+        if let Ok(mut master_len) = self.master_loop_length.try_lock()
+            && *master_len != length
+        {
+            *master_len = length;
+            self.send_event(AudioEvent::MasterLoopChanged(length));
+        } else {
+            return;
+        }
+
+        // Fitted layers follow the new master, or stop fitting if they can't
+        for (layer_id, layer_arc) in self.layers.iter().enumerate() {
+            if let Ok(mut layer) = layer_arc.try_lock()
+                && layer.fit_length.is_some()
+            {
+                layer.fit_length = length.and_then(|master| layer.fit_target(master));
+                if layer.fit_length.is_none() {
+                    self.send_event(AudioEvent::LayerFitChanged(layer_id, false));
+                }
+            }
+        }
+    }
+
+    /// Length of the lowest-numbered layer holding audio
+    fn first_layer_length(&self) -> Option<usize> {
+        self.layers
+            .iter()
+            .filter_map(|layer| layer.try_lock().ok().map(|l| l.get_buffer_length()))
+            .find(|&len| len > 0)
+    }
+
+    /// Apply queued mute/solo toggles if `boundary` is the one they wait for
+    fn run_pending_mix(&self, boundary: MixQuantize) {
+        if self.mix_quantize.try_lock().map(|q| *q).ok() != Some(boundary) {
+            return;
+        }
+        if let Ok(mut changes) = self.pending_mix.try_lock() {
+            while let Some((layer_id, change)) = changes.pop() {
+                self.apply_mix_change(layer_id, change);
+            }
+        }
+    }
+
+    /// Queue a mute/solo toggle for the next boundary; false if it should apply now
+    fn queue_mix_change(&self, layer_id: usize, change: MixChange) -> bool {
+        let quantize = self.mix_quantize.try_lock().map(|q| *q).unwrap_or_default();
+        let sync = self
+            .beat_sync_enabled
+            .try_lock()
+            .map(|b| *b)
+            .unwrap_or(true);
+        if quantize == MixQuantize::Off || !sync {
+            return false;
+        }
+        if let Ok(mut changes) = self.pending_mix.try_lock() {
+            // A second press before the boundary cancels the first
+            if let Some(index) = changes.iter().position(|c| *c == (layer_id, change)) {
+                changes.remove(index);
+            } else if changes.len() < changes.capacity() {
+                changes.push((layer_id, change));
+            }
+        }
+        true
+    }
+
+    fn apply_mix_change(&self, layer_id: usize, change: MixChange) {
+        let Ok(mut layer) = self.layers[layer_id].try_lock() else {
+            return;
+        };
+        match change {
+            MixChange::Mute => {
+                layer.toggle_mute();
+                if layer.is_muted {
+                    self.send_event(AudioEvent::LayerMuted(layer_id));
+                } else {
+                    self.send_event(AudioEvent::LayerUnmuted(layer_id));
+                }
+            }
+            MixChange::Solo => {
+                layer.toggle_solo();
+                if layer.is_solo {
+                    self.send_event(AudioEvent::LayerSoloed(layer_id));
+                } else {
+                    self.send_event(AudioEvent::LayerUnsoloed(layer_id));
+                }
+            }
         }
     }
 
@@ -321,6 +1737,7 @@ impl LooperEngine {
         layers: &Arc<Vec<Arc<Mutex<AudioLayer>>>>,
         output: &mut [f32],
         scratch_buffer: &Arc<Mutex<Vec<f32>>>,
+        bus: Option<OutputBus>,
     ) {
         let mut has_solo = false;
 
@@ -344,28 +1761,12 @@ impl LooperEngine {
                 // Fallback: mix without scratch buffer (slower but safe)
                 for layer_arc in layers.iter() {
                     if let Ok(mut layer) = layer_arc.try_lock() {
-                        if !layer.is_playing || layer.is_muted || (has_solo && !layer.is_solo) {
+                        let Some(gain) = layer.bus_gain(bus, has_solo) else {
                             continue;
-                        }
+                        };
 
                         // Mix directly sample by sample (no allocation)
-                        let buffer_len = layer.buffer.len();
-                        let loop_len = layer.loop_end - layer.loop_start;
-
-                        if loop_len == 0 {
-                            continue;
-                        }
-
-                        for output_sample in output.iter_mut() {
-                            if layer.playback_position >= buffer_len {
-                                layer.playback_position = layer.loop_start;
-                            }
-
-                            let sample = layer.buffer[layer.playback_position];
-                            let volume_sample = sample * layer.volume;
-                            *output_sample += volume_sample;
-                            layer.playback_position += 1;
-                        }
+                        layer.mix_next_samples(output, gain);
 
                         // Update meter
                         layer.meter.update(output);
@@ -389,19 +1790,10 @@ impl LooperEngine {
         // Mix layers using scratch buffer
         for layer_arc in layers.iter() {
             if let Ok(mut layer) = layer_arc.try_lock() {
-                if !layer.is_playing {
-                    continue;
-                }
-
-                // Skip if solo is active and this layer is not soloed
-                if has_solo && !layer.is_solo {
+                // Skip stopped, muted, non-soloed and other-bus layers
+                let Some(gain) = layer.bus_gain(bus, has_solo) else {
                     continue;
-                }
-
-                // Skip if layer is muted
-                if layer.is_muted {
-                    continue;
-                }
+                };
 
                 // NO ALLOCATION: Fill scratch buffer
                 let scratch_slice = &mut scratch[..buffer_len];
@@ -409,7 +1801,7 @@ impl LooperEngine {
 
                 // Mix into output buffer
                 for (i, &sample) in scratch_slice.iter().enumerate() {
-                    output[i] += sample;
+                    output[i] += sample * gain;
                 }
             }
         }
@@ -420,14 +1812,188 @@ impl LooperEngine {
         }
     }
 
+    /// REAL-TIME SAFE: Reduced-quality scalar mix used under CPU pressure
+    /// Skips SIMD setup and per-layer meter updates
+    fn mix_layers_reduced(
+        layers: &Arc<Vec<Arc<Mutex<AudioLayer>>>>,
+        output: &mut [f32],
+        scratch_buffer: &Arc<Mutex<Vec<f32>>>,
+        bus: Option<OutputBus>,
+    ) {
+        output.fill(0.0);
+
+        let Ok(mut scratch) = scratch_buffer.try_lock() else {
+            return;
+        };
+
+        let has_solo = layers
+            .iter()
+            .any(|layer| layer.try_lock().map(|l| l.is_solo).unwrap_or(false));
+
+        let buffer_len = output.len().min(scratch.len());
+        for layer_arc in layers.iter() {
+            if let Ok(mut layer) = layer_arc.try_lock() {
+                let Some(gain) = layer.bus_gain(bus, has_solo) else {
+                    continue;
+                };
+
+                let scratch_slice = &mut scratch[..buffer_len];
+                layer.fill_next_samples_unmetered(scratch_slice);
+                for (out, &sample) in output.iter_mut().zip(scratch_slice.iter()) {
+                    *out += sample * gain;
+                }
+            }
+        }
+
+        for sample in output.iter_mut() {
+            *sample = sample.clamp(-1.0, 1.0);
+        }
+    }
+
     pub fn set_command_channel(&self, receiver: Receiver<LayerCommand>) {
         let mut cmd_receiver = self.command_receiver.lock().unwrap();
         *cmd_receiver = Some(receiver);
     }
 
-    pub fn set_event_sender(&self, sender: Sender<AudioEvent>) {
+    pub fn set_event_sender(&self, sender: EventSender) {
         let mut evt_sender = self.event_sender.lock().unwrap();
         *evt_sender = Some(sender);
+        drop(evt_sender);
+
+        self.start_status_publisher();
+    }
+
+    /// Send LayerStatusBatch events from a background thread (the audio thread never allocates them)
+    fn start_status_publisher(&self) {
+        if self.status_publisher_active.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        let layer_status = Arc::clone(&self.layer_status);
+        let event_sender = Arc::clone(&self.event_sender);
+        let active = Arc::clone(&self.status_publisher_active);
+        let input_meter = Arc::clone(&self.input_meter);
+        let transport_position = Arc::clone(&self.transport_position);
+        std::thread::spawn(move || {
+            let mut sent_markers = vec![Vec::new(); layer_status.len()];
+            loop {
+                std::thread::sleep(Self::STATUS_INTERVAL);
+
+                let Some(tx) = event_sender.lock().unwrap().clone() else {
+                    continue;
+                };
+                let batch = layer_status.iter().map(|s| s.snapshot()).collect();
+                // A full queue only drops this batch; the next one carries the same state
+                if let Err(TrySendError::Disconnected(_)) =
+                    tx.try_send(AudioEvent::LayerStatusBatch(batch))
+                {
+                    break; // Receiver gone
+                }
+                let _ = tx.try_send(AudioEvent::InputLevel {
+                    peak: input_meter.get_peak(),
+                    peak_hold: input_meter.get_peak_hold(),
+                });
+                let position = *transport_position.lock().unwrap();
+                let _ = tx.try_send(AudioEvent::TransportPosition(position));
+                for (layer_id, status) in layer_status.iter().enumerate() {
+                    let markers = status.markers();
+                    if markers != sent_markers[layer_id] {
+                        sent_markers[layer_id] = markers.clone();
+                        let _ = tx.try_send(AudioEvent::LayerMarkers(layer_id, markers));
+                    }
+                }
+            }
+            active.store(false, Ordering::SeqCst);
+        });
+    }
+
+    /// Make the engine usable again after the audio thread panicked
+    /// Clears mutex poisoning (try_lock would otherwise fail forever) and repairs
+    /// layer positions; layer buffers are kept as they were
+    pub fn recover_after_panic(&self) {
+        fn clear<T>(mutex: &Mutex<T>) {
+            mutex.clear_poison();
+        }
+
+        for layer_arc in self.layers.iter() {
+            clear(layer_arc);
+            if let Ok(mut layer) = layer_arc.lock() {
+                let len = layer.get_buffer_length();
+                layer.loop_end = if layer.loop_end == 0 || layer.loop_end > len {
+                    len
+                } else {
+                    layer.loop_end
+                };
+                layer.loop_start = layer.loop_start.min(layer.loop_end);
+                if layer.playback_position >= len {
+                    layer.playback_position = layer.loop_start;
+                }
+            }
+        }
+        for status in self.layer_status.iter() {
+            status.clear_poison();
+        }
+        self.input_buffer.clear_poison();
+        clear(&self.master_loop_length);
+        clear(&self.is_recording);
+        clear(&self.recording_layer);
+        clear(&self.command_receiver);
+        clear(&self.event_sender);
+        clear(&self.debug_mode);
+        clear(&self.tempo);
+        clear(&self.beat_sync_enabled);
+        clear(&self.pending_play);
+        clear(&self.pending_stop);
+        clear(&self.pending_record);
+        clear(&self.pending_jumps);
+        clear(&self.mix_quantize);
+        clear(&self.pending_mix);
+        clear(&self.latency_compensation);
+        clear(&self.scheduler);
+        clear(&self.transport_clock);
+        clear(&self.transport_position);
+        clear(&self.metronome_enabled);
+        clear(&self.metronome_sample);
+        clear(&self.metronome_playhead);
+        clear(&self.metronome_accent);
+        clear(&self.preview_sample);
+        clear(&self.preview_playhead);
+        clear(&self.audition);
+        clear(&self.count_in_mode);
+        clear(&self.count_in_measures);
+        clear(&self.count_out_enabled);
+        clear(&self.pre_count_enabled);
+        clear(&self.count_out_remaining);
+        clear(&self.simd_mixer);
+        clear(&self.scratch_buffer);
+        clear(&self.recording_scratch);
+        clear(&self.load_monitor);
+        clear(&self.autosnapshot_interval);
+        clear(&self.autosnapshot_active);
+        clear(&self.export_bit_depth);
+        clear(&self.export_hook);
+        clear(&self.import_tempo_match);
+        clear(&self.import_limit);
+        clear(&self.analysis_active);
+        clear(&self.parking_active);
+        clear(&self.compaction_active);
+        clear(&self.noise_profile);
+        clear(&self.input_high_pass);
+        clear(&self.stereo_width);
+        self.analysis_tap.clear_poison();
+        clear(&self.transaction_log);
+        clear(&self.metadata);
+        clear(&self.save_history);
+        clear(&self.click_bus);
+        clear(&self.click);
+
+        // Whatever was playing from the scratch channels is dropped
+        if let Ok(mut playhead) = self.metronome_playhead.lock() {
+            *playhead = None;
+        }
+        if let Ok(mut playhead) = self.preview_playhead.lock() {
+            *playhead = None;
+        }
     }
 
     pub fn set_debug_mode(&self, debug_mode: bool) {
@@ -447,9 +2013,18 @@ impl LooperEngine {
             // Process commands one-by-one without collecting (zero allocations)
             // NOTE: File I/O removed from audio thread for real-time safety
             // Debug logging should use lock-free channel to separate thread
+            let mut processed = false;
             while let Ok(command) = cmd_receiver.try_recv() {
-                // Silently drop errors (avoid eprintln! in audio thread)
-                let _ = self.send_command(command);
+                // Report errors to the UI (avoid eprintln! in audio thread)
+                if let Err(e) = self.send_command(command) {
+                    self.send_event(AudioEvent::Error(e));
+                }
+                processed = true;
+            }
+
+            // Group the undo snapshots these commands produced into one transaction
+            if processed {
+                self.sync_transaction_log();
             }
         }
     }
@@ -462,7 +2037,7 @@ impl LooperEngine {
         }
     }
 
-    pub fn send_command(&self, command: LayerCommand) -> Result<(), Box<dyn std::error::Error>> {
+    pub fn send_command(&self, command: LayerCommand) -> Result<(), LooperError> {
         match command {
             LayerCommand::SwitchInputDevice(_device_name) => {
                 // Notify UI; actual device switch is handled in the audio thread
@@ -472,117 +2047,719 @@ impl LooperEngine {
                 // Notify UI; actual device switch is handled in the audio thread
                 self.send_event(AudioEvent::DeviceSwitchRequested);
             }
-            LayerCommand::Record(layer_id) => {
+            LayerCommand::SwitchHost(_host_name) => {
+                // Notify UI; the streams are rebuilt on the new backend in the audio thread
+                self.send_event(AudioEvent::DeviceSwitchRequested);
+            }
+            LayerCommand::SetBufferSize(_frames) => {
+                // Streams are rebuilt with the new size in the audio thread
+                self.send_event(AudioEvent::DeviceSwitchRequested);
+            }
+            LayerCommand::Record(layer_id) => {
+                if layer_id >= self.config.max_layers {
+                    return Err(LooperError::InvalidLayer(layer_id));
+                }
+                self.ensure_unlocked(layer_id)?;
+                self.check_transition(layer_id, LayerPhase::Recording)?;
+                self.finish_current_take();
+
+                // Start recording on new layer
+                if let Ok(mut layer) = self.layers[layer_id].try_lock() {
+                    layer.start_recording();
+                    if let Ok(mut recording_layer) = self.recording_layer.try_lock() {
+                        *recording_layer = Some(layer_id);
+                    }
+                    if let Ok(mut is_recording) = self.is_recording.try_lock() {
+                        *is_recording = true;
+                    }
+                    self.send_event(AudioEvent::LayerRecording(layer_id, self.transport_stamp()));
+                }
+            }
+            LayerCommand::StopRecording(layer_id) => {
+                if layer_id >= self.config.max_layers {
+                    return Err(LooperError::InvalidLayer(layer_id));
+                }
+
+                let mut was_recording = false;
+                if let Ok(mut layer) = self.layers[layer_id].try_lock() {
+                    if layer.is_replacing {
+                        // The old take plays on until the re-take is kept or reverted
+                        if layer.stop_replacement(self.latency_offset()) {
+                            self.send_event(AudioEvent::ReplacementReady(layer_id));
+                        } else {
+                            self.send_event(AudioEvent::ReplacementReverted(layer_id));
+                        }
+                    } else {
+                        // An overdub just ends; the loop plays on unchanged in length
+                        let overdubbing = layer.phase() == LayerPhase::Overdubbing;
+                        was_recording = layer.phase() == LayerPhase::Recording;
+                        layer.compensate_latency(self.latency_offset());
+                        layer.stop_recording(); // This automatically starts playback if there's content
+                        if !overdubbing {
+                            self.send_event(AudioEvent::LayerStopped(layer_id));
+                        }
+                    }
+                }
+                if was_recording {
+                    self.start_count_out();
+                    self.check_loop_length(layer_id);
+                }
+                self.clear_recording_layer(layer_id);
+            }
+            LayerCommand::Overdub(layer_id) => {
+                if layer_id >= self.config.max_layers {
+                    return Err(LooperError::InvalidLayer(layer_id));
+                }
+                self.ensure_unlocked(layer_id)?;
+                self.check_transition(layer_id, LayerPhase::Overdubbing)?;
+                // Input goes to one layer at a time
+                self.finish_current_take();
+
+                if let Ok(mut layer) = self.layers[layer_id].try_lock()
+                    && layer.start_overdub()
+                {
+                    if let Ok(mut recording_layer) = self.recording_layer.try_lock() {
+                        *recording_layer = Some(layer_id);
+                    }
+                    if let Ok(mut is_recording) = self.is_recording.try_lock() {
+                        *is_recording = true;
+                    }
+                }
+            }
+            LayerCommand::RecordReplacement(layer_id) => {
+                if layer_id >= self.config.max_layers {
+                    return Err(LooperError::InvalidLayer(layer_id));
+                }
+                self.ensure_unlocked(layer_id)?;
+                self.ensure_not_capturing(layer_id, "record a new take")?;
+                if self.layer_phase(layer_id) == Some(LayerPhase::Empty) {
+                    return Err(LooperError::EmptyLayer(layer_id));
+                }
+                self.finish_current_take();
+
+                if let Ok(mut layer) = self.layers[layer_id].try_lock()
+                    && layer.start_replacement()
+                {
+                    if let Ok(mut recording_layer) = self.recording_layer.try_lock() {
+                        *recording_layer = Some(layer_id);
+                    }
+                    if let Ok(mut is_recording) = self.is_recording.try_lock() {
+                        *is_recording = true;
+                    }
+                    self.send_event(AudioEvent::ReplacementRecording(layer_id));
+                }
+            }
+            LayerCommand::KeepReplacement(layer_id) => {
+                if layer_id >= self.config.max_layers {
+                    return Err(LooperError::InvalidLayer(layer_id));
+                }
+                self.ensure_unlocked(layer_id)?;
+                // Keeping a re-take still being captured ends it first
+                if self.layers[layer_id]
+                    .try_lock()
+                    .is_ok_and(|layer| layer.is_replacing)
+                {
+                    self.finish_current_take();
+                    self.clear_recording_layer(layer_id);
+                }
+
+                let mut layer = self.layers[layer_id]
+                    .try_lock()
+                    .map_err(|_| LooperError::Busy("Layer"))?;
+                if !layer.confirm_replacement() {
+                    return Err(LooperError::NoReplacement(layer_id));
+                }
+                drop(layer);
+                self.send_event(AudioEvent::ReplacementKept(layer_id));
+                self.check_loop_length(layer_id);
+            }
+            LayerCommand::SelectTake(layer_id, take) => {
+                if layer_id >= self.config.max_layers {
+                    return Err(LooperError::InvalidLayer(layer_id));
+                }
+                self.ensure_unlocked(layer_id)?;
+                self.ensure_not_capturing(layer_id, "switch takes")?;
+                let mut layer = self.layers[layer_id]
+                    .try_lock()
+                    .map_err(|_| LooperError::Busy("Layer"))?;
+                if !layer.select_take(take) {
+                    return Err(LooperError::TakeNotFound { layer_id, take });
+                }
+                drop(layer);
+                self.send_event(AudioEvent::TakeSelected(layer_id, take));
+            }
+            LayerCommand::KeepTake(layer_id) => {
+                if layer_id >= self.config.max_layers {
+                    return Err(LooperError::InvalidLayer(layer_id));
+                }
+                let mut layer = self.layers[layer_id]
+                    .try_lock()
+                    .map_err(|_| LooperError::Busy("Layer"))?;
+                if !layer.keep_take() {
+                    return Err(LooperError::NoOtherTakes(layer_id));
+                }
+                drop(layer);
+                self.send_event(AudioEvent::TakeKept(layer_id));
+            }
+            LayerCommand::RevertReplacement(layer_id) => {
+                if layer_id >= self.config.max_layers {
+                    return Err(LooperError::InvalidLayer(layer_id));
+                }
+                let mut layer = self.layers[layer_id]
+                    .try_lock()
+                    .map_err(|_| LooperError::Busy("Layer"))?;
+                if !layer.revert_replacement() {
+                    return Err(LooperError::NoReplacement(layer_id));
+                }
+                drop(layer);
+                self.clear_recording_layer(layer_id);
+                self.send_event(AudioEvent::ReplacementReverted(layer_id));
+            }
+            LayerCommand::StopPlaying(layer_id) => {
+                if layer_id >= self.config.max_layers {
+                    return Err(LooperError::InvalidLayer(layer_id));
+                }
+
+                // A take in progress is ended with StopRecording; an overdub is
+                // finished before stopping
+                match self.layer_phase(layer_id) {
+                    Some(LayerPhase::Recording) => {
+                        self.check_transition(layer_id, LayerPhase::Stopped)?;
+                    }
+                    Some(LayerPhase::Overdubbing) => {
+                        self.send_command(LayerCommand::StopRecording(layer_id))?;
+                    }
+                    _ => {}
+                }
+                if let Ok(mut layer) = self.layers[layer_id].try_lock() {
+                    layer.stop_playing();
+                    self.send_event(AudioEvent::LayerStopped(layer_id));
+                }
+            }
+            LayerCommand::Play(layer_id) => {
+                if layer_id >= self.config.max_layers {
+                    return Err(LooperError::InvalidLayer(layer_id));
+                }
+
+                // Playing a layer that's capturing closes the take first
+                if matches!(
+                    self.layer_phase(layer_id),
+                    Some(LayerPhase::Recording | LayerPhase::Overdubbing)
+                ) {
+                    self.send_command(LayerCommand::StopRecording(layer_id))?;
+                }
+                if let Ok(mut layer) = self.layers[layer_id].try_lock() {
+                    if layer.is_empty() {
+                        return Err(LooperError::EmptyLayer(layer_id));
+                    }
+                    layer.start_playing();
+                    self.send_event(AudioEvent::LayerPlaying(layer_id, self.transport_stamp()));
+                }
+            }
+            LayerCommand::Mute(layer_id) => {
+                if layer_id >= self.config.max_layers {
+                    return Err(LooperError::InvalidLayer(layer_id));
+                }
+
+                if !self.queue_mix_change(layer_id, MixChange::Mute) {
+                    self.apply_mix_change(layer_id, MixChange::Mute);
+                }
+            }
+            LayerCommand::Solo(layer_id) => {
+                if layer_id >= self.config.max_layers {
+                    return Err(LooperError::InvalidLayer(layer_id));
+                }
+
+                if !self.queue_mix_change(layer_id, MixChange::Solo) {
+                    self.apply_mix_change(layer_id, MixChange::Solo);
+                }
+            }
+            LayerCommand::ToggleSoloSafe(layer_id) => {
+                if layer_id >= self.config.max_layers {
+                    return Err(LooperError::InvalidLayer(layer_id));
+                }
+
+                if let Ok(mut layer) = self.layers[layer_id].try_lock() {
+                    layer.is_solo_safe = !layer.is_solo_safe;
+                    self.send_event(AudioEvent::LayerSoloSafeChanged(
+                        layer_id,
+                        layer.is_solo_safe,
+                    ));
+                }
+            }
+            LayerCommand::ToggleFitToMaster(layer_id) => {
+                if layer_id >= self.config.max_layers {
+                    return Err(LooperError::InvalidLayer(layer_id));
+                }
+                let master = self.master_loop_length.try_lock().ok().and_then(|m| *m);
+
+                if let Ok(mut layer) = self.layers[layer_id].try_lock() {
+                    if layer.fit_length.is_some() {
+                        layer.fit_length = None;
+                    } else {
+                        if layer.is_empty() {
+                            return Err(LooperError::EmptyLayer(layer_id));
+                        }
+                        let master = master.ok_or(LooperError::NoMasterLoop)?;
+                        layer.fit_length = Some(
+                            layer
+                                .fit_target(master)
+                                .ok_or(LooperError::CannotFit(layer_id))?,
+                        );
+                    }
+                    self.send_event(AudioEvent::LayerFitChanged(
+                        layer_id,
+                        layer.fit_length.is_some(),
+                    ));
+                }
+            }
+            LayerCommand::FreezeLayer(layer_id) => {
+                if layer_id >= self.config.max_layers {
+                    return Err(LooperError::InvalidLayer(layer_id));
+                }
+                self.ensure_unlocked(layer_id)?;
+                self.ensure_not_capturing(layer_id, "freeze")?;
+                let target = self.layers[layer_id]
+                    .try_lock()
+                    .map(|layer| layer.fit_length.filter(|_| layer.playback_rate() != 1.0))
+                    .map_err(|_| LooperError::Busy("Layer"))?
+                    .ok_or(LooperError::NothingToFreeze(layer_id))?;
+
+                // Rendering a whole loop is too slow for the audio thread
+                let layers = Arc::clone(&self.layers);
+                let event_sender = Arc::clone(&self.event_sender);
+                let transaction_log = Arc::clone(&self.transaction_log);
+
+                std::thread::spawn(move || {
+                    let Some(source) = layers[layer_id].lock().ok().map(|mut l| l.edit_source())
+                    else {
+                        return;
+                    };
+                    // Same sample-count-as-rate trick as a stretch fix
+                    let mut samples =
+                        super::io::resample_audio(&source, source.len() as u32, target as u32, 1)
+                            .unwrap_or_else(|_| source.to_vec());
+                    samples.resize(target, 0.0);
+
+                    let frozen =
+                        Self::edit_layer_from(&layers, layer_id, "freeze", &source, |layer| {
+                            layer.replace_processed(samples)
+                        });
+                    if let Ok(mut log) = transaction_log.lock() {
+                        Self::record_transactions(&layers, &mut log);
+                    }
+                    if let Ok(sender) = event_sender.try_lock()
+                        && let Some(ref tx) = *sender
+                    {
+                        let _ = tx.try_send(match frozen {
+                            Ok(()) => AudioEvent::LayerFrozen(layer_id),
+                            Err(e) => AudioEvent::Error(e),
+                        });
+                    }
+                });
+            }
+            LayerCommand::LearnNoise(layer_id) => {
+                if layer_id >= self.config.max_layers {
+                    return Err(LooperError::InvalidLayer(layer_id));
+                }
+                if self.layers[layer_id]
+                    .try_lock()
+                    .map_err(|_| LooperError::Busy("Layer"))?
+                    .is_empty()
+                {
+                    return Err(LooperError::EmptyLayer(layer_id));
+                }
+
+                let layers = Arc::clone(&self.layers);
+                let noise_profile = Arc::clone(&self.noise_profile);
+                let event_sender = Arc::clone(&self.event_sender);
+                std::thread::spawn(move || {
+                    let send = |event| {
+                        if let Ok(sender) = event_sender.try_lock()
+                            && let Some(ref tx) = *sender
+                        {
+                            let _ = tx.try_send(event);
+                        }
+                    };
+                    // The loop region, so a count-in or tail can be trimmed off
+                    let Some(profile) = layers[layer_id].lock().ok().map(|layer| {
+                        let end = layer.loop_end.min(layer.get_buffer_length());
+                        NoiseProfile::learn(&layer.audio()[layer.loop_start.min(end)..end])
+                    }) else {
+                        return;
+                    };
+                    match profile {
+                        Some(profile) => {
+                            *noise_profile.lock().unwrap() = Some(profile);
+                            send(AudioEvent::NoiseLearned(layer_id));
+                        }
+                        None => send(AudioEvent::Error(LooperError::NoiseTooShort(layer_id))),
+                    }
+                });
+            }
+            LayerCommand::Denoise(layer_id) => {
                 if layer_id >= self.config.max_layers {
-                    return Err("Layer ID out of range".into());
+                    return Err(LooperError::InvalidLayer(layer_id));
                 }
-
-                // Stop any current recording
-                if let Ok(recording_layer) = self.recording_layer.try_lock()
-                    && let Some(current_layer) = *recording_layer
-                    && let Ok(mut layer) = self.layers[current_layer].try_lock()
+                self.ensure_unlocked(layer_id)?;
+                self.ensure_not_capturing(layer_id, "denoise")?;
+                let profile = self
+                    .noise_profile
+                    .try_lock()
+                    .map_err(|_| LooperError::Busy("Noise print"))?
+                    .clone()
+                    .ok_or(LooperError::NoNoiseProfile)?;
+                if self.layers[layer_id]
+                    .try_lock()
+                    .map_err(|_| LooperError::Busy("Layer"))?
+                    .is_empty()
                 {
-                    layer.stop_recording();
+                    return Err(LooperError::EmptyLayer(layer_id));
                 }
 
-                // Start recording on new layer
-                if let Ok(mut layer) = self.layers[layer_id].try_lock() {
-                    layer.start_recording();
-                    if let Ok(mut recording_layer) = self.recording_layer.try_lock() {
-                        *recording_layer = Some(layer_id);
-                    }
-                    if let Ok(mut is_recording) = self.is_recording.try_lock() {
-                        *is_recording = true;
+                // Whole-loop FFT processing is far too slow for the audio thread
+                let layers = Arc::clone(&self.layers);
+                let event_sender = Arc::clone(&self.event_sender);
+                let transaction_log = Arc::clone(&self.transaction_log);
+                std::thread::spawn(move || {
+                    let send = |event| {
+                        if let Ok(sender) = event_sender.try_lock()
+                            && let Some(ref tx) = *sender
+                        {
+                            let _ = tx.try_send(event);
+                        }
+                    };
+                    let Some(source) = layers[layer_id].lock().ok().map(|mut l| l.edit_source())
+                    else {
+                        return;
+                    };
+                    let mut sent = 0;
+                    let samples = profile.denoise(&source, |done| {
+                        let percent = (done * 100.0) as u8;
+                        if percent >= sent + 10 {
+                            sent = percent;
+                            send(AudioEvent::DenoiseProgress { layer_id, percent });
+                        }
+                    });
+
+                    let denoised =
+                        Self::edit_layer_from(&layers, layer_id, "denoise", &source, |layer| {
+                            layer.replace_processed(samples)
+                        });
+                    if let Ok(mut log) = transaction_log.lock() {
+                        Self::record_transactions(&layers, &mut log);
                     }
-                    self.send_event(AudioEvent::LayerRecording(layer_id));
-                }
+                    send(match denoised {
+                        Ok(()) => AudioEvent::LayerDenoised(layer_id),
+                        Err(e) => AudioEvent::Error(e),
+                    });
+                });
             }
-            LayerCommand::StopRecording(layer_id) => {
+            LayerCommand::Declick(layer_id) => {
                 if layer_id >= self.config.max_layers {
-                    return Err("Layer ID out of range".into());
+                    return Err(LooperError::InvalidLayer(layer_id));
                 }
-
-                if let Ok(mut layer) = self.layers[layer_id].try_lock() {
-                    layer.stop_recording(); // This automatically starts playback if there's content
-                    self.send_event(AudioEvent::LayerStopped(layer_id));
-                }
-
-                if let Ok(mut recording_layer) = self.recording_layer.try_lock()
-                    && *recording_layer == Some(layer_id)
+                self.ensure_unlocked(layer_id)?;
+                self.ensure_not_capturing(layer_id, "declick")?;
+                if self.layers[layer_id]
+                    .try_lock()
+                    .map_err(|_| LooperError::Busy("Layer"))?
+                    .is_empty()
                 {
-                    *recording_layer = None;
-                }
-                if let Ok(mut is_recording) = self.is_recording.try_lock() {
-                    *is_recording = false;
+                    return Err(LooperError::EmptyLayer(layer_id));
                 }
+
+                let layers = Arc::clone(&self.layers);
+                let event_sender = Arc::clone(&self.event_sender);
+                let transaction_log = Arc::clone(&self.transaction_log);
+                std::thread::spawn(move || {
+                    let Some(source) = layers[layer_id].lock().ok().map(|mut l| l.edit_source())
+                    else {
+                        return;
+                    };
+                    let mut samples = source.to_vec();
+                    let repaired = super::declick(&mut samples);
+                    // Nothing found leaves the layer (and its undo history) alone
+                    let mut declicked = Ok(());
+                    if repaired > 0 {
+                        declicked =
+                            Self::edit_layer_from(&layers, layer_id, "declick", &source, |layer| {
+                                layer.replace_processed(samples)
+                            });
+                        if let Ok(mut log) = transaction_log.lock() {
+                            Self::record_transactions(&layers, &mut log);
+                        }
+                    }
+                    if let Ok(sender) = event_sender.try_lock()
+                        && let Some(ref tx) = *sender
+                    {
+                        let _ = tx.try_send(match declicked {
+                            Ok(()) => AudioEvent::LayerDeclicked(layer_id, repaired),
+                            Err(e) => AudioEvent::Error(e),
+                        });
+                    }
+                });
             }
-            LayerCommand::StopPlaying(layer_id) => {
+            LayerCommand::RemoveDcOffset(layer_id) => {
                 if layer_id >= self.config.max_layers {
-                    return Err("Layer ID out of range".into());
+                    return Err(LooperError::InvalidLayer(layer_id));
                 }
-
-                if let Ok(mut layer) = self.layers[layer_id].try_lock() {
-                    layer.stop_playing();
-                    self.send_event(AudioEvent::LayerStopped(layer_id));
+                self.ensure_unlocked(layer_id)?;
+                self.ensure_not_capturing(layer_id, "remove DC offset")?;
+                if self.layers[layer_id]
+                    .try_lock()
+                    .map_err(|_| LooperError::Busy("Layer"))?
+                    .is_empty()
+                {
+                    return Err(LooperError::EmptyLayer(layer_id));
                 }
+
+                let layers = Arc::clone(&self.layers);
+                let event_sender = Arc::clone(&self.event_sender);
+                let transaction_log = Arc::clone(&self.transaction_log);
+                std::thread::spawn(move || {
+                    let Some(source) = layers[layer_id].lock().ok().map(|mut l| l.edit_source())
+                    else {
+                        return;
+                    };
+                    let offset = super::dc_offset(&source);
+                    // A centred layer keeps its undo history as it is
+                    let mut removed = Ok(());
+                    if offset != 0.0 {
+                        let samples = source.iter().map(|s| s - offset).collect();
+                        removed = Self::edit_layer_from(
+                            &layers,
+                            layer_id,
+                            "remove DC offset",
+                            &source,
+                            |layer| layer.replace_processed(samples),
+                        );
+                        if let Ok(mut log) = transaction_log.lock() {
+                            Self::record_transactions(&layers, &mut log);
+                        }
+                    }
+                    if let Ok(sender) = event_sender.try_lock()
+                        && let Some(ref tx) = *sender
+                    {
+                        let _ = tx.try_send(match removed {
+                            Ok(()) => AudioEvent::DcOffsetRemoved(layer_id, offset),
+                            Err(e) => AudioEvent::Error(e),
+                        });
+                    }
+                });
             }
-            LayerCommand::Play(layer_id) => {
+            LayerCommand::SelectLayer(layer_id) => {
                 if layer_id >= self.config.max_layers {
-                    return Err("Layer ID out of range".into());
-                }
-
-                if let Ok(mut layer) = self.layers[layer_id].try_lock() {
-                    layer.start_playing();
-                    self.send_event(AudioEvent::LayerPlaying(layer_id));
+                    return Err(LooperError::InvalidLayer(layer_id));
                 }
+                self.send_event(AudioEvent::LayerSelected(layer_id));
             }
-            LayerCommand::Mute(layer_id) => {
+            LayerCommand::AnalyzeLayer(layer_id) => {
                 if layer_id >= self.config.max_layers {
-                    return Err("Layer ID out of range".into());
+                    return Err(LooperError::InvalidLayer(layer_id));
+                }
+                let (bpm, beats_per_measure) = self
+                    .tempo
+                    .try_lock()
+                    .map(|t| (t.bpm, t.beats_per_measure))
+                    .map_err(|_| LooperError::Busy("Tempo"))?;
+                if self.layers[layer_id]
+                    .try_lock()
+                    .map_err(|_| LooperError::Busy("Layer"))?
+                    .is_empty()
+                {
+                    return Err(LooperError::EmptyLayer(layer_id));
                 }
 
-                if let Ok(mut layer) = self.layers[layer_id].try_lock() {
-                    layer.toggle_mute();
-                    if layer.is_muted {
-                        self.send_event(AudioEvent::LayerMuted(layer_id));
-                    } else {
-                        self.send_event(AudioEvent::LayerUnmuted(layer_id));
+                let layers = Arc::clone(&self.layers);
+                let event_sender = Arc::clone(&self.event_sender);
+                let sample_rate = self.config.sample_rate;
+                std::thread::spawn(move || {
+                    // The loop region is what plays
+                    let Some(stats) = layers[layer_id].lock().ok().map(|layer| {
+                        let end = layer.loop_end.min(layer.get_buffer_length());
+                        let audio = layer.audio();
+                        let region = &audio[layer.loop_start.min(end)..end];
+                        LayerStats::measure(region, sample_rate, bpm, beats_per_measure)
+                    }) else {
+                        return;
+                    };
+                    if let Ok(sender) = event_sender.try_lock()
+                        && let Some(ref tx) = *sender
+                    {
+                        let _ = tx.try_send(AudioEvent::LayerStatsReady(layer_id, stats));
                     }
-                }
+                });
             }
-            LayerCommand::Solo(layer_id) => {
-                if layer_id >= self.config.max_layers {
-                    return Err("Layer ID out of range".into());
+            LayerCommand::SetLatencyCompensation(delay) => {
+                let samples = (delay.as_secs_f64() * self.config.sample_rate as f64) as usize;
+                if let Ok(mut compensation) = self.latency_compensation.try_lock() {
+                    *compensation = samples;
                 }
-
-                if let Ok(mut layer) = self.layers[layer_id].try_lock() {
-                    layer.toggle_solo();
-                    if layer.is_solo {
-                        self.send_event(AudioEvent::LayerSoloed(layer_id));
-                    } else {
-                        self.send_event(AudioEvent::LayerUnsoloed(layer_id));
+                self.send_event(AudioEvent::LatencyCompensationChanged(delay));
+            }
+            LayerCommand::SetInputHighPass(cutoff) => {
+                let cutoff =
+                    cutoff.map(|hz| hz.clamp(Self::INPUT_HIGH_PASS_MIN, Self::INPUT_HIGH_PASS_MAX));
+                let filter = cutoff.map(|hz| {
+                    Biquad::high_pass(
+                        self.config.sample_rate as f32,
+                        hz,
+                        std::f32::consts::FRAC_1_SQRT_2,
+                    )
+                });
+                *self
+                    .input_high_pass
+                    .try_lock()
+                    .map_err(|_| LooperError::Busy("Input filter"))? = filter;
+                self.send_event(AudioEvent::InputHighPassChanged(cutoff));
+            }
+            LayerCommand::SetStereoWidth(width) => {
+                let width = width.clamp(0.0, MAX_STEREO_WIDTH);
+                *self
+                    .stereo_width
+                    .try_lock()
+                    .map_err(|_| LooperError::Busy("Stereo width"))? = width;
+                self.send_event(AudioEvent::StereoWidthChanged(width));
+            }
+            LayerCommand::ResetPeaks => {
+                for layer_arc in self.layers.iter() {
+                    if let Ok(layer) = layer_arc.try_lock() {
+                        layer.meter.reset_peaks();
                     }
                 }
+                self.input_meter.reset_peaks();
+                self.send_event(AudioEvent::PeaksReset);
+            }
+            LayerCommand::SetMixAnalysis(enabled) => {
+                self.set_mix_analysis(enabled);
+                self.send_event(AudioEvent::MixAnalysisToggled(enabled));
             }
             LayerCommand::SetVolume(layer_id, volume) => {
                 if layer_id >= self.config.max_layers {
-                    return Err("Layer ID out of range".into());
+                    return Err(LooperError::InvalidLayer(layer_id));
                 }
+                self.ensure_unlocked(layer_id)?;
 
                 if let Ok(mut layer) = self.layers[layer_id].try_lock() {
                     layer.set_volume(volume);
                     self.send_event(AudioEvent::VolumeChanged(layer_id, volume));
                 }
             }
+            LayerCommand::SetMasterLength(length) => {
+                self.set_master_length((length > 0).then_some(length));
+            }
+            LayerCommand::MasterFromLayer(layer_id) => {
+                if layer_id >= self.config.max_layers {
+                    return Err(LooperError::InvalidLayer(layer_id));
+                }
+                let length = self.layers[layer_id]
+                    .try_lock()
+                    .map(|layer| layer.get_buffer_length())
+                    .map_err(|_| LooperError::Busy("Layer"))?;
+                if length == 0 {
+                    return Err(LooperError::EmptyLayer(layer_id));
+                }
+                self.set_master_length(Some(length));
+            }
+            LayerCommand::ClearMasterLength => {
+                self.set_master_length(None);
+            }
+            LayerCommand::FixLoopLength(layer_id, fix) => {
+                if layer_id >= self.config.max_layers {
+                    return Err(LooperError::InvalidLayer(layer_id));
+                }
+                self.ensure_unlocked(layer_id)?;
+                let length = self.layers[layer_id]
+                    .try_lock()
+                    .map(|layer| layer.get_buffer_length())
+                    .map_err(|_| LooperError::Busy("Layer"))?;
+                if length == 0 {
+                    return Err(LooperError::EmptyLayer(layer_id));
+                }
+                let master = self
+                    .master_loop_length
+                    .try_lock()
+                    .map(|m| *m)
+                    .map_err(|_| LooperError::Busy("Master loop"))?;
+                let master_length = match (master, fix) {
+                    (Some(master_length), LoopFix::Trim | LoopFix::Stretch) => master_length,
+                    _ => {
+                        self.set_master_length(Some(length));
+                        self.send_event(AudioEvent::LoopLengthFixed(layer_id, LoopFix::SetMaster));
+                        return Ok(());
+                    }
+                };
+
+                // Resampling a whole loop is too slow for the audio thread
+                let layers = Arc::clone(&self.layers);
+                let event_sender = Arc::clone(&self.event_sender);
+                let transaction_log = Arc::clone(&self.transaction_log);
+
+                std::thread::spawn(move || {
+                    let Some(source) = layers[layer_id].lock().ok().map(|mut l| l.edit_source())
+                    else {
+                        return;
+                    };
+                    let buffer = source.to_vec();
+                    let target = LoopRatio::nearest(buffer.len(), master_length)
+                        .target_length(master_length);
+                    let mut samples = match fix {
+                        // Sample counts stand in for rates: the loop is played back faster or slower
+                        LoopFix::Stretch => super::io::resample_audio(
+                            &buffer,
+                            buffer.len() as u32,
+                            target as u32,
+                            1,
+                        )
+                        .unwrap_or(buffer),
+                        _ => buffer,
+                    };
+                    samples.resize(target, 0.0);
+
+                    let fixed = Self::edit_layer_from(
+                        &layers,
+                        layer_id,
+                        "fix the loop length",
+                        &source,
+                        |layer| {
+                            layer.replace_buffer(samples);
+                            if layer.playback_position >= target {
+                                layer.playback_position = layer.loop_start;
+                            }
+                        },
+                    );
+                    if let Ok(mut log) = transaction_log.lock() {
+                        Self::record_transactions(&layers, &mut log);
+                    }
+                    if let Ok(sender) = event_sender.try_lock()
+                        && let Some(ref tx) = *sender
+                    {
+                        let _ = tx.try_send(match fixed {
+                            Ok(()) => AudioEvent::LoopLengthFixed(layer_id, fix),
+                            Err(e) => AudioEvent::Error(e),
+                        });
+                    }
+                });
+            }
             LayerCommand::StopAll => {
-                for layer_arc in self.layers.iter() {
+                self.cancel_pending(None);
+                let latency_offset = self.latency_offset();
+                for (layer_id, layer_arc) in self.layers.iter().enumerate() {
                     if let Ok(mut layer) = layer_arc.try_lock() {
+                        layer.compensate_latency(latency_offset);
                         layer.stop_recording();
+                        // A re-take is kept aside until it's kept or reverted
+                        if layer.stop_replacement(latency_offset) {
+                            self.send_event(AudioEvent::ReplacementReady(layer_id));
+                        }
                         layer.stop_playing();
                     }
                 }
-                if let Ok(mut recording_layer) = self.recording_layer.try_lock() {
-                    *recording_layer = None;
+                if let Ok(mut recording_layer) = self.recording_layer.try_lock()
+                    && let Some(layer_id) = recording_layer.take()
+                {
+                    self.check_loop_length(layer_id);
                 }
                 if let Ok(mut is_recording) = self.is_recording.try_lock() {
                     *is_recording = false;
@@ -591,12 +2768,23 @@ impl LooperEngine {
             }
             LayerCommand::Clear(layer_id) => {
                 if layer_id >= self.config.max_layers {
-                    return Err("Layer ID out of range".into());
+                    return Err(LooperError::InvalidLayer(layer_id));
                 }
-
-                if let Ok(mut layer) = self.layers[layer_id].try_lock() {
+                {
+                    // Checked and cleared under one guard so a lock or a take can't
+                    // slip in between
+                    let mut layer = self.layers[layer_id]
+                        .try_lock()
+                        .map_err(|_| LooperError::Busy("Layer"))?;
+                    Self::check_unlocked(&layer, layer_id)?;
+                    Self::check_not_capturing(&layer, layer_id, "clear")?;
+                    self.cancel_pending(Some(layer_id));
+                    let samples = layer.get_buffer_length();
                     layer.clear();
-                    self.send_event(AudioEvent::LayerCleared(layer_id));
+                    self.send_event(AudioEvent::LayerCleared(layer_id, samples));
+                }
+                if self.first_layer_length().is_none() {
+                    self.set_master_length(None);
                 }
 
                 // If this was the recording layer, clear it
@@ -610,11 +2798,33 @@ impl LooperEngine {
                 }
             }
             LayerCommand::ClearAll => {
-                for layer_arc in self.layers.iter() {
-                    if let Ok(mut layer) = layer_arc.try_lock() {
+                self.cancel_pending(None);
+                let mut take_in_progress = false;
+                for (layer_id, layer_arc) in self.layers.iter().enumerate() {
+                    if let Ok(mut layer) = layer_arc.try_lock()
+                        && !layer.is_locked
+                    {
+                        // A take in progress keeps its layer; the rest still clear
+                        if layer.is_recording || layer.is_replacing {
+                            take_in_progress = true;
+                            self.send_event(AudioEvent::Error(LooperError::LayerCapturing {
+                                layer_id,
+                                action: "clear",
+                            }));
+                            continue;
+                        }
+                        let samples = layer.get_buffer_length();
                         layer.clear();
+                        if samples > 0 {
+                            self.send_event(AudioEvent::LayerCleared(layer_id, samples));
+                        }
                     }
                 }
+                // Locked layers survive; the master follows them or starts over
+                self.set_master_length(self.first_layer_length());
+                if take_in_progress {
+                    return Ok(());
+                }
                 if let Ok(mut recording_layer) = self.recording_layer.try_lock() {
                     *recording_layer = None;
                 }
@@ -624,118 +2834,493 @@ impl LooperEngine {
             }
             LayerCommand::Undo(layer_id) => {
                 if layer_id >= self.config.max_layers {
-                    return Err("Layer ID out of range".into());
+                    return Err(LooperError::InvalidLayer(layer_id));
                 }
 
-                if let Ok(mut layer) = self.layers[layer_id].try_lock()
-                    && layer.undo()
-                {
+                self.sync_transaction_log();
+                let mut layer = self.layers[layer_id]
+                    .try_lock()
+                    .map_err(|_| LooperError::Busy("Layer"))?;
+                // History is audio too: a locked layer can't be undone
+                Self::check_unlocked(&layer, layer_id)?;
+                if layer.undo() {
+                    drop(layer);
+                    if let Ok(mut log) = self.transaction_log.try_lock() {
+                        log.note_layer_undo(layer_id);
+                    }
                     self.send_event(AudioEvent::LayerUpdated(layer_id));
                 }
             }
             LayerCommand::Redo(layer_id) => {
                 if layer_id >= self.config.max_layers {
-                    return Err("Layer ID out of range".into());
+                    return Err(LooperError::InvalidLayer(layer_id));
                 }
 
-                if let Ok(mut layer) = self.layers[layer_id].try_lock()
-                    && layer.redo()
-                {
+                let mut layer = self.layers[layer_id]
+                    .try_lock()
+                    .map_err(|_| LooperError::Busy("Layer"))?;
+                Self::check_unlocked(&layer, layer_id)?;
+                if layer.redo() {
+                    drop(layer);
+                    if let Ok(mut log) = self.transaction_log.try_lock() {
+                        log.note_layer_redo(layer_id);
+                    }
+                    self.send_event(AudioEvent::LayerUpdated(layer_id));
+                }
+            }
+            LayerCommand::GlobalUndo => {
+                // Fold pending snapshots first so the latest action is undone
+                self.sync_transaction_log();
+                let layer_ids = self.apply_global_undo()?;
+                for &layer_id in &layer_ids {
+                    self.send_event(AudioEvent::LayerUpdated(layer_id));
+                }
+                self.send_event(AudioEvent::GlobalUndoApplied(layer_ids));
+            }
+            LayerCommand::GlobalRedo => {
+                let layer_ids = self.apply_global_redo()?;
+                for &layer_id in &layer_ids {
                     self.send_event(AudioEvent::LayerUpdated(layer_id));
                 }
+                self.send_event(AudioEvent::GlobalRedoApplied(layer_ids));
             }
             LayerCommand::PlayAll => {
                 for layer_arc in self.layers.iter() {
                     if let Ok(mut layer) = layer_arc.try_lock()
-                        && !layer.buffer.is_empty()
+                        && !layer.is_empty()
                     {
                         layer.start_playing();
                     }
                 }
                 self.send_event(AudioEvent::AllPlaying);
             }
-            LayerCommand::ImportWav(layer_id, file_path) => {
+            LayerCommand::ImportWav(layer_id, file_path, mode) => {
                 if layer_id >= self.config.max_layers {
-                    return Err("Layer ID out of range".into());
+                    return Err(LooperError::InvalidLayer(layer_id));
                 }
+                self.ensure_unlocked(layer_id)?;
+                self.ensure_not_capturing(layer_id, "import")?;
+
+                let tempo_match = self.import_tempo_match.try_lock().is_ok_and(|m| *m);
+                let target_bpm = self
+                    .tempo
+                    .try_lock()
+                    .ok()
+                    .map(|t| t.bpm)
+                    .filter(|_| tempo_match);
 
                 // CRITICAL: Move file I/O to separate thread to avoid blocking audio thread
                 let layers = Arc::clone(&self.layers);
                 let sample_rate = self.config.sample_rate;
+                let import_limit = self.current_import_limit();
                 let event_sender = Arc::clone(&self.event_sender);
+                let transaction_log = Arc::clone(&self.transaction_log);
 
                 std::thread::spawn(move || {
-                    match super::io::import_wav(&file_path, sample_rate) {
+                    let decoded = if file_path == STDIN_PATH {
+                        super::io::import_wav_from_reader(
+                            std::io::stdin().lock(),
+                            sample_rate,
+                            import_limit,
+                        )
+                    } else {
+                        super::io::import_wav_capped(&file_path, sample_rate, import_limit)
+                    };
+                    match decoded {
                         Ok(samples) => {
-                            if let Some(layer_arc) = layers.get(layer_id)
-                                && let Ok(mut layer) = layer_arc.lock()
-                            {
-                                layer.buffer = samples;
-                                layer.loop_end = layer.buffer.len();
+                            let issues = super::check_import(&samples);
+                            // Standard input can't be read again for its chunks
+                            let info = if file_path == STDIN_PATH {
+                                WavLoopInfo::default()
+                            } else {
+                                read_loop_info(&file_path, sample_rate).unwrap_or_default()
+                            };
+                            let result = Self::import_into_layer(
+                                &layers, layer_id, samples, mode, info, target_bpm,
+                            );
+                            if let Ok(mut log) = transaction_log.lock() {
+                                Self::record_transactions(&layers, &mut log);
                             }
                             // Notify UI
                             if let Ok(sender) = event_sender.try_lock()
                                 && let Some(ref tx) = *sender
                             {
-                                let _ = tx.try_send(AudioEvent::WavImported(layer_id, file_path));
+                                match result {
+                                    Ok(loop_event) => {
+                                        if let Some(event) = loop_event {
+                                            let _ = tx.try_send(event);
+                                        }
+                                        let _ = tx
+                                            .try_send(AudioEvent::WavImported(layer_id, file_path));
+                                        for issue in issues {
+                                            let _ = tx.try_send(AudioEvent::ImportWarning(
+                                                layer_id, issue,
+                                            ));
+                                        }
+                                    }
+                                    Err(e) => {
+                                        let _ = tx.try_send(AudioEvent::Error(e));
+                                    }
+                                }
                             }
                         }
                         Err(e) => {
                             if let Ok(sender) = event_sender.try_lock()
                                 && let Some(ref tx) = *sender
                             {
-                                let _ = tx.try_send(AudioEvent::Error(format!(
-                                    "Failed to import WAV: {}",
-                                    e
+                                let _ = tx.try_send(AudioEvent::Error(LooperError::io(
+                                    "import WAV",
+                                    file_path,
+                                    &e,
                                 )));
                             }
                         }
                     }
                 });
             }
-            LayerCommand::ExportWav(file_path) => {
-                // CRITICAL: Move cloning and file I/O to separate thread
-                let layers = Arc::clone(&self.layers);
+            LayerCommand::PreviewWav(file_path) => {
+                // Decode on a separate thread, then hand the samples to the preview channel
+                let preview_sample = Arc::clone(&self.preview_sample);
+                let preview_playhead = Arc::clone(&self.preview_playhead);
                 let sample_rate = self.config.sample_rate;
+                let import_limit = self.current_import_limit();
                 let event_sender = Arc::clone(&self.event_sender);
 
                 std::thread::spawn(move || {
-                    // Clone buffers in this thread, not audio thread
-                    let layer_buffers: Vec<Vec<f32>> = layers
-                        .iter()
-                        .filter_map(|layer_arc| {
-                            layer_arc.lock().ok().map(|layer| layer.buffer.clone())
-                        })
-                        .collect();
-
-                    // Perform file I/O
-                    match super::io::export_mixed_wav(&file_path, &layer_buffers, sample_rate) {
-                        Ok(()) => {
-                            if let Ok(sender) = event_sender.try_lock()
-                                && let Some(ref tx) = *sender
-                            {
-                                let _ = tx.try_send(AudioEvent::WavExported(file_path));
+                    let event =
+                        match super::io::import_wav_capped(&file_path, sample_rate, import_limit) {
+                            Ok(samples) => {
+                                *preview_playhead.lock().unwrap() = None;
+                                *preview_sample.lock().unwrap() = samples;
+                                *preview_playhead.lock().unwrap() = Some(0);
+                                AudioEvent::PreviewStarted(file_path)
+                            }
+                            Err(e) => {
+                                AudioEvent::Error(LooperError::io("preview WAV", file_path, &e))
+                            }
+                        };
+                    if let Ok(sender) = event_sender.try_lock()
+                        && let Some(ref tx) = *sender
+                    {
+                        let _ = tx.try_send(event);
+                    }
+                });
+            }
+            LayerCommand::StopPreview => {
+                if let Ok(mut playhead) = self.preview_playhead.try_lock()
+                    && playhead.take().is_some()
+                {
+                    self.send_event(AudioEvent::PreviewStopped);
+                }
+            }
+            LayerCommand::AuditionWav(layer_id, file_path) => {
+                if layer_id >= self.config.max_layers {
+                    return Err(LooperError::InvalidLayer(layer_id));
+                }
+                self.ensure_unlocked(layer_id)?;
+                self.ensure_not_capturing(layer_id, "import")?;
+
+                let audition = Arc::clone(&self.audition);
+                let tempo = Arc::clone(&self.tempo);
+                let sync = self
+                    .beat_sync_enabled
+                    .try_lock()
+                    .map(|b| *b)
+                    .unwrap_or(true);
+                let sample_rate = self.config.sample_rate;
+                let import_limit = self.current_import_limit();
+                let event_sender = Arc::clone(&self.event_sender);
+
+                std::thread::spawn(move || {
+                    let event =
+                        match super::io::import_wav_capped(&file_path, sample_rate, import_limit) {
+                            Ok(samples) => {
+                                let loop_info =
+                                    read_loop_info(&file_path, sample_rate).unwrap_or_default();
+                                let samples_per_measure = tempo
+                                    .lock()
+                                    .map(|t| t.samples_per_measure)
+                                    .unwrap_or(sample_rate as usize * 2);
+                                *audition.lock().unwrap() = Some(Audition {
+                                    layer_id,
+                                    file_path: file_path.clone(),
+                                    samples,
+                                    loop_info,
+                                    position: 0,
+                                    remaining: samples_per_measure * Self::AUDITION_MEASURES,
+                                    waiting_for_measure: sync,
+                                });
+                                AudioEvent::AuditionStarted(layer_id, file_path)
+                            }
+                            Err(e) => {
+                                AudioEvent::Error(LooperError::io("import WAV", file_path, &e))
+                            }
+                        };
+                    if let Ok(sender) = event_sender.try_lock()
+                        && let Some(ref tx) = *sender
+                    {
+                        let _ = tx.try_send(event);
+                    }
+                });
+            }
+            LayerCommand::ConfirmAudition(mode) => {
+                // The layer may have been locked while auditioning; keep the candidate
+                if let Some(layer_id) = self
+                    .audition
+                    .try_lock()
+                    .ok()
+                    .and_then(|a| a.as_ref().map(|c| c.layer_id))
+                {
+                    self.ensure_unlocked(layer_id)?;
+                    self.ensure_not_capturing(layer_id, "import")?;
+                }
+                let Some(candidate) = self.audition.try_lock().ok().and_then(|mut a| a.take())
+                else {
+                    return Err(LooperError::NoAudition);
+                };
+
+                let tempo_match = self.import_tempo_match.try_lock().is_ok_and(|m| *m);
+                let target_bpm = self
+                    .tempo
+                    .try_lock()
+                    .ok()
+                    .map(|t| t.bpm)
+                    .filter(|_| tempo_match);
+
+                // Commit on a separate thread (snapshots allocate)
+                let layers = Arc::clone(&self.layers);
+                let audition = Arc::clone(&self.audition);
+                let event_sender = Arc::clone(&self.event_sender);
+                let transaction_log = Arc::clone(&self.transaction_log);
+
+                std::thread::spawn(move || {
+                    let send = |event| {
+                        if let Ok(sender) = event_sender.try_lock()
+                            && let Some(ref tx) = *sender
+                        {
+                            let _ = tx.try_send(event);
+                        }
+                    };
+                    let layer_id = candidate.layer_id;
+                    // A copy is imported, so a candidate the layer refuses (locked or
+                    // capturing since) goes back to auditioning instead of being lost
+                    let result = Self::import_into_layer(
+                        &layers,
+                        layer_id,
+                        candidate.samples.clone(),
+                        mode,
+                        candidate.loop_info,
+                        target_bpm,
+                    );
+                    if let Ok(mut log) = transaction_log.lock() {
+                        Self::record_transactions(&layers, &mut log);
+                    }
+                    let event = match result {
+                        Ok(loop_event) => {
+                            if let Some(event) = loop_event {
+                                send(event);
                             }
+                            AudioEvent::WavImported(layer_id, candidate.file_path)
                         }
                         Err(e) => {
-                            if let Ok(sender) = event_sender.try_lock()
-                                && let Some(ref tx) = *sender
+                            // Unless another audition started meanwhile
+                            if let Ok(mut audition) = audition.lock()
+                                && audition.is_none()
                             {
-                                let _ = tx.try_send(AudioEvent::Error(format!(
-                                    "Failed to export WAV: {}",
-                                    e
-                                )));
+                                *audition = Some(candidate);
                             }
+                            AudioEvent::Error(e)
+                        }
+                    };
+                    send(event);
+                });
+            }
+            LayerCommand::CancelAudition => {
+                if let Ok(mut audition) = self.audition.try_lock()
+                    && let Some(candidate) = audition.take()
+                {
+                    // Free the decoded audio off the audio thread
+                    std::thread::spawn(move || drop(candidate));
+                    self.send_event(AudioEvent::AuditionCancelled);
+                }
+            }
+            LayerCommand::SetImportTempoMatch(enabled) => {
+                if let Ok(mut tempo_match) = self.import_tempo_match.try_lock() {
+                    *tempo_match = enabled;
+                }
+            }
+            LayerCommand::SetImportLimit(limit) => {
+                if let Ok(mut current) = self.import_limit.try_lock() {
+                    *current = limit;
+                }
+            }
+            LayerCommand::SetExportHook(hook) => {
+                if let Ok(mut current) = self.export_hook.try_lock() {
+                    *current = hook;
+                }
+            }
+            LayerCommand::SetExportBitDepth(bit_depth) => {
+                if let Ok(mut depth) = self.export_bit_depth.try_lock() {
+                    *depth = bit_depth;
+                }
+            }
+            LayerCommand::ExportLoopPack(directory) => {
+                self.ensure_none_capturing("export")?;
+                let tempo = self.loop_tempo().ok_or(LooperError::Busy("tempo"))?;
+                let layers = Arc::clone(&self.layers);
+                let sample_rate = self.config.sample_rate;
+                let bit_depth = self.export_bit_depth();
+                let name_tokens = self.name_tokens();
+                let event_sender = Arc::clone(&self.event_sender);
+
+                std::thread::spawn(move || {
+                    let event = match Self::write_loop_pack(
+                        &directory,
+                        &layers,
+                        &name_tokens,
+                        sample_rate,
+                        bit_depth,
+                        tempo,
+                    ) {
+                        Ok(files) => AudioEvent::LoopPackExported(directory, files),
+                        Err(e) => {
+                            AudioEvent::Error(LooperError::io("export loop pack", directory, &e))
+                        }
+                    };
+                    if let Ok(sender) = event_sender.try_lock()
+                        && let Some(ref tx) = *sender
+                    {
+                        let _ = tx.try_send(event);
+                    }
+                });
+            }
+            LayerCommand::ExportProject(file_path) => {
+                self.ensure_none_capturing("export")?;
+                // Stem export on a separate thread
+                let layers = Arc::clone(&self.layers);
+                let tempo = Arc::clone(&self.tempo);
+                let sample_rate = self.config.sample_rate;
+                let bit_depth = self.export_bit_depth();
+                let event_sender = Arc::clone(&self.event_sender);
+
+                std::thread::spawn(move || {
+                    let event = match Self::write_daw_project(
+                        &file_path,
+                        &layers,
+                        &tempo,
+                        sample_rate,
+                        bit_depth,
+                    ) {
+                        Ok(()) => AudioEvent::ProjectExported(file_path),
+                        Err(e) => {
+                            AudioEvent::Error(LooperError::io("export project", file_path, &e))
                         }
+                    };
+                    if let Ok(sender) = event_sender.try_lock()
+                        && let Some(ref tx) = *sender
+                    {
+                        let _ = tx.try_send(event);
+                    }
+                });
+            }
+            LayerCommand::ExportWav(file_path, normalization) => {
+                self.ensure_none_capturing("export")?;
+                // CRITICAL: Move cloning and file I/O to separate thread
+                let layers = Arc::clone(&self.layers);
+                let sample_rate = self.config.sample_rate;
+                let bit_depth = self.export_bit_depth();
+                let tempo = self.loop_tempo();
+                let hook = self.export_hook();
+                let name_tokens = self.name_tokens();
+                let event_sender = Arc::clone(&self.event_sender);
+
+                std::thread::spawn(move || {
+                    let template = file_path;
+                    let file_path = expand_file_name(&template, &name_tokens);
+                    // Render in this thread, not the audio thread
+                    let result = Self::render_mix(&layers, None).and_then(|mix| {
+                        Self::write_export(
+                            &file_path,
+                            mix,
+                            sample_rate,
+                            normalization,
+                            bit_depth,
+                            tempo,
+                        )
+                    });
+                    if result.is_err() {
+                        release_file_name(&template, &file_path);
+                    }
+                    Self::finish_export(&event_sender, "export WAV", file_path, result, hook);
+                });
+            }
+            LayerCommand::ExportLoop(file_path, region, normalization) => {
+                self.ensure_none_capturing("export")?;
+                let (start, length) = match region {
+                    ExportRegion::Cycles(cycles) => {
+                        let master = self.master_loop_length.try_lock().ok().and_then(|m| *m);
+                        let master = master.ok_or(LooperError::NoMasterLoop)?;
+                        (0, master * cycles as usize)
+                    }
+                    ExportRegion::Bars { first, last } => {
+                        let measure = self
+                            .tempo
+                            .try_lock()
+                            .map(|t| t.samples_per_measure)
+                            .map_err(|_| LooperError::Busy("tempo"))?;
+                        (
+                            (first as usize - 1) * measure,
+                            (last - first + 1) as usize * measure,
+                        )
+                    }
+                };
+                let layers = Arc::clone(&self.layers);
+                let sample_rate = self.config.sample_rate;
+                let bit_depth = self.export_bit_depth();
+                let tempo = self.loop_tempo();
+                let hook = self.export_hook();
+                let name_tokens = self.name_tokens();
+                let event_sender = Arc::clone(&self.event_sender);
+
+                std::thread::spawn(move || {
+                    let template = file_path;
+                    let file_path = expand_file_name(&template, &name_tokens);
+                    let result = Self::render_mix(&layers, Some((start, length))).and_then(|mix| {
+                        Self::write_export(
+                            &file_path,
+                            mix,
+                            sample_rate,
+                            normalization,
+                            bit_depth,
+                            tempo,
+                        )
+                    });
+                    if result.is_err() {
+                        release_file_name(&template, &file_path);
                     }
+                    Self::finish_export(&event_sender, "export loop", file_path, result, hook);
                 });
             }
             // Tempo / Sync controls
             LayerCommand::TapTempo => {
                 if let Ok(mut t) = self.tempo.try_lock() {
                     t.tap_tempo();
-                    let bpm = t.bpm;
+                    let (bpm, taps, locked) = (t.bpm, t.tap_count(), t.tap_locked);
                     self.send_event(AudioEvent::BpmChanged(bpm));
+                    self.send_event(AudioEvent::TapStatus { taps, locked });
+                }
+            }
+            LayerCommand::SetTapRounding(enabled) => {
+                if let Ok(mut t) = self.tempo.try_lock() {
+                    t.round_tap_bpm = enabled;
+                    self.send_event(AudioEvent::TapRoundingToggled(enabled));
+                }
+            }
+            LayerCommand::SetSwing(percent) => {
+                if let Ok(mut t) = self.tempo.try_lock() {
+                    t.set_swing(percent);
+                    let swing = t.swing;
+                    self.send_event(AudioEvent::SwingChanged(swing));
                 }
             }
             LayerCommand::SetBpm(bpm) => {
@@ -749,6 +3334,21 @@ impl LooperEngine {
                 if let Ok(mut flag) = self.beat_sync_enabled.try_lock() {
                     *flag = enabled;
                 }
+                self.send_event(AudioEvent::BeatSyncToggled(enabled));
+            }
+            LayerCommand::SetMixQuantize(quantize) => {
+                if let Ok(mut current) = self.mix_quantize.try_lock() {
+                    *current = quantize;
+                }
+                // Switching off releases anything still waiting
+                if quantize == MixQuantize::Off
+                    && let Ok(mut changes) = self.pending_mix.try_lock()
+                {
+                    while let Some((layer_id, change)) = changes.pop() {
+                        self.apply_mix_change(layer_id, change);
+                    }
+                }
+                self.send_event(AudioEvent::MixQuantizeChanged(quantize));
             }
             LayerCommand::ToggleCountInMode(enabled) => {
                 if let Ok(mut flag) = self.count_in_mode.try_lock() {
@@ -756,7 +3356,32 @@ impl LooperEngine {
                 }
                 self.send_event(AudioEvent::CountInModeToggled(enabled));
             }
+            LayerCommand::SetCountInMeasures(measures) => {
+                let measures = measures.clamp(1, 4);
+                if let Ok(mut current) = self.count_in_measures.try_lock() {
+                    *current = measures;
+                }
+                self.send_event(AudioEvent::CountInMeasuresChanged(measures));
+            }
+            LayerCommand::ToggleCountOut(enabled) => {
+                if let Ok(mut flag) = self.count_out_enabled.try_lock() {
+                    *flag = enabled;
+                }
+                if !enabled && let Ok(mut remaining) = self.count_out_remaining.try_lock() {
+                    *remaining = 0;
+                }
+                self.send_event(AudioEvent::CountOutToggled(enabled));
+            }
+            LayerCommand::SetPreCount(enabled) => {
+                if let Ok(mut flag) = self.pre_count_enabled.try_lock() {
+                    *flag = enabled;
+                }
+            }
             LayerCommand::StartCountIn { layer_id, measures } => {
+                if layer_id >= self.config.max_layers {
+                    return Err(LooperError::InvalidLayer(layer_id));
+                }
+                self.ensure_unlocked(layer_id)?;
                 if let Ok(mut t) = self.tempo.try_lock() {
                     let beats = measures.saturating_mul(t.beats_per_measure);
                     t.start_count_in(layer_id, beats);
@@ -764,6 +3389,16 @@ impl LooperEngine {
                 }
             }
             LayerCommand::SyncPlay(layer_id) => {
+                if layer_id >= self.config.max_layers {
+                    return Err(LooperError::InvalidLayer(layer_id));
+                }
+                if self.layers[layer_id]
+                    .try_lock()
+                    .is_ok_and(|layer| layer.is_empty())
+                {
+                    return Err(LooperError::EmptyLayer(layer_id));
+                }
+
                 let sync = self
                     .beat_sync_enabled
                     .try_lock()
@@ -778,10 +3413,14 @@ impl LooperEngine {
                     }
                 } else if let Ok(mut layer) = self.layers[layer_id].try_lock() {
                     layer.start_playing();
-                    self.send_event(AudioEvent::LayerPlaying(layer_id));
+                    self.send_event(AudioEvent::LayerPlaying(layer_id, self.transport_stamp()));
                 }
             }
             LayerCommand::SyncStop(layer_id) => {
+                if layer_id >= self.config.max_layers {
+                    return Err(LooperError::InvalidLayer(layer_id));
+                }
+
                 let sync = self
                     .beat_sync_enabled
                     .try_lock()
@@ -800,6 +3439,12 @@ impl LooperEngine {
                 }
             }
             LayerCommand::SyncRecord(layer_id) => {
+                if layer_id >= self.config.max_layers {
+                    return Err(LooperError::InvalidLayer(layer_id));
+                }
+                self.ensure_unlocked(layer_id)?;
+                self.check_transition(layer_id, LayerPhase::Recording)?;
+
                 let sync = self
                     .beat_sync_enabled
                     .try_lock()
@@ -808,11 +3453,12 @@ impl LooperEngine {
                 if sync {
                     let count_in_on = self.count_in_mode.try_lock().map(|b| *b).unwrap_or(false);
                     if count_in_on {
-                        // Count-in enabled: start a count-in for one measure, then (optionally) auto-start when finished (handled above)
+                        // Count-in enabled: start the pre-roll, then (optionally) auto-start when finished (handled above)
+                        let measures = self.count_in_measures.try_lock().map(|m| *m).unwrap_or(1);
                         if let Ok(mut t) = self.tempo.try_lock()
                             && !t.count_in_active
                         {
-                            let beats = t.beats_per_measure;
+                            let beats = measures.saturating_mul(t.beats_per_measure);
                             t.start_count_in(layer_id, beats);
                             self.send_event(AudioEvent::CountInStarted { layer_id, beats });
                         }
@@ -830,19 +3476,879 @@ impl LooperEngine {
                     if let Ok(mut is_recording) = self.is_recording.try_lock() {
                         *is_recording = true;
                     }
-                    self.send_event(AudioEvent::LayerRecording(layer_id));
+                    self.send_event(AudioEvent::LayerRecording(layer_id, self.transport_stamp()));
                 }
             }
+            LayerCommand::CancelPending(layer_id) => {
+                if layer_id >= self.config.max_layers {
+                    return Err(LooperError::InvalidLayer(layer_id));
+                }
+                self.cancel_pending(Some(layer_id));
+            }
+            LayerCommand::PauseTransport(paused) => {
+                self.tempo
+                    .try_lock()
+                    .map_err(|_| LooperError::Busy("Tempo"))?
+                    .paused = paused;
+                self.send_event(AudioEvent::TransportPaused(paused));
+            }
+            LayerCommand::ResetTransport => {
+                // "Set bar 1 here": this block's first sample becomes the downbeat
+                let (origin, samples_per_beat) = {
+                    let mut tempo = self
+                        .tempo
+                        .try_lock()
+                        .map_err(|_| LooperError::Busy("Tempo"))?;
+                    let origin = tempo.global_position;
+                    tempo.reset_position();
+                    (origin, tempo.samples_per_beat)
+                };
+                if let Ok(mut scheduler) = self.scheduler.try_lock() {
+                    scheduler.rebase(origin, samples_per_beat);
+                }
+                // Now is a downbeat: what waits for the next bar starts here
+                let stamp = TransportStamp::new(0, Instant::now());
+                self.send_event(AudioEvent::TransportReset);
+                self.send_event(AudioEvent::Beat(1, 0, stamp));
+                self.run_downbeat(stamp);
+            }
             LayerCommand::ToggleMetronome(enabled) => {
                 if let Ok(mut flag) = self.metronome_enabled.try_lock() {
                     *flag = enabled;
                 }
                 self.send_event(AudioEvent::MetronomeToggled(enabled));
             }
+            LayerCommand::SetLayerBus(layer_id, bus) => {
+                if layer_id >= self.config.max_layers {
+                    return Err(LooperError::InvalidLayer(layer_id));
+                }
+
+                if let Ok(mut layer) = self.layers[layer_id].try_lock() {
+                    layer.output_bus = bus;
+                    self.send_event(AudioEvent::LayerBusChanged(layer_id, bus));
+                }
+            }
+            LayerCommand::ToggleCue(layer_id) => {
+                if layer_id >= self.config.max_layers {
+                    return Err(LooperError::InvalidLayer(layer_id));
+                }
+
+                if let Ok(mut layer) = self.layers[layer_id].try_lock() {
+                    if layer.is_cueing {
+                        layer.stop_playing();
+                        self.send_event(AudioEvent::LayerStopped(layer_id));
+                    } else if layer.is_empty() {
+                        return Err(LooperError::EmptyLayer(layer_id));
+                    } else if layer.is_playing || layer.is_recording {
+                        return Err(LooperError::LayerInMix(layer_id));
+                    } else {
+                        layer.start_cue();
+                        self.send_event(AudioEvent::LayerCued(layer_id));
+                    }
+                }
+            }
+            LayerCommand::ToggleLock(layer_id) => {
+                if layer_id >= self.config.max_layers {
+                    return Err(LooperError::InvalidLayer(layer_id));
+                }
+
+                if let Ok(mut layer) = self.layers[layer_id].try_lock() {
+                    layer.is_locked = !layer.is_locked;
+                    self.send_event(AudioEvent::LayerLockChanged(layer_id, layer.is_locked));
+                }
+            }
+            LayerCommand::SetClickBus(bus) => {
+                if let Ok(mut click_bus) = self.click_bus.try_lock() {
+                    *click_bus = bus;
+                }
+                self.send_event(AudioEvent::ClickBusChanged(bus));
+            }
+            LayerCommand::SetClick(settings) => {
+                let settings = ClickSettings {
+                    volume: settings.volume.clamp(0.0, 1.0),
+                    pan: settings.pan.clamp(-1.0, 1.0),
+                    record_only: settings.record_only,
+                };
+                if let Ok(mut click) = self.click.try_lock() {
+                    *click = settings;
+                }
+                self.send_event(AudioEvent::ClickChanged(settings));
+            }
+            LayerCommand::SetClickSound(sound) => {
+                let metronome_sample = Arc::clone(&self.metronome_sample);
+                let sample_rate = self.config.sample_rate;
+                let event_sender = Arc::clone(&self.event_sender);
+
+                // WAV clicks are read from disk
+                std::thread::spawn(move || match sound.render(sample_rate) {
+                    Ok(samples) => *metronome_sample.lock().unwrap() = samples,
+                    Err(e) => {
+                        let path = String::from(sound);
+                        if let Ok(sender) = event_sender.try_lock()
+                            && let Some(ref tx) = *sender
+                        {
+                            let _ = tx.try_send(AudioEvent::Error(LooperError::io(
+                                "load metronome sound",
+                                path,
+                                &e,
+                            )));
+                        }
+                    }
+                });
+            }
+            LayerCommand::SetMarker { layer_id, name } => {
+                if layer_id >= self.config.max_layers {
+                    return Err(LooperError::InvalidLayer(layer_id));
+                }
+
+                if let Ok(mut layer) = self.layers[layer_id].try_lock() {
+                    if layer.is_empty() {
+                        return Err(LooperError::EmptyLayer(layer_id));
+                    }
+                    let position = layer.playback_position;
+                    layer.set_marker(&name, position);
+                    layer.checkpoint();
+                    let position = layer.marker_position(&name).unwrap_or(position);
+                    self.send_event(AudioEvent::MarkerSet {
+                        layer_id,
+                        name,
+                        position,
+                    });
+                }
+            }
+            LayerCommand::RemoveMarker { layer_id, name } => {
+                if layer_id >= self.config.max_layers {
+                    return Err(LooperError::InvalidLayer(layer_id));
+                }
+
+                if let Ok(mut layer) = self.layers[layer_id].try_lock()
+                    && layer.remove_marker(&name)
+                {
+                    layer.checkpoint();
+                    self.send_event(AudioEvent::MarkerRemoved { layer_id, name });
+                }
+            }
+            LayerCommand::JumpToMarker { layer_id, name } => {
+                if layer_id >= self.config.max_layers {
+                    return Err(LooperError::InvalidLayer(layer_id));
+                }
+
+                let Some(position) = self
+                    .layers
+                    .get(layer_id)
+                    .and_then(|layer| layer.try_lock().ok())
+                    .and_then(|layer| layer.marker_position(&name))
+                else {
+                    return Err(LooperError::MarkerNotFound { layer_id, name });
+                };
+
+                let sync = self
+                    .beat_sync_enabled
+                    .try_lock()
+                    .map(|b| *b)
+                    .unwrap_or(true);
+                if sync {
+                    if let Ok(mut v) = self.pending_jumps.try_lock() {
+                        // Replace an earlier jump for the same layer
+                        v.retain(|(id, _)| *id != layer_id);
+                        if v.len() < v.capacity() {
+                            v.push((layer_id, position));
+                        }
+                    }
+                } else if let Ok(mut layer) = self.layers[layer_id].try_lock() {
+                    layer.jump_to(position);
+                    self.send_event(AudioEvent::MarkerJumped { layer_id, position });
+                }
+            }
+            LayerCommand::Scheduled { at, cmd } => {
+                let samples_per_beat = self
+                    .tempo
+                    .try_lock()
+                    .map(|t| t.samples_per_beat)
+                    .map_err(|_| LooperError::Busy("Tempo"))?;
+                let mut scheduler = self
+                    .scheduler
+                    .try_lock()
+                    .map_err(|_| LooperError::Busy("Scheduler"))?;
+                if scheduler
+                    .schedule(at.to_sample(samples_per_beat), *cmd)
+                    .is_err()
+                {
+                    return Err(LooperError::BufferFull("Schedule queue"));
+                }
+            }
+            LayerCommand::Tracked { id, cmd } => {
+                // The result goes into the ack, so it isn't reported twice
+                let result = self.send_command(*cmd);
+                self.send_event(AudioEvent::CommandAck { id, result });
+            }
+            LayerCommand::SetMetadata(metadata) => {
+                if let Ok(mut current) = self.metadata.try_lock() {
+                    *current = metadata.clone();
+                    self.send_event(AudioEvent::MetadataChanged(metadata));
+                }
+            }
+            LayerCommand::SaveSession(file_path) => {
+                self.save_session(file_path, false);
+            }
+            LayerCommand::SaveTemplate(file_path) => {
+                self.save_session(file_path, true);
+            }
+            LayerCommand::SetSaveHistory(enabled) => {
+                if let Ok(mut save_history) = self.save_history.try_lock() {
+                    *save_history = enabled;
+                }
+            }
+            LayerCommand::SetIdleParking(enabled) => {
+                self.set_idle_parking(enabled);
+            }
+            LayerCommand::SetUndoCompression(enabled) => {
+                self.set_undo_compression(enabled);
+            }
+            LayerCommand::LoadSession(file_path) => {
+                // File I/O and resampling on a separate thread
+                let layers = Arc::clone(&self.layers);
+                let tempo = Arc::clone(&self.tempo);
+                let master_loop_length = Arc::clone(&self.master_loop_length);
+                let transaction_log = Arc::clone(&self.transaction_log);
+                let metadata = Arc::clone(&self.metadata);
+                let metronome_enabled = Arc::clone(&self.metronome_enabled);
+                let click_bus = Arc::clone(&self.click_bus);
+                let click = Arc::clone(&self.click);
+                let sample_rate = self.config.sample_rate;
+                let event_sender = Arc::clone(&self.event_sender);
+
+                std::thread::spawn(move || {
+                    let send = |event: AudioEvent| {
+                        if let Ok(sender) = event_sender.try_lock()
+                            && let Some(ref tx) = *sender
+                        {
+                            let _ = tx.try_send(event);
+                        }
+                    };
+
+                    let session = match super::Session::load(&file_path) {
+                        Ok(session) => session,
+                        Err(e) => {
+                            send(AudioEvent::Error(LooperError::io(
+                                "load session",
+                                file_path,
+                                &e,
+                            )));
+                            return;
+                        }
+                    };
+
+                    // Decode all audio before touching the layers; files shared by
+                    // saved versions are read once
+                    let mut cache = Vec::new();
+                    let mut decoded = Vec::with_capacity(session.layers.len());
+                    let mut histories = Vec::with_capacity(session.layers.len());
+                    for state in &session.layers {
+                        let samples = match &state.audio_file {
+                            Some(file) => match Self::load_session_audio(
+                                &file_path,
+                                file,
+                                sample_rate,
+                                &mut cache,
+                            ) {
+                                Ok(samples) => samples.as_ref().clone(),
+                                Err(e) => {
+                                    send(AudioEvent::Error(e));
+                                    return;
+                                }
+                            },
+                            None => Vec::new(),
+                        };
+                        decoded.push(samples);
+
+                        let history = match &state.history {
+                            Some(saved) => match Self::load_layer_history(
+                                &session,
+                                &file_path,
+                                saved,
+                                sample_rate,
+                                &mut cache,
+                            ) {
+                                Ok(history) => Some(history),
+                                Err(e) => {
+                                    send(AudioEvent::Error(e));
+                                    return;
+                                }
+                            },
+                            None => None,
+                        };
+                        histories.push(history);
+                    }
+
+                    for layer_arc in layers.iter() {
+                        if let Ok(mut layer) = layer_arc.lock() {
+                            layer.stop_recording();
+                            layer.clear();
+                        }
+                    }
+                    for ((state, samples), history) in
+                        session.layers.iter().zip(decoded).zip(histories)
+                    {
+                        let Some(layer_arc) = layers.get(state.id) else {
+                            continue;
+                        };
+                        if let Ok(mut layer) = layer_arc.lock() {
+                            layer.buffer = samples;
+                            layer.volume = state.volume;
+                            layer.is_muted = state.muted;
+                            layer.is_solo = state.solo;
+                            layer.output_bus = state.output_bus;
+                            layer.is_locked = state.locked;
+                            layer.is_solo_safe = state.solo_safe;
+                            layer.fit_length = None;
+                            let len = layer.get_buffer_length();
+                            let loop_end = session.rescale(state.loop_end, sample_rate).min(len);
+                            layer.loop_start =
+                                session.rescale(state.loop_start, sample_rate).min(loop_end);
+                            layer.loop_end = if loop_end == 0 { len } else { loop_end };
+                            layer.markers = state
+                                .markers
+                                .iter()
+                                .map(|m| super::Marker {
+                                    name: m.name.clone(),
+                                    position: session
+                                        .rescale(m.position, sample_rate)
+                                        .min(len.saturating_sub(1)),
+                                })
+                                .collect();
+                            layer.playback_position = layer.loop_start;
+                            if !layer.is_empty() {
+                                layer.start_playing();
+                            }
+                            if let (Some(saved), Some((undo, takes))) = (&state.history, history) {
+                                layer.undo_history.restore(undo, saved.undo_position);
+                                layer.takes.restore(takes, saved.current_take);
+                            }
+                            layer.checkpoint();
+                        }
+                    }
+
+                    if let Ok(mut master_len) = master_loop_length.lock() {
+                        *master_len = layers
+                            .iter()
+                            .filter_map(|layer| layer.lock().ok().map(|l| l.get_buffer_length()))
+                            .find(|&len| len > 0);
+                        send(AudioEvent::MasterLoopChanged(*master_len));
+
+                        // Fitting is re-derived against the restored master
+                        for state in session.layers.iter().filter(|s| s.fit_to_master) {
+                            if let Some(master) = *master_len
+                                && let Some(Ok(mut layer)) = layers.get(state.id).map(|l| l.lock())
+                            {
+                                layer.fit_length = layer.fit_target(master);
+                            }
+                        }
+                    }
+                    if let Ok(mut log) = transaction_log.lock() {
+                        Self::record_transactions(&layers, &mut log);
+                    }
+                    let (bpm, swing) = match tempo.lock() {
+                        Ok(mut t) => {
+                            t.set_bpm(session.bpm);
+                            t.set_swing(session.swing);
+                            (t.bpm, t.swing)
+                        }
+                        Err(_) => (session.bpm, session.swing),
+                    };
+
+                    *metadata.lock().unwrap() = session.metadata.clone();
+                    if let Some(state) = session.metronome {
+                        *metronome_enabled.lock().unwrap() = state.enabled;
+                        *click_bus.lock().unwrap() = state.bus;
+                        *click.lock().unwrap() = state.click;
+                        send(AudioEvent::MetronomeToggled(state.enabled));
+                        send(AudioEvent::ClickBusChanged(state.bus));
+                        send(AudioEvent::ClickChanged(state.click));
+                    }
+
+                    send(AudioEvent::BpmChanged(bpm));
+                    send(AudioEvent::SwingChanged(swing));
+                    send(AudioEvent::MetadataChanged(session.metadata.clone()));
+                    send(AudioEvent::AllCleared);
+                    for state in &session.layers {
+                        send(AudioEvent::LayerUpdated(state.id));
+                    }
+                    send(AudioEvent::SessionLoaded(file_path));
+                });
+            }
         }
         Ok(())
     }
 
+    /// Write a session, or a template without audio, on a separate thread
+    fn save_session(&self, file_path: String, template: bool) {
+        let layers = Arc::clone(&self.layers);
+        let tempo = Arc::clone(&self.tempo);
+        let metadata = Arc::clone(&self.metadata);
+        let metronome = MetronomeState {
+            enabled: self
+                .metronome_enabled
+                .try_lock()
+                .map(|m| *m)
+                .unwrap_or(false),
+            bus: self.click_bus.try_lock().map(|b| *b).unwrap_or_default(),
+            click: self.click.try_lock().map(|c| *c).unwrap_or_default(),
+        };
+        let sample_rate = self.config.sample_rate;
+        let content = if template {
+            SessionContent::Template
+        } else {
+            SessionContent::Audio {
+                history: self.save_history.try_lock().is_ok_and(|s| *s),
+            }
+        };
+        let event_sender = Arc::clone(&self.event_sender);
+
+        std::thread::spawn(move || {
+            let metadata = metadata.lock().unwrap().clone();
+            let event = match Self::write_session(
+                &file_path,
+                &layers,
+                &tempo,
+                metadata,
+                metronome,
+                sample_rate,
+                content,
+            ) {
+                Ok(_) if template => AudioEvent::TemplateSaved(file_path),
+                Ok(history_bytes) if history_bytes > Self::HISTORY_WARNING_BYTES => {
+                    AudioEvent::LargeSessionHistory(file_path, history_bytes)
+                }
+                Ok(_) => AudioEvent::SessionSaved(file_path),
+                Err(e) => AudioEvent::Error(LooperError::io(
+                    if template {
+                        "save template"
+                    } else {
+                        "save session"
+                    },
+                    file_path,
+                    &e,
+                )),
+            };
+            if let Ok(sender) = event_sender.try_lock()
+                && let Some(ref tx) = *sender
+            {
+                let _ = tx.try_send(event);
+            }
+        });
+    }
+
+    fn write_session(
+        file_path: &str,
+        layers: &[Arc<Mutex<AudioLayer>>],
+        tempo: &Mutex<TempoEngine>,
+        metadata: SessionMetadata,
+        metronome: MetronomeState,
+        sample_rate: u32,
+        content: SessionContent,
+    ) -> anyhow::Result<usize> {
+        let (bpm, beats_per_measure, swing) = {
+            let t = tempo.lock().unwrap();
+            (t.bpm, t.beats_per_measure, t.swing)
+        };
+        let mut session = super::Session::new(sample_rate, bpm, beats_per_measure);
+        let mut history_bytes = 0;
+        session.swing = swing;
+        session.metadata = metadata;
+        session.metronome = Some(metronome);
+
+        for layer_arc in layers.iter() {
+            // Copied out so the layer isn't held while the files are written
+            let layer = layer_arc.lock().unwrap();
+            if content == SessionContent::Template {
+                // Every layer's settings, but nothing tied to recorded audio
+                session.layers.push(super::LayerState {
+                    id: layer.id,
+                    volume: layer.volume,
+                    muted: layer.is_muted,
+                    solo: layer.is_solo,
+                    loop_start: 0,
+                    loop_end: 0,
+                    audio_file: None,
+                    markers: Vec::new(),
+                    output_bus: layer.output_bus,
+                    locked: layer.is_locked,
+                    solo_safe: layer.is_solo_safe,
+                    fit_to_master: layer.fit_length.is_some(),
+                    history: None,
+                });
+                continue;
+            }
+            if layer.is_empty() && layer.markers.is_empty() {
+                continue;
+            }
+            let audio = (!layer.is_empty()).then(|| layer.audio().to_vec());
+            let versions = (content == (SessionContent::Audio { history: true }))
+                .then(|| LayerVersions::of(&layer));
+            let mut state = super::LayerState {
+                id: layer.id,
+                volume: layer.volume,
+                muted: layer.is_muted,
+                solo: layer.is_solo,
+                loop_start: layer.loop_start,
+                loop_end: layer.loop_end,
+                audio_file: None,
+                markers: layer.markers.clone(),
+                output_bus: layer.output_bus,
+                locked: layer.is_locked,
+                solo_safe: layer.is_solo_safe,
+                fit_to_master: layer.fit_length.is_some(),
+                history: None,
+            };
+            drop(layer);
+
+            let audio = audio.unwrap_or_default();
+            if !audio.is_empty() {
+                let name = super::Session::layer_audio_name(file_path, state.id);
+                super::io::export_wav(
+                    super::Session::resolve(file_path, &name),
+                    &audio,
+                    sample_rate,
+                    BitDepth::Float32,
+                )?;
+                state.audio_file = Some(name);
+            }
+            if let Some(versions) = versions {
+                let (history, bytes) = Self::write_layer_history(
+                    file_path,
+                    state.id,
+                    &audio,
+                    versions,
+                    state.audio_file.as_deref(),
+                    sample_rate,
+                )?;
+                history_bytes += bytes;
+                state.history = Some(history);
+            }
+            session.layers.push(state);
+        }
+
+        session.save(file_path)?;
+        Ok(history_bytes)
+    }
+
+    /// Write a layer's undo states and takes next to the session, each distinct
+    /// buffer once (the layer's own audio reuses `audio_file`)
+    /// Returns the history and the bytes of audio it added
+    fn write_layer_history(
+        file_path: &str,
+        layer_id: usize,
+        audio: &[f32],
+        versions: LayerVersions,
+        audio_file: Option<&str>,
+        sample_rate: u32,
+    ) -> anyhow::Result<(LayerHistory, usize)> {
+        let mut written: Vec<(Arc<Vec<f32>>, String)> = Vec::new();
+        let mut bytes = 0;
+        let mut version = |snapshot: &LayerSnapshot| -> anyhow::Result<LayerVersion> {
+            let audio_file = if snapshot.buffer.is_empty() {
+                None
+            } else if let Some((_, name)) = written
+                .iter()
+                .find(|(buffer, _)| Arc::ptr_eq(buffer, &snapshot.buffer))
+            {
+                Some(name.clone())
+            } else if audio_file.is_some() && snapshot.buffer.as_slice() == audio {
+                audio_file.map(str::to_string)
+            } else {
+                let name = super::Session::version_audio_name(file_path, layer_id, written.len());
+                super::io::export_wav(
+                    super::Session::resolve(file_path, &name),
+                    &snapshot.buffer,
+                    sample_rate,
+                    BitDepth::Float32,
+                )?;
+                bytes += snapshot.buffer.len() * std::mem::size_of::<f32>();
+                written.push((Arc::clone(&snapshot.buffer), name.clone()));
+                Some(name)
+            };
+            Ok(LayerVersion {
+                audio_file,
+                volume: snapshot.volume,
+                muted: snapshot.is_muted,
+                solo: snapshot.is_solo,
+                loop_start: snapshot.loop_start,
+                loop_end: snapshot.loop_end,
+                markers: snapshot.markers.clone(),
+            })
+        };
+
+        let undo = versions
+            .undo
+            .iter()
+            .map(&mut version)
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let takes = versions
+            .takes
+            .iter()
+            .map(&mut version)
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let history = LayerHistory {
+            undo,
+            undo_position: versions.undo_position,
+            takes,
+            current_take: versions.current_take,
+        };
+        Ok((history, bytes))
+    }
+
+    /// Import cap for the next decode; the default if the setting is being changed
+    fn current_import_limit(&self) -> Option<u32> {
+        self.import_limit
+            .try_lock()
+            .map(|limit| *limit)
+            .unwrap_or(Some(DEFAULT_MAX_IMPORT_SECONDS))
+    }
+
+    /// Read a session WAV at the engine rate; files already read are shared
+    /// Not capped: sessions hold the engine's own recordings, however long
+    fn load_session_audio(
+        file_path: &str,
+        file: &str,
+        sample_rate: u32,
+        cache: &mut Vec<(String, Arc<Vec<f32>>)>,
+    ) -> Result<Arc<Vec<f32>>, LooperError> {
+        if let Some((_, samples)) = cache.iter().find(|(name, _)| name == file) {
+            return Ok(Arc::clone(samples));
+        }
+        let path = super::Session::resolve(file_path, file);
+        let samples = super::io::import_wav_capped(&path, sample_rate, None)
+            .map_err(|e| LooperError::io("load session audio", path.display().to_string(), &e))?;
+        let samples = Arc::new(samples);
+        cache.push((file.to_string(), Arc::clone(&samples)));
+        Ok(samples)
+    }
+
+    /// Saved undo states and takes as snapshots at the engine rate
+    fn load_layer_history(
+        session: &super::Session,
+        file_path: &str,
+        saved: &LayerHistory,
+        sample_rate: u32,
+        cache: &mut Vec<(String, Arc<Vec<f32>>)>,
+    ) -> Result<(Vec<LayerSnapshot>, Vec<LayerSnapshot>), LooperError> {
+        let mut restore = |version: &LayerVersion| -> Result<LayerSnapshot, LooperError> {
+            let buffer = match &version.audio_file {
+                Some(file) => Self::load_session_audio(file_path, file, sample_rate, cache)?,
+                None => Arc::new(Vec::new()),
+            };
+            let len = buffer.len();
+            let loop_end = session.rescale(version.loop_end, sample_rate).min(len);
+            let loop_start = session
+                .rescale(version.loop_start, sample_rate)
+                .min(loop_end);
+            Ok(LayerSnapshot {
+                buffer,
+                volume: version.volume,
+                loop_start,
+                loop_end: if loop_end == 0 { len } else { loop_end },
+                playback_position: loop_start,
+                is_muted: version.muted,
+                is_solo: version.solo,
+                markers: version
+                    .markers
+                    .iter()
+                    .map(|m| super::Marker {
+                        name: m.name.clone(),
+                        position: session
+                            .rescale(m.position, sample_rate)
+                            .min(len.saturating_sub(1)),
+                    })
+                    .collect(),
+            })
+        };
+        let undo = saved
+            .undo
+            .iter()
+            .map(&mut restore)
+            .collect::<Result<Vec<_>, _>>()?;
+        let takes = saved
+            .takes
+            .iter()
+            .map(&mut restore)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok((undo, takes))
+    }
+
+    /// The main bus as the live mixer plays it (volume, mute, solo, routing and
+    /// fit-to-master included), with every layer starting from its loop start
+    /// `region` is (start, length) in samples; None renders the longest loop once
+    fn render_mix(
+        layers: &[Arc<Mutex<AudioLayer>>],
+        region: Option<(usize, usize)>,
+    ) -> anyhow::Result<Vec<f32>> {
+        let mut copies: Vec<Arc<Mutex<AudioLayer>>> = Vec::new();
+        for layer_arc in layers {
+            let Ok(layer) = layer_arc.lock() else {
+                continue;
+            };
+            // Checked under the guard the copy is taken from, so a take that
+            // started after the command was accepted isn't half-rendered
+            Self::check_not_capturing(&layer, layer.id, "export")?;
+            if !layer.is_empty() {
+                copies.push(Arc::new(Mutex::new(layer.playback_copy())));
+            }
+        }
+        if copies.is_empty() {
+            anyhow::bail!("All layers are empty");
+        }
+        let (start, length) = region.unwrap_or_else(|| {
+            let longest = copies
+                .iter()
+                .map(|layer| {
+                    let layer = layer.lock().unwrap();
+                    layer.fit_length.unwrap_or(layer.get_loop_length())
+                })
+                .max()
+                .unwrap_or(0);
+            (0, longest)
+        });
+        let mut mixer = SimdMixer::new(Self::RENDER_BLOCK);
+        Ok(mixer.render(&copies, start, length, Some(OutputBus::Main)))
+    }
+
+    /// Each layer's loop region, exactly, as `<session>_layer-N_<bpm>bpm.wav` carrying
+    /// the smpl loop and ACID tempo; returns how many files were written
+    fn write_loop_pack(
+        directory: &str,
+        layers: &[Arc<Mutex<AudioLayer>>],
+        name_tokens: &NameTokens,
+        sample_rate: u32,
+        bit_depth: BitDepth,
+        tempo: LoopTempo,
+    ) -> anyhow::Result<usize> {
+        std::fs::create_dir_all(directory)?;
+        let mut files = 0;
+        for layer_arc in layers.iter() {
+            // Copied out so the layer isn't held while the file is written
+            let (layer_id, audio) = {
+                let layer = layer_arc.lock().unwrap();
+                Self::check_not_capturing(&layer, layer.id, "export")?;
+                if layer.is_empty() {
+                    continue;
+                }
+                let end = layer.loop_end.clamp(1, layer.get_buffer_length());
+                let audio = layer.audio()[layer.loop_start.min(end - 1)..end].to_vec();
+                (layer.id, audio)
+            };
+            let template = std::path::Path::new(directory)
+                .join(format!("{{session}}_layer-{}_{{bpm}}bpm.wav", layer_id + 1));
+            let path = expand_file_name(&template.to_string_lossy(), name_tokens);
+            super::io::export_wav(&path, &audio, sample_rate, bit_depth)?;
+            super::io::write_loop_chunks(&path, audio.len(), sample_rate, tempo)?;
+            files += 1;
+        }
+        if files == 0 {
+            anyhow::bail!("All layers are empty");
+        }
+        Ok(files)
+    }
+
+    /// Write each layer's loop region as a stem plus a Reaper project (background thread)
+    fn write_daw_project(
+        file_path: &str,
+        layers: &[Arc<Mutex<AudioLayer>>],
+        tempo: &Mutex<TempoEngine>,
+        sample_rate: u32,
+        bit_depth: BitDepth,
+    ) -> anyhow::Result<()> {
+        let (bpm, beats_per_measure) = {
+            let t = tempo.lock().unwrap();
+            (t.bpm, t.beats_per_measure)
+        };
+        let mut project = super::DawProject::new(sample_rate, bpm, beats_per_measure);
+
+        for layer_arc in layers.iter() {
+            // Copied out so the layer isn't held while the stem is written
+            let (stem, track) = {
+                let layer = layer_arc.lock().unwrap();
+                Self::check_not_capturing(&layer, layer.id, "export")?;
+                if layer.is_empty() {
+                    continue;
+                }
+                let end = layer.loop_end.clamp(1, layer.get_buffer_length());
+                let stem = layer.audio()[layer.loop_start.min(end - 1)..end].to_vec();
+                let track = super::DawTrack {
+                    name: format!("Layer {}", layer.id + 1),
+                    file: super::Session::layer_audio_name(file_path, layer.id),
+                    length: stem.len(),
+                    volume: layer.volume,
+                    muted: layer.is_muted,
+                    solo: layer.is_solo,
+                };
+                (stem, track)
+            };
+            super::io::export_wav(
+                super::Session::resolve(file_path, &track.file),
+                &stem,
+                sample_rate,
+                bit_depth,
+            )?;
+            project.tracks.push(track);
+        }
+
+        if project.tracks.is_empty() {
+            anyhow::bail!("All layers are empty");
+        }
+        project.save(file_path)
+    }
+
+    /// Render the whole mix as `ExportWav` does and write the WAV to `sink` (a pipe,
+    /// a socket, standard output) on the calling thread instead of to a file
+    pub fn write_mix<W: Write>(
+        &self,
+        sink: W,
+        normalization: ExportNormalization,
+    ) -> Result<(), LooperError> {
+        self.ensure_none_capturing("export")?;
+        Self::render_mix(&self.layers, None)
+            .and_then(|mix| {
+                super::io::export_normalized_wav_to(
+                    sink,
+                    mix,
+                    self.config.sample_rate,
+                    normalization,
+                    self.export_bit_depth(),
+                    self.loop_tempo(),
+                )
+            })
+            .map_err(|e| LooperError::io("export WAV", "stream", &e))
+    }
+
+    /// `ExportWav` on the calling thread: WAV or FLAC by extension, `-` for stdout;
+    /// returns the path written, name tokens expanded
+    pub fn export_mix(
+        &self,
+        file_path: &str,
+        normalization: ExportNormalization,
+    ) -> Result<String, LooperError> {
+        self.ensure_none_capturing("export")?;
+        let template = file_path;
+        let file_path = expand_file_name(template, &self.name_tokens());
+        Self::render_mix(&self.layers, None)
+            .and_then(|mix| {
+                Self::write_export(
+                    &file_path,
+                    mix,
+                    self.config.sample_rate,
+                    normalization,
+                    self.export_bit_depth(),
+                    self.loop_tempo(),
+                )
+            })
+            .map_err(|e| {
+                release_file_name(template, &file_path);
+                LooperError::io("export", file_path.clone(), &e)
+            })?;
+        Ok(file_path)
+    }
+
     pub fn get_layer(&self, id: usize) -> Option<Arc<Mutex<AudioLayer>>> {
         if id < self.layers.len() {
             Some(Arc::clone(&self.layers[id]))
@@ -855,10 +4361,23 @@ impl LooperEngine {
         Arc::clone(&self.layers)
     }
 
+    /// Layer state for display; reading it never locks the layers
+    pub fn get_layer_status(&self) -> Arc<Vec<LayerStatus>> {
+        Arc::clone(&self.layer_status)
+    }
+
+    pub fn get_metadata(&self) -> SessionMetadata {
+        self.metadata.lock().unwrap().clone()
+    }
+
     pub fn get_master_loop_length(&self) -> Option<usize> {
         *self.master_loop_length.lock().unwrap()
     }
 
+    pub fn get_bpm(&self) -> f64 {
+        self.tempo.lock().unwrap().bpm
+    }
+
     pub fn is_recording(&self) -> bool {
         *self.is_recording.lock().unwrap()
     }
@@ -867,11 +4386,32 @@ impl LooperEngine {
         *self.recording_layer.lock().unwrap()
     }
 
+    /// Smoothed DSP load (1.0 = processing took the whole buffer duration)
+    pub fn get_dsp_load(&self) -> f32 {
+        self.load_monitor.lock().unwrap().load()
+    }
+
+    pub fn is_quality_degraded(&self) -> bool {
+        self.load_monitor.lock().unwrap().is_degraded()
+    }
+
     pub fn get_config(&self) -> &AudioConfig {
         &self.config
     }
 
+    /// Take input blocks of up to `frames` samples whole; call before the
+    /// streams delivering them start (buffers are reallocated here, never on
+    /// the audio thread)
+    pub fn reserve_input(&self, frames: usize) {
+        self.input_buffer.reserve(frames * 4);
+        let mut scratch = self.recording_scratch.lock().unwrap();
+        if scratch.len() < frames {
+            scratch.resize(frames, 0.0);
+        }
+    }
+
     pub fn store_input_samples(&self, samples: &[f32]) {
+        self.input_meter.update(samples);
         self.input_buffer.try_write(samples);
     }
 
@@ -885,20 +4425,23 @@ impl LooperEngine {
         &self,
         layer_id: usize,
         samples: Vec<f32>,
-    ) -> Result<(), Box<dyn std::error::Error>> {
+    ) -> Result<(), LooperError> {
         if layer_id >= self.config.max_layers {
-            return Err("Layer ID out of range".into());
+            return Err(LooperError::InvalidLayer(layer_id));
         }
 
         if let Ok(mut layer) = self.layers[layer_id].lock() {
-            layer.buffer = samples;
-            layer.loop_end = layer.buffer.len();
+            if layer.is_locked {
+                return Err(LooperError::LayerLocked(layer_id));
+            }
+            layer.replace_buffer(samples);
 
             // Set as master if it's the first layer with content
             {
                 let mut master_len = self.master_loop_length.lock().unwrap();
-                if master_len.is_none() && !layer.buffer.is_empty() {
-                    *master_len = Some(layer.buffer.len());
+                if master_len.is_none() && !layer.is_empty() {
+                    *master_len = Some(layer.get_buffer_length());
+                    self.send_event(AudioEvent::MasterLoopChanged(*master_len));
                 }
             }
         }
@@ -1,22 +1,82 @@
+pub mod analysis;
+pub mod biquad;
+pub mod block_adapter;
+pub mod channels;
+pub mod click;
+pub mod daw_project;
+pub mod declick;
+pub mod denoise;
+pub mod drift;
+pub mod error;
+pub mod event_log;
+pub mod export_hook;
+pub mod flac;
+pub mod input_capture;
 pub mod io;
+pub mod latency;
 pub mod layer;
+pub mod layer_phase;
+pub mod layer_stats;
+pub mod layer_status;
+pub mod load_monitor;
 pub mod lockfree_buffer;
+pub mod loop_ratio;
 pub mod looper;
 pub mod peak_meter;
+pub mod rate_converter;
+pub mod routing;
+pub mod scheduler;
+pub mod session;
 pub mod simd_mixer;
 pub mod stream;
+pub mod take_lanes;
 pub mod tempo;
+pub mod transaction_log;
 pub mod undo_history;
 
-pub use io::{export_wav, import_wav};
-pub use layer::AudioLayer;
+pub use analysis::{
+    ANALYSIS_BANDS, AnalysisFrame, BAND_CENTRES, ImportIssue, MixAnalyzer, check_import, dc_offset,
+    integrated_loudness, true_peak,
+};
+pub use block_adapter::BlockAdapter;
+pub use channels::{
+    ChannelStats, CommandSender, EventReceiver, EventSender, command_channel, event_channel,
+};
+pub use click::{ClickFlavor, ClickSound};
+pub use daw_project::{DawProject, DawTrack};
+pub use declick::declick;
+pub use denoise::NoiseProfile;
+pub use drift::DriftEstimator;
+pub use error::LooperError;
+pub use event_log::EventLog;
+pub use export_hook::ExportHook;
+pub use input_capture::InputCapture;
+pub use io::{BitDepth, LoopTempo, WavLoopInfo, export_wav, import_wav, read_loop_info};
+pub use latency::{BufferTrial, CallbackTimer, DeviceLatency};
+pub use layer::{AudioLayer, Marker};
+pub use layer_phase::LayerPhase;
+pub use layer_stats::LayerStats;
+pub use layer_status::{LayerStatus, LayerStatusSnapshot, QueuedActions};
+pub use load_monitor::{DspLoadMonitor, QualityChange};
 pub use lockfree_buffer::{AudioBufferPair, LockFreeAudioBuffer, SharedLockFreeBuffer};
+pub use loop_ratio::{LoopFix, LoopRatio};
 pub use looper::LooperEngine;
 pub use peak_meter::{MeterColor, PeakMeter};
+pub use rate_converter::RateConverter;
+pub use routing::{ClickSettings, MAX_STEREO_WIDTH, OutputBus, OutputRouting, stereo_width};
+pub use scheduler::{CommandScheduler, TransportTime};
+pub use session::{
+    LayerHistory, LayerState, LayerVersion, MetronomeState, Session, SessionMetadata,
+};
 pub use simd_mixer::{ScalarMixer, SimdMixer};
-pub use stream::AudioStream;
-pub use tempo::TempoEngine;
-pub use undo_history::{LayerSnapshot, UndoHistory};
+pub use stream::{AudioStream, LooperStreams};
+pub use take_lanes::TakeLanes;
+pub use tempo::{TempoEngine, TransportPosition, TransportStamp};
+pub use transaction_log::{Transaction, TransactionLog};
+pub use undo_history::{LayerSnapshot, QuantizedBuffer, UndoHistory};
+
+/// Layers a running instance has; control lines can't address more
+pub const MAX_LAYERS: usize = 16;
 
 #[derive(Debug, Clone)]
 pub struct AudioConfig {
@@ -30,68 +90,345 @@ impl Default for AudioConfig {
         Self {
             sample_rate: 44100,
             buffer_size: 512,
-            max_layers: 16,
+            max_layers: MAX_LAYERS,
+        }
+    }
+}
+
+/// Where imported audio goes in the target layer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ImportMode {
+    #[default]
+    Replace, // replace the whole buffer
+    Append,           // add after the end of the existing buffer
+    InsertAtPlayhead, // splice in at the current playback position
+}
+
+/// Level an exported mix is scaled to
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ExportNormalization {
+    #[default]
+    Peak, // loudest sample at 0.95
+    Loudness(f32), // integrated LUFS; the true peak is still kept at or below -1 dBTP
+    TruePeak(f32), // dBTP
+}
+
+impl std::fmt::Display for ExportNormalization {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Peak => write!(f, "peak"),
+            Self::Loudness(lufs) => write!(f, "{} LUFS", lufs),
+            Self::TruePeak(dbtp) => write!(f, "{} dBTP", dbtp),
+        }
+    }
+}
+
+/// Parses what the export prompt accepts: `peak`, `-14 LUFS` (or just `-14`) and `-1 dBTP`
+impl std::str::FromStr for ExportNormalization {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let text = text.trim().to_lowercase();
+        if text == "peak" {
+            return Ok(Self::Peak);
+        }
+        let (value, unit) = match text.find(|c: char| c.is_ascii_alphabetic()) {
+            Some(i) => (text[..i].trim(), text[i..].trim()),
+            None => (text.as_str(), "lufs"),
+        };
+        let value: f32 = value
+            .parse()
+            .map_err(|_| format!("Expected peak, -14 LUFS or -1 dBTP, got {}", text))?;
+        match unit {
+            "lufs" | "lu" if (-60.0..=0.0).contains(&value) => Ok(Self::Loudness(value)),
+            "dbtp" | "db" if (-20.0..=0.0).contains(&value) => Ok(Self::TruePeak(value)),
+            "lufs" | "lu" => Err("Loudness target must be between -60 and 0 LUFS".to_string()),
+            "dbtp" | "db" => Err("True peak target must be between -20 and 0 dBTP".to_string()),
+            _ => Err(format!("Unknown unit {} (use LUFS or dBTP)", unit)),
+        }
+    }
+}
+
+/// Span of the loop mix written by `LayerCommand::ExportLoop`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportRegion {
+    Cycles(u32),                    // whole passes of the master loop
+    Bars { first: u32, last: u32 }, // 1-based and inclusive, at the current tempo
+}
+
+impl std::fmt::Display for ExportRegion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Cycles(1) => write!(f, "1 cycle"),
+            Self::Cycles(cycles) => write!(f, "{} cycles", cycles),
+            Self::Bars { first, last } => write!(f, "bars {}-{}", first, last),
         }
     }
 }
 
+/// Parses `4` / `4 cycles` and `bars 1-8` / `1-8`
+impl std::str::FromStr for ExportRegion {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let text = text.trim().to_lowercase();
+        let number = |word: &str| match word.trim().parse::<u32>() {
+            Ok(n) if n > 0 => Ok(n),
+            _ => Err(format!("Expected N cycles or bars A-B, got {}", text)),
+        };
+        if let Some((first, last)) = text.trim_start_matches("bars").split_once('-') {
+            let (first, last) = (number(first)?, number(last)?);
+            if first > last {
+                return Err(format!("Bar {} comes after bar {}", first, last));
+            }
+            return Ok(Self::Bars { first, last });
+        }
+        let cycles = text
+            .trim_end_matches("cycles")
+            .trim_end_matches("cycle")
+            .trim_end_matches('x');
+        Ok(Self::Cycles(number(cycles)?))
+    }
+}
+
+/// Boundary that mute/solo changes wait for while beat sync is on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MixQuantize {
+    #[default]
+    Off, // apply immediately
+    Beat,
+    Measure,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum LayerCommand {
     Record(usize),
-    StopRecording(usize),
+    StopRecording(usize), // also ends an overdub
+    Overdub(usize),       // layer input onto a playing loop
+    // Re-take: the old take plays on the cue bus until the new one is kept
+    RecordReplacement(usize), // StopRecording ends the capture
+    KeepReplacement(usize),   // swap the new take in (undoable)
+    RevertReplacement(usize), // drop the new take
+    // Take lanes: every record pass is kept as a take
+    SelectTake(usize, usize), // layer_id, take (from 0); undoable
+    KeepTake(usize),          // keep the take playing, drop the others
+    SelectLayer(usize),       // move the UI selection (control socket, layers 11-16)
     StopPlaying(usize),
     Play(usize),
     Mute(usize),
     Solo(usize),
+    ToggleSoloSafe(usize),    // keep playing while other layers are soloed
+    ToggleFitToMaster(usize), // varispeed so the loop lasts exactly a master loop ratio
+    FreezeLayer(usize),       // render the varispeed into the audio (saves CPU)
+    LearnNoise(usize),        // noise print from a layer holding room tone
+    Denoise(usize),           // gate the layer against the noise print
+    Declick(usize),           // interpolate over pops and single-sample spikes
+    RemoveDcOffset(usize),    // center the waveform on zero
+    AnalyzeLayer(usize),      // length, levels and estimated tempo for the info overlay
+    SetMixQuantize(MixQuantize),
+    SetLatencyCompensation(std::time::Duration), // shift new takes earlier (zero = off)
+    SetInputHighPass(Option<f32>), // cutoff in Hz (40-120) for recorded input; None = off
+    SetStereoWidth(f32),           // main bus mid/side width: 0.0 mono, 1.0 as mixed, up to 1.5
+    ResetPeaks,                    // peak hold and clip indicators of every layer and the input
+    SetMixAnalysis(bool),          // loudness / spectrum of the master mix for the analysis pane
     SetVolume(usize, f32),
+    FixLoopLength(usize, LoopFix), // after a LoopLengthMismatch warning
+    // Master loop length that new recordings are checked against
+    SetMasterLength(usize), // samples; 0 clears it
+    MasterFromLayer(usize),
+    ClearMasterLength, // the next finished recording sets it again
     StopAll,
     Clear(usize),
     ClearAll,
     PlayAll,
     Undo(usize),
     Redo(usize),
-    ImportWav(usize, String),   // layer_id, file_path
-    ExportWav(String),          // file_path
-    SwitchInputDevice(String),  // device_name
-    SwitchOutputDevice(String), // device_name
+    GlobalUndo, // undo the last action regardless of layer
+    GlobalRedo,
+    ImportWav(usize, String, ImportMode), // layer_id, file_path, mode
+    // Stretch imports carrying an ACID tempo to the session BPM (replace imports only)
+    SetImportTempoMatch(bool),
+    SetImportLimit(Option<u32>), // seconds; longer imports fail with LooperError::TooLarge
+    PreviewWav(String),          // file_path, played on the preview channel
+    StopPreview,
+    AuditionWav(usize, String), // layer_id, file_path: loop in sync before importing
+    ConfirmAudition(ImportMode),
+    CancelAudition,
+    ExportWav(String, ExportNormalization), // file_path
+    // Loops rendered to an exact length, so the file loops seamlessly
+    ExportLoop(String, ExportRegion, ExportNormalization), // file_path
+    SetExportBitDepth(BitDepth), // WAV exports and stems (sessions stay 32-bit float)
+    ExportProject(String),       // .rpp path; stems are written next to it
+    // Each layer's loop as its own tempo-tagged WAV, for samplers
+    ExportLoopPack(String),            // directory
+    SetExportHook(Option<ExportHook>), // run on each exported WAV in the background
+    SwitchInputDevice(String),         // device_name
+    SwitchOutputDevice(String),        // device_name
+    SwitchHost(String),                // audio backend name; devices revert to its defaults
+    SetBufferSize(Option<u32>),        // frames (None = device default); streams are rebuilt
     // Tempo / Sync controls
     TapTempo,
+    SetTapRounding(bool), // round tapped BPM to a whole number
     SetBpm(f64),
+    SetSwing(f64),        // percent, 50 = straight
+    PauseTransport(bool), // freeze the tempo clock (false resumes it)
+    ResetTransport,       // make now beat 1 of bar 1
     ToggleBeatSync(bool),
     ToggleCountInMode(bool),
-    StartCountIn { layer_id: usize, measures: u32 },
+    SetCountInMeasures(u32), // pre-roll length for synced recording
+    ToggleCountOut(bool),    // click for one bar after recording stops
+    SetPreCount(bool),       // click the beats before an armed synced record starts
+    StartCountIn {
+        layer_id: usize,
+        measures: u32,
+    },
     SyncPlay(usize),
     SyncStop(usize),
     SyncRecord(usize),
+    CancelPending(usize), // drop the layer's queued sync actions and count-in
     // Metronome
     ToggleMetronome(bool),
+    // Output buses (hardware channels per bus are set on AudioStream)
+    SetLayerBus(usize, OutputBus),
+    ToggleCue(usize), // pre-listen a stopped layer on the cue bus
+    // Locked layers refuse record, clear, import and volume commands
+    ToggleLock(usize),
+    SetClickBus(OutputBus),
+    SetClick(ClickSettings),   // volume, pan and record-only mode
+    SetClickSound(ClickSound), // rendered at the engine rate off the command path
+    // Markers (positions in the layer buffer)
+    SetMarker {
+        layer_id: usize,
+        name: String,
+    }, // at the current playback position
+    RemoveMarker {
+        layer_id: usize,
+        name: String,
+    },
+    JumpToMarker {
+        layer_id: usize,
+        name: String,
+    }, // quantized to the next beat when synced
+    // Session files
+    SaveSession(String),      // file_path
+    SaveTemplate(String),     // file_path; settings only, no audio
+    SetSaveHistory(bool),     // sessions also keep each layer's undo states and takes
+    SetIdleParking(bool),     // keep stopped layers 16-bit until played again
+    SetUndoCompression(bool), // store inactive undo states of long loops 16-bit (lossy)
+    LoadSession(String),      // file_path
+    SetMetadata(SessionMetadata),
+    // Run a command exactly at a transport position (sample-accurate)
+    Scheduled {
+        at: TransportTime,
+        cmd: Box<LayerCommand>,
+    },
+    // Run a command and answer with AudioEvent::CommandAck carrying the same id
+    Tracked {
+        id: u64,
+        cmd: Box<LayerCommand>,
+    },
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum AudioEvent {
-    LayerRecording(usize),
+    LayerRecording(usize, TransportStamp), // layer_id, when recording started
     LayerStopped(usize),
-    LayerPlaying(usize),
+    LayerPhaseChanged(usize, LayerPhase), // layer_id, new phase (after every transition)
+    LayerPlaying(usize, TransportStamp),  // layer_id, when playback started
     LayerMuted(usize),
     LayerUnmuted(usize),
     LayerSoloed(usize),
     LayerUnsoloed(usize),
+    LayerSoloSafeChanged(usize, bool),
+    LayerFitChanged(usize, bool),
+    MixQuantizeChanged(MixQuantize),
+    LatencyCompensationChanged(std::time::Duration),
+    InputHighPassChanged(Option<f32>), // Hz
+    StereoWidthChanged(f32),
+    PeaksReset,
     VolumeChanged(usize, f32),
     AllStopped,
-    LayerCleared(usize),
+    PendingCancelled(Option<usize>), // queued sync actions dropped (None = every layer)
+    LayerCleared(usize, usize),      // (layer_id, samples destroyed)
+    SilentInput(usize),              // capturing layer got a second of no signal
+    ReplacementRecording(usize),     // re-take started over the old one
+    ReplacementReady(usize),         // re-take stopped; waiting to be kept or reverted
+    ReplacementKept(usize),
+    ReplacementReverted(usize),
+    TakeSelected(usize, usize), // layer_id, take (from 0)
+    TakeKept(usize),
+    LayerSelected(usize),
     LayerUpdated(usize),
+    // Recorded layer isn't a whole-number ratio of the master loop
+    LoopLengthMismatch {
+        layer_id: usize,
+        ratio: LoopRatio, // nearest ratio
+        length: usize,
+        target: usize, // length at that ratio
+    },
+    LoopLengthFixed(usize, LoopFix),
+    LayerFrozen(usize),
+    NoiseLearned(usize), // layer the print came from
+    DenoiseProgress {
+        layer_id: usize,
+        percent: u8,
+    },
+    LayerDenoised(usize),
+    LayerDeclicked(usize, usize),      // layer_id, clicks repaired
+    DcOffsetRemoved(usize, f32),       // layer_id, offset taken out
+    ImportWarning(usize, ImportIssue), // layer_id; the file is loaded anyway
+    LayerStatsReady(usize, LayerStats),
+    MasterLoopChanged(Option<usize>), // samples; None until the next recording finishes
     AllCleared,
     AllPlaying,
-    WavImported(usize, String),                     // layer_id, file_path
-    WavExported(String),                            // file_path
-    Error(String),                                  // error message
+    WavImported(usize, String), // layer_id, file_path
+    // Loop points / tempo read from the imported file's smpl and acid chunks
+    ImportLoopInfo {
+        layer_id: usize,
+        info: WavLoopInfo,
+        stretched_to: Option<f64>, // session BPM the file was resampled to
+    },
+    WavExported(String), // file_path
+    UploadProgress {
+        file_path: String,
+        percent: u8,
+    },
+    UploadFinished(String),          // file_path; failures arrive as Error
+    ProjectExported(String),         // .rpp path
+    LoopPackExported(String, usize), // directory, files written
+    PreviewStarted(String),          // file_path
+    PreviewStopped,
+    AuditionStarted(usize, String), // layer_id, file_path
+    AuditionCancelled,
+    Error(LooperError),
+    CommandAck {
+        id: u64, // from LayerCommand::Tracked
+        result: Result<(), LooperError>,
+    },
     DevicesUpdated(Option<String>, Option<String>), // (input_name, output_name)
+    HostUpdated(String),                            // audio backend the streams run on
+    LatencyMeasured(std::time::Duration),           // device round trip, once per stream start
+    SampleRateConverted {
+        device_rate: u32, // input device's native rate
+        engine_rate: u32, // rate layers were recorded at
+    },
+    MixAnalysis(AnalysisFrame), // every 100 ms while analysis is on
+    MixAnalysisToggled(bool),
     DeviceSwitchRequested,
     DeviceSwitchComplete,
     DeviceSwitchFailed(String),
+    AudioEngineRestarted, // audio thread panicked; streams rebuilt, layers kept
     // Tempo / Sync updates
     BpmChanged(f64),
-    Beat(u32, usize), // (beat, measure)
+    TransportPaused(bool),
+    TransportReset,
+    TapStatus {
+        taps: usize,
+        locked: bool, // tap estimate has stabilized
+    },
+    TapRoundingToggled(bool),
+    SwingChanged(f64),
+    Beat(u32, usize, TransportStamp), // (beat, measure, beat boundary)
     CountInStarted {
         layer_id: usize,
         beats: u32,
@@ -104,6 +441,65 @@ pub enum AudioEvent {
         remaining_beats: u32,
     },
     CountInModeToggled(bool),
+    CountInMeasuresChanged(u32),
+    CountOutToggled(bool),
+    BeatSyncToggled(bool),
+    // Network jam with another instance
+    JamConnected(String), // peer address
+    JamDisconnected,
+    JamLoopReceived(usize), // layer the peer's loop was put on
     // Metronome
     MetronomeToggled(bool),
+    LayerBusChanged(usize, OutputBus),
+    LayerCued(usize), // pre-listen started (stopping it sends LayerStopped)
+    LayerLockChanged(usize, bool),
+    ClickBusChanged(OutputBus),
+    ClickChanged(ClickSettings),
+    // Adaptive quality
+    QualityDegraded(f32), // smoothed DSP load that triggered the switch
+    QualityRestored,
+    // Global undo (affected layer ids; empty if nothing to undo/redo)
+    GlobalUndoApplied(Vec<usize>),
+    GlobalRedoApplied(Vec<usize>),
+    // Markers
+    MarkerSet {
+        layer_id: usize,
+        name: String,
+        position: usize,
+    },
+    MarkerRemoved {
+        layer_id: usize,
+        name: String,
+    },
+    MarkerJumped {
+        layer_id: usize,
+        position: usize,
+    },
+    // Session files
+    SessionSaved(String),  // file_path
+    TemplateSaved(String), // file_path
+    // Saved with history past the size warning (file_path, bytes of history audio)
+    LargeSessionHistory(String, usize),
+    SessionLoaded(String), // file_path
+    MetadataChanged(SessionMetadata),
+    // Periodic display state for every layer (index = layer id)
+    LayerStatusBatch(Vec<LayerStatusSnapshot>),
+    InputLevel {
+        peak: f32,
+        peak_hold: f32,
+    }, // live input, sent with each status batch
+    TransportPosition(TransportPosition), // sent with each status batch
+    LayerMarkers(usize, Vec<Marker>),     // sent when a layer's markers change
+}
+
+impl AudioEvent {
+    /// Sent on a wall-clock timer; a missed one is replaced by the next
+    pub fn is_periodic(&self) -> bool {
+        matches!(
+            self,
+            AudioEvent::LayerStatusBatch(_)
+                | AudioEvent::InputLevel { .. }
+                | AudioEvent::TransportPosition(_)
+        )
+    }
 }
@@ -10,6 +10,7 @@ pub struct PeakMeter {
     peak_hold: AtomicU32,         // Peak hold value
     rms_level: AtomicU32,         // RMS level
     peak_hold_counter: AtomicU32, // Frames to hold peak
+    max_peak: AtomicU32,          // Highest peak since the last reset (clip indicator)
 }
 
 impl PeakMeter {
@@ -22,6 +23,7 @@ impl PeakMeter {
             peak_hold: AtomicU32::new(0),
             rms_level: AtomicU32::new(0),
             peak_hold_counter: AtomicU32::new(0),
+            max_peak: AtomicU32::new(0),
         }
     }
 
@@ -43,6 +45,10 @@ impl PeakMeter {
         };
         self.peak_level.store(new_peak.to_bits(), Ordering::Relaxed);
 
+        if peak > self.get_max_peak() {
+            self.max_peak.store(peak.to_bits(), Ordering::Relaxed);
+        }
+
         // Update peak hold
         let current_hold = f32::from_bits(self.peak_hold.load(Ordering::Relaxed));
         if peak > current_hold {
@@ -88,6 +94,18 @@ impl PeakMeter {
         f32::from_bits(self.rms_level.load(Ordering::Relaxed))
     }
 
+    /// Highest peak since the meter was last reset; doesn't decay
+    pub fn get_max_peak(&self) -> f32 {
+        f32::from_bits(self.max_peak.load(Ordering::Relaxed))
+    }
+
+    /// Clear the held peak and clip indicator, keeping the live level
+    pub fn reset_peaks(&self) {
+        self.peak_hold.store(0, Ordering::Relaxed);
+        self.peak_hold_counter.store(0, Ordering::Relaxed);
+        self.max_peak.store(0, Ordering::Relaxed);
+    }
+
     /// Convert linear level to dB
     pub fn to_db(level: f32) -> f32 {
         if level <= 0.0 {
@@ -116,6 +134,7 @@ impl PeakMeter {
         self.peak_hold.store(0, Ordering::Relaxed);
         self.rms_level.store(0, Ordering::Relaxed);
         self.peak_hold_counter.store(0, Ordering::Relaxed);
+        self.max_peak.store(0, Ordering::Relaxed);
     }
 }
 
@@ -132,6 +151,7 @@ impl Clone for PeakMeter {
             peak_hold: AtomicU32::new(self.peak_hold.load(Ordering::Relaxed)),
             rms_level: AtomicU32::new(self.rms_level.load(Ordering::Relaxed)),
             peak_hold_counter: AtomicU32::new(self.peak_hold_counter.load(Ordering::Relaxed)),
+            max_peak: AtomicU32::new(self.max_peak.load(Ordering::Relaxed)),
         }
     }
 }
@@ -179,6 +199,15 @@ mod tests {
 
         // Peak hold should still be high
         assert!(meter.get_peak_hold() >= 0.7);
+
+        // The clip indicator keeps the highest peak until reset
+        for _ in 0..100 {
+            meter.update(&lower);
+        }
+        assert_eq!(meter.get_max_peak(), 0.8);
+        meter.reset_peaks();
+        assert_eq!((meter.get_max_peak(), meter.get_peak_hold()), (0.0, 0.0));
+        assert!(meter.get_peak() > 0.0);
     }
 
     #[test]
@@ -0,0 +1,89 @@
+// src/audio/rate_converter.rs
+// Streaming linear resampler for the capture path, so the engine keeps its
+// original rate when the input device runs at a different one
+
+/// Converts consecutive blocks from one rate to another, carrying the
+/// fractional position and last sample across blocks so there are no seams
+#[derive(Debug, Clone)]
+pub struct RateConverter {
+    step: f64,     // source samples per converted sample
+    position: f64, // read position relative to the current block (-1 = previous sample)
+    previous: f32,
+}
+
+impl RateConverter {
+    pub fn new(from_rate: u32, to_rate: u32) -> Self {
+        Self {
+            step: from_rate as f64 / to_rate.max(1) as f64,
+            position: 0.0,
+            previous: 0.0,
+        }
+    }
+
+    /// True when the rates match and blocks can be passed straight through
+    pub fn is_passthrough(&self) -> bool {
+        self.step == 1.0
+    }
+
+    /// Converted samples a block of `input_len` can produce, for sizing buffers
+    pub fn max_output(&self, input_len: usize) -> usize {
+        (input_len as f64 / self.step).ceil() as usize + 1
+    }
+
+    /// Convert one block into `output`; returns how many samples were written
+    pub fn process(&mut self, input: &[f32], output: &mut [f32]) -> usize {
+        if input.is_empty() {
+            return 0;
+        }
+        let sample_at = |index: isize| {
+            if index < 0 {
+                self.previous
+            } else {
+                input[index as usize]
+            }
+        };
+
+        let last = input.len() as f64 - 1.0;
+        let mut written = 0;
+        while self.position < last && written < output.len() {
+            let index = self.position.floor();
+            let frac = (self.position - index) as f32;
+            let s1 = sample_at(index as isize);
+            let s2 = sample_at(index as isize + 1);
+            output[written] = s1 + (s2 - s1) * frac;
+            written += 1;
+            self.position += self.step;
+        }
+
+        // Positions are relative to the next block from here on
+        self.position = (self.position - input.len() as f64).max(-1.0);
+        self.previous = input[input.len() - 1];
+        written
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_block_sizes_and_continuity() {
+        let mut converter = RateConverter::new(48000, 44100);
+        assert!(!converter.is_passthrough());
+        assert!(RateConverter::new(44100, 44100).is_passthrough());
+
+        // A ramp stays a ramp across block boundaries
+        let input: Vec<f32> = (0..48000).map(|i| i as f32).collect();
+        let mut output = vec![0.0f32; converter.max_output(512)];
+        let mut converted = Vec::new();
+        for block in input.chunks(512) {
+            let written = converter.process(block, &mut output);
+            converted.extend_from_slice(&output[..written]);
+        }
+        assert!((converted.len() as i64 - 44100).abs() <= 1);
+        let step = 48000.0 / 44100.0;
+        for (i, sample) in converted.iter().enumerate() {
+            assert!((sample - (i as f64 * step) as f32).abs() < 0.01);
+        }
+    }
+}
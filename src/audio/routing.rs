@@ -0,0 +1,238 @@
+// src/audio/routing.rs
+// Output buses (main mix / cue) and the hardware channels each one plays on
+
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+
+/// Destination of a layer or of the click
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputBus {
+    #[default]
+    Main, // front of house
+    Cue, // performer monitoring, e.g. in-ears
+}
+
+impl OutputBus {
+    pub fn toggled(self) -> Self {
+        match self {
+            Self::Main => Self::Cue,
+            Self::Cue => Self::Main,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Main => "Main",
+            Self::Cue => "Cue",
+        }
+    }
+}
+
+/// Metronome level and placement (the bus is chosen separately)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ClickSettings {
+    pub volume: f32,       // 0.0..=1.0
+    pub pan: f32,          // -1.0 (left) ..= 1.0 (right)
+    pub record_only: bool, // metronome only clicks while a layer records
+}
+
+impl Default for ClickSettings {
+    fn default() -> Self {
+        Self {
+            volume: 1.0,
+            pan: 0.0,
+            record_only: false,
+        }
+    }
+}
+
+impl ClickSettings {
+    /// (left, right) gains; centre keeps both sides at full level
+    pub fn pan_gains(&self) -> (f32, f32) {
+        let pan = self.pan.clamp(-1.0, 1.0);
+        ((1.0 - pan).min(1.0), (1.0 + pan).min(1.0))
+    }
+}
+
+/// Widest the main bus can be made (150 %)
+pub const MAX_STEREO_WIDTH: f32 = 1.5;
+
+/// Mid/side width of one left/right pair: 0.0 folds it to mono (to hear what a
+/// mono venue system will), 1.0 leaves it as mixed, above 1.0 widens it
+pub fn stereo_width(left: f32, right: f32, width: f32) -> (f32, f32) {
+    let mid = (left + right) * 0.5;
+    let side = (left - right) * 0.5 * width;
+    (mid + side, mid - side)
+}
+
+/// Hardware output channels (0-based) per bus; `None` plays on every channel
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OutputRouting {
+    pub main: Option<Vec<usize>>,
+    pub cue: Option<Vec<usize>>,
+}
+
+impl OutputRouting {
+    /// Parse a 1-based channel list such as `1-2`, `3,4` or `1,3-4`
+    pub fn parse_channels(text: &str) -> Result<Vec<usize>> {
+        let mut channels = Vec::new();
+        for part in text.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let (first, last) = match part.split_once('-') {
+                Some((first, last)) => (first.trim(), last.trim()),
+                None => (part, part),
+            };
+            let first: usize = first
+                .parse()
+                .map_err(|_| anyhow!("Invalid channel: {}", part))?;
+            let last: usize = last
+                .parse()
+                .map_err(|_| anyhow!("Invalid channel: {}", part))?;
+            if first == 0 || last < first {
+                return Err(anyhow!("Invalid channel range: {}", part));
+            }
+            channels.extend((first..=last).map(|channel| channel - 1));
+        }
+        if channels.is_empty() {
+            return Err(anyhow!("No output channels given"));
+        }
+        Ok(channels)
+    }
+
+    /// Whether the cue bus needs its own mix (otherwise everything goes to main)
+    pub fn is_split(&self) -> bool {
+        self.main != self.cue
+    }
+
+    /// (main, cue) gain for each of the device's output channels
+    /// Channels beyond the device's count are ignored
+    pub fn gains(&self, channel_count: usize) -> Vec<(f32, f32)> {
+        let plays_on = |channels: &Option<Vec<usize>>, channel: usize| match channels {
+            Some(channels) => channels.contains(&channel),
+            None => true,
+        };
+        (0..channel_count)
+            .map(|channel| {
+                (
+                    plays_on(&self.main, channel) as u8 as f32,
+                    plays_on(&self.cue, channel) as u8 as f32,
+                )
+            })
+            .collect()
+    }
+
+    /// (left, right) channel pairs of the main bus for mid/side processing; the
+    /// bus's channels alternate left/right as for the click
+    pub fn main_pairs(&self, channel_count: usize) -> Vec<(usize, usize)> {
+        let channels: Vec<usize> = match &self.main {
+            Some(channels) => channels
+                .iter()
+                .copied()
+                .filter(|&channel| channel < channel_count)
+                .collect(),
+            None => (0..channel_count).collect(),
+        };
+        channels
+            .chunks_exact(2)
+            .map(|pair| (pair[0], pair[1]))
+            .collect()
+    }
+
+    /// Click gain on one output channel: the bus's channels alternate left/right
+    /// A bus with a single channel ignores the pan
+    pub fn click_gain(
+        &self,
+        channel: usize,
+        channel_count: usize,
+        bus: OutputBus,
+        (left, right): (f32, f32),
+    ) -> f32 {
+        let channels = match bus {
+            OutputBus::Main => &self.main,
+            OutputBus::Cue => &self.cue,
+        };
+        let (position, width) = match channels {
+            Some(channels) => (channels.iter().position(|&c| c == channel), channels.len()),
+            None => (Some(channel), channel_count),
+        };
+        match position {
+            None => 0.0,
+            Some(_) if width < 2 => 1.0,
+            Some(position) if position % 2 == 0 => left,
+            Some(_) => right,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stereo_width() {
+        // Mono folds to the mid signal, 1.0 is untouched, wider grows the side
+        assert_eq!(stereo_width(1.0, 0.0, 0.0), (0.5, 0.5));
+        assert_eq!(stereo_width(1.0, 0.5, 1.0), (1.0, 0.5));
+        assert_eq!(stereo_width(1.0, 0.0, MAX_STEREO_WIDTH), (1.25, -0.25));
+
+        let routing = OutputRouting {
+            main: Some(vec![0, 1, 4]),
+            cue: None,
+        };
+        assert_eq!(routing.main_pairs(4), vec![(0, 1)]);
+        assert_eq!(OutputRouting::default().main_pairs(4), vec![(0, 1), (2, 3)]);
+        assert!(OutputRouting::default().main_pairs(1).is_empty());
+    }
+
+    #[test]
+    fn test_parse_channels() {
+        assert_eq!(OutputRouting::parse_channels("3-4").unwrap(), vec![2, 3]);
+        assert_eq!(
+            OutputRouting::parse_channels("1, 3-4").unwrap(),
+            vec![0, 2, 3]
+        );
+        assert!(OutputRouting::parse_channels("0").is_err());
+        assert!(OutputRouting::parse_channels("4-3").is_err());
+        assert!(OutputRouting::parse_channels("").is_err());
+    }
+
+    #[test]
+    fn test_gains_split_click_to_in_ears() {
+        let routing = OutputRouting {
+            main: Some(vec![0, 1]),
+            cue: Some(vec![2, 3]),
+        };
+        assert!(routing.is_split());
+        assert_eq!(
+            routing.gains(4),
+            vec![(1.0, 0.0), (1.0, 0.0), (0.0, 1.0), (0.0, 1.0)]
+        );
+        // Default routing plays everything everywhere
+        assert!(!OutputRouting::default().is_split());
+        assert_eq!(OutputRouting::default().gains(2), vec![(1.0, 1.0); 2]);
+    }
+
+    #[test]
+    fn test_click_pan() {
+        let click = ClickSettings {
+            pan: -0.5,
+            ..Default::default()
+        };
+        assert_eq!(click.pan_gains(), (1.0, 0.5));
+
+        let routing = OutputRouting {
+            main: Some(vec![0, 1]),
+            cue: Some(vec![2, 3]),
+        };
+        let gains: Vec<f32> = (0..4)
+            .map(|channel| routing.click_gain(channel, 4, OutputBus::Cue, click.pan_gains()))
+            .collect();
+        assert_eq!(gains, vec![0.0, 0.0, 1.0, 0.5]);
+        // Mono output can't be panned
+        assert_eq!(
+            OutputRouting::default().click_gain(0, 1, OutputBus::Main, (0.0, 1.0)),
+            1.0
+        );
+    }
+}
@@ -0,0 +1,160 @@
+// src/audio/scheduler.rs
+// Sample-accurate command scheduling against the transport position
+
+use super::LayerCommand;
+
+/// Position on the engine transport
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransportTime {
+    Sample(usize), // absolute sample position
+    Beat(usize),   // absolute beat number (resolved with the tempo at scheduling time)
+}
+
+impl TransportTime {
+    pub fn to_sample(self, samples_per_beat: usize) -> usize {
+        match self {
+            TransportTime::Sample(sample) => sample,
+            TransportTime::Beat(beat) => beat.saturating_mul(samples_per_beat),
+        }
+    }
+}
+
+/// Queue of commands waiting for a transport position
+/// Capacity is fixed up front so scheduling never reallocates on the audio thread
+#[derive(Debug)]
+pub struct CommandScheduler {
+    // Sorted by position, latest first, so the next due command is at the end
+    queue: Vec<(usize, LayerCommand)>,
+}
+
+impl CommandScheduler {
+    pub const DEFAULT_CAPACITY: usize = 256;
+
+    pub fn new() -> Self {
+        Self::with_capacity(Self::DEFAULT_CAPACITY)
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            queue: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Queue a command; hands it back if the queue is full
+    /// Commands at the same position run in the order they were scheduled
+    pub fn schedule(
+        &mut self,
+        at_sample: usize,
+        command: LayerCommand,
+    ) -> Result<(), LayerCommand> {
+        if self.queue.len() >= self.queue.capacity() {
+            return Err(command);
+        }
+        let index = self.queue.partition_point(|(at, _)| *at > at_sample);
+        self.queue.insert(index, (at_sample, command));
+        Ok(())
+    }
+
+    /// Position of the next queued command
+    pub fn next_at(&self) -> Option<usize> {
+        self.queue.last().map(|(at, _)| *at)
+    }
+
+    /// Take the next command due at or before `position`
+    pub fn pop_due(&mut self, position: usize) -> Option<LayerCommand> {
+        if self.next_at()? <= position {
+            self.queue.pop().map(|(_, command)| command)
+        } else {
+            None
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.queue.clear();
+    }
+
+    /// Shift queued positions so `origin` becomes position 0 after the transport
+    /// is re-anchored; commands that were on a beat move to the nearest beat of
+    /// the new grid, the rest keep their distance from now
+    pub fn rebase(&mut self, origin: usize, samples_per_beat: usize) {
+        for (at, _) in self.queue.iter_mut() {
+            let on_beat = samples_per_beat > 0 && *at % samples_per_beat == 0;
+            *at = at.saturating_sub(origin);
+            if on_beat {
+                *at = (*at + samples_per_beat / 2) / samples_per_beat * samples_per_beat;
+            }
+        }
+        // Snapping can reorder neighbours; an insertion sort keeps same-position
+        // commands in order without allocating
+        for i in 1..self.queue.len() {
+            let mut j = i;
+            while j > 0 && self.queue[j - 1].0 < self.queue[j].0 {
+                self.queue.swap(j - 1, j);
+                j -= 1;
+            }
+        }
+    }
+}
+
+impl Default for CommandScheduler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_commands_pop_in_transport_order() {
+        let mut scheduler = CommandScheduler::new();
+        scheduler.schedule(300, LayerCommand::Play(2)).unwrap();
+        scheduler.schedule(100, LayerCommand::Play(0)).unwrap();
+        scheduler.schedule(100, LayerCommand::Mute(0)).unwrap();
+        scheduler.schedule(200, LayerCommand::Play(1)).unwrap();
+
+        assert_eq!(scheduler.next_at(), Some(100));
+        assert_eq!(scheduler.pop_due(99), None);
+        assert_eq!(scheduler.pop_due(100), Some(LayerCommand::Play(0)));
+        assert_eq!(scheduler.pop_due(100), Some(LayerCommand::Mute(0)));
+        assert_eq!(scheduler.pop_due(100), None);
+
+        // Anything overdue runs as soon as it is polled
+        assert_eq!(scheduler.pop_due(1000), Some(LayerCommand::Play(1)));
+        assert_eq!(scheduler.pop_due(1000), Some(LayerCommand::Play(2)));
+        assert!(scheduler.is_empty());
+
+        // Bar 1 moved to sample 4300: the beat at 5000 snaps to the new grid
+        scheduler.schedule(5000, LayerCommand::Play(3)).unwrap();
+        scheduler.schedule(5250, LayerCommand::Play(4)).unwrap();
+        scheduler.rebase(4300, 1000);
+        assert_eq!(scheduler.pop_due(950), Some(LayerCommand::Play(4)));
+        assert_eq!(scheduler.next_at(), Some(1000));
+    }
+
+    #[test]
+    fn test_full_queue_returns_command() {
+        let mut scheduler = CommandScheduler::with_capacity(1);
+        scheduler.schedule(10, LayerCommand::StopAll).unwrap();
+        assert_eq!(
+            scheduler.schedule(20, LayerCommand::PlayAll),
+            Err(LayerCommand::PlayAll)
+        );
+        assert_eq!(scheduler.len(), 1);
+    }
+
+    #[test]
+    fn test_beat_resolution() {
+        assert_eq!(TransportTime::Beat(4).to_sample(22050), 88200);
+        assert_eq!(TransportTime::Sample(123).to_sample(22050), 123);
+    }
+}
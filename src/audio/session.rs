@@ -0,0 +1,322 @@
+// src/audio/session.rs
+// Session file (TOML) with per-layer state; layer audio is stored as WAV next to it
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use super::layer::Marker;
+use super::{ClickSettings, OutputBus};
+
+/// Saved state of one layer
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LayerState {
+    pub id: usize,
+    pub volume: f32,
+    #[serde(default)]
+    pub muted: bool,
+    #[serde(default)]
+    pub solo: bool,
+    #[serde(default)]
+    pub loop_start: usize,
+    #[serde(default)]
+    pub loop_end: usize,
+    /// WAV file relative to the session file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub audio_file: Option<String>,
+    #[serde(default)]
+    pub markers: Vec<Marker>,
+    #[serde(default)]
+    pub output_bus: OutputBus,
+    #[serde(default)]
+    pub locked: bool,
+    #[serde(default)]
+    pub solo_safe: bool,
+    #[serde(default)]
+    pub fit_to_master: bool,
+    /// Undo states and takes, when saved with history
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub history: Option<LayerHistory>,
+}
+
+/// A layer's undo states and take lanes, so they survive reopening the session
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LayerHistory {
+    pub undo: Vec<LayerVersion>, // oldest first
+    pub undo_position: usize,    // state the layer is in
+    #[serde(default)]
+    pub takes: Vec<LayerVersion>,
+    #[serde(default)]
+    pub current_take: usize,
+}
+
+/// One saved version of a layer; audio shared between versions is written once
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LayerVersion {
+    /// WAV file relative to the session file (None = empty)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub audio_file: Option<String>,
+    pub volume: f32,
+    #[serde(default)]
+    pub muted: bool,
+    #[serde(default)]
+    pub solo: bool,
+    #[serde(default)]
+    pub loop_start: usize,
+    #[serde(default)]
+    pub loop_end: usize,
+    #[serde(default)]
+    pub markers: Vec<Marker>,
+}
+
+/// Metronome setup restored with a session or template
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MetronomeState {
+    pub enabled: bool,
+    pub bus: OutputBus,
+    pub click: ClickSettings,
+}
+
+/// Descriptive session details (shown in the title bar)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SessionMetadata {
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub author: String,
+    #[serde(default)]
+    pub notes: String, // set lists, lyrics, ...
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+impl SessionMetadata {
+    /// Split a comma separated tag list, dropping empty entries
+    pub fn parse_tags(text: &str) -> Vec<String> {
+        text.split(',')
+            .map(|tag| tag.trim().to_string())
+            .filter(|tag| !tag.is_empty())
+            .collect()
+    }
+
+    /// One-line summary, e.g. `Night Set by Sam [live, ambient]`
+    pub fn summary(&self) -> Option<String> {
+        let mut text = self.title.trim().to_string();
+        if !self.author.trim().is_empty() {
+            if !text.is_empty() {
+                text.push(' ');
+            }
+            text.push_str(&format!("by {}", self.author.trim()));
+        }
+        if !self.tags.is_empty() {
+            if !text.is_empty() {
+                text.push(' ');
+            }
+            text.push_str(&format!("[{}]", self.tags.join(", ")));
+        }
+        if text.is_empty() { None } else { Some(text) }
+    }
+}
+
+/// Complete session description
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Session {
+    pub version: u32,
+    pub sample_rate: u32,
+    pub bpm: f64,
+    pub beats_per_measure: u32,
+    #[serde(default = "default_swing")]
+    pub swing: f64,
+    #[serde(default)]
+    pub metadata: SessionMetadata,
+    /// Missing in files from older versions, which leave the metronome as it is
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metronome: Option<MetronomeState>,
+    #[serde(default)]
+    pub layers: Vec<LayerState>,
+}
+
+fn default_swing() -> f64 {
+    super::TempoEngine::STRAIGHT_SWING
+}
+
+impl Session {
+    pub const VERSION: u32 = 1;
+    /// Templates are sessions without audio: layer settings, tempo, routing and metronome
+    pub const TEMPLATE_EXTENSION: &str = "slt";
+
+    pub fn new(sample_rate: u32, bpm: f64, beats_per_measure: u32) -> Self {
+        Self {
+            version: Self::VERSION,
+            sample_rate,
+            bpm,
+            beats_per_measure,
+            swing: default_swing(),
+            metadata: SessionMetadata::default(),
+            metronome: None,
+            layers: Vec::new(),
+        }
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let text = toml::to_string_pretty(self).context("Failed to serialize session")?;
+        std::fs::write(&path, text)
+            .with_context(|| format!("Failed to write {}", path.as_ref().display()))?;
+        Ok(())
+    }
+
+    pub fn is_template<P: AsRef<Path>>(path: P) -> bool {
+        path.as_ref()
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case(Self::TEMPLATE_EXTENSION))
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let text = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.as_ref().display()))?;
+        let session: Session = toml::from_str(&text).context("Invalid session file")?;
+        Ok(session)
+    }
+
+    /// File name used for a layer's audio, e.g. `mysession_layer01.wav`
+    pub fn layer_audio_name<P: AsRef<Path>>(session_path: P, layer_id: usize) -> String {
+        let stem = session_path
+            .as_ref()
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_else(|| "session".to_string());
+        format!("{}_layer{:02}.wav", stem, layer_id + 1)
+    }
+
+    /// File name for one of a layer's saved versions, e.g. `mysession_layer01_v03.wav`
+    pub fn version_audio_name<P: AsRef<Path>>(
+        session_path: P,
+        layer_id: usize,
+        version: usize,
+    ) -> String {
+        let layer_file = Self::layer_audio_name(session_path, layer_id);
+        format!(
+            "{}_v{:02}.wav",
+            layer_file.trim_end_matches(".wav"),
+            version + 1
+        )
+    }
+
+    /// Resolve a path stored in the session relative to the session file
+    pub fn resolve<P: AsRef<Path>>(session_path: P, file: &str) -> PathBuf {
+        match session_path.as_ref().parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir.join(file),
+            _ => PathBuf::from(file),
+        }
+    }
+
+    /// Scale a sample position saved at the session rate to another rate
+    pub fn rescale(&self, position: usize, target_sample_rate: u32) -> usize {
+        if self.sample_rate == 0 || self.sample_rate == target_sample_rate {
+            return position;
+        }
+        (position as f64 * target_sample_rate as f64 / self.sample_rate as f64).round() as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_session_roundtrip() {
+        let mut session = Session::new(44100, 98.5, 4);
+        session.metadata = SessionMetadata {
+            title: "Night Set".to_string(),
+            author: "Sam".to_string(),
+            notes: "1. Intro\n2. Verse \"quoted\"".to_string(),
+            tags: vec!["live".to_string(), "ambient".to_string()],
+        };
+        session.metronome = Some(MetronomeState {
+            enabled: true,
+            bus: OutputBus::Cue,
+            click: ClickSettings {
+                volume: 0.5,
+                pan: -1.0,
+                record_only: true,
+            },
+        });
+        session.layers.push(LayerState {
+            id: 2,
+            volume: 0.75,
+            muted: true,
+            solo: false,
+            loop_start: 0,
+            loop_end: 88200,
+            audio_file: Some("test_session_layer03.wav".to_string()),
+            markers: vec![Marker {
+                name: "verse".to_string(),
+                position: 44100,
+            }],
+            output_bus: OutputBus::Cue,
+            locked: true,
+            solo_safe: true,
+            fit_to_master: true,
+            history: Some(LayerHistory {
+                undo: vec![LayerVersion {
+                    audio_file: Some("test_session_layer03_v01.wav".to_string()),
+                    volume: 1.0,
+                    muted: false,
+                    solo: false,
+                    loop_start: 0,
+                    loop_end: 44100,
+                    markers: Vec::new(),
+                }],
+                undo_position: 0,
+                takes: Vec::new(),
+                current_take: 0,
+            }),
+        });
+
+        let path = "test_session_roundtrip.toml";
+        session.save(path).unwrap();
+        let loaded = Session::load(path).unwrap();
+        let _ = fs::remove_file(path);
+
+        assert_eq!(loaded, session);
+    }
+
+    #[test]
+    fn test_metadata_tags_and_summary() {
+        let metadata = SessionMetadata {
+            title: "Night Set".to_string(),
+            author: "Sam".to_string(),
+            tags: SessionMetadata::parse_tags(" live, ,ambient "),
+            ..Default::default()
+        };
+        assert_eq!(metadata.tags, vec!["live", "ambient"]);
+        assert_eq!(
+            metadata.summary().as_deref(),
+            Some("Night Set by Sam [live, ambient]")
+        );
+        assert_eq!(SessionMetadata::default().summary(), None);
+    }
+
+    #[test]
+    fn test_paths_and_rescale() {
+        assert_eq!(
+            Session::layer_audio_name("sets/live.toml", 0),
+            "live_layer01.wav"
+        );
+        assert_eq!(
+            Session::version_audio_name("sets/live.toml", 0, 2),
+            "live_layer01_v03.wav"
+        );
+        assert_eq!(
+            Session::resolve("sets/live.toml", "live_layer01.wav"),
+            PathBuf::from("sets/live_layer01.wav")
+        );
+
+        let session = Session::new(44100, 120.0, 4);
+        assert_eq!(session.rescale(44100, 48000), 48000);
+        assert!(Session::is_template("rigs/live.SLT"));
+        assert!(!Session::is_template("sets/live.toml"));
+    }
+}
@@ -2,7 +2,7 @@
 // SIMD-accelerated audio mixing for multi-layer performance
 // Add to Cargo.toml: wide = "0.7"
 
-use super::AudioLayer;
+use super::{AudioLayer, OutputBus};
 use std::sync::{Arc, Mutex};
 use wide::f32x4;
 
@@ -24,6 +24,17 @@ impl SimdMixer {
     /// This is 2-4x faster than scalar mixing for 4+ layers
     /// REAL-TIME SAFE: Zero allocations, uses preallocated scratch buffer
     pub fn mix_layers(&mut self, layers: &[Arc<Mutex<AudioLayer>>], output: &mut [f32]) {
+        self.mix_bus(layers, output, None);
+    }
+
+    /// Mix only the layers routed to `bus` (all layers if None)
+    /// Solo still applies across every bus
+    pub fn mix_bus(
+        &mut self,
+        layers: &[Arc<Mutex<AudioLayer>>],
+        output: &mut [f32],
+        bus: Option<OutputBus>,
+    ) {
         // Clear output
         self.clear_buffer_simd(output);
 
@@ -46,15 +57,15 @@ impl SimdMixer {
         // Mix each layer using preallocated scratch buffer
         for layer_arc in layers {
             if let Ok(mut layer) = layer_arc.try_lock() {
-                if !Self::should_mix_layer(&layer, has_solo) {
+                let Some(gain) = layer.bus_gain(bus, has_solo) else {
                     continue;
-                }
+                };
 
-                // NO ALLOCATION: Write directly to scratch buffer
+                // NO ALLOCATION: Write directly to scratch buffer (volume applied)
                 layer.fill_next_samples(&mut self.scratch_buffer[..buffer_len]);
 
                 // NO ALLOCATION: Mix scratch into output
-                self.add_buffer_simd(output, &self.scratch_buffer[..buffer_len], layer.volume);
+                self.add_buffer_simd(output, &self.scratch_buffer[..buffer_len], gain);
             }
         }
 
@@ -62,6 +73,33 @@ impl SimdMixer {
         self.soft_clip_simd(output);
     }
 
+    /// Offline run of `mix_bus` for exports: `length` samples of the mix, starting
+    /// `start` samples in (the skipped part is mixed and dropped so every layer's
+    /// position, varispeed included, advances exactly as it would live)
+    pub fn render(
+        &mut self,
+        layers: &[Arc<Mutex<AudioLayer>>],
+        start: usize,
+        length: usize,
+        bus: Option<OutputBus>,
+    ) -> Vec<f32> {
+        let block_len = self.scratch_buffer.len().max(1);
+        let mut output = vec![0.0; length];
+        let mut block = vec![0.0; block_len];
+        let mut position = 0;
+        while position < start + length {
+            let len = block_len.min(start + length - position);
+            self.mix_bus(layers, &mut block[..len], bus);
+            let from = position.max(start);
+            let to = position + len;
+            if to > from {
+                output[from - start..to - start].copy_from_slice(&block[from - position..len]);
+            }
+            position = to;
+        }
+        output
+    }
+
     /// Clear buffer using SIMD (4x faster than fill)
     #[inline]
     fn clear_buffer_simd(&self, buffer: &mut [f32]) {
@@ -137,11 +175,6 @@ impl SimdMixer {
             *item = item.clamp(-1.0, 1.0);
         }
     }
-
-    #[inline]
-    fn should_mix_layer(layer: &AudioLayer, has_solo: bool) -> bool {
-        layer.is_playing && !layer.is_muted && (!has_solo || layer.is_solo)
-    }
 }
 
 // ==============================================================================
@@ -176,18 +209,18 @@ impl ScalarMixer {
 
         for layer_arc in layers {
             if let Ok(mut layer) = layer_arc.try_lock() {
-                if !layer.is_playing || layer.is_muted || (has_solo && !layer.is_solo) {
+                let Some(gain) = layer.bus_gain(None, has_solo) else {
                     continue;
-                }
+                };
 
-                // NO ALLOCATION: Write to scratch buffer
+                // NO ALLOCATION: Write to scratch buffer (volume applied)
                 let scratch = &mut self.scratch_buffer[..buffer_len];
                 layer.fill_next_samples(scratch);
 
                 // Mix into output buffer
                 for (i, &sample) in scratch.iter().enumerate() {
                     if i < output.len() {
-                        output[i] += sample * layer.volume;
+                        output[i] += sample * gain;
                     }
                 }
             }
@@ -245,6 +278,58 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_solo_safe_layer_survives_solo() {
+        let layers = create_test_layers(3, 64);
+        for layer in &layers {
+            layer.lock().unwrap().volume = 0.5;
+        }
+        layers[0].lock().unwrap().is_solo = true;
+        layers[1].lock().unwrap().is_solo_safe = true;
+        let mut simd_output = vec![0.0; 64];
+        let mut scalar_output = vec![0.0; 64];
+        SimdMixer::new(64).mix_layers(&layers, &mut simd_output);
+        ScalarMixer::new(64).mix_layers(&layers, &mut scalar_output);
+
+        // Layer 2 is silenced, so this must match soloing both 0 and 1
+        {
+            let mut safe = layers[1].lock().unwrap();
+            safe.is_solo_safe = false;
+            safe.is_solo = true;
+        }
+        let mut expected = vec![0.0; 64];
+        ScalarMixer::new(64).mix_layers(&layers, &mut expected);
+        assert!(expected[0] > 0.0);
+        for ((simd, scalar), expected) in simd_output.iter().zip(&scalar_output).zip(&expected) {
+            assert!((simd - expected).abs() < 0.001);
+            assert!((scalar - expected).abs() < 0.001);
+        }
+    }
+
+    #[test]
+    fn test_render_matches_live_mix() {
+        let layers = create_test_layers(3, 100);
+        layers[1].lock().unwrap().is_muted = true;
+        layers[2].lock().unwrap().output_bus = OutputBus::Cue;
+        let copies: Vec<_> = layers
+            .iter()
+            .map(|layer| Arc::new(Mutex::new(layer.lock().unwrap().playback_copy())))
+            .collect();
+
+        let mut live = vec![0.0; 250];
+        let mut mixer = SimdMixer::new(64);
+        for block in live.chunks_mut(64) {
+            mixer.mix_bus(&layers, block, Some(OutputBus::Main));
+        }
+        // Block size and start offset don't change the result
+        let rendered = SimdMixer::new(48).render(&copies, 30, 220, Some(OutputBus::Main));
+        assert_eq!(rendered.len(), 220);
+        for (rendered, live) in rendered.iter().zip(&live[30..]) {
+            assert!((rendered - live).abs() < 1e-6);
+        }
+        assert!(rendered[0] > 0.0);
+    }
+
     #[test]
     fn test_soft_clipping() {
         let mixer = SimdMixer::new(128);
@@ -253,7 +338,7 @@ mod tests {
 
         // Check all values are in range
         for &sample in &buffer {
-            assert!(sample >= -1.0 && sample <= 1.0);
+            assert!((-1.0..=1.0).contains(&sample));
         }
 
         // Values above threshold should be compressed
@@ -1,10 +1,17 @@
 use anyhow::{Result, anyhow};
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
 use cpal::{Device, Host, SampleFormat, Stream, StreamConfig};
-use crossbeam::channel::{Receiver, Sender};
+use crossbeam::channel::Receiver;
+use std::panic::{AssertUnwindSafe, catch_unwind};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
-use super::{AudioConfig, LayerCommand, LooperEngine};
+use super::{
+    AudioConfig, BlockAdapter, BufferTrial, CallbackTimer, ClickSettings, DeviceLatency,
+    DriftEstimator, EventSender, InputCapture, LayerCommand, LooperEngine, LooperError, OutputBus,
+    OutputRouting, RateConverter, stereo_width,
+};
 
 pub struct AudioStream {
     host: Host,
@@ -15,9 +22,46 @@ pub struct AudioStream {
     sample_format: SampleFormat,
     // For resampling between different rates
     resample_ratio: f64,
+    // Rate the looper runs at; stays fixed when devices are switched mid-session
+    engine_sample_rate: u32,
     // Device names for UI display
     input_device_name: String,
     output_device_name: String,
+    // Set when a stream callback panics; the owner rebuilds the streams
+    callback_panicked: Arc<AtomicBool>,
+    // Hardware output channels per bus (applied when the streams start)
+    routing: OutputRouting,
+    // Capture/playback delays reported by the running callbacks
+    latency: Arc<DeviceLatency>,
+    // Fixed engine block in frames (None = one block per device callback)
+    block_frames: Option<usize>,
+}
+
+/// Input and output streams of a running looper; dropping them stops audio
+pub struct LooperStreams {
+    input: Stream,
+    output: Stream,
+    fading_out: Arc<AtomicBool>,
+}
+
+impl LooperStreams {
+    /// Output ramp when streams start and before they're swapped out, so the
+    /// changeover doesn't click
+    pub const FADE: Duration = Duration::from_millis(10);
+    // Long enough for a couple of callbacks at default buffer sizes to run the ramp
+    const FADE_WAIT: Duration = Duration::from_millis(50);
+
+    pub fn play(&self) -> Result<()> {
+        self.input.play()?;
+        self.output.play()?;
+        Ok(())
+    }
+
+    /// Ramp the output to silence, then close both streams
+    pub fn fade_out(self) {
+        self.fading_out.store(true, Ordering::Relaxed);
+        std::thread::sleep(Self::FADE_WAIT);
+    }
 }
 
 impl AudioStream {
@@ -128,19 +172,25 @@ impl AudioStream {
             output_config,
             sample_format: output_default.sample_format(),
             resample_ratio,
+            engine_sample_rate: input_default.sample_rate().0,
             input_device_name,
             output_device_name,
+            callback_panicked: Arc::new(AtomicBool::new(false)),
+            routing: OutputRouting::default(),
+            latency: Arc::new(DeviceLatency::default()),
+            block_frames: None,
         })
     }
 
-    // Create a new AudioStream with specific device names (or defaults)
+    // Create a new AudioStream on a specific backend and devices (or defaults)
     pub fn new_with_devices(
         _config: AudioConfig,
         debug_mode: bool,
+        host_name: Option<String>,
         input_device_name: Option<String>,
         output_device_name: Option<String>,
     ) -> Result<Self> {
-        let host = cpal::default_host();
+        let host = host_by_name(host_name.as_deref())?;
 
         // Resolve input device
         let input_device = if let Some(name) = input_device_name.clone() {
@@ -195,6 +245,7 @@ impl AudioStream {
                 .map(|mut file| {
                     use std::io::Write;
                     let _ = writeln!(file, "═══ Device Switch ═══");
+                    let _ = writeln!(file, "Backend: {}", host.id().name());
                     let _ = writeln!(file, "Input: {}", input_device_name);
                     let _ = writeln!(file, "Output: {}", output_device_name);
                 });
@@ -223,18 +274,25 @@ impl AudioStream {
             output_config,
             sample_format: output_default.sample_format(),
             resample_ratio,
+            engine_sample_rate: input_default.sample_rate().0,
             input_device_name,
             output_device_name,
+            callback_panicked: Arc::new(AtomicBool::new(false)),
+            routing: OutputRouting::default(),
+            latency: Arc::new(DeviceLatency::default()),
+            block_frames: None,
         })
     }
 
-    pub fn start_audio_looper(
+    /// Build the looper's streams without starting them; the engine keeps
+    /// running on any streams already playing until these are started
+    pub fn build_audio_looper(
         &self,
         looper_engine: Arc<LooperEngine>,
         command_receiver: Receiver<LayerCommand>,
-        event_sender: Sender<super::AudioEvent>,
+        event_sender: EventSender,
         debug_mode: bool,
-    ) -> Result<(Stream, Stream)>
+    ) -> Result<LooperStreams>
     where
         LooperEngine: Send + 'static,
     {
@@ -242,6 +300,7 @@ impl AudioStream {
         // Clone event sender for error callbacks before moving it into the engine
         let input_err_sender = event_sender.clone();
         let output_err_sender = event_sender.clone();
+        let host_id = self.host.id();
         looper_engine.set_command_channel(command_receiver);
         looper_engine.set_event_sender(event_sender);
         looper_engine.set_debug_mode(debug_mode);
@@ -249,30 +308,55 @@ impl AudioStream {
         // Build input stream
         let looper_clone = Arc::clone(&looper_engine);
         let input_channels = self.input_config.channels;
+        let engine_sample_rate = self.engine_sample_rate;
+        // Input recorded at another rate than the engine's is converted on the way in
+        let input_converter =
+            RateConverter::new(self.input_config.sample_rate.0, engine_sample_rate);
+        // The largest callback, at the engine rate, must reach the layer whole
+        let max_input_samples = input_converter.max_output(self.max_input_frames());
+        looper_engine.reserve_input(max_input_samples);
+        let mut input_capture =
+            InputCapture::new(input_channels, self.max_input_frames(), input_converter);
+        // Samples delivered by the input clock, for drift estimation
+        let input_produced = Arc::new(AtomicU64::new(0));
+        let input_produced_writer = Arc::clone(&input_produced);
+        let input_latency = Arc::clone(&self.latency);
+        let output_latency = Arc::clone(&self.latency);
+        let input_panicked = Arc::clone(&self.callback_panicked);
+        let output_panicked = Arc::clone(&self.callback_panicked);
+        let fading_out = Arc::new(AtomicBool::new(false));
+        let fade_signal = Arc::clone(&fading_out);
+
+        let mut capture_input = move |data: &[f32]| {
+            // Mono at the engine rate, in preallocated chunks (no frames dropped)
+            let produced =
+                input_capture.process(data, |samples| looper_clone.store_input_samples(samples));
+            input_produced_writer.fetch_add(produced as u64, Ordering::Relaxed);
+        };
 
         let input_stream = self.input_device.build_input_stream(
             &self.input_config,
-            move |data: &[f32], _: &cpal::InputCallbackInfo| {
-                // Convert multi-channel to mono with stack buffer (typical max ~2048 samples)
-                let frame_count = data.len() / input_channels as usize;
-                let mut mono_buffer = [0.0f32; 4096]; // Stack allocated
-
-                for (i, chunk) in data.chunks(input_channels as usize).enumerate() {
-                    if i < mono_buffer.len() {
-                        mono_buffer[i] = chunk.iter().sum::<f32>() / chunk.len() as f32;
-                    }
+            move |data: &[f32], info: &cpal::InputCallbackInfo| {
+                if input_panicked.load(Ordering::Relaxed) {
+                    return;
+                }
+                let timestamp = info.timestamp();
+                if let Some(delay) = timestamp.callback.duration_since(&timestamp.capture) {
+                    input_latency.record_input(delay);
+                }
+                if catch_unwind(AssertUnwindSafe(|| capture_input(data))).is_err() {
+                    input_panicked.store(true, Ordering::Relaxed);
                 }
-
-                looper_clone.store_input_samples(&mono_buffer[..frame_count]);
             },
             move |_err| {
-                // Send error (use owned string to avoid format! allocation in callback)
+                // Send a typed error (no allocation in the callback)
                 // Note: Error callbacks may run in audio thread depending on backend
                 let _ = input_err_sender
-                    .try_send(super::AudioEvent::Error(String::from("Input stream error")));
+                    .try_send(super::AudioEvent::Error(LooperError::DeviceLost("input")));
                 // Try to get a new default input and notify UI
-                let new_input = cpal::default_host()
-                    .default_input_device()
+                let new_input = cpal::host_from_id(host_id)
+                    .ok()
+                    .and_then(|host| host.default_input_device())
                     .and_then(|d| d.name().ok());
                 let _ =
                     input_err_sender.try_send(super::AudioEvent::DevicesUpdated(new_input, None));
@@ -286,91 +370,221 @@ impl AudioStream {
         let resample_ratio = self.resample_ratio;
         let input_sample_rate = self.input_config.sample_rate.0;
         let output_sample_rate = self.output_config.sample_rate.0;
+        // Output gain ramps up from silence on start and back down before a swap
+        let mut fade_gain = 0.0f32;
+        let fade_step = 1.0 / (LooperStreams::FADE.as_secs_f32() * output_sample_rate as f32);
 
         // Accumulator for resampling
         let phase = Arc::new(Mutex::new(0.0_f64));
+        // Input and output may run on different hardware clocks
+        let mut drift = DriftEstimator::new(engine_sample_rate);
 
         // Preallocate buffers for output callback to avoid allocations in RT context
-        // Max buffer size: 4096 samples per channel, worst case resampling needs ~8192
-        let max_input_buffer_size = 8192;
+        // At least 8192 (worst case resampling of a 4096 output block), more if a
+        // single input callback can deliver more
+        let max_input_buffer_size = max_input_samples.max(8192);
         let input_buffer_state = Arc::new(Mutex::new(vec![0.0f32; max_input_buffer_size]));
-        let input_samples_buffer = Arc::new(Mutex::new(vec![0.0f32; 4096]));
-
-        let output_stream = self.output_device.build_output_stream(
-            &self.output_config,
-            move |data: &mut [f32], _: &cpal::OutputCallbackInfo| {
-                // NOTE: File I/O removed from audio callback for real-time safety
-                // Debug logging should use lock-free channel to separate thread
-
-                // Create buffer at input sample rate
-                let mono_len = data.len() / output_channels as usize;
-
-                // Calculate how many input samples we need
-                let input_samples_needed = (mono_len as f64 / resample_ratio).ceil() as usize;
-
-                // Work directly with preallocated heap buffers (no stack allocation, no copy)
-                // All locks held for entire operation to minimize contention window
-                if let (Ok(mut input_samples_buf), Ok(mut input_buf), Ok(mut phase_locked)) = (
-                    input_samples_buffer.try_lock(),
-                    input_buffer_state.try_lock(),
-                    phase.try_lock(),
-                ) {
-                    // Read input samples
-                    let input_samples_read = looper_clone
-                        .read_input_samples(&mut input_samples_buf)
-                        .min(4096);
-
-                    let process_len = input_samples_needed.min(input_buf.len());
-
-                    // Process audio at input sample rate directly into input_buf
-                    looper_clone.process_audio(
-                        &input_samples_buf[..input_samples_read],
-                        &mut input_buf[..process_len],
-                    );
+        let input_samples_buffer = Arc::new(Mutex::new(vec![0.0f32; max_input_buffer_size]));
+        // Cue bus is only rendered separately when it plays on other channels than main
+        let cue_buffer_state = Arc::new(Mutex::new(vec![
+            0.0f32;
+            if self.routing.is_split() {
+                max_input_buffer_size
+            } else {
+                0
+            }
+        ]));
+        let channel_gains = self.routing.gains(output_channels as usize);
+        // The click gets its own buffer so it can be panned (needs 2+ channels)
+        let click_buffer_state = Arc::new(Mutex::new(vec![
+            0.0f32;
+            if output_channels >= 2 {
+                max_input_buffer_size
+            } else {
+                0
+            }
+        ]));
+        let routing = self.routing.clone();
+        let click_gain = move |channel: usize, (bus, click): (OutputBus, ClickSettings)| {
+            routing.click_gain(channel, output_channels as usize, bus, click.pan_gains())
+        };
+        let mut click_gains: Vec<f32> = (0..output_channels as usize)
+            .map(|channel| click_gain(channel, (OutputBus::Main, ClickSettings::default())))
+            .collect();
+        // Left/right pairs the main bus's stereo width applies to
+        let width_pairs = self.routing.main_pairs(output_channels as usize);
+        let mut width = 1.0;
+        let mut block_adapter = self
+            .block_frames
+            .map(|frames| BlockAdapter::new(frames, self.routing.is_split(), output_channels >= 2));
+        // Input read while no new block was due waits for the next one
+        let mut pending_input = 0;
+
+        let mut render_output = move |data: &mut [f32]| {
+            // NOTE: File I/O removed from audio callback for real-time safety
+            // Debug logging should use lock-free channel to separate thread
+
+            // Create buffer at the engine sample rate
+            let mono_len = data.len() / output_channels as usize;
+
+            // Engine-rate samples per output sample, corrected for clock drift
+            let nominal_step = 1.0 / resample_ratio;
+            let correction = drift.update(
+                input_produced.load(Ordering::Relaxed),
+                mono_len as f64 * nominal_step,
+            );
+            let step = nominal_step * correction;
+
+            // Work directly with preallocated heap buffers (no stack allocation, no copy)
+            // All locks held for entire operation to minimize contention window
+            if let (
+                Ok(mut input_samples_buf),
+                Ok(mut input_buf),
+                Ok(mut cue_buf),
+                Ok(mut click_buf),
+                Ok(mut phase_locked),
+            ) = (
+                input_samples_buffer.try_lock(),
+                input_buffer_state.try_lock(),
+                cue_buffer_state.try_lock(),
+                click_buffer_state.try_lock(),
+                phase.try_lock(),
+            ) {
+                // Read input samples
+                pending_input +=
+                    looper_clone.read_input_samples(&mut input_samples_buf[pending_input..]);
+
+                // Whole samples consumed this callback; the fraction carries over in phase
+                let target = *phase_locked + mono_len as f64 * step;
+                let process_len = (target.floor() as usize).min(input_buf.len());
+
+                // Process audio at engine sample rate directly into input_buf (and cue_buf)
+                let split = !cue_buf.is_empty();
+                let separate_click = !click_buf.is_empty();
+                let cue_out = if split {
+                    Some(&mut cue_buf[..process_len])
+                } else {
+                    None
+                };
+                let click_out = if separate_click {
+                    Some(&mut click_buf[..process_len])
+                } else {
+                    None
+                };
+                let mut process =
+                    |output: &mut [f32], cue: Option<&mut [f32]>, click: Option<&mut [f32]>| {
+                        let input = &input_samples_buf[..std::mem::take(&mut pending_input)];
+                        looper_clone.process_audio_routed(input, output, cue, click);
+                    };
+                match block_adapter.as_mut() {
+                    Some(adapter) => {
+                        adapter.fill(&mut input_buf[..process_len], cue_out, click_out, process)
+                    }
+                    None => process(&mut input_buf[..process_len], cue_out, click_out),
+                }
 
-                    // Resample directly from input_buf (no copy needed)
-                    for i in 0..mono_len {
-                        let input_pos = *phase_locked;
-                        let input_idx = input_pos.floor() as usize;
-                        let frac = (input_pos - input_pos.floor()) as f32;
-
-                        // Branchless interpolation with bounds checking
-                        let idx_curr = input_idx.min(process_len.saturating_sub(1));
-                        let idx_next = (input_idx + 1).min(process_len.saturating_sub(1));
-                        let s1 = input_buf[idx_curr];
-                        let s2 = input_buf[idx_next];
-                        let sample = s1 + (s2 - s1) * frac;
-
-                        // Copy to all channels
-                        for channel in 0..output_channels as usize {
-                            if let Some(output_sample) =
-                                data.get_mut(i * output_channels as usize + channel)
-                            {
-                                *output_sample = sample;
+                // Keep the previous pan and width if the engine is busy
+                if separate_click && let Some(click) = looper_clone.click_output() {
+                    for (channel, gain) in click_gains.iter_mut().enumerate() {
+                        *gain = click_gain(channel, click);
+                    }
+                }
+                width = looper_clone.stereo_width().unwrap_or(width);
+
+                // Resample directly from input_buf (no copy needed)
+                for i in 0..mono_len {
+                    let input_pos = *phase_locked;
+                    let input_idx = input_pos.floor() as usize;
+                    let frac = (input_pos - input_pos.floor()) as f32;
+
+                    // Branchless interpolation with bounds checking
+                    let idx_curr = input_idx.min(process_len.saturating_sub(1));
+                    let idx_next = (input_idx + 1).min(process_len.saturating_sub(1));
+                    let s1 = input_buf[idx_curr];
+                    let s2 = input_buf[idx_next];
+                    let sample = s1 + (s2 - s1) * frac;
+                    let cue_sample = if split {
+                        let c1 = cue_buf[idx_curr];
+                        let c2 = cue_buf[idx_next];
+                        c1 + (c2 - c1) * frac
+                    } else {
+                        0.0
+                    };
+                    let click_sample = if separate_click {
+                        let k1 = click_buf[idx_curr];
+                        let k2 = click_buf[idx_next];
+                        k1 + (k2 - k1) * frac
+                    } else {
+                        0.0
+                    };
+
+                    // Copy to the channels each bus is routed to
+                    let frame = i * output_channels as usize;
+                    for (channel, &(main_gain, cue_gain)) in channel_gains.iter().enumerate() {
+                        if let Some(output_sample) = data.get_mut(frame + channel) {
+                            *output_sample = sample * main_gain
+                                + cue_sample * cue_gain
+                                + click_sample * click_gains[channel];
+                        }
+                    }
+                    if width != 1.0 {
+                        for &(left, right) in &width_pairs {
+                            if frame + left.max(right) < data.len() {
+                                (data[frame + left], data[frame + right]) =
+                                    stereo_width(data[frame + left], data[frame + right], width);
                             }
                         }
-
-                        *phase_locked += 1.0 / resample_ratio;
                     }
 
-                    // Reset phase when it gets too large
-                    if process_len > 0 {
-                        *phase_locked = (*phase_locked % process_len as f64).max(0.0);
-                    }
+                    *phase_locked += step;
+                }
+
+                // Keep only the fractional position into the next block
+                *phase_locked = (target - process_len as f64).clamp(0.0, 1.0);
+            } else {
+                // Fallback: output silence if any lock fails
+                data[..mono_len].fill(0.0);
+            }
+        };
+
+        let output_stream = self.output_device.build_output_stream(
+            &self.output_config,
+            move |data: &mut [f32], info: &cpal::OutputCallbackInfo| {
+                // After a panic, stay silent until the stream is rebuilt
+                if output_panicked.load(Ordering::Relaxed) {
+                    data.fill(0.0);
+                    return;
+                }
+                let timestamp = info.timestamp();
+                if let Some(delay) = timestamp.playback.duration_since(&timestamp.callback) {
+                    output_latency.record_output(delay);
+                }
+                if catch_unwind(AssertUnwindSafe(|| render_output(data))).is_err() {
+                    output_panicked.store(true, Ordering::Relaxed);
+                    data.fill(0.0);
+                    return;
+                }
+                let target = if fade_signal.load(Ordering::Relaxed) {
+                    0.0
                 } else {
-                    // Fallback: output silence if any lock fails
-                    data[..mono_len].fill(0.0);
+                    1.0
+                };
+                if fade_gain != target || target == 0.0 {
+                    for frame in data.chunks_mut(output_channels as usize) {
+                        fade_gain += (target - fade_gain).clamp(-fade_step, fade_step);
+                        frame.iter_mut().for_each(|sample| *sample *= fade_gain);
+                    }
                 }
             },
             move |_err| {
-                // Send error (use owned string to avoid format! allocation in callback)
+                // Send a typed error (no allocation in the callback)
                 // Note: Error callbacks may run in audio thread depending on backend
-                let _ = output_err_sender.try_send(super::AudioEvent::Error(String::from(
-                    "Output stream error",
-                )));
+                let _ = output_err_sender
+                    .try_send(super::AudioEvent::Error(LooperError::DeviceLost("output")));
                 // Try to get a new default output and notify UI
-                let new_output = cpal::default_host()
-                    .default_output_device()
+                let new_output = cpal::host_from_id(host_id)
+                    .ok()
+                    .and_then(|host| host.default_output_device())
                     .and_then(|d| d.name().ok());
                 let _ =
                     output_err_sender.try_send(super::AudioEvent::DevicesUpdated(None, new_output));
@@ -378,10 +592,6 @@ impl AudioStream {
             None,
         )?;
 
-        // Start both streams
-        input_stream.play()?;
-        output_stream.play()?;
-
         if debug_mode {
             let _ = std::fs::OpenOptions::new()
                 .create(true)
@@ -391,13 +601,145 @@ impl AudioStream {
                     use std::io::Write;
                     writeln!(
                         file,
-                        "═══ Audio streams started: {}Hz input -> {}Hz output ═══",
-                        input_sample_rate, output_sample_rate
+                        "═══ Audio streams opened: {}Hz input -> {}Hz engine -> {}Hz output ═══",
+                        input_sample_rate, engine_sample_rate, output_sample_rate
                     )
                 });
         }
 
-        Ok((input_stream, output_stream))
+        Ok(LooperStreams {
+            input: input_stream,
+            output: output_stream,
+            fading_out,
+        })
+    }
+
+    /// Run the looper at `sample_rate` regardless of the devices' rates (before starting)
+    /// Layers recorded earlier in the session keep their pitch and speed after a switch
+    pub fn set_engine_sample_rate(&mut self, sample_rate: u32) {
+        self.engine_sample_rate = sample_rate;
+        self.resample_ratio = self.output_config.sample_rate.0 as f64 / sample_rate as f64;
+    }
+
+    /// Set which output channels the main and cue buses play on (before starting)
+    pub fn set_output_routing(&mut self, routing: OutputRouting) {
+        self.routing = routing;
+    }
+
+    /// Run the engine in fixed blocks of `frames` whatever the device callback
+    /// size (None or 0 = one block per callback, before starting)
+    pub fn set_block_size(&mut self, frames: Option<u32>) {
+        self.block_frames = frames
+            .filter(|&frames| frames > 0)
+            .map(|frames| frames as usize);
+    }
+
+    /// Request a fixed hardware buffer in frames (None = device default, before starting)
+    /// Sizes outside what a device reports supporting are clamped to its range
+    pub fn set_buffer_size(&mut self, frames: Option<u32>) {
+        let buffer_size = |supported: Option<cpal::SupportedBufferSize>| match (frames, supported) {
+            (None, _) => cpal::BufferSize::Default,
+            (Some(frames), Some(cpal::SupportedBufferSize::Range { min, max })) => {
+                cpal::BufferSize::Fixed(frames.clamp(min, max))
+            }
+            (Some(frames), _) => cpal::BufferSize::Fixed(frames),
+        };
+        let input_supported = self
+            .input_device
+            .default_input_config()
+            .ok()
+            .map(|config| *config.buffer_size());
+        let output_supported = self
+            .output_device
+            .default_output_config()
+            .ok()
+            .map(|config| *config.buffer_size());
+        self.input_config.buffer_size = buffer_size(input_supported);
+        self.output_config.buffer_size = buffer_size(output_supported);
+    }
+
+    /// Run silent streams at `frames` for `duration`, timing the output callbacks
+    /// (latency tuning; the buffer size stays set afterwards)
+    pub fn probe_buffer_size(&mut self, frames: u32, duration: Duration) -> Result<BufferTrial> {
+        self.set_buffer_size(Some(frames));
+        let frames = match self.output_config.buffer_size {
+            cpal::BufferSize::Fixed(size) => size,
+            cpal::BufferSize::Default => frames,
+        };
+        let sample_rate = self.output_config.sample_rate.0;
+        let errors = Arc::new(AtomicU64::new(0));
+
+        let input_errors = Arc::clone(&errors);
+        let input_stream = self.input_device.build_input_stream(
+            &self.input_config,
+            |_data: &[f32], _: &cpal::InputCallbackInfo| {},
+            move |_err| {
+                input_errors.fetch_add(1, Ordering::Relaxed);
+            },
+            None,
+        )?;
+
+        let timer = Arc::new(Mutex::new(CallbackTimer::new(
+            frames,
+            sample_rate,
+            Instant::now(),
+        )));
+        let output_timer = Arc::clone(&timer);
+        let output_errors = Arc::clone(&errors);
+        let output_stream = self.output_device.build_output_stream(
+            &self.output_config,
+            move |data: &mut [f32], _: &cpal::OutputCallbackInfo| {
+                data.fill(0.0);
+                if let Ok(mut timer) = output_timer.try_lock() {
+                    timer.tick(Instant::now());
+                }
+            },
+            move |_err| {
+                output_errors.fetch_add(1, Ordering::Relaxed);
+            },
+            None,
+        )?;
+
+        input_stream.play()?;
+        output_stream.play()?;
+        std::thread::sleep(duration);
+        drop(output_stream);
+        drop(input_stream);
+
+        let timer = timer.lock().unwrap();
+        Ok(BufferTrial::from_timer(
+            frames,
+            sample_rate,
+            &timer,
+            errors.load(Ordering::Relaxed),
+        ))
+    }
+
+    /// Largest input callback to preallocate for: the fixed buffer size, else
+    /// the most the device reports it may deliver (8192 frames if unknown)
+    fn max_input_frames(&self) -> usize {
+        match self.input_config.buffer_size {
+            cpal::BufferSize::Fixed(frames) => frames as usize,
+            cpal::BufferSize::Default => match self
+                .input_device
+                .default_input_config()
+                .ok()
+                .map(|config| *config.buffer_size())
+            {
+                Some(cpal::SupportedBufferSize::Range { max, .. }) => max as usize,
+                _ => 8192,
+            },
+        }
+    }
+
+    /// Device delays measured once the streams run (for high-latency warnings)
+    pub fn latency(&self) -> Arc<DeviceLatency> {
+        Arc::clone(&self.latency)
+    }
+
+    /// Shared flag raised when an audio callback panics
+    pub fn panic_flag(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.callback_panicked)
     }
 
     pub fn get_sample_rate(&self) -> u32 {
@@ -419,6 +761,11 @@ impl AudioStream {
         self.output_config.channels
     }
 
+    /// Audio backend the streams run on, e.g. ALSA or WASAPI
+    pub fn get_host_name(&self) -> &'static str {
+        self.host.id().name()
+    }
+
     pub fn get_input_device_name(&self) -> &str {
         &self.input_device_name
     }
@@ -452,9 +799,50 @@ impl AudioStream {
     }
 }
 
+/// Names of the audio backends usable on this machine (e.g. ALSA, JACK)
+pub fn available_host_names() -> Vec<String> {
+    cpal::available_hosts()
+        .into_iter()
+        .map(|id| id.name().to_string())
+        .collect()
+}
+
+/// The named backend (any case), or the platform default for None
+pub fn host_by_name(name: Option<&str>) -> Result<Host> {
+    let Some(name) = name else {
+        return Ok(cpal::default_host());
+    };
+    let id = cpal::available_hosts()
+        .into_iter()
+        .find(|id| id.name().eq_ignore_ascii_case(name))
+        .ok_or_else(|| {
+            anyhow!(
+                "Audio backend '{}' not available (available: {})",
+                name,
+                available_host_names().join(", ")
+            )
+        })?;
+    Ok(cpal::host_from_id(id)?)
+}
+
+/// Default input and output device names on a backend
+pub fn default_device_names(host_name: Option<&str>) -> Result<(String, String)> {
+    let host = host_by_name(host_name)?;
+    let input = host
+        .default_input_device()
+        .ok_or_else(|| anyhow!("No input device available"))?;
+    let output = host
+        .default_output_device()
+        .ok_or_else(|| anyhow!("No output device available"))?;
+    Ok((
+        input.name().unwrap_or_else(|_| "Unknown".to_string()),
+        output.name().unwrap_or_else(|_| "Unknown".to_string()),
+    ))
+}
+
 // Public helper to enumerate device names for UI consumption
-pub fn enumerate_device_names() -> Result<(Vec<String>, Vec<String>)> {
-    let host = cpal::default_host();
+pub fn enumerate_device_names(host_name: Option<&str>) -> Result<(Vec<String>, Vec<String>)> {
+    let host = host_by_name(host_name)?;
 
     let mut inputs = Vec::new();
     let mut outputs = Vec::new();
@@ -469,3 +857,13 @@ pub fn enumerate_device_names() -> Result<(Vec<String>, Vec<String>)> {
 
     Ok((inputs, outputs))
 }
+
+/// Default sample rate and channel count of the named input device
+pub fn input_device_format(host_name: Option<&str>, name: &str) -> Result<(u32, u16)> {
+    let device = host_by_name(host_name)?
+        .input_devices()?
+        .find(|d| d.name().map(|n| n == name).unwrap_or(false))
+        .ok_or_else(|| anyhow!("Input device not found: {}", name))?;
+    let config = device.default_input_config()?;
+    Ok((config.sample_rate().0, config.channels()))
+}
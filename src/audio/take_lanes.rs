@@ -0,0 +1,134 @@
+// src/audio/take_lanes.rs
+// Take lanes: each record pass on a layer is kept as a take, so the best one
+// can be picked after the fact
+
+use std::sync::Arc;
+
+use super::undo_history::LayerSnapshot;
+
+/// A layer's takes, oldest first; buffers are shared with the undo history
+#[derive(Debug, Clone, Default)]
+pub struct TakeLanes {
+    takes: Vec<LayerSnapshot>,
+    current: usize, // take the layer is playing
+}
+
+impl TakeLanes {
+    pub const MAX_TAKES: usize = 4;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a take and make it current, dropping the oldest past MAX_TAKES
+    /// The current take's audio is refreshed instead of added twice
+    pub fn push(&mut self, take: LayerSnapshot) {
+        if let Some(current) = self.takes.get_mut(self.current)
+            && Arc::ptr_eq(&current.buffer, &take.buffer)
+        {
+            *current = take;
+            return;
+        }
+        if self.takes.len() == Self::MAX_TAKES {
+            self.takes.remove(0);
+        }
+        self.takes.push(take);
+        self.current = self.takes.len() - 1;
+    }
+
+    /// Replace the current take (e.g. after an overdub on it)
+    pub fn update_current(&mut self, take: LayerSnapshot) {
+        match self.takes.get_mut(self.current) {
+            Some(current) => *current = take,
+            None => self.push(take),
+        }
+    }
+
+    /// Make take `index` current and return it for the layer to play
+    pub fn select(&mut self, index: usize) -> Option<&LayerSnapshot> {
+        let take = self.takes.get(index)?;
+        self.current = index;
+        Some(take)
+    }
+
+    /// Keep only the current take; returns false if there was nothing to drop
+    pub fn keep_current(&mut self) -> bool {
+        if self.takes.len() < 2 {
+            return false;
+        }
+        let kept = self.takes.swap_remove(self.current);
+        self.takes = vec![kept];
+        self.current = 0;
+        true
+    }
+
+    /// The takes, oldest first (for saving)
+    pub fn takes(&self) -> &[LayerSnapshot] {
+        &self.takes
+    }
+
+    /// Replace the takes with saved ones
+    pub fn restore(&mut self, mut takes: Vec<LayerSnapshot>, current: usize) {
+        let skip = takes.len().saturating_sub(Self::MAX_TAKES);
+        takes.drain(..skip);
+        self.current = current
+            .saturating_sub(skip)
+            .min(takes.len().saturating_sub(1));
+        self.takes = takes;
+    }
+
+    pub fn clear(&mut self) {
+        self.takes.clear();
+        self.current = 0;
+    }
+
+    pub fn len(&self) -> usize {
+        self.takes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.takes.is_empty()
+    }
+
+    /// Index of the take playing, None before the first pass
+    pub fn current(&self) -> Option<usize> {
+        (!self.takes.is_empty()).then_some(self.current)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn take(samples: usize) -> LayerSnapshot {
+        LayerSnapshot {
+            buffer: Arc::new(vec![0.1; samples]),
+            loop_end: samples,
+            ..LayerSnapshot::new()
+        }
+    }
+
+    #[test]
+    fn test_push_select_and_keep() {
+        let mut lanes = TakeLanes::new();
+        assert_eq!(lanes.current(), None);
+        for samples in 1..=5 {
+            lanes.push(take(samples));
+        }
+        // The first pass fell out; the newest is current
+        assert_eq!(lanes.len(), TakeLanes::MAX_TAKES);
+        assert_eq!(lanes.current(), Some(3));
+
+        // The current take's audio again refreshes it instead of adding a take
+        let again = lanes.select(1).unwrap().clone();
+        lanes.push(again);
+        assert_eq!(lanes.len(), TakeLanes::MAX_TAKES);
+        assert_eq!(lanes.current(), Some(1));
+        assert!(lanes.select(7).is_none());
+
+        assert!(lanes.keep_current());
+        assert_eq!(lanes.len(), 1);
+        assert_eq!(lanes.select(0).unwrap().loop_end, 3);
+        assert!(!lanes.keep_current());
+    }
+}
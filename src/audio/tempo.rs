@@ -1,4 +1,57 @@
-use std::time::Instant;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// When a transport event happened: stream sample position plus host clock time
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransportStamp {
+    pub sample_position: usize,
+    pub host_time: Instant,
+}
+
+impl TransportStamp {
+    pub fn new(sample_position: usize, host_time: Instant) -> Self {
+        Self {
+            sample_position,
+            host_time,
+        }
+    }
+
+    /// Stamp for a later position in the same stream
+    pub fn at_position(&self, sample_position: usize, sample_rate: u32) -> Self {
+        let delta = sample_position.saturating_sub(self.sample_position);
+        Self {
+            sample_position,
+            host_time: self.host_time
+                + Duration::from_secs_f64(delta as f64 / sample_rate.max(1) as f64),
+        }
+    }
+}
+
+/// Transport position as bar:beat:tick (bars and beats from 1) plus time since bar 1
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TransportPosition {
+    pub bar: usize,
+    pub beat: u32,
+    pub tick: u32, // TempoEngine::TICKS_PER_BEAT per beat
+    pub elapsed: Duration,
+}
+
+impl Default for TransportPosition {
+    fn default() -> Self {
+        Self {
+            bar: 1,
+            beat: 1,
+            tick: 0,
+            elapsed: Duration::ZERO,
+        }
+    }
+}
+
+impl fmt::Display for TransportPosition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{:03}", self.bar, self.beat, self.tick)
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct TempoEngine {
@@ -7,16 +60,28 @@ pub struct TempoEngine {
     pub sample_rate: u32,
     pub samples_per_beat: usize,
     pub samples_per_measure: usize,
+    pub swing: f64, // off-beat position in percent of a beat (50 = straight)
     pub global_position: usize,
     pub last_tap_time: Option<Instant>,
     pub tap_times: Vec<Instant>,
+    pub tap_locked: bool,    // tap estimate has stabilized
+    pub round_tap_bpm: bool, // round tapped BPM to a whole number
     pub count_in_active: bool,
     pub count_in_remaining_beats: u32,
     pub count_in_layer: Option<usize>,
+    pub paused: bool, // clock frozen; beats, measures and scheduled commands wait
     last_processed_beat: usize, // NEW: Track last beat to prevent double-triggers
 }
 
 impl TempoEngine {
+    pub const STRAIGHT_SWING: f64 = 50.0;
+    pub const MAX_SWING: f64 = 75.0;
+    pub const TICKS_PER_BEAT: u32 = 960;
+    const MAX_TAPS: usize = 8;
+    const TAP_TOLERANCE: f64 = 0.15; // interval deviation from the median treated as an outlier
+    const LOCK_MIN_TAPS: usize = 4;
+    const LOCK_SPREAD: f64 = 0.04; // max interval spread, relative to the mean, when locked
+
     pub fn new(sample_rate: u32, bpm: f64, beats_per_measure: u32) -> Self {
         let samples_per_beat = Self::calculate_samples_per_beat(sample_rate, bpm);
         let samples_per_measure = samples_per_beat * beats_per_measure as usize;
@@ -27,12 +92,16 @@ impl TempoEngine {
             sample_rate,
             samples_per_beat,
             samples_per_measure,
+            swing: Self::STRAIGHT_SWING,
             global_position: 0,
             last_tap_time: None,
-            tap_times: Vec::with_capacity(4),
+            tap_times: Vec::with_capacity(Self::MAX_TAPS),
+            tap_locked: false,
+            round_tap_bpm: false,
             count_in_active: false,
             count_in_remaining_beats: 0,
             count_in_layer: None,
+            paused: false,
             last_processed_beat: 0, // NEW
         }
     }
@@ -47,58 +116,121 @@ impl TempoEngine {
         self.samples_per_measure = self.samples_per_beat * self.beats_per_measure as usize;
     }
 
+    pub fn set_swing(&mut self, percent: f64) {
+        self.swing = percent.clamp(Self::STRAIGHT_SWING, Self::MAX_SWING);
+    }
+
+    pub fn swing_active(&self) -> bool {
+        self.swing > Self::STRAIGHT_SWING
+    }
+
+    /// Samples from a beat to its (swung) off-beat eighth
+    pub fn offbeat_offset(&self) -> usize {
+        (self.samples_per_beat as f64 * self.swing / 100.0) as usize
+    }
+
+    /// Latest swung off-beat in (from, to]; None when playing straight
+    pub fn offbeat_between(&self, from: usize, to: usize) -> Option<usize> {
+        if !self.swing_active() || self.samples_per_beat == 0 {
+            return None;
+        }
+        let offset = self.offbeat_offset();
+        let beat = to.checked_sub(offset)? / self.samples_per_beat;
+        let position = beat * self.samples_per_beat + offset;
+        (position > from).then_some(position)
+    }
+
     pub fn tap_tempo(&mut self) {
-        let now = Instant::now();
-
-        if let Some(last_tap) = self.last_tap_time {
-            let elapsed = now.duration_since(last_tap).as_secs_f64();
-
-            // If tap is within reasonable range (20-300 BPM equivalent)
-            // 0.2s = 300 BPM, 3.0s = 20 BPM
-            if (0.2..=3.0).contains(&elapsed) {
-                // Calculate BPM from this single tap interval
-                let new_bpm = 60.0 / elapsed;
-
-                // If we have previous taps, do a weighted average (favor recent taps)
-                if !self.tap_times.is_empty() {
-                    // Calculate BPM from last tap interval
-                    let last_interval = self
-                        .tap_times
-                        .last()
-                        .map(|last| now.duration_since(*last).as_secs_f64())
-                        .unwrap_or(elapsed);
-                    let last_bpm = 60.0 / last_interval;
-
-                    // Weighted average: 70% new tap, 30% previous (smooth but responsive)
-                    let averaged_bpm = new_bpm * 0.7 + last_bpm * 0.3;
-                    self.set_bpm(averaged_bpm);
-                } else {
-                    // First real tap (second overall), just use it directly
-                    self.set_bpm(new_bpm);
-                }
+        self.tap_at(Instant::now());
+    }
 
-                self.tap_times.push(now);
+    /// Register a tap; BPM is the mean of the recent intervals with outliers dropped
+    pub fn tap_at(&mut self, now: Instant) {
+        let elapsed = self
+            .last_tap_time
+            .map(|last| now.duration_since(last).as_secs_f64());
+        self.last_tap_time = Some(now);
 
-                // Keep only the last 2 taps (we only need the most recent for smoothing)
-                if self.tap_times.len() > 2 {
-                    self.tap_times.remove(0);
-                }
-            } else {
-                // Reset if too long between taps (>3s means they're starting over)
-                self.tap_times.clear();
-                self.tap_times.push(now);
-            }
-        } else {
-            // First tap - just record it, don't calculate yet
+        // 0.2s = 300 BPM, 3.0s = 20 BPM; anything else starts a new tap sequence
+        if !elapsed.is_some_and(|e| (0.2..=3.0).contains(&e)) {
             self.tap_times.clear();
             self.tap_times.push(now);
+            self.tap_locked = false;
+            return;
         }
 
-        self.last_tap_time = Some(now);
+        // Capacity is fixed, so this never reallocates
+        if self.tap_times.len() >= Self::MAX_TAPS {
+            self.tap_times.remove(0);
+        }
+        self.tap_times.push(now);
+
+        let mut intervals = [0.0; Self::MAX_TAPS];
+        let mut count = self.tap_intervals(&mut intervals);
+        let mut median = Self::median(&intervals[..count]);
+
+        // Two outliers in a row: the player changed tempo, keep only the new taps
+        if count >= 3
+            && intervals[count - 2..count]
+                .iter()
+                .all(|&i| Self::is_tap_outlier(i, median))
+        {
+            let stale = self.tap_times.len() - 3;
+            self.tap_times.drain(..stale);
+            count = self.tap_intervals(&mut intervals);
+            median = Self::median(&intervals[..count]);
+        }
+
+        let (sum, kept, min, max) = intervals[..count]
+            .iter()
+            .filter(|&&i| !Self::is_tap_outlier(i, median))
+            .fold((0.0, 0, f64::MAX, 0.0_f64), |(sum, kept, min, max), &i| {
+                (sum + i, kept + 1, min.min(i), max.max(i))
+            });
+        let mean = sum / kept as f64;
+
+        let mut bpm = 60.0 / mean;
+        if self.round_tap_bpm {
+            bpm = bpm.round();
+        }
+        self.set_bpm(bpm);
+
+        // Locked once enough taps agree closely
+        self.tap_locked =
+            kept + 1 >= Self::LOCK_MIN_TAPS && (max - min) / mean <= Self::LOCK_SPREAD;
+    }
+
+    /// Number of taps in the current sequence
+    pub fn tap_count(&self) -> usize {
+        self.tap_times.len()
+    }
+
+    fn tap_intervals(&self, intervals: &mut [f64; Self::MAX_TAPS]) -> usize {
+        let mut count = 0;
+        for (slot, pair) in intervals.iter_mut().zip(self.tap_times.windows(2)) {
+            *slot = pair[1].duration_since(pair[0]).as_secs_f64();
+            count += 1;
+        }
+        count
+    }
+
+    fn median(values: &[f64]) -> f64 {
+        let mut sorted = [0.0; Self::MAX_TAPS];
+        let sorted = &mut sorted[..values.len()];
+        sorted.copy_from_slice(values);
+        sorted.sort_by(f64::total_cmp);
+        sorted[sorted.len() / 2]
+    }
+
+    fn is_tap_outlier(interval: f64, median: f64) -> bool {
+        (interval - median).abs() > median * Self::TAP_TOLERANCE
     }
 
     // UPDATED: Fixed advance method
     pub fn advance(&mut self, sample_count: usize) {
+        if self.paused {
+            return;
+        }
         let previous_position = self.global_position;
         self.global_position = self.global_position.saturating_add(sample_count);
 
@@ -110,11 +242,16 @@ impl TempoEngine {
         if current_beat_number > previous_beat_number
             && current_beat_number > self.last_processed_beat
         {
+            // A single advance may span several beats (large buffers)
+            let beats_crossed =
+                current_beat_number - previous_beat_number.max(self.last_processed_beat);
             self.last_processed_beat = current_beat_number;
 
             // Handle count-in
             if self.count_in_active && self.count_in_remaining_beats > 0 {
-                self.count_in_remaining_beats -= 1;
+                self.count_in_remaining_beats = self
+                    .count_in_remaining_beats
+                    .saturating_sub(beats_crossed as u32);
 
                 if self.count_in_remaining_beats == 0 {
                     self.count_in_active = false;
@@ -154,6 +291,19 @@ impl TempoEngine {
         self.global_position / self.samples_per_measure
     }
 
+    pub fn position(&self) -> TransportPosition {
+        let samples_per_beat = self.samples_per_beat.max(1) as u64;
+        let into_beat = self.global_position as u64 % samples_per_beat;
+        TransportPosition {
+            bar: self.get_current_measure() + 1,
+            beat: self.get_current_beat(),
+            tick: (into_beat * Self::TICKS_PER_BEAT as u64 / samples_per_beat) as u32,
+            elapsed: Duration::from_secs_f64(
+                self.global_position as f64 / self.sample_rate.max(1) as f64,
+            ),
+        }
+    }
+
     pub fn is_on_measure_boundary(&self, tolerance_samples: usize) -> bool {
         let position_in_measure = self.global_position % self.samples_per_measure;
         position_in_measure <= tolerance_samples
@@ -218,4 +368,102 @@ mod tests {
         assert!(!tempo.count_in_active);
         assert_eq!(tempo.count_in_remaining_beats, 0);
     }
+
+    #[test]
+    fn test_pause_and_reset() {
+        let mut tempo = TempoEngine::new(44100, 120.0, 4);
+        tempo.advance(22050 * 6); // bar 2, beat 3
+
+        tempo.paused = true;
+        tempo.advance(22050);
+        assert_eq!(tempo.get_current_beat(), 3);
+        tempo.paused = false;
+
+        tempo.reset_position();
+        assert_eq!(
+            (tempo.get_current_measure(), tempo.get_current_beat()),
+            (0, 1)
+        );
+        tempo.advance(22050);
+        assert_eq!(tempo.get_current_beat(), 2);
+    }
+
+    #[test]
+    fn test_position_display() {
+        let mut tempo = TempoEngine::new(44100, 120.0, 4);
+        assert_eq!(tempo.position().to_string(), "1:1:000");
+        // Two bars, two beats and half a beat in
+        tempo.advance(22050 * 10 + 11025);
+        let position = tempo.position();
+        assert_eq!(position.to_string(), "3:3:480");
+        assert_eq!(position.elapsed, Duration::from_millis(5250));
+    }
+
+    #[test]
+    fn test_swung_offbeats() {
+        let mut tempo = TempoEngine::new(44100, 120.0, 4);
+        assert_eq!(tempo.offbeat_between(0, 88200), None); // straight
+
+        tempo.set_swing(60.0);
+        assert_eq!(tempo.offbeat_offset(), 13230);
+        assert_eq!(tempo.offbeat_between(0, 13229), None);
+        assert_eq!(tempo.offbeat_between(0, 13230), Some(13230));
+        assert_eq!(tempo.offbeat_between(13230, 22050 + 13229), None);
+        assert_eq!(tempo.offbeat_between(30000, 40000), Some(22050 + 13230));
+
+        tempo.set_swing(90.0);
+        assert_eq!(tempo.swing, TempoEngine::MAX_SWING);
+    }
+
+    fn tap_sequence(tempo: &mut TempoEngine, intervals_ms: &[u64]) {
+        let mut now = Instant::now();
+        tempo.tap_at(now);
+        for &ms in intervals_ms {
+            now += Duration::from_millis(ms);
+            tempo.tap_at(now);
+        }
+    }
+
+    #[test]
+    fn test_tap_tempo_rejects_outliers_and_locks() {
+        let mut tempo = TempoEngine::new(44100, 100.0, 4);
+
+        tap_sequence(&mut tempo, &[500, 500]);
+        assert!((tempo.bpm - 120.0).abs() < 0.01);
+        assert!(!tempo.tap_locked);
+
+        // One late tap is ignored
+        tap_sequence(&mut tempo, &[500, 500, 800, 500, 500]);
+        assert!((tempo.bpm - 120.0).abs() < 0.01);
+        assert!(tempo.tap_locked);
+        assert_eq!(tempo.tap_count(), 6);
+    }
+
+    #[test]
+    fn test_tap_tempo_follows_tempo_change() {
+        let mut tempo = TempoEngine::new(44100, 100.0, 4);
+        tap_sequence(&mut tempo, &[500, 500, 500, 400, 400]);
+        assert!((tempo.bpm - 150.0).abs() < 0.01);
+        assert_eq!(tempo.tap_count(), 3);
+    }
+
+    #[test]
+    fn test_tap_tempo_rounding() {
+        let mut tempo = TempoEngine::new(44100, 100.0, 4);
+        tempo.round_tap_bpm = true;
+        tap_sequence(&mut tempo, &[490, 490, 490]);
+        assert_eq!(tempo.bpm, 122.0);
+    }
+
+    #[test]
+    fn test_stamp_offset() {
+        let start = TransportStamp::new(1000, Instant::now());
+        let later = start.at_position(1000 + 22050, 44100);
+
+        assert_eq!(later.sample_position, 23050);
+        assert_eq!(
+            later.host_time - start.host_time,
+            Duration::from_millis(500)
+        );
+    }
 }
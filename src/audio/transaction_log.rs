@@ -0,0 +1,188 @@
+// src/audio/transaction_log.rs
+// Engine-level undo log: orders snapshot-saving actions across all layers
+
+use std::collections::VecDeque;
+
+/// One user action: how many undo steps it added to each affected layer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transaction {
+    pub steps: Vec<(usize, u32)>, // (layer_id, snapshot count)
+}
+
+impl Transaction {
+    pub fn layer_ids(&self) -> Vec<usize> {
+        self.steps.iter().map(|(layer_id, _)| *layer_id).collect()
+    }
+
+    fn contains(&self, layer_id: usize) -> bool {
+        self.steps.iter().any(|(id, _)| *id == layer_id)
+    }
+
+    /// Remove one step for a layer; returns true if the transaction is now empty
+    fn remove_step(&mut self, layer_id: usize) -> bool {
+        if let Some(pos) = self.steps.iter().position(|(id, _)| *id == layer_id) {
+            self.steps[pos].1 -= 1;
+            if self.steps[pos].1 == 0 {
+                self.steps.remove(pos);
+            }
+        }
+        self.steps.is_empty()
+    }
+}
+
+/// Transaction log built from per-layer undo history revisions
+/// Global undo replays the per-layer undo steps of the most recent transaction
+#[derive(Debug, Clone)]
+pub struct TransactionLog {
+    undo_stack: VecDeque<Transaction>,
+    redo_stack: Vec<Transaction>,
+    seen_revisions: Vec<u64>,
+    capacity: usize,
+}
+
+impl TransactionLog {
+    const DEFAULT_CAPACITY: usize = 64;
+
+    pub fn new(layer_count: usize) -> Self {
+        Self::with_revisions(vec![0; layer_count])
+    }
+
+    /// Start from existing history revisions (states already saved are not undoable globally)
+    pub fn with_revisions(seen_revisions: Vec<u64>) -> Self {
+        Self {
+            undo_stack: VecDeque::with_capacity(Self::DEFAULT_CAPACITY),
+            redo_stack: Vec::with_capacity(Self::DEFAULT_CAPACITY),
+            seen_revisions,
+            capacity: Self::DEFAULT_CAPACITY,
+        }
+    }
+
+    /// Record snapshots saved since the last sync as a single transaction
+    /// `revisions` yields (layer_id, current history revision)
+    pub fn sync<I>(&mut self, revisions: I) -> bool
+    where
+        I: IntoIterator<Item = (usize, u64)>,
+    {
+        let mut steps = Vec::new();
+        for (layer_id, revision) in revisions {
+            let Some(seen) = self.seen_revisions.get_mut(layer_id) else {
+                continue;
+            };
+            if revision > *seen {
+                steps.push((layer_id, (revision - *seen) as u32));
+                *seen = revision;
+            }
+        }
+
+        if steps.is_empty() {
+            return false;
+        }
+
+        // A new action invalidates the redo chain
+        self.redo_stack.clear();
+        if self.undo_stack.len() >= self.capacity {
+            self.undo_stack.pop_front();
+        }
+        self.undo_stack.push_back(Transaction { steps });
+        true
+    }
+
+    /// Take the most recent transaction for undo
+    pub fn take_undo(&mut self) -> Option<Transaction> {
+        self.undo_stack.pop_back()
+    }
+
+    /// Take the most recently undone transaction for redo
+    pub fn take_redo(&mut self) -> Option<Transaction> {
+        self.redo_stack.pop()
+    }
+
+    /// Record a transaction that was undone so it can be redone
+    pub fn push_redo(&mut self, transaction: Transaction) {
+        self.redo_stack.push(transaction);
+    }
+
+    /// Record a transaction that was redone so it can be undone again
+    pub fn push_undo(&mut self, transaction: Transaction) {
+        if self.undo_stack.len() >= self.capacity {
+            self.undo_stack.pop_front();
+        }
+        self.undo_stack.push_back(transaction);
+    }
+
+    /// Keep the log consistent with a per-layer undo
+    pub fn note_layer_undo(&mut self, layer_id: usize) {
+        if let Some(pos) = self.undo_stack.iter().rposition(|t| t.contains(layer_id)) {
+            if self.undo_stack[pos].remove_step(layer_id) {
+                self.undo_stack.remove(pos);
+            }
+            self.redo_stack.push(Transaction {
+                steps: vec![(layer_id, 1)],
+            });
+        }
+    }
+
+    /// Keep the log consistent with a per-layer redo
+    pub fn note_layer_redo(&mut self, layer_id: usize) {
+        if let Some(pos) = self.redo_stack.iter().rposition(|t| t.contains(layer_id)) {
+            if self.redo_stack[pos].remove_step(layer_id) {
+                self.redo_stack.remove(pos);
+            }
+            self.push_undo(Transaction {
+                steps: vec![(layer_id, 1)],
+            });
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sync_groups_layers_into_one_transaction() {
+        let mut log = TransactionLog::new(4);
+
+        assert!(log.sync([(0, 1), (2, 2)]));
+        assert!(!log.sync([(0, 1), (2, 2)])); // nothing new
+
+        let transaction = log.take_undo().unwrap();
+        assert_eq!(transaction.steps, vec![(0, 1), (2, 2)]);
+        assert!(!log.can_undo());
+    }
+
+    #[test]
+    fn test_new_action_clears_redo() {
+        let mut log = TransactionLog::new(2);
+        log.sync([(0, 1)]);
+
+        let transaction = log.take_undo().unwrap();
+        log.push_redo(transaction);
+        assert!(log.can_redo());
+
+        log.sync([(1, 1)]);
+        assert!(!log.can_redo());
+    }
+
+    #[test]
+    fn test_layer_undo_moves_step_to_redo() {
+        let mut log = TransactionLog::new(2);
+        log.sync([(0, 1)]);
+        log.sync([(1, 1)]);
+
+        log.note_layer_undo(0);
+        assert_eq!(log.take_undo().unwrap().layer_ids(), vec![1]);
+        assert!(!log.can_undo());
+
+        log.note_layer_redo(0);
+        assert_eq!(log.take_undo().unwrap().layer_ids(), vec![0]);
+    }
+}
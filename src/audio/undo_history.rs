@@ -2,31 +2,48 @@
 // 5-level circular buffer undo/redo history for audio layers
 
 use std::collections::VecDeque;
+use std::sync::Arc;
+
+use super::layer::Marker;
 
 /// Represents a complete state snapshot of an audio layer
+/// The buffer is reference-counted so unchanged audio is shared between snapshots
 #[derive(Debug, Clone)]
 pub struct LayerSnapshot {
-    pub buffer: Vec<f32>,
+    pub buffer: Arc<Vec<f32>>,
     pub volume: f32,
     pub loop_start: usize,
     pub loop_end: usize,
     pub playback_position: usize,
     pub is_muted: bool,
     pub is_solo: bool,
+    pub markers: Vec<Marker>,
 }
 
 impl LayerSnapshot {
     pub fn new() -> Self {
         Self {
-            buffer: Vec::new(),
+            buffer: Arc::new(Vec::new()),
             volume: 1.0,
             loop_start: 0,
             loop_end: 0,
             playback_position: 0,
             is_muted: false,
             is_solo: false,
+            markers: Vec::new(),
         }
     }
+
+    /// Compare layer content, ignoring the playback position
+    pub fn same_content(&self, other: &LayerSnapshot) -> bool {
+        (Arc::ptr_eq(&self.buffer, &other.buffer) || self.buffer == other.buffer)
+            && self.volume == other.volume
+            && self.loop_start == other.loop_start
+            && self.loop_end == other.loop_end
+            && self.is_muted == other.is_muted
+            && self.is_solo == other.is_solo
+            && self.markers == other.markers
+    }
 }
 
 impl Default for LayerSnapshot {
@@ -35,23 +52,89 @@ impl Default for LayerSnapshot {
     }
 }
 
+/// 16-bit quantized audio, scaled to the buffer's peak to keep resolution
+#[derive(Debug)]
+pub struct QuantizedBuffer {
+    samples: Vec<i16>,
+    scale: f32,
+}
+
+impl QuantizedBuffer {
+    /// Allocates and scans the whole buffer: call it off the audio thread
+    pub fn encode(buffer: &[f32]) -> Self {
+        let peak = buffer.iter().map(|s| s.abs()).fold(0.0f32, f32::max);
+        let scale = if peak > 0.0 { peak } else { 1.0 };
+        let samples = buffer
+            .iter()
+            .map(|&s| ((s / scale) * i16::MAX as f32).round() as i16)
+            .collect();
+        Self { samples, scale }
+    }
+
+    /// Allocates: call it off the audio thread
+    pub fn decode(&self) -> Vec<f32> {
+        let factor = self.scale / i16::MAX as f32;
+        self.samples.iter().map(|&q| q as f32 * factor).collect()
+    }
+
+    /// REAL-TIME SAFE: One sample, decoded in place
+    pub fn sample(&self, index: usize) -> f32 {
+        self.samples[index] as f32 * (self.scale / i16::MAX as f32)
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+}
+
+/// History slot: inactive snapshots of long loops keep only a quantized copy
+#[derive(Debug, Clone)]
+struct HistoryEntry {
+    snapshot: LayerSnapshot,
+    quantized: Option<Arc<QuantizedBuffer>>,
+}
+
+impl HistoryEntry {
+    fn full(snapshot: LayerSnapshot) -> Self {
+        Self {
+            snapshot,
+            quantized: None,
+        }
+    }
+
+    /// Restore full precision storage so the entry can be applied or compared
+    fn expand(&mut self) {
+        if let Some(quantized) = self.quantized.take() {
+            self.snapshot.buffer = Arc::new(quantized.decode());
+        }
+    }
+}
+
 /// 5-level circular buffer undo/redo history
+/// The current state is kept at full precision unless its layer is parked
+/// (`park_current`). With a compression threshold set, other states of longer
+/// loops can be stored 16-bit quantized (lossy: undoing or redoing to one gives
+/// back 16-bit audio); nothing is quantized until `compact` or
+/// `install_compacted` is called
 #[derive(Debug, Clone)]
 pub struct UndoHistory {
-    history: VecDeque<LayerSnapshot>,
+    history: VecDeque<HistoryEntry>,
     max_levels: usize,
     current_index: isize, // -1 means no current state, 0+ is index in history
+    compression_threshold: Option<usize>, // samples; None = never compress
+    revision: u64,        // number of states ever saved
 }
 
 impl UndoHistory {
     const DEFAULT_MAX_LEVELS: usize = 5;
+    pub const DEFAULT_COMPRESSION_THRESHOLD: usize = 480_000; // ~10s at 48kHz
 
     pub fn new() -> Self {
-        Self {
-            history: VecDeque::with_capacity(Self::DEFAULT_MAX_LEVELS),
-            max_levels: Self::DEFAULT_MAX_LEVELS,
-            current_index: -1,
-        }
+        Self::new_with_levels(Self::DEFAULT_MAX_LEVELS)
     }
 
     pub fn new_with_levels(max_levels: usize) -> Self {
@@ -59,11 +142,36 @@ impl UndoHistory {
             history: VecDeque::with_capacity(max_levels),
             max_levels,
             current_index: -1,
+            compression_threshold: None,
+            revision: 0,
+        }
+    }
+
+    /// Set the buffer length (in samples) above which inactive snapshots may be
+    /// quantized to 16-bit; None brings every snapshot back to full precision
+    /// (a parked current state stays 16-bit until its layer is unparked)
+    pub fn set_compression_threshold(&mut self, samples: Option<usize>) {
+        self.compression_threshold = samples;
+        if samples.is_none() {
+            let parked = self.parked().cloned();
+            self.history
+                .iter_mut()
+                .filter(|entry| {
+                    !matches!((&entry.quantized, &parked),
+                        (Some(quantized), Some(parked)) if Arc::ptr_eq(quantized, parked))
+                })
+                .for_each(HistoryEntry::expand);
         }
     }
 
     /// Save current state to history (creates new snapshot)
+    /// A parked layer's state keeps sharing the current state's 16-bit audio
     pub fn save_state(&mut self, snapshot: LayerSnapshot) {
+        let quantized = self
+            .current_entry()
+            .filter(|entry| Arc::ptr_eq(&entry.snapshot.buffer, &snapshot.buffer))
+            .and_then(|entry| entry.quantized.clone());
+
         // If we're not at the end of history, truncate future states
         if self.current_index >= 0 {
             let truncate_from = (self.current_index + 1) as usize;
@@ -73,7 +181,10 @@ impl UndoHistory {
         }
 
         // Add new state
-        self.history.push_back(snapshot);
+        self.history.push_back(HistoryEntry {
+            snapshot,
+            quantized,
+        });
         self.current_index = (self.history.len() - 1) as isize;
 
         // Maintain max history size
@@ -81,13 +192,20 @@ impl UndoHistory {
             self.history.pop_front();
             self.current_index = (self.history.len() - 1) as isize;
         }
+
+        self.revision += 1;
+    }
+
+    /// Monotonic count of saved states (not affected by undo/redo or clear)
+    pub fn revision(&self) -> u64 {
+        self.revision
     }
 
     /// Undo to previous state
     pub fn undo(&mut self) -> Option<LayerSnapshot> {
         if self.can_undo() {
             self.current_index -= 1;
-            Some(self.history[self.current_index as usize].clone())
+            Some(self.activate_current())
         } else {
             None
         }
@@ -97,7 +215,7 @@ impl UndoHistory {
     pub fn redo(&mut self) -> Option<LayerSnapshot> {
         if self.can_redo() {
             self.current_index += 1;
-            Some(self.history[self.current_index as usize].clone())
+            Some(self.activate_current())
         } else {
             None
         }
@@ -115,13 +233,225 @@ impl UndoHistory {
 
     /// Get current state without modifying history
     pub fn get_current(&self) -> Option<LayerSnapshot> {
-        if self.current_index >= 0 && (self.current_index as usize) < self.history.len() {
-            Some(self.history[self.current_index as usize].clone())
+        self.current().cloned()
+    }
+
+    /// Borrow current state without cloning
+    /// A parked state's buffer is empty: its audio is `parked()`
+    pub fn current(&self) -> Option<&LayerSnapshot> {
+        self.current_entry().map(|entry| &entry.snapshot)
+    }
+
+    fn current_entry(&self) -> Option<&HistoryEntry> {
+        if self.current_index >= 0 {
+            self.history.get(self.current_index as usize)
         } else {
             None
         }
     }
 
+    /// Check if a snapshot has the same content as the current state
+    pub fn matches_current(&self, snapshot: &LayerSnapshot) -> bool {
+        self.current_entry().is_some_and(|entry| {
+            // A parked state's audio is its 16-bit copy, only shared by pointer
+            (entry.quantized.is_none() || Arc::ptr_eq(&entry.snapshot.buffer, &snapshot.buffer))
+                && entry.snapshot.same_content(snapshot)
+        })
+    }
+
+    /// Copy-on-write: reuse the current snapshot's buffer if the audio is unchanged,
+    /// otherwise copy it into a new shared buffer
+    pub fn share_buffer(&self, buffer: &[f32]) -> Arc<Vec<f32>> {
+        match self.current_entry() {
+            Some(entry)
+                if entry.quantized.is_none() && entry.snapshot.buffer.as_slice() == buffer =>
+            {
+                Arc::clone(&entry.snapshot.buffer)
+            }
+            _ => Arc::new(buffer.to_vec()),
+        }
+    }
+
+    /// The current state's 16-bit audio while its layer is parked
+    pub fn parked(&self) -> Option<&Arc<QuantizedBuffer>> {
+        self.current_entry()
+            .and_then(|entry| entry.quantized.as_ref())
+    }
+
+    /// Park the current state: `buffer` (its audio) is replaced by `quantized` in
+    /// every state holding it, freeing the full-precision copy. False if the
+    /// current state doesn't hold `buffer`
+    pub fn park_current(
+        &mut self,
+        buffer: &Arc<Vec<f32>>,
+        quantized: Arc<QuantizedBuffer>,
+    ) -> bool {
+        if self.parked().is_some()
+            || self
+                .current()
+                .is_none_or(|current| !Arc::ptr_eq(&current.buffer, buffer))
+        {
+            return false;
+        }
+        // One empty buffer for all of them, so states saved while parked match
+        let placeholder = Arc::new(Vec::new());
+        for entry in self.history.iter_mut() {
+            if entry.quantized.is_none() && Arc::ptr_eq(&entry.snapshot.buffer, buffer) {
+                entry.quantized = Some(Arc::clone(&quantized));
+                entry.snapshot.buffer = Arc::clone(&placeholder);
+            }
+        }
+        true
+    }
+
+    /// Unpark the current state with `audio`, the decoded copy of its 16-bit
+    /// audio, which every state sharing that audio gets back
+    pub fn unpark_current(&mut self, audio: Arc<Vec<f32>>) {
+        let Some(parked) = self.parked().cloned() else {
+            return;
+        };
+        for entry in self.history.iter_mut() {
+            if entry
+                .quantized
+                .as_ref()
+                .is_some_and(|quantized| Arc::ptr_eq(quantized, &parked))
+            {
+                entry.quantized = None;
+                entry.snapshot.buffer = Arc::clone(&audio);
+            }
+        }
+    }
+
+    /// Every state at full precision, oldest first, with the index of the current
+    /// one (for saving); audio shared between states stays shared
+    pub fn snapshots(&self) -> (Vec<LayerSnapshot>, usize) {
+        let mut decoded: Vec<(*const QuantizedBuffer, Arc<Vec<f32>>)> = Vec::new();
+        let snapshots = self
+            .history
+            .iter()
+            .map(|entry| {
+                let mut snapshot = entry.snapshot.clone();
+                if let Some(quantized) = &entry.quantized {
+                    let ptr = Arc::as_ptr(quantized);
+                    snapshot.buffer = match decoded.iter().find(|(p, _)| *p == ptr) {
+                        Some((_, buffer)) => Arc::clone(buffer),
+                        None => {
+                            let buffer = Arc::new(quantized.decode());
+                            decoded.push((ptr, Arc::clone(&buffer)));
+                            buffer
+                        }
+                    };
+                }
+                snapshot
+            })
+            .collect();
+        (snapshots, self.current_index.max(0) as usize)
+    }
+
+    /// Replace the history with saved states (oldest first); the oldest are
+    /// dropped past the level limit
+    pub fn restore(&mut self, snapshots: Vec<LayerSnapshot>, current: usize) {
+        self.history.clear();
+        self.current_index = -1;
+        if snapshots.is_empty() {
+            return;
+        }
+        let skip = snapshots.len().saturating_sub(self.max_levels);
+        let current = current.min(snapshots.len() - 1).max(skip) - skip;
+        self.revision += snapshots.len() as u64;
+        self.history = snapshots
+            .into_iter()
+            .skip(skip)
+            .map(HistoryEntry::full)
+            .collect();
+        self.current_index = current as isize;
+    }
+
+    /// Approximate bytes of audio held by the history (shared buffers counted once)
+    pub fn memory_bytes(&self) -> usize {
+        let mut seen_full: Vec<*const Vec<f32>> = Vec::with_capacity(self.history.len());
+        let mut seen_quantized: Vec<*const QuantizedBuffer> =
+            Vec::with_capacity(self.history.len());
+        let mut total = 0;
+
+        for entry in &self.history {
+            if let Some(quantized) = &entry.quantized {
+                let ptr = Arc::as_ptr(quantized);
+                if !seen_quantized.contains(&ptr) {
+                    seen_quantized.push(ptr);
+                    total += quantized.samples.len() * std::mem::size_of::<i16>();
+                }
+            } else {
+                let ptr = Arc::as_ptr(&entry.snapshot.buffer);
+                if !seen_full.contains(&ptr) {
+                    seen_full.push(ptr);
+                    total += entry.snapshot.buffer.len() * std::mem::size_of::<f32>();
+                }
+            }
+        }
+        total
+    }
+
+    /// Expand the entry at current_index and return it
+    fn activate_current(&mut self) -> LayerSnapshot {
+        let index = self.current_index as usize;
+        self.history[index].expand();
+        self.history[index].snapshot.clone()
+    }
+
+    /// Buffers of inactive entries long enough to quantize, each once; encode
+    /// them without holding the history, then hand them to `install_compacted`
+    pub fn compaction_candidates(&self) -> Vec<Arc<Vec<f32>>> {
+        let (Some(threshold), Some(current)) = (self.compression_threshold, self.current()) else {
+            return Vec::new();
+        };
+        let mut candidates: Vec<Arc<Vec<f32>>> = Vec::new();
+        for entry in &self.history {
+            let buffer = &entry.snapshot.buffer;
+            if entry.quantized.is_none()
+                && buffer.len() >= threshold
+                && !Arc::ptr_eq(buffer, &current.buffer)
+                && !candidates.iter().any(|c| Arc::ptr_eq(c, buffer))
+            {
+                candidates.push(Arc::clone(buffer));
+            }
+        }
+        candidates
+    }
+
+    /// Store `buffer`'s quantized copy in place of it in every inactive entry
+    /// still holding it; false if none does (it became current or was dropped)
+    pub fn install_compacted(
+        &mut self,
+        buffer: &Arc<Vec<f32>>,
+        quantized: Arc<QuantizedBuffer>,
+    ) -> bool {
+        if self.compression_threshold.is_none()
+            || self
+                .current()
+                .is_none_or(|current| Arc::ptr_eq(&current.buffer, buffer))
+        {
+            return false;
+        }
+        let mut installed = false;
+        for entry in self.history.iter_mut() {
+            if entry.quantized.is_none() && Arc::ptr_eq(&entry.snapshot.buffer, buffer) {
+                entry.quantized = Some(Arc::clone(&quantized));
+                entry.snapshot.buffer = Arc::new(Vec::new());
+                installed = true;
+            }
+        }
+        installed
+    }
+
+    /// Quantize every candidate on the calling thread (not the audio thread)
+    pub fn compact(&mut self) {
+        for buffer in self.compaction_candidates() {
+            let quantized = Arc::new(QuantizedBuffer::encode(&buffer));
+            self.install_compacted(&buffer, quantized);
+        }
+    }
+
     /// Clear all history
     pub fn clear(&mut self) {
         self.history.clear();
@@ -173,21 +503,21 @@ mod tests {
 
         // Save initial state
         let mut snapshot = LayerSnapshot::new();
-        snapshot.buffer = vec![1.0, 2.0, 3.0];
+        snapshot.buffer = Arc::new(vec![1.0, 2.0, 3.0]);
         history.save_state(snapshot);
 
         // Save second state
         let mut snapshot2 = LayerSnapshot::new();
-        snapshot2.buffer = vec![4.0, 5.0, 6.0];
+        snapshot2.buffer = Arc::new(vec![4.0, 5.0, 6.0]);
         history.save_state(snapshot2);
 
         // Test redo (should go back to first state)
         let undo_result = history.undo().unwrap();
-        assert_eq!(undo_result.buffer, vec![1.0, 2.0, 3.0]);
+        assert_eq!(*undo_result.buffer, vec![1.0, 2.0, 3.0]);
 
         // Test redo (should go forward to second state)
         let redo_result = history.redo().unwrap();
-        assert_eq!(redo_result.buffer, vec![4.0, 5.0, 6.0]);
+        assert_eq!(*redo_result.buffer, vec![4.0, 5.0, 6.0]);
     }
 
     #[test]
@@ -197,7 +527,7 @@ mod tests {
         // Add more than max levels
         for i in 0..6 {
             let mut snapshot = LayerSnapshot::new();
-            snapshot.buffer = vec![i as f32];
+            snapshot.buffer = Arc::new(vec![i as f32]);
             history.save_state(snapshot);
         }
 
@@ -243,7 +573,7 @@ mod tests {
         // Add 3 states
         for i in 0..3 {
             let mut snapshot = LayerSnapshot::new();
-            snapshot.buffer = vec![i as f32];
+            snapshot.buffer = Arc::new(vec![i as f32]);
             history.save_state(snapshot);
         }
 
@@ -253,10 +583,161 @@ mod tests {
 
         // Save new state (should truncate future)
         let mut snapshot = LayerSnapshot::new();
-        snapshot.buffer = vec![99.0];
+        snapshot.buffer = Arc::new(vec![99.0]);
         history.save_state(snapshot);
 
         // Should not be able to redo to old future state
         assert!(!history.can_redo());
     }
+
+    #[test]
+    fn test_share_buffer_reuses_unchanged_audio() {
+        let mut history = UndoHistory::new();
+        let mut snapshot = LayerSnapshot::new();
+        snapshot.buffer = Arc::new(vec![0.1, 0.2, 0.3]);
+        history.save_state(snapshot);
+
+        let shared = history.share_buffer(&[0.1, 0.2, 0.3]);
+        assert!(Arc::ptr_eq(&shared, &history.current().unwrap().buffer));
+
+        let copied = history.share_buffer(&[0.1, 0.2, 0.4]);
+        assert!(!Arc::ptr_eq(&copied, &history.current().unwrap().buffer));
+    }
+
+    #[test]
+    fn test_matches_current_ignores_playback_position() {
+        let mut history = UndoHistory::new();
+        let mut snapshot = LayerSnapshot::new();
+        snapshot.buffer = Arc::new(vec![1.0; 8]);
+        history.save_state(snapshot.clone());
+
+        snapshot.playback_position = 5;
+        assert!(history.matches_current(&snapshot));
+
+        snapshot.volume = 0.5;
+        assert!(!history.matches_current(&snapshot));
+    }
+
+    #[test]
+    fn test_long_snapshots_are_quantized() {
+        let mut history = UndoHistory::new();
+        history.set_compression_threshold(Some(1000));
+
+        let original: Vec<f32> = (0..4000).map(|i| (i as f32 * 0.01).sin() * 0.8).collect();
+        let mut snapshot = LayerSnapshot::new();
+        snapshot.buffer = Arc::new(original.clone());
+        history.save_state(snapshot);
+
+        let mut snapshot2 = LayerSnapshot::new();
+        snapshot2.buffer = Arc::new(vec![0.25; 4000]);
+        history.save_state(snapshot2);
+        // Nothing is quantized until compaction runs
+        assert_eq!(history.memory_bytes(), 16000 + 16000);
+        history.compact();
+
+        // Inactive 4000-sample snapshot stored as i16 (8000 bytes) + current f32 (16000)
+        assert_eq!(history.memory_bytes(), 8000 + 16000);
+
+        // Undo restores audio within 16-bit resolution
+        let restored = history.undo().unwrap();
+        assert_eq!(restored.buffer.len(), original.len());
+        for (orig, rest) in original.iter().zip(restored.buffer.iter()) {
+            assert!((orig - rest).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn test_parked_state_is_kept_16_bit() {
+        let mut history = UndoHistory::new();
+        let audio = Arc::new(vec![0.5; 4000]);
+        let mut snapshot = LayerSnapshot::new();
+        snapshot.buffer = Arc::clone(&audio);
+        history.save_state(snapshot.clone());
+        history.save_state(LayerSnapshot {
+            volume: 0.5,
+            ..snapshot
+        });
+
+        let quantized = Arc::new(QuantizedBuffer::encode(&audio));
+        assert!(history.park_current(&audio, Arc::clone(&quantized)));
+        assert!(!history.park_current(&audio, Arc::clone(&quantized)));
+        // Both states shared the audio, so only the 16-bit copy is left
+        assert_eq!(history.memory_bytes(), 8000);
+
+        // A state saved while parked shares it; audio saved after doesn't
+        let mut parked = history.get_current().unwrap();
+        parked.volume = 0.25;
+        history.save_state(parked);
+        assert!(Arc::ptr_eq(history.parked().unwrap(), &quantized));
+        let cleared = LayerSnapshot::new();
+        assert!(!history.matches_current(&cleared));
+        assert!(history.share_buffer(&[]).is_empty());
+        assert!(!Arc::ptr_eq(
+            &history.share_buffer(&[]),
+            &history.current().unwrap().buffer
+        ));
+
+        history.unpark_current(Arc::new(quantized.decode()));
+        assert!(history.parked().is_none());
+        assert_eq!(history.memory_bytes(), 16000);
+        assert_eq!(history.undo().unwrap().buffer.as_slice(), audio.as_slice());
+    }
+
+    #[test]
+    fn test_snapshots_restore_roundtrip() {
+        let mut history = UndoHistory::new();
+        history.set_compression_threshold(Some(1000));
+        for value in [0.25, 0.5, 0.25] {
+            let mut snapshot = LayerSnapshot::new();
+            snapshot.buffer = Arc::new(vec![value; 2000]);
+            history.save_state(snapshot);
+        }
+        history.undo().unwrap();
+        history.compact();
+
+        let (snapshots, current) = history.snapshots();
+        assert_eq!((snapshots.len(), current), (3, 1));
+        assert!((snapshots[0].buffer[0] - 0.25).abs() < 1e-4);
+
+        let mut restored = UndoHistory::new_with_levels(2);
+        restored.restore(snapshots, current);
+        assert_eq!(restored.history_size(), 2);
+        assert_eq!((restored.undo_levels(), restored.redo_levels()), (0, 1));
+        assert_eq!(restored.current().unwrap().buffer[0], 0.5);
+    }
+
+    #[test]
+    fn test_compaction_skips_a_buffer_made_current() {
+        let mut history = UndoHistory::new();
+        history.set_compression_threshold(Some(1000));
+        for value in [0.25, 0.5] {
+            let mut snapshot = LayerSnapshot::new();
+            snapshot.buffer = Arc::new(vec![value; 2000]);
+            history.save_state(snapshot);
+        }
+        let candidates = history.compaction_candidates();
+        assert_eq!(candidates.len(), 1);
+        let quantized = Arc::new(QuantizedBuffer::encode(&candidates[0]));
+
+        // Undone to while it was being encoded: it stays full precision
+        history.undo().unwrap();
+        assert!(!history.install_compacted(&candidates[0], quantized));
+        assert_eq!(history.memory_bytes(), 8000 + 8000);
+
+        history.set_compression_threshold(None);
+        assert!(history.compaction_candidates().is_empty());
+    }
+
+    #[test]
+    fn test_short_snapshots_stay_full_precision() {
+        let mut history = UndoHistory::new();
+        for i in 0..3 {
+            let mut snapshot = LayerSnapshot::new();
+            snapshot.buffer = Arc::new(vec![0.123_456_7 * i as f32; 64]);
+            history.save_state(snapshot);
+        }
+
+        let restored = history.undo().unwrap();
+        assert_eq!(*restored.buffer, vec![0.123_456_7; 64]);
+    }
 }
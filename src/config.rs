@@ -0,0 +1,308 @@
+// src/config.rs
+// User preferences read from soundlooper.toml at startup (written by the setup wizard)
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::Duration;
+
+use crate::audio::io::DEFAULT_MAX_IMPORT_SECONDS;
+use crate::audio::{ClickSound, ExportHook};
+
+/// Contents of the config file; every section and key is optional
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct AppConfig {
+    pub audio: DeviceConfig,
+    pub tempo: TempoConfig,
+    pub metronome: MetronomeConfig,
+    pub export: ExportConfig,
+    pub import: ImportConfig,
+    pub safety: SafetyConfig,
+    pub session: SessionConfig,
+    pub ui: UiConfig,
+    pub keys: KeysConfig,
+}
+
+/// Devices by name and hardware buffer; unset keys use the system defaults
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct DeviceConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub host: Option<String>, // audio backend, e.g. "ALSA", "JACK", "WASAPI"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub input_device: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_device: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub buffer_size: Option<u32>, // frames; smaller is lower latency but may crackle
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latency_compensation_ms: Option<u32>, // shift recordings earlier (Bluetooth devices)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub input_high_pass_hz: Option<f32>, // 40-120 Hz rumble filter on recorded input
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stereo_width_percent: Option<u32>, // main bus width: 0 mono, 100 as mixed, up to 150
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_size: Option<u32>, // frames the engine processes at a time (default: per callback)
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct TempoConfig {
+    pub bpm: f64, // starting tempo
+}
+
+impl Default for TempoConfig {
+    fn default() -> Self {
+        Self { bpm: 120.0 }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct MetronomeConfig {
+    pub sound: ClickSound,
+    pub pre_count: bool, // click the rest of the bar before an armed synced record
+    pub count_in_bars: u32, // count-in length: 1, 2 or 4 bars
+}
+
+impl Default for MetronomeConfig {
+    fn default() -> Self {
+        Self {
+            sound: ClickSound::default(),
+            pre_count: false,
+            count_in_bars: 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ExportConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bit_depth: Option<u16>, // 16, 24 or 32 (float, the default); 16 and 24 are dithered
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub directory: Option<String>, // where snapshot exports go (default "exports")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hook: Option<String>, // shell command run on each exported WAV ({file} = path)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub upload_url: Option<String>, // http:// URL each export is PUT to ({name} = file name)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name_template: Option<String>, // e.g. "{session}_{bpm}bpm_{date}_{n}.wav"
+}
+
+impl ExportConfig {
+    pub const DEFAULT_DIRECTORY: &str = "exports";
+
+    /// Post-export hook; a command wins over an upload URL
+    pub fn hook(&self) -> Option<ExportHook> {
+        self.hook
+            .clone()
+            .map(ExportHook::Command)
+            .or_else(|| self.upload_url.clone().map(ExportHook::HttpPut))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ImportConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub watch_folder: Option<String>, // new WAVs here go into the next empty layer
+    pub match_tempo: bool, // stretch files with an ACID tempo to the session BPM
+    pub max_seconds: u32,  // longer files are refused before decoding; 0 for no limit
+}
+
+impl Default for ImportConfig {
+    fn default() -> Self {
+        Self {
+            watch_folder: None,
+            match_tempo: false,
+            max_seconds: DEFAULT_MAX_IMPORT_SECONDS,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SafetyConfig {
+    pub confirm_clear: bool, // C / X must be pressed twice on recorded layers
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SessionConfig {
+    pub save_history: bool, // undo states and takes saved with sessions (larger files)
+    pub park_idle_layers: bool, // stopped layers kept 16-bit until played again (lossy)
+    pub compress_undo: bool, // inactive undo states of long loops kept 16-bit (lossy)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub event_log: Option<String>, // directory for a JSONL log of every command and event
+}
+
+impl Default for SafetyConfig {
+    fn default() -> Self {
+        Self {
+            confirm_clear: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct UiConfig {
+    pub refresh_ms: u64,             // shortest time between redraws
+    pub low_power: bool,             // redraw at most 4 times a second (battery)
+    pub bell: bool,                  // ring the terminal bell on critical errors
+    pub desktop_notifications: bool, // also notify the desktop (`notifications` feature)
+}
+
+impl UiConfig {
+    pub const LOW_POWER_REFRESH: Duration = Duration::from_millis(250);
+
+    /// Redraw interval; low-power mode never goes faster than `LOW_POWER_REFRESH`
+    pub fn refresh(&self) -> Duration {
+        let refresh = Duration::from_millis(self.refresh_ms.max(1));
+        if self.low_power {
+            refresh.max(Self::LOW_POWER_REFRESH)
+        } else {
+            refresh
+        }
+    }
+}
+
+impl Default for UiConfig {
+    fn default() -> Self {
+        Self {
+            refresh_ms: 50,
+            low_power: false,
+            bell: true,
+            desktop_notifications: false,
+        }
+    }
+}
+
+/// Record/stop/play key per layer, in layer order: one character or F1-F12
+/// each; unset means 1-9, 0 and F1-F6
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct KeysConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub layers: Option<Vec<String>>,
+}
+
+impl AppConfig {
+    /// Looked up in the working directory unless `--config` names another file
+    pub const DEFAULT_PATH: &str = "soundlooper.toml";
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        toml::from_str(&text).with_context(|| format!("Invalid config {}", path.display()))
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        let text = toml::to_string_pretty(self).context("Failed to serialize config")?;
+        std::fs::write(path, text).with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// The default file is optional; a missing one means default settings
+    pub fn load_default() -> Result<Self> {
+        if Path::new(Self::DEFAULT_PATH).exists() {
+            Self::load(Self::DEFAULT_PATH)
+        } else {
+            Ok(Self::default())
+        }
+    }
+
+    /// Record devices picked during a session so the next start reopens them
+    /// Re-reads the file so settings edited elsewhere aren't overwritten; an
+    /// invalid file is left alone
+    pub fn save_devices<P: AsRef<Path>>(path: P, devices: &DeviceConfig) -> Result<()> {
+        let path = path.as_ref();
+        let mut config = if path.exists() {
+            Self::load(path)?
+        } else {
+            Self::default()
+        };
+        config.audio = DeviceConfig {
+            latency_compensation_ms: config.audio.latency_compensation_ms,
+            block_size: config.audio.block_size,
+            input_high_pass_hz: config.audio.input_high_pass_hz,
+            stereo_width_percent: config.audio.stereo_width_percent,
+            ..devices.clone()
+        };
+        config.save(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::ClickFlavor;
+
+    #[test]
+    fn test_parse_metronome_sound() {
+        let config: AppConfig =
+            toml::from_str("[metronome]\nsound = \"tick\"\npre_count = true\ncount_in_bars = 2\n")
+                .unwrap();
+        assert_eq!(config.metronome.sound, ClickSound::Synth(ClickFlavor::Tick));
+        assert!(config.metronome.pre_count);
+        assert_eq!(config.metronome.count_in_bars, 2);
+        assert!(config.safety.confirm_clear);
+        assert_eq!(config.ui.refresh(), Duration::from_millis(50));
+        let config: AppConfig =
+            toml::from_str("[ui]\nrefresh_ms = 20\nlow_power = true\n").unwrap();
+        assert_eq!(config.ui.refresh(), UiConfig::LOW_POWER_REFRESH);
+        let config: AppConfig = toml::from_str("").unwrap();
+        assert_eq!(config, AppConfig::default());
+    }
+
+    #[test]
+    fn test_config_round_trip() {
+        let mut config = AppConfig::default();
+        config.audio.output_device = Some("USB Audio".to_string());
+        config.audio.buffer_size = Some(128);
+        config.tempo.bpm = 94.5;
+        config.metronome.sound = ClickSound::Synth(ClickFlavor::Wood);
+        let text = toml::to_string_pretty(&config).unwrap();
+        assert!(!text.contains("input_device"));
+        assert_eq!(toml::from_str::<AppConfig>(&text).unwrap(), config);
+    }
+
+    #[test]
+    fn test_save_devices_keeps_other_settings() {
+        let path =
+            std::env::temp_dir().join(format!("soundlooper-devices-{}.toml", std::process::id()));
+        let mut config = AppConfig::default();
+        config.tempo.bpm = 100.0;
+        config.audio.input_device = Some("Old Mic".to_string());
+        config.audio.latency_compensation_ms = Some(120);
+        config.audio.block_size = Some(128);
+        config.audio.input_high_pass_hz = Some(80.0);
+        config.audio.stereo_width_percent = Some(0);
+        config.save(&path).unwrap();
+
+        let devices = DeviceConfig {
+            host: Some("ALSA".to_string()),
+            input_device: Some("USB Audio".to_string()),
+            output_device: Some("Headphones".to_string()),
+            buffer_size: Some(256),
+            latency_compensation_ms: None,
+            block_size: None,
+            input_high_pass_hz: None,
+            stereo_width_percent: None,
+        };
+        AppConfig::save_devices(&path, &devices).unwrap();
+        let saved = AppConfig::load(&path).unwrap();
+        assert_eq!(saved.tempo.bpm, 100.0);
+        assert_eq!(saved.audio.input_device.as_deref(), Some("USB Audio"));
+        assert_eq!(saved.audio.buffer_size, Some(256));
+        assert_eq!(saved.audio.latency_compensation_ms, Some(120));
+        assert_eq!(saved.audio.block_size, Some(128));
+        assert_eq!(saved.audio.input_high_pass_hz, Some(80.0));
+        assert_eq!(saved.audio.stereo_width_percent, Some(0));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
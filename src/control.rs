@@ -0,0 +1,412 @@
+// src/control.rs
+// Text command protocol for driving a running instance (stream decks, scripts)
+
+use anyhow::{Context, Result, anyhow};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+
+use crate::audio::{
+    CommandSender, ExportNormalization, ExportRegion, ImportMode, LayerCommand, MAX_LAYERS,
+    MixQuantize,
+};
+
+/// Sent in reply to `help`; layers are numbered from 1 as in the UI
+pub const HELP: &str = "\
+select N | record N | stop-record N | overdub N | play N | stop N | cancel N | cue N
+retake N | keep N | revert N | take N K | keep-take N
+sync-record N | sync-play N | sync-stop N
+mute N | solo N | solo-safe N | fit N | freeze N | learn-noise N | denoise N | declick N | remove-dc N | info N | lock N | volume N 0.0-1.0 | clear N | undo N | redo N
+play-all | stop-all | clear-all | undo | redo | reset-peaks
+bpm BPM | tap | sync on|off | metronome on|off | quantize off|beat|measure | highpass off|HZ | width 0-150
+transport pause|resume|reset | downbeat
+marker N NAME | jump N NAME | import N FILE
+master N | master clear | master-samples SAMPLES
+save FILE | save-template FILE | load FILE | export FILE
+export-loop CYCLES FILE | export-bars FIRST-LAST FILE | export-pack DIR";
+
+/// Parse one line such as `play 3` or `volume 2 0.8`
+pub fn parse_command(line: &str) -> Result<LayerCommand> {
+    let mut words = line.split_whitespace();
+    let verb = words.next().ok_or_else(|| anyhow!("Empty command"))?;
+    let mut arg = |what: &str| {
+        words
+            .next()
+            .ok_or_else(|| anyhow!("{} needs {}", verb, what))
+    };
+
+    let command = match verb.to_lowercase().as_str() {
+        "select" => LayerCommand::SelectLayer(layer(arg("a layer")?)?),
+        "record" => LayerCommand::Record(layer(arg("a layer")?)?),
+        "stop-record" => LayerCommand::StopRecording(layer(arg("a layer")?)?),
+        "overdub" => LayerCommand::Overdub(layer(arg("a layer")?)?),
+        "retake" => LayerCommand::RecordReplacement(layer(arg("a layer")?)?),
+        "keep" => LayerCommand::KeepReplacement(layer(arg("a layer")?)?),
+        "revert" => LayerCommand::RevertReplacement(layer(arg("a layer")?)?),
+        "take" => {
+            let layer_id = layer(arg("a layer and a take")?)?;
+            // Takes are numbered from 1 like layers
+            let take = layer(arg("a take")?).map_err(|_| anyhow!("Invalid take"))?;
+            LayerCommand::SelectTake(layer_id, take)
+        }
+        "keep-take" => LayerCommand::KeepTake(layer(arg("a layer")?)?),
+        "play" => LayerCommand::Play(layer(arg("a layer")?)?),
+        // On the next measure, as the UI does with beat sync on
+        "sync-record" => LayerCommand::SyncRecord(layer(arg("a layer")?)?),
+        "sync-play" => LayerCommand::SyncPlay(layer(arg("a layer")?)?),
+        "sync-stop" => LayerCommand::SyncStop(layer(arg("a layer")?)?),
+        "stop" => LayerCommand::StopPlaying(layer(arg("a layer")?)?),
+        "cancel" => LayerCommand::CancelPending(layer(arg("a layer")?)?),
+        "cue" => LayerCommand::ToggleCue(layer(arg("a layer")?)?),
+        "mute" => LayerCommand::Mute(layer(arg("a layer")?)?),
+        "solo" => LayerCommand::Solo(layer(arg("a layer")?)?),
+        "solo-safe" => LayerCommand::ToggleSoloSafe(layer(arg("a layer")?)?),
+        "fit" => LayerCommand::ToggleFitToMaster(layer(arg("a layer")?)?),
+        "freeze" => LayerCommand::FreezeLayer(layer(arg("a layer")?)?),
+        "learn-noise" => LayerCommand::LearnNoise(layer(arg("a layer")?)?),
+        "denoise" => LayerCommand::Denoise(layer(arg("a layer")?)?),
+        "declick" => LayerCommand::Declick(layer(arg("a layer")?)?),
+        "remove-dc" => LayerCommand::RemoveDcOffset(layer(arg("a layer")?)?),
+        "info" => LayerCommand::AnalyzeLayer(layer(arg("a layer")?)?),
+        "lock" => LayerCommand::ToggleLock(layer(arg("a layer")?)?),
+        "clear" => LayerCommand::Clear(layer(arg("a layer")?)?),
+        "volume" => {
+            let layer_id = layer(arg("a layer")?)?;
+            let volume: f32 = arg("a volume")?
+                .parse()
+                .map_err(|_| anyhow!("Invalid volume"))?;
+            LayerCommand::SetVolume(layer_id, volume.clamp(0.0, 1.0))
+        }
+        // Without a layer these undo/redo the last action on any layer
+        "undo" => match arg("a layer").ok() {
+            Some(n) => LayerCommand::Undo(layer(n)?),
+            None => LayerCommand::GlobalUndo,
+        },
+        "redo" => match arg("a layer").ok() {
+            Some(n) => LayerCommand::Redo(layer(n)?),
+            None => LayerCommand::GlobalRedo,
+        },
+        "play-all" => LayerCommand::PlayAll,
+        "stop-all" => LayerCommand::StopAll,
+        "clear-all" => LayerCommand::ClearAll,
+        "bpm" => LayerCommand::SetBpm(
+            arg("a tempo")?
+                .parse()
+                .map_err(|_| anyhow!("Invalid BPM"))?,
+        ),
+        "master" => match arg("a layer or clear")? {
+            w if w.eq_ignore_ascii_case("clear") => LayerCommand::ClearMasterLength,
+            n => LayerCommand::MasterFromLayer(layer(n)?),
+        },
+        "master-samples" => LayerCommand::SetMasterLength(
+            arg("a length")?
+                .parse()
+                .map_err(|_| anyhow!("Invalid length"))?,
+        ),
+        "tap" => LayerCommand::TapTempo,
+        // Set bar 1 here
+        "downbeat" => LayerCommand::ResetTransport,
+        "transport" => match arg("pause, resume or reset")? {
+            w if w.eq_ignore_ascii_case("pause") => LayerCommand::PauseTransport(true),
+            w if w.eq_ignore_ascii_case("resume") => LayerCommand::PauseTransport(false),
+            w if w.eq_ignore_ascii_case("reset") => LayerCommand::ResetTransport,
+            w => return Err(anyhow!("Expected pause, resume or reset, got {}", w)),
+        },
+        "sync" => LayerCommand::ToggleBeatSync(on_off(arg("on or off")?)?),
+        "metronome" => LayerCommand::ToggleMetronome(on_off(arg("on or off")?)?),
+        "quantize" => LayerCommand::SetMixQuantize(match arg("off, beat or measure")? {
+            w if w.eq_ignore_ascii_case("off") => MixQuantize::Off,
+            w if w.eq_ignore_ascii_case("beat") => MixQuantize::Beat,
+            w if w.eq_ignore_ascii_case("measure") => MixQuantize::Measure,
+            w => return Err(anyhow!("Expected off, beat or measure, got {}", w)),
+        }),
+        "reset-peaks" => LayerCommand::ResetPeaks,
+        "highpass" => LayerCommand::SetInputHighPass(match arg("off or a cutoff in Hz")? {
+            w if w.eq_ignore_ascii_case("off") => None,
+            w => Some(w.parse().with_context(|| format!("Bad cutoff: {}", w))?),
+        }),
+        // Percent, as the UI shows it
+        "width" => {
+            let percent: f32 = arg("a width in percent")?
+                .parse()
+                .map_err(|_| anyhow!("Invalid width"))?;
+            LayerCommand::SetStereoWidth(percent / 100.0)
+        }
+        "marker" | "jump" => {
+            let layer_id = layer(arg("a layer")?)?;
+            let name = arg("a marker name")?.to_string();
+            if verb.eq_ignore_ascii_case("marker") {
+                LayerCommand::SetMarker { layer_id, name }
+            } else {
+                LayerCommand::JumpToMarker { layer_id, name }
+            }
+        }
+        // Paths are the rest of the line so they may contain spaces
+        "import" => {
+            let layer_id = layer(arg("a layer")?)?;
+            let path = rest(line, 2).ok_or_else(|| anyhow!("import needs a file"))?;
+            LayerCommand::ImportWav(layer_id, path, ImportMode::Replace)
+        }
+        "save" => {
+            LayerCommand::SaveSession(rest(line, 1).ok_or_else(|| anyhow!("save needs a file"))?)
+        }
+        "save-template" => LayerCommand::SaveTemplate(
+            rest(line, 1).ok_or_else(|| anyhow!("save-template needs a file"))?,
+        ),
+        "load" => {
+            LayerCommand::LoadSession(rest(line, 1).ok_or_else(|| anyhow!("load needs a file"))?)
+        }
+        "export" => LayerCommand::ExportWav(
+            rest(line, 1).ok_or_else(|| anyhow!("export needs a file"))?,
+            ExportNormalization::Peak,
+        ),
+        // Master loop cycles or a bar range, rendered to an exact length
+        "export-loop" | "export-bars" => {
+            let region: ExportRegion = arg("a length")?.parse().map_err(|e: String| anyhow!(e))?;
+            let path = rest(line, 2).ok_or_else(|| anyhow!("{} needs a file", verb))?;
+            LayerCommand::ExportLoop(path, region, ExportNormalization::Peak)
+        }
+        "export-pack" => LayerCommand::ExportLoopPack(
+            rest(line, 1).ok_or_else(|| anyhow!("export-pack needs a directory"))?,
+        ),
+        _ => return Err(anyhow!("Unknown command: {} (try help)", verb)),
+    };
+    Ok(command)
+}
+
+/// The line `parse_command` reads back as `command`, for replay scripts; None for
+/// commands the protocol has no verb for (device switches, UI settings)
+pub fn format_command(command: &LayerCommand) -> Option<String> {
+    // Paths and marker names must survive the round trip
+    let word = |name: &str| !name.is_empty() && !name.contains(char::is_whitespace);
+    let path = |path: &str| path.trim() == path && !path.is_empty();
+    let on_off = |on: bool| if on { "on" } else { "off" };
+    let line = match command {
+        LayerCommand::Tracked { cmd, .. } => return format_command(cmd),
+        LayerCommand::Record(id) => format!("record {}", id + 1),
+        LayerCommand::StopRecording(id) => format!("stop-record {}", id + 1),
+        LayerCommand::Overdub(id) => format!("overdub {}", id + 1),
+        LayerCommand::RecordReplacement(id) => format!("retake {}", id + 1),
+        LayerCommand::KeepReplacement(id) => format!("keep {}", id + 1),
+        LayerCommand::RevertReplacement(id) => format!("revert {}", id + 1),
+        LayerCommand::SelectTake(id, take) => format!("take {} {}", id + 1, take + 1),
+        LayerCommand::KeepTake(id) => format!("keep-take {}", id + 1),
+        LayerCommand::SelectLayer(id) => format!("select {}", id + 1),
+        LayerCommand::Play(id) => format!("play {}", id + 1),
+        LayerCommand::SyncRecord(id) => format!("sync-record {}", id + 1),
+        LayerCommand::SyncPlay(id) => format!("sync-play {}", id + 1),
+        LayerCommand::SyncStop(id) => format!("sync-stop {}", id + 1),
+        LayerCommand::StopPlaying(id) => format!("stop {}", id + 1),
+        LayerCommand::CancelPending(id) => format!("cancel {}", id + 1),
+        LayerCommand::ToggleCue(id) => format!("cue {}", id + 1),
+        LayerCommand::Mute(id) => format!("mute {}", id + 1),
+        LayerCommand::Solo(id) => format!("solo {}", id + 1),
+        LayerCommand::ToggleSoloSafe(id) => format!("solo-safe {}", id + 1),
+        LayerCommand::ToggleFitToMaster(id) => format!("fit {}", id + 1),
+        LayerCommand::FreezeLayer(id) => format!("freeze {}", id + 1),
+        LayerCommand::LearnNoise(id) => format!("learn-noise {}", id + 1),
+        LayerCommand::Denoise(id) => format!("denoise {}", id + 1),
+        LayerCommand::Declick(id) => format!("declick {}", id + 1),
+        LayerCommand::RemoveDcOffset(id) => format!("remove-dc {}", id + 1),
+        LayerCommand::AnalyzeLayer(id) => format!("info {}", id + 1),
+        LayerCommand::ToggleLock(id) => format!("lock {}", id + 1),
+        LayerCommand::Clear(id) => format!("clear {}", id + 1),
+        LayerCommand::SetVolume(id, volume) => format!("volume {} {}", id + 1, volume),
+        LayerCommand::Undo(id) => format!("undo {}", id + 1),
+        LayerCommand::Redo(id) => format!("redo {}", id + 1),
+        LayerCommand::GlobalUndo => "undo".to_string(),
+        LayerCommand::GlobalRedo => "redo".to_string(),
+        LayerCommand::PlayAll => "play-all".to_string(),
+        LayerCommand::StopAll => "stop-all".to_string(),
+        LayerCommand::ClearAll => "clear-all".to_string(),
+        LayerCommand::SetBpm(bpm) => format!("bpm {}", bpm),
+        LayerCommand::MasterFromLayer(id) => format!("master {}", id + 1),
+        LayerCommand::ClearMasterLength => "master clear".to_string(),
+        LayerCommand::SetMasterLength(samples) => format!("master-samples {}", samples),
+        LayerCommand::TapTempo => "tap".to_string(),
+        LayerCommand::ResetTransport => "transport reset".to_string(),
+        LayerCommand::PauseTransport(true) => "transport pause".to_string(),
+        LayerCommand::PauseTransport(false) => "transport resume".to_string(),
+        LayerCommand::ToggleBeatSync(on) => format!("sync {}", on_off(*on)),
+        LayerCommand::ToggleMetronome(on) => format!("metronome {}", on_off(*on)),
+        LayerCommand::SetMixQuantize(quantize) => format!(
+            "quantize {}",
+            match quantize {
+                MixQuantize::Off => "off",
+                MixQuantize::Beat => "beat",
+                MixQuantize::Measure => "measure",
+            }
+        ),
+        LayerCommand::ResetPeaks => "reset-peaks".to_string(),
+        LayerCommand::SetInputHighPass(None) => "highpass off".to_string(),
+        LayerCommand::SetInputHighPass(Some(hz)) => format!("highpass {}", hz),
+        LayerCommand::SetStereoWidth(width) => format!("width {}", (width * 100.0).round()),
+        LayerCommand::SetMarker { layer_id, name } if word(name) => {
+            format!("marker {} {}", layer_id + 1, name)
+        }
+        LayerCommand::JumpToMarker { layer_id, name } if word(name) => {
+            format!("jump {} {}", layer_id + 1, name)
+        }
+        LayerCommand::ImportWav(id, file, ImportMode::Replace) if path(file) => {
+            format!("import {} {}", id + 1, file)
+        }
+        LayerCommand::SaveSession(file) if path(file) => format!("save {}", file),
+        LayerCommand::SaveTemplate(file) if path(file) => format!("save-template {}", file),
+        LayerCommand::LoadSession(file) if path(file) => format!("load {}", file),
+        LayerCommand::ExportWav(file, ExportNormalization::Peak) if path(file) => {
+            format!("export {}", file)
+        }
+        LayerCommand::ExportLoopPack(directory) if path(directory) => {
+            format!("export-pack {}", directory)
+        }
+        LayerCommand::ExportLoop(file, region, ExportNormalization::Peak) if path(file) => {
+            match region {
+                ExportRegion::Cycles(cycles) => format!("export-loop {} {}", cycles, file),
+                ExportRegion::Bars { first, last } => {
+                    format!("export-bars {}-{} {}", first, last, file)
+                }
+            }
+        }
+        _ => return None,
+    };
+    Some(line)
+}
+
+/// 1-based layer number to layer id
+fn layer(word: &str) -> Result<usize> {
+    match word.parse::<usize>() {
+        Ok(n) if (1..=MAX_LAYERS).contains(&n) => Ok(n - 1),
+        _ => Err(anyhow!("Invalid layer: {}", word)),
+    }
+}
+
+fn on_off(word: &str) -> Result<bool> {
+    match word.to_lowercase().as_str() {
+        "on" => Ok(true),
+        "off" => Ok(false),
+        _ => Err(anyhow!("Expected on or off, got {}", word)),
+    }
+}
+
+/// Everything after the first `skip` words
+fn rest(line: &str, skip: usize) -> Option<String> {
+    let mut remaining = line.trim_start();
+    for _ in 0..skip {
+        let end = remaining.find(char::is_whitespace)?;
+        remaining = remaining[end..].trim_start();
+    }
+    let remaining = remaining.trim_end();
+    (!remaining.is_empty()).then(|| remaining.to_string())
+}
+
+/// Listen for control connections; each line is one command, answered with
+/// `ok` or `error: ...` (`ok` means queued; engine errors show in the UI)
+pub fn spawn_control_server(addr: &str, sender: CommandSender) -> Result<SocketAddr> {
+    let listener =
+        TcpListener::bind(addr).with_context(|| format!("Failed to listen on {}", addr))?;
+    let local_addr = listener.local_addr()?;
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let sender = sender.clone();
+            std::thread::spawn(move || {
+                let _ = serve_connection(stream, &sender);
+            });
+        }
+    });
+    Ok(local_addr)
+}
+
+fn serve_connection(stream: TcpStream, sender: &CommandSender) -> std::io::Result<()> {
+    let mut writer = stream.try_clone()?;
+    for line in BufReader::new(stream).lines() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if line.eq_ignore_ascii_case("help") {
+            writeln!(writer, "{}", HELP)?;
+            continue;
+        }
+        let reply = match parse_command(line) {
+            Ok(command) => match sender.send(command) {
+                Ok(()) => "ok".to_string(),
+                Err(e) => format!("error: {}", e),
+            },
+            Err(e) => format!("error: {}", e),
+        };
+        writeln!(writer, "{}", reply)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_commands() {
+        assert_eq!(parse_command("play 3").unwrap(), LayerCommand::Play(2));
+        assert_eq!(
+            parse_command("VOLUME 1 0.5").unwrap(),
+            LayerCommand::SetVolume(0, 0.5)
+        );
+        assert_eq!(parse_command("undo").unwrap(), LayerCommand::GlobalUndo);
+        assert_eq!(
+            parse_command("metronome off").unwrap(),
+            LayerCommand::ToggleMetronome(false)
+        );
+        assert_eq!(
+            parse_command("import 2 my loops/drums.wav").unwrap(),
+            LayerCommand::ImportWav(1, "my loops/drums.wav".to_string(), ImportMode::Replace)
+        );
+        assert_eq!(
+            parse_command("quantize measure").unwrap(),
+            LayerCommand::SetMixQuantize(MixQuantize::Measure)
+        );
+        assert_eq!(
+            parse_command("export-bars 5-8 drop.wav").unwrap(),
+            LayerCommand::ExportLoop(
+                "drop.wav".to_string(),
+                ExportRegion::Bars { first: 5, last: 8 },
+                ExportNormalization::Peak
+            )
+        );
+        assert!(parse_command("export-loop 0 loop.wav").is_err());
+        assert!(parse_command("play 0").is_err());
+        assert!(parse_command("sync-stop 99").is_err());
+        assert!(parse_command("play").is_err());
+        assert!(parse_command("dance").is_err());
+    }
+
+    #[test]
+    fn test_format_round_trip() {
+        for line in [
+            "take 2 3",
+            "volume 1 0.5",
+            "sync-play 4",
+            "undo",
+            "quantize beat",
+            "highpass 80",
+            "width 50",
+            "import 2 my loops/drums.wav",
+            "export-bars 5-8 drop.wav",
+            "export-pack packs/night session",
+            "remove-dc 3",
+            "select 12",
+        ] {
+            let command = parse_command(line).unwrap();
+            assert_eq!(format_command(&command).as_deref(), Some(line));
+        }
+        let tracked = LayerCommand::Tracked {
+            id: 7,
+            cmd: Box::new(LayerCommand::Play(0)),
+        };
+        assert_eq!(format_command(&tracked).as_deref(), Some("play 1"));
+        let marker = LayerCommand::SetMarker {
+            layer_id: 0,
+            name: "two words".to_string(),
+        };
+        assert_eq!(format_command(&marker), None);
+        assert_eq!(format_command(&LayerCommand::StopPreview), None);
+    }
+}
@@ -0,0 +1,417 @@
+// src/jam.rs
+// Two-instance jam over TCP: shared tempo and (optionally) each other's new loops
+
+use anyhow::{Context, Result, anyhow};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::audio::io::resample_audio;
+use crate::audio::{
+    AudioEvent, CommandSender, EventSender, LayerCommand, LooperEngine, LooperError,
+};
+
+/// Which side opens the connection
+#[derive(Debug, Clone, PartialEq)]
+pub enum JamRole {
+    Listen(String),  // wait for the peer on this address
+    Connect(String), // dial the peer (retried until it answers)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct JamOptions {
+    pub role: JamRole,
+    pub share_loops: bool, // send newly recorded/imported loops to the peer
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum JamMessage {
+    Tempo(f64),
+    Loop { sample_rate: u32, samples: Vec<f32> },
+}
+
+impl JamMessage {
+    const TEMPO: u8 = b'T';
+    const LOOP: u8 = b'L';
+    // A 10 minute loop at 96 kHz encodes well below this
+    const MAX_PAYLOAD: usize = 256 * 1024 * 1024;
+
+    /// Frame: type byte, payload length (u32 LE), payload
+    fn write_to(&self, writer: &mut impl Write) -> io::Result<()> {
+        let (kind, payload) = match self {
+            Self::Tempo(bpm) => (Self::TEMPO, bpm.to_le_bytes().to_vec()),
+            Self::Loop {
+                sample_rate,
+                samples,
+            } => {
+                let mut payload = Vec::with_capacity(8 + samples.len());
+                payload.extend_from_slice(&sample_rate.to_le_bytes());
+                payload.extend_from_slice(&(samples.len() as u32).to_le_bytes());
+                payload.extend_from_slice(&encode_samples(samples));
+                (Self::LOOP, payload)
+            }
+        };
+        writer.write_all(&[kind])?;
+        writer.write_all(&(payload.len() as u32).to_le_bytes())?;
+        writer.write_all(&payload)?;
+        writer.flush()
+    }
+
+    fn read_from(reader: &mut impl Read) -> io::Result<Self> {
+        let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+        let mut header = [0u8; 5];
+        reader.read_exact(&mut header)?;
+        let len = u32::from_le_bytes([header[1], header[2], header[3], header[4]]) as usize;
+        if len > Self::MAX_PAYLOAD {
+            return Err(invalid("jam message too large"));
+        }
+        let mut payload = vec![0u8; len];
+        reader.read_exact(&mut payload)?;
+
+        match header[0] {
+            Self::TEMPO if len == 8 => Ok(Self::Tempo(f64::from_le_bytes(
+                payload[..8].try_into().unwrap(),
+            ))),
+            Self::LOOP if len >= 8 => {
+                let sample_rate = u32::from_le_bytes(payload[0..4].try_into().unwrap());
+                let count = u32::from_le_bytes(payload[4..8].try_into().unwrap()) as usize;
+                // Every sample takes at least a byte; a bigger count is corrupt or hostile
+                if count > len - 8 {
+                    return Err(invalid("jam loop shorter than its sample count"));
+                }
+                let samples =
+                    decode_samples(&payload[8..], count).map_err(|e| invalid(&e.to_string()))?;
+                Ok(Self::Loop {
+                    sample_rate,
+                    samples,
+                })
+            }
+            _ => Err(invalid("unknown jam message")),
+        }
+    }
+}
+
+/// Loop compression: 16-bit samples, delta from the previous sample, zigzag + varint
+/// Audio changes little between neighbouring samples, so most deltas fit in 1-2 bytes
+pub fn encode_samples(samples: &[f32]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(samples.len() * 2);
+    let mut previous = 0i32;
+    for &sample in samples {
+        let quantized = (sample.clamp(-1.0, 1.0) * 32767.0).round() as i32;
+        let delta = quantized - previous;
+        previous = quantized;
+        let mut zigzag = ((delta << 1) ^ (delta >> 31)) as u32;
+        while zigzag >= 0x80 {
+            out.push((zigzag as u8 & 0x7f) | 0x80);
+            zigzag >>= 7;
+        }
+        out.push(zigzag as u8);
+    }
+    out
+}
+
+pub fn decode_samples(bytes: &[u8], count: usize) -> Result<Vec<f32>> {
+    let mut samples = Vec::with_capacity(count.min(bytes.len()));
+    let mut bytes = bytes.iter();
+    let mut previous = 0i32;
+    for _ in 0..count {
+        let mut zigzag = 0u32;
+        let mut shift = 0;
+        loop {
+            let byte = *bytes.next().ok_or_else(|| anyhow!("Truncated loop data"))?;
+            if shift > 28 {
+                return Err(anyhow!("Corrupt loop data"));
+            }
+            zigzag |= ((byte & 0x7f) as u32) << shift;
+            if byte & 0x80 == 0 {
+                break;
+            }
+            shift += 7;
+        }
+        let delta = (zigzag >> 1) as i32 ^ -((zigzag & 1) as i32);
+        // Only valid streams stay within 16 bits; garbage must not panic
+        previous = previous.wrapping_add(delta);
+        samples.push(previous as f32 / 32767.0);
+    }
+    Ok(samples)
+}
+
+/// State shared by the sending and receiving halves of a connection
+struct JamState {
+    engine: Arc<LooperEngine>,
+    commands: CommandSender,
+    events: EventSender,
+    // Last undo revision seen per layer; loops are sent when it changes
+    revisions: Mutex<Vec<u64>>,
+    // Tempo received from the peer, not yet applied by the engine (don't echo it)
+    remote_bpm: Mutex<Option<(f64, Instant)>>,
+}
+
+impl JamState {
+    // How often local tempo and layers are checked for changes
+    const POLL_INTERVAL: Duration = Duration::from_millis(100);
+    // Give up waiting for a received tempo to show up in the engine
+    const REMOTE_BPM_TIMEOUT: Duration = Duration::from_secs(1);
+
+    fn layer_revision(&self, layer_id: usize) -> Option<u64> {
+        let layer = self.engine.get_layer(layer_id)?;
+        let layer = layer.lock().ok()?;
+        Some(layer.undo_history.revision())
+    }
+
+    /// Put a received loop on the first free layer and start it in sync
+    fn receive_loop(&self, sample_rate: u32, samples: Vec<f32>) {
+        let target_rate = self.engine.get_config().sample_rate;
+        let samples = match resample_audio(&samples, sample_rate, target_rate, 1) {
+            Ok(samples) => samples,
+            Err(_) => return,
+        };
+        let free_layer = self.engine.get_layer_status().iter().position(|status| {
+            let status = status.snapshot();
+            status.is_empty() && !status.is_recording && !status.is_locked
+        });
+        let Some(layer_id) = free_layer else {
+            let _ = self
+                .events
+                .try_send(AudioEvent::Error(LooperError::BufferFull("All layers")));
+            return;
+        };
+        // Held across the load so the loop isn't sent straight back
+        let mut revisions = self.revisions.lock().unwrap();
+        if self.engine.load_audio_to_layer(layer_id, samples).is_ok() {
+            if let Some(revision) = self.layer_revision(layer_id) {
+                revisions[layer_id] = revision;
+            }
+            drop(revisions);
+            let _ = self.commands.send(LayerCommand::SyncPlay(layer_id));
+            let _ = self.events.try_send(AudioEvent::JamLoopReceived(layer_id));
+        }
+    }
+
+    fn receive(&self, stream: TcpStream, connected: &AtomicBool) {
+        let mut reader = BufReader::new(stream);
+        while let Ok(message) = JamMessage::read_from(&mut reader) {
+            match message {
+                JamMessage::Tempo(bpm) => {
+                    *self.remote_bpm.lock().unwrap() = Some((bpm, Instant::now()));
+                    let _ = self.commands.send(LayerCommand::SetBpm(bpm));
+                }
+                JamMessage::Loop {
+                    sample_rate,
+                    samples,
+                } => self.receive_loop(sample_rate, samples),
+            }
+        }
+        connected.store(false, Ordering::Relaxed);
+    }
+
+    /// Send local tempo changes (and new loops) until the connection drops
+    /// The listening side announces its tempo first so both start in step
+    fn send(&self, stream: TcpStream, announce: bool, share_loops: bool, connected: &AtomicBool) {
+        let mut writer = BufWriter::new(stream);
+        let mut last_bpm = self.engine.get_bpm();
+        if announce && JamMessage::Tempo(last_bpm).write_to(&mut writer).is_err() {
+            connected.store(false, Ordering::Relaxed);
+        }
+
+        while connected.load(Ordering::Relaxed) {
+            thread::sleep(Self::POLL_INTERVAL);
+
+            let bpm = self.engine.get_bpm();
+            let mut remote = self.remote_bpm.lock().unwrap();
+            match *remote {
+                // Wait until the peer's tempo is applied, then treat it as current
+                Some((remote_bpm, received_at))
+                    if (bpm - remote_bpm).abs() < 0.01
+                        || received_at.elapsed() > Self::REMOTE_BPM_TIMEOUT =>
+                {
+                    last_bpm = bpm;
+                    *remote = None;
+                }
+                Some(_) => {}
+                None if (bpm - last_bpm).abs() >= 0.01 => {
+                    last_bpm = bpm;
+                    if JamMessage::Tempo(bpm).write_to(&mut writer).is_err() {
+                        break;
+                    }
+                }
+                None => {}
+            }
+            drop(remote);
+
+            if share_loops && self.send_new_loops(&mut writer).is_err() {
+                break;
+            }
+        }
+        connected.store(false, Ordering::Relaxed);
+        // Unblocks the receiving half
+        let _ = writer.get_ref().shutdown(Shutdown::Both);
+    }
+
+    fn send_new_loops(&self, writer: &mut impl Write) -> io::Result<()> {
+        let sample_rate = self.engine.get_config().sample_rate;
+        for (layer_id, layer) in self.engine.get_layers().iter().enumerate() {
+            // Copy out, then send without holding the layer
+            let samples = {
+                let Ok(layer) = layer.try_lock() else {
+                    continue;
+                };
+                let revision = layer.undo_history.revision();
+                let Ok(mut revisions) = self.revisions.try_lock() else {
+                    continue;
+                };
+                if revisions[layer_id] == revision {
+                    continue;
+                }
+                // Recording bumps the revision again when it stops
+                revisions[layer_id] = revision;
+                if layer.is_recording || layer.is_empty() {
+                    continue;
+                }
+                layer.audio().to_vec()
+            };
+            JamMessage::Loop {
+                sample_rate,
+                samples,
+            }
+            .write_to(writer)?;
+        }
+        Ok(())
+    }
+}
+
+/// Start jamming in the background; reconnects whenever the peer goes away
+pub fn spawn_jam(
+    options: JamOptions,
+    engine: Arc<LooperEngine>,
+    commands: CommandSender,
+    events: EventSender,
+) -> Result<()> {
+    // Bind up front so a bad address is reported before the UI starts
+    let listener = match &options.role {
+        JamRole::Listen(addr) => {
+            Some(TcpListener::bind(addr).with_context(|| format!("Failed to listen on {}", addr))?)
+        }
+        JamRole::Connect(_) => None,
+    };
+
+    let revisions = (0..engine.get_layers().len())
+        .map(|layer_id| {
+            engine.get_layers()[layer_id]
+                .lock()
+                .map(|layer| layer.undo_history.revision())
+                .unwrap_or(0)
+        })
+        .collect();
+    let state = Arc::new(JamState {
+        engine,
+        commands,
+        events,
+        revisions: Mutex::new(revisions),
+        remote_bpm: Mutex::new(None),
+    });
+
+    thread::spawn(move || {
+        loop {
+            let stream = match (&listener, &options.role) {
+                (Some(listener), _) => listener.accept().map(|(stream, _)| stream),
+                (None, JamRole::Connect(addr)) => TcpStream::connect(addr),
+                (None, JamRole::Listen(_)) => return,
+            };
+            let Ok(stream) = stream else {
+                thread::sleep(Duration::from_secs(2));
+                continue;
+            };
+            let _ = stream.set_nodelay(true);
+            let peer = stream
+                .peer_addr()
+                .map(|addr| addr.to_string())
+                .unwrap_or_default();
+            let Ok(read_stream) = stream.try_clone() else {
+                continue;
+            };
+            let _ = state.events.try_send(AudioEvent::JamConnected(peer));
+
+            let connected = Arc::new(AtomicBool::new(true));
+            let receiver = {
+                let state = Arc::clone(&state);
+                let connected = Arc::clone(&connected);
+                thread::spawn(move || state.receive(read_stream, &connected))
+            };
+            let announce = listener.is_some();
+            state.send(stream, announce, options.share_loops, &connected);
+            let _ = receiver.join();
+            let _ = state.events.try_send(AudioEvent::JamDisconnected);
+            thread::sleep(Duration::from_secs(2));
+        }
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_loop_codec_roundtrip() {
+        let samples: Vec<f32> = (0..48000)
+            .map(|i| (i as f32 * 440.0 * std::f32::consts::TAU / 48000.0).sin() * 0.5)
+            .collect();
+        let encoded = encode_samples(&samples);
+        // Smaller than 16-bit PCM
+        assert!(encoded.len() < samples.len() * 2);
+        let decoded = decode_samples(&encoded, samples.len()).unwrap();
+        assert!(
+            samples
+                .iter()
+                .zip(&decoded)
+                .all(|(a, b)| (a - b).abs() <= 1.0 / 32767.0)
+        );
+        assert!(decode_samples(&encoded[..10], samples.len()).is_err());
+    }
+
+    #[test]
+    fn test_message_framing() {
+        let mut wire = Vec::new();
+        JamMessage::Tempo(98.5).write_to(&mut wire).unwrap();
+        JamMessage::Loop {
+            sample_rate: 44100,
+            samples: vec![0.0, 0.25, -0.25],
+        }
+        .write_to(&mut wire)
+        .unwrap();
+
+        let mut reader = wire.as_slice();
+        assert_eq!(
+            JamMessage::read_from(&mut reader).unwrap(),
+            JamMessage::Tempo(98.5)
+        );
+        match JamMessage::read_from(&mut reader).unwrap() {
+            JamMessage::Loop {
+                sample_rate,
+                samples,
+            } => {
+                assert_eq!(sample_rate, 44100);
+                assert_eq!(samples.len(), 3);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+
+        // A count the payload can't hold is refused before anything is allocated
+        let mut wire = vec![JamMessage::LOOP, 9, 0, 0, 0];
+        wire.extend_from_slice(&44100u32.to_le_bytes());
+        wire.extend_from_slice(&u32::MAX.to_le_bytes());
+        wire.push(0);
+        assert!(JamMessage::read_from(&mut wire.as_slice()).is_err());
+        assert!(
+            decode_samples(
+                &[0xfe, 0xff, 0xff, 0xff, 0x0f, 0xfe, 0xff, 0xff, 0xff, 0x0f],
+                2
+            )
+            .is_ok()
+        );
+    }
+}
@@ -1,4 +1,8 @@
 pub mod audio;
+pub mod config;
+pub mod control;
+pub mod jam;
+pub mod replay;
 pub mod ui;
 
 pub use audio::{AudioConfig, LooperEngine};
@@ -1,20 +1,82 @@
 use anyhow::Result;
 use crossbeam::channel;
-use soundlooper::audio::{AudioConfig, AudioEvent, AudioStream, LayerCommand, LooperEngine};
+use soundlooper::audio::channels::COMMAND_CAPACITY;
+use soundlooper::audio::io::STDIN_PATH;
+use soundlooper::audio::latency::{TRIAL_BUFFER_SIZES, TRIAL_DURATION, recommend};
+use soundlooper::audio::stream::{
+    available_host_names, default_device_names, enumerate_device_names, host_by_name,
+};
+use soundlooper::audio::{
+    AudioConfig, AudioEvent, AudioStream, BitDepth, ChannelStats, EventLog, EventSender,
+    ImportMode, LayerCommand, LooperEngine, LooperError, LooperStreams, OutputRouting,
+    command_channel, event_channel,
+};
+use soundlooper::config::{AppConfig, DeviceConfig};
+use soundlooper::control::{format_command, spawn_control_server};
+use soundlooper::jam::{JamOptions, JamRole, spawn_jam};
+use soundlooper::replay::{Script, replay_offline, spawn_replay};
 use soundlooper::ui::TerminalUI;
-use std::sync::atomic::{AtomicBool, Ordering};
+use soundlooper::ui::announce::Announcer;
+use soundlooper::ui::layer_keys::LayerKeys;
+use soundlooper::ui::mirror::spawn_mirror_server;
+use std::io::IsTerminal;
+use std::panic::AssertUnwindSafe;
+use std::sync::atomic::Ordering;
 use std::sync::{Arc, Mutex};
 use std::thread;
+use std::time::Duration;
 
 fn print_help() {
     println!("Soundlooper - Terminal-based multi-layer audio looper");
     println!();
     println!("USAGE:");
     println!("    soundlooper [OPTIONS]");
+    println!("    soundlooper replay <FILE> [--offline [--export <OUT>]] [OPTIONS]");
     println!();
     println!("OPTIONS:");
     println!("    -h, --help      Print this help message");
     println!("    --debug         Enable debug logging");
+    println!("    --autosnapshot <SECS>  Undo autosnapshot interval (off unless given)");
+    println!(
+        "    --main-outputs <CH>    Output channels for the main mix, e.g. 1-2 (default: all)"
+    );
+    println!("    --cue-outputs <CH>     Output channels for the cue bus, e.g. 3-4 (default: all)");
+    println!("    --control <ADDR>       Accept text commands over TCP, e.g. 127.0.0.1:7700");
+    println!(
+        "    --host <NAME>          Audio backend (available here: {})",
+        available_host_names().join(", ")
+    );
+    println!("    --announce [FILE]      Status lines for screen readers to stderr or FILE (FIFO)");
+    println!(
+        "    --mirror <ADDR>        Show a read-only status view to viewers on ADDR (nc/telnet)"
+    );
+    println!("    --jam-listen <ADDR>    Jam with another instance: wait for it on ADDR");
+    println!("    --jam-connect <ADDR>   Jam with another instance listening on ADDR");
+    println!("    --jam-loops            Also send new loops to the jam peer");
+    println!(
+        "    --config <FILE>        Settings file (default: {} if present)",
+        AppConfig::DEFAULT_PATH
+    );
+    println!("    --template <FILE>      Start from a session template (.slt, saved with W)");
+    println!(
+        "    --bit-depth <BITS>     WAV export format: 16, 24 (dithered) or 32 float (default)"
+    );
+    println!("    --watch <DIR>          Import new WAVs dropped into DIR into empty layers");
+    println!("    --low-power            Redraw the screen at most 4 times a second (battery)");
+    println!("    --import-layer <N> <FILE>  Import a WAV into layer N at startup (- for stdin)");
+    println!("    --event-log <DIR>      Log every command and event to a timestamped JSONL file");
+    println!(
+        "    --offline              With replay: no devices or UI, print events (for regression runs)"
+    );
+    println!(
+        "    --export <FILE>        With replay --offline: write the final mix (- for stdout)"
+    );
+    println!(
+        "    --setup                Run the setup wizard (runs by default when no config exists)"
+    );
+    println!(
+        "    --tune-latency         Find the smallest stable buffer size and save it to the config"
+    );
     println!();
     println!("DESCRIPTION:");
     println!("    A terminal-based multi-layer audio looper supporting real-time");
@@ -30,32 +92,95 @@ fn print_help() {
     println!();
     println!("CONTROLS:");
     println!("    ↑↓     Select layer");
-    println!("    1-9,0  Record/Stop/Play layer 1-10");
+    println!("    1-9,0  Record/Stop/Play layer 1-10 (F1-F6 for 11-16; [keys] in the config)");
     println!("    R      Record on selected layer");
     println!("    S      Stop selected layer");
     println!("    Space  Stop all layers");
     println!("    P      Play selected layer");
     println!("    A      Play all layers");
-    println!("    O      Options (select input/output devices)");
+    println!("    O      Options (select devices; I for an input check, H for audio backend)");
     println!("    +/-    Adjust volume");
     println!("    M      Mute/unmute selected layer");
     println!("    L      Solo/unsolo selected layer");
-    println!("    C      Clear selected layer");
-    println!("    X      Clear all layers");
+    println!("    C      Clear selected layer (press twice if it has audio)");
+    println!("    X      Clear all layers (press twice)");
+    println!("    Shift+C  Cancel queued sync actions on selected layer");
+    println!("    Shift+D  Overdub on selected layer (again to finish)");
+    println!("    ;      Re-take selected layer over its old take (again to finish)");
+    println!("    ' / \"  Keep the new take / revert to the old one");
+    println!("    `      Play next take on selected layer");
+    println!("    ~      Keep the take playing, drop the others");
+    println!("    Shift+L  Lock/unlock selected layer");
+    println!("    Shift+R  Master loop length from selected layer");
+    println!("    Shift+X  Clear master loop length");
+    println!("    Shift+F  Fit selected layer to master loop (varispeed)");
+    println!("    Shift+J  Freeze selected layer (render its varispeed, saves CPU)");
+    println!("    #        Learn a noise print from selected layer (record room tone on it)");
+    println!("    $        Denoise selected layer with the noise print");
+    println!("    %        Remove clicks and pops from selected layer");
+    println!("    &        Remove DC offset from selected layer");
+    println!("    !        Reset peak hold and clip indicators");
+    println!("    ?        Layer info: length, peak/RMS, crest factor, estimated tempo");
+    println!("    Shift+A  Toggle latency compensation (Bluetooth and other slow devices)");
+    println!("    Shift+V  Cycle mix analysis pane (loudness, spectrogram, off)");
+    println!("    Shift+K  Snapshot export: next numbered take in the export directory");
+    println!("    Shift+U  Recent imports/exports (Enter: re-import, O: reveal, D: forget)");
+    println!("    Shift+S  Toggle solo-safe on selected layer");
+    println!("    Shift+Q  Cycle mute/solo quantize (off, beat, measure)");
     println!("    I      Import WAV file to selected layer");
-    println!("    E      Export composition as WAV");
+    println!("    E      Export composition as WAV (length in loop cycles or bars, normalization)");
+    println!("    Shift+E  Export stems + Reaper project (.rpp)");
+    println!("    ^      Big screen: bar/beat, section and layer meters for a stage monitor");
+    println!("    @      Export loop pack (one tempo-tagged WAV per layer loop)");
+    println!("    K      Set marker at playback position (selected layer)");
+    println!("    J      Jump to next marker (on the beat when synced)");
+    println!("    W      Save session (.toml + layer WAVs, .slt = template)");
+    println!("    Shift+W  Load session");
+    println!("    D      Edit session title, author and tags");
+    println!("    V      Session notes (Esc saves)");
     println!("    Z      Undo on selected layer");
     println!("    Y      Redo on selected layer");
+    println!("    Shift+Z  Undo last action (any layer)");
+    println!("    Shift+Y  Redo last undone action (any layer)");
     println!("    B      Tap tempo");
+    println!("    Shift+B  Toggle whole-number tap BPM");
     println!("    T      Set BPM");
+    println!("    [ ]    Nudge BPM -1 / +1");
+    println!("    {{ }}    Fine-adjust BPM -0.1 / +0.1");
+    println!("    / *    Halve / double BPM");
+    println!("    , .    Less / more swing");
+    println!("    \\      Pause/resume the tempo clock");
+    println!("    |      Reset transport (bar 1 starts now)");
     println!("    G      Toggle beat sync");
     println!("    H      Toggle count-in mode");
+    println!("    U      Cycle count-in length (1, 2, 4 bars)");
+    println!("    F      Toggle count-out after recording");
     println!("    N      Toggle metronome");
+    println!("    Shift+N  Click to main/cue bus");
+    println!("    Shift+M  Metronome only while recording");
+    println!("    ( )    Metronome volume down/up");
+    println!("    < >    Metronome pan left/right");
+    println!("    Shift+O  Selected layer to main/cue bus");
+    println!("    Shift+P  Cue (pre-listen) selected layer");
     println!("    Q      Quit");
     println!();
     println!("EXAMPLES:");
     println!("    soundlooper              # Start with default settings");
     println!("    soundlooper --debug      # Start with debug logging");
+    println!("    soundlooper --autosnapshot 10  # Snapshot changed layers every 10s");
+    println!("    soundlooper --template mysetup.slt  # Layer volumes, routing, BPM, click");
+    println!("    soundlooper --tune-latency  # Try buffer sizes on the configured devices");
+    println!(
+        "    arecord -f S16_LE -r 48000 -d 8 | soundlooper --import-layer 1 -  # Pipe in a take"
+    );
+    println!("    soundlooper replay logs/session_2024-02-29_12-34-56.jsonl  # Redo a logged set");
+    println!("    soundlooper replay set.txt --offline --export - | ffmpeg -i - set.mp3  # Render");
+    println!("    soundlooper --main-outputs 1-2 --cue-outputs 3-4  # Click to in-ears on 3-4");
+    println!("    soundlooper --jam-listen 0.0.0.0:7800 --jam-loops  # Host a jam on the LAN");
+    println!(
+        "    soundlooper --control 127.0.0.1:7700  # Then e.g. `echo \"play 1\" | nc localhost 7700`"
+    );
+    println!("    soundlooper --mirror 0.0.0.0:7900  # Bandmates watch with `nc HOST 7900`");
     println!();
     println!("For more information, visit: https://github.com/Cod-e-Codes/soundlooper");
 }
@@ -72,15 +197,365 @@ fn main() -> Result<()> {
 
     let debug_mode = args.contains(&"--debug".to_string());
 
+    // `replay FILE` sends a recorded command stream (or a script) back in
+    let replay = match args.get(1).map(String::as_str) {
+        Some("replay") => match args.get(2).map(|path| Script::load(path)) {
+            Some(Ok(script)) => Some(script),
+            Some(Err(e)) => {
+                eprintln!("{:#}", e);
+                return Ok(());
+            }
+            None => {
+                eprintln!("replay requires a log (--event-log) or script file");
+                return Ok(());
+            }
+        },
+        _ => None,
+    };
+    if let Some(script) = &replay {
+        if script.skipped > 0 {
+            eprintln!(
+                "Skipping {} logged commands with no control line (device and UI settings)",
+                script.skipped
+            );
+        }
+        // No devices or UI: events are printed as the engine renders silence
+        if args.contains(&"--offline".to_string()) {
+            let export = match args.iter().position(|a| a == "--export") {
+                Some(index) => match args.get(index + 1) {
+                    Some(path) => Some(path.as_str()),
+                    None => {
+                        eprintln!("--export requires a file path (or - for standard output)");
+                        return Ok(());
+                    }
+                },
+                None => None,
+            };
+            return replay_offline(script, AudioConfig::default(), export);
+        }
+    }
+    if args.contains(&"--export".to_string()) {
+        eprintln!("--export renders a replay: soundlooper replay FILE --offline --export OUT");
+        return Ok(());
+    }
+
+    let autosnapshot_secs = match args.iter().position(|a| a == "--autosnapshot") {
+        Some(index) => match args.get(index + 1).and_then(|v| v.parse::<u64>().ok()) {
+            Some(secs) => secs,
+            None => {
+                eprintln!("--autosnapshot requires a number of seconds");
+                return Ok(());
+            }
+        },
+        None => 0,
+    };
+
+    let mut routing = OutputRouting::default();
+    for (flag, bus) in [
+        ("--main-outputs", &mut routing.main),
+        ("--cue-outputs", &mut routing.cue),
+    ] {
+        if let Some(index) = args.iter().position(|a| a == flag) {
+            match args
+                .get(index + 1)
+                .map(|v| OutputRouting::parse_channels(v))
+            {
+                Some(Ok(channels)) => *bus = Some(channels),
+                Some(Err(e)) => {
+                    eprintln!("{}: {}", flag, e);
+                    return Ok(());
+                }
+                None => {
+                    eprintln!("{} requires a channel list such as 1-2", flag);
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    let control_addr = match args.iter().position(|a| a == "--control") {
+        Some(index) => match args.get(index + 1) {
+            Some(addr) => Some(addr.clone()),
+            None => {
+                eprintln!("--control requires an address such as 127.0.0.1:7700");
+                return Ok(());
+            }
+        },
+        None => None,
+    };
+    let mirror_addr = match args.iter().position(|a| a == "--mirror") {
+        Some(index) => match args.get(index + 1) {
+            Some(addr) => Some(addr.clone()),
+            None => {
+                eprintln!("--mirror requires an address such as 0.0.0.0:7900");
+                return Ok(());
+            }
+        },
+        None => None,
+    };
+
+    // --announce [FILE]: screen reader lines to stderr, or appended to FILE (e.g. a FIFO)
+    let announce_path = args.iter().position(|a| a == "--announce").map(|index| {
+        args.get(index + 1)
+            .filter(|path| !path.starts_with("--"))
+            .cloned()
+    });
+
+    let mut jam_role = None;
+    for flag in ["--jam-listen", "--jam-connect"] {
+        if let Some(index) = args.iter().position(|a| a == flag) {
+            let Some(addr) = args.get(index + 1).cloned() else {
+                eprintln!("{} requires an address such as 192.168.1.20:7800", flag);
+                return Ok(());
+            };
+            jam_role = Some(if flag == "--jam-listen" {
+                JamRole::Listen(addr)
+            } else {
+                JamRole::Connect(addr)
+            });
+        }
+    }
+    let jam_options = jam_role.map(|role| JamOptions {
+        role,
+        share_loops: args.contains(&"--jam-loops".to_string()),
+    });
+
+    let template = match args.iter().position(|a| a == "--template") {
+        Some(index) => match args.get(index + 1) {
+            Some(path) if std::path::Path::new(path).is_file() => Some(path.clone()),
+            Some(path) => {
+                eprintln!("Template not found: {}", path);
+                return Ok(());
+            }
+            None => {
+                eprintln!("--template requires a file path");
+                return Ok(());
+            }
+        },
+        None => None,
+    };
+    // --import-layer N FILE: loaded at startup; FILE `-` reads a WAV piped into stdin
+    let startup_import = match args.iter().position(|a| a == "--import-layer") {
+        Some(index) => {
+            let layer = args.get(index + 1).and_then(|n| n.parse::<usize>().ok());
+            match (layer, args.get(index + 2)) {
+                (Some(layer), Some(path)) if layer >= 1 => {
+                    if path == STDIN_PATH && std::io::stdin().is_terminal() {
+                        eprintln!("--import-layer {} -: pipe a WAV into standard input", layer);
+                        return Ok(());
+                    }
+                    if path != STDIN_PATH && !std::path::Path::new(path).is_file() {
+                        eprintln!("File not found: {}", path);
+                        return Ok(());
+                    }
+                    Some((layer - 1, path.clone()))
+                }
+                _ => {
+                    eprintln!("--import-layer requires a layer number and a WAV file (or -)");
+                    return Ok(());
+                }
+            }
+        }
+        None => None,
+    };
+    let reads_stdin = startup_import
+        .as_ref()
+        .is_some_and(|(_, path)| path == STDIN_PATH);
+
+    let config_path = match args.iter().position(|a| a == "--config") {
+        Some(index) => match args.get(index + 1) {
+            Some(path) => Some(path.clone()),
+            None => {
+                eprintln!("--config requires a file path");
+                return Ok(());
+            }
+        },
+        None => None,
+    };
+    // First launch: walk through devices, latency, tempo and click instead of guessing
+    // (the wizard reads answers from stdin, so not while a WAV is piped in)
+    let run_setup = args.contains(&"--setup".to_string())
+        || (replay.is_none()
+            && !reads_stdin
+            && config_path.is_none()
+            && !std::path::Path::new(AppConfig::DEFAULT_PATH).exists());
+    let config = match &config_path {
+        Some(path) if run_setup && !std::path::Path::new(path).exists() => Ok(AppConfig::default()),
+        Some(path) => AppConfig::load(path),
+        None => AppConfig::load_default(),
+    };
+    let mut config = match config {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{:#}", e);
+            return Ok(());
+        }
+    };
+
+    // --host overrides the backend saved in the config
+    match args.iter().position(|a| a == "--host") {
+        Some(index) => match args.get(index + 1) {
+            Some(name) => {
+                if let Err(e) = host_by_name(Some(name)) {
+                    eprintln!("{:#}", e);
+                    return Ok(());
+                }
+                config.audio.host = Some(name.clone());
+            }
+            None => {
+                eprintln!("--host requires a backend name such as ALSA");
+                return Ok(());
+            }
+        },
+        None => {
+            if let Err(e) = host_by_name(config.audio.host.as_deref()) {
+                eprintln!("Warning: {:#}; using the default backend", e);
+                config.audio.host = None;
+            }
+        }
+    }
+
+    // --bit-depth overrides the export format saved in the config
+    if let Some(index) = args.iter().position(|a| a == "--bit-depth") {
+        match args.get(index + 1).and_then(|v| v.parse::<u16>().ok()) {
+            Some(bits) => config.export.bit_depth = Some(bits),
+            None => {
+                eprintln!("--bit-depth requires 16, 24 or 32");
+                return Ok(());
+            }
+        }
+    }
+    if args.contains(&"--low-power".to_string()) {
+        config.ui.low_power = true;
+    }
+    let layer_keys = match &config.keys.layers {
+        Some(names) => match LayerKeys::parse(names) {
+            Ok(keys) => keys,
+            Err(e) => {
+                eprintln!("[keys] layers: {}", e);
+                return Ok(());
+            }
+        },
+        None => LayerKeys::default(),
+    };
+    if config.ui.desktop_notifications && !cfg!(feature = "notifications") {
+        eprintln!(
+            "Warning: built without the notifications feature; desktop notifications are off"
+        );
+    }
+    // --watch overrides the watch folder saved in the config
+    if let Some(index) = args.iter().position(|a| a == "--watch") {
+        match args.get(index + 1) {
+            Some(directory) => config.import.watch_folder = Some(directory.clone()),
+            None => {
+                eprintln!("--watch requires a directory");
+                return Ok(());
+            }
+        }
+    }
+    if let Some(directory) = &config.import.watch_folder
+        && let Err(e) = std::fs::create_dir_all(directory)
+    {
+        eprintln!("Cannot create watch folder {}: {}", directory, e);
+        return Ok(());
+    }
+    // --event-log overrides the log directory saved in the config
+    if let Some(index) = args.iter().position(|a| a == "--event-log") {
+        match args.get(index + 1) {
+            Some(directory) => config.session.event_log = Some(directory.clone()),
+            None => {
+                eprintln!("--event-log requires a directory");
+                return Ok(());
+            }
+        }
+    }
+    // Shared by the command forwarder and the UI, so both streams share one clock
+    let event_log = match &config.session.event_log {
+        Some(directory) => {
+            match EventLog::create(
+                std::path::Path::new(directory),
+                std::time::SystemTime::now(),
+            ) {
+                Ok(log) => {
+                    println!("Logging commands and events to {}", log.path().display());
+                    Some(Arc::new(Mutex::new(log)))
+                }
+                Err(e) => {
+                    eprintln!("Cannot create event log in {}: {}", directory, e);
+                    return Ok(());
+                }
+            }
+        }
+        None => None,
+    };
+    let bit_depth = match config.export.bit_depth {
+        Some(bits) => match BitDepth::from_bits(bits) {
+            Some(bit_depth) => bit_depth,
+            None => {
+                eprintln!("Unsupported bit depth {} (use 16, 24 or 32)", bits);
+                return Ok(());
+            }
+        },
+        None => BitDepth::default(),
+    };
+
+    if args.contains(&"--tune-latency".to_string()) {
+        let path = config_path.unwrap_or_else(|| AppConfig::DEFAULT_PATH.to_string());
+        return tune_latency(config, &path, debug_mode);
+    }
+
     if debug_mode {
         println!("Starting Soundlooper in DEBUG mode...");
     } else {
         println!("Starting Soundlooper...");
     }
 
+    // Devices saved from an earlier session may be unplugged; only those fall back
+    if let Ok((inputs, outputs)) = enumerate_device_names(config.audio.host.as_deref()) {
+        if let Some(name) = &config.audio.input_device
+            && !inputs.contains(name)
+        {
+            eprintln!(
+                "Warning: input device \"{}\" not found, using the default",
+                name
+            );
+            config.audio.input_device = None;
+        }
+        if let Some(name) = &config.audio.output_device
+            && !outputs.contains(name)
+        {
+            eprintln!(
+                "Warning: output device \"{}\" not found, using the default",
+                name
+            );
+            config.audio.output_device = None;
+        }
+    }
+
     // Create a provisional audio config and audio stream to detect actual device rates
     let provisional_config = AudioConfig::default();
-    let audio_stream = AudioStream::new(provisional_config.clone(), debug_mode)?;
+    let audio_stream = match AudioStream::new_with_devices(
+        provisional_config.clone(),
+        debug_mode,
+        config.audio.host.clone(),
+        config.audio.input_device.clone(),
+        config.audio.output_device.clone(),
+    ) {
+        Ok(stream) => stream,
+        Err(e) => {
+            eprintln!(
+                "Warning: configured audio devices unavailable, using defaults: {}",
+                e
+            );
+            AudioStream::new_with_devices(
+                provisional_config.clone(),
+                debug_mode,
+                config.audio.host.clone(),
+                None,
+                None,
+            )?
+        }
+    };
 
     // Build the runtime audio config to MATCH the device input sample rate
     let runtime_config = AudioConfig {
@@ -98,143 +573,325 @@ fn main() -> Result<()> {
 
     // Create looper engine (now thread-safe) with the ACTUAL processing sample rate
     let looper_engine = Arc::new(LooperEngine::new(runtime_config.clone()));
-    // Load metronome asset once at startup (resampled to engine rate)
-    match soundlooper::audio::import_wav("assets/metronome.wav", runtime_config.sample_rate) {
+    if autosnapshot_secs > 0 {
+        looper_engine.set_autosnapshot_interval(Some(Duration::from_secs(autosnapshot_secs)));
+    }
+    // Load the configured click once at startup (resampled to engine rate)
+    match config.metronome.sound.render(runtime_config.sample_rate) {
         Ok(samples) => {
             looper_engine.set_metronome_sample(samples);
         }
         Err(e) => {
-            eprintln!("Warning: failed to load metronome.wav: {}", e);
+            eprintln!(
+                "Warning: failed to load metronome sound, using built-in click: {}",
+                e
+            );
+        }
+    }
+
+    let layer_count = runtime_config.max_layers;
+
+    // Create communication channels (bounded; overflow is counted in channel_stats)
+    let channel_stats = ChannelStats::new();
+    let (command_sender, command_receiver) = command_channel(&channel_stats);
+    let (event_sender, event_receiver) = event_channel(&channel_stats);
+
+    // Applied once the engine starts taking commands (a template's BPM wins)
+    let _ = command_sender.send(LayerCommand::SetBpm(config.tempo.bpm));
+    let _ = command_sender.send(LayerCommand::SetExportBitDepth(bit_depth));
+    let _ = command_sender.send(LayerCommand::SetExportHook(config.export.hook()));
+    let _ = command_sender.send(LayerCommand::SetImportTempoMatch(config.import.match_tempo));
+    let _ = command_sender.send(LayerCommand::SetImportLimit(
+        Some(config.import.max_seconds).filter(|&seconds| seconds > 0),
+    ));
+    let _ = command_sender.send(LayerCommand::SetPreCount(config.metronome.pre_count));
+    let _ = command_sender.send(LayerCommand::SetSaveHistory(config.session.save_history));
+    let _ = command_sender.send(LayerCommand::SetIdleParking(
+        config.session.park_idle_layers,
+    ));
+    let _ = command_sender.send(LayerCommand::SetUndoCompression(
+        config.session.compress_undo,
+    ));
+    let _ = command_sender.send(LayerCommand::SetCountInMeasures(
+        config.metronome.count_in_bars,
+    ));
+    if let Some(ms) = config.audio.latency_compensation_ms {
+        let _ = command_sender.send(LayerCommand::SetLatencyCompensation(Duration::from_millis(
+            ms.into(),
+        )));
+    }
+    if let Some(hz) = config.audio.input_high_pass_hz {
+        let _ = command_sender.send(LayerCommand::SetInputHighPass(Some(hz)));
+    }
+    if let Some(percent) = config.audio.stereo_width_percent {
+        let _ = command_sender.send(LayerCommand::SetStereoWidth(percent as f32 / 100.0));
+    }
+    if let Some(path) = template {
+        let _ = command_sender.send(LayerCommand::LoadSession(path));
+    }
+    if let Some((layer_id, path)) = startup_import {
+        let _ = command_sender.send(LayerCommand::ImportWav(layer_id, path, ImportMode::Replace));
+    }
+
+    if let Some(script) = replay {
+        spawn_replay(script, command_sender.clone());
+    }
+
+    // External control (stream decks, scripts) shares the UI's command queue
+    if let Some(addr) = &control_addr {
+        match spawn_control_server(addr, command_sender.clone()) {
+            Ok(local_addr) => println!("Accepting control commands on {}", local_addr),
+            Err(e) => {
+                eprintln!("{:#}", e);
+                return Ok(());
+            }
         }
     }
-    let layers = looper_engine.get_layers();
+    // Read-only status for bandmates (nc / telnet to the address)
+    let mirror = match &mirror_addr {
+        Some(addr) => match spawn_mirror_server(addr) {
+            Ok((local_addr, mirror)) => {
+                println!("Mirroring the status view on {}", local_addr);
+                Some(mirror)
+            }
+            Err(e) => {
+                eprintln!("{:#}", e);
+                return Ok(());
+            }
+        },
+        None => None,
+    };
 
-    // Create communication channels
-    let (command_sender, command_receiver) = channel::unbounded::<LayerCommand>();
-    let (event_sender, event_receiver) = channel::unbounded::<AudioEvent>();
+    // Tempo (and optionally loops) shared with another instance on the LAN
+    if let Some(options) = jam_options
+        && let Err(e) = spawn_jam(
+            options,
+            Arc::clone(&looper_engine),
+            command_sender.clone(),
+            event_sender.clone(),
+        )
+    {
+        eprintln!("{:#}", e);
+        return Ok(());
+    }
 
     // Extract device names before moving audio_stream into thread
+    let host_name = audio_stream.get_host_name().to_string();
     let input_device_name = audio_stream.get_input_device_name().to_string();
     let output_device_name = audio_stream.get_output_device_name().to_string();
 
-    // Prepare restart mechanism and shared device names
-    let restart_audio = Arc::new(AtomicBool::new(false));
-    let restart_audio_clone = Arc::clone(&restart_audio);
-
-    let current_input_device = Arc::new(Mutex::new(input_device_name.clone()));
-    let current_output_device = Arc::new(Mutex::new(output_device_name.clone()));
-
-    let input_device_clone = Arc::clone(&current_input_device);
-    let output_device_clone = Arc::clone(&current_output_device);
+    // Devices the audio thread runs on; switches from the TUI update it
+    let selection = DeviceSelection {
+        host: host_name,
+        input: input_device_name.clone(),
+        output: output_device_name.clone(),
+        buffer_size: config.audio.buffer_size,
+    };
+    let block_size = config.audio.block_size;
+    let devices_path = config_path
+        .clone()
+        .unwrap_or_else(|| AppConfig::DEFAULT_PATH.to_string());
 
     // Start audio thread with the SAME looper engine
     let looper_clone = Arc::clone(&looper_engine);
+    let command_log = event_log.clone();
 
     let _audio_thread = thread::spawn(move || {
-        loop {
-            // Read current desired device names
-            let input_name = input_device_clone.lock().unwrap().clone();
-            let output_name = output_device_clone.lock().unwrap().clone();
+        // One engine command channel for the whole session, so commands queued during
+        // a device swap wait for the new streams instead of going down with the old ones
+        let (forward_tx, forward_rx) = channel::bounded::<LayerCommand>(COMMAND_CAPACITY);
 
-            // Build a fresh stream with selected devices
-            let audio_stream = match AudioStream::new_with_devices(
-                runtime_config.clone(),
-                debug_mode,
-                Some(input_name.clone()),
-                Some(output_name.clone()),
-            ) {
-                Ok(stream) => stream,
+        // Build streams for a set of devices without starting them
+        let open = |devices: &DeviceSelection| {
+            let opened = std::panic::catch_unwind(AssertUnwindSafe(|| {
+                let mut audio_stream = AudioStream::new_with_devices(
+                    runtime_config.clone(),
+                    debug_mode,
+                    Some(devices.host.clone()),
+                    Some(devices.input.clone()),
+                    Some(devices.output.clone()),
+                )?;
+                // Layers were recorded at the first device's rate; keep the engine there
+                audio_stream.set_engine_sample_rate(runtime_config.sample_rate);
+                audio_stream.set_output_routing(routing.clone());
+                audio_stream.set_buffer_size(devices.buffer_size);
+                audio_stream.set_block_size(block_size);
+                let streams = audio_stream.build_audio_looper(
+                    Arc::clone(&looper_clone),
+                    forward_rx.clone(),
+                    event_sender.clone(),
+                    debug_mode,
+                )?;
+                Ok((audio_stream, streams))
+            }));
+            opened.unwrap_or_else(|_| {
+                // Layer buffers live in the engine, so they survive a retry
+                looper_clone.recover_after_panic();
+                Err(anyhow::anyhow!("audio setup panicked"))
+            })
+        };
+
+        let mut selection = selection;
+        let mut running: Option<LooperStreams> = None;
+        let mut last_good: Option<DeviceSelection> = None;
+        // Set by switches made in the TUI; saved to the config once the new streams open
+        let mut devices_chosen = false;
+        loop {
+            // Open the new devices while the current streams keep playing
+            let opened = match open(&selection) {
+                Ok(opened) => Ok(opened),
+                // Some devices can't be opened twice (e.g. a new buffer size on the same card)
+                Err(_) if running.is_some() => {
+                    if let Some(old) = running.take() {
+                        old.fade_out();
+                    }
+                    open(&selection)
+                }
+                Err(e) => Err(e),
+            };
+            // The gap between the old streams stopping and the new ones starting is the fade
+            let started = opened.and_then(|(audio_stream, streams)| {
+                if let Some(old) = running.take() {
+                    old.fade_out();
+                }
+                streams.play()?;
+                Ok((audio_stream, streams))
+            });
+            let (audio_stream, streams) = match started {
+                Ok(started) => started,
                 Err(e) => {
                     eprintln!("Failed to create audio stream: {}", e);
                     let _ = event_sender.try_send(AudioEvent::DeviceSwitchFailed(format!(
                         "Failed to switch devices: {}",
                         e
                     )));
-                    std::thread::sleep(std::time::Duration::from_secs(1));
+                    // Go back to the last devices that worked, or wait and retry
+                    match last_good.take() {
+                        Some(good) => selection = good,
+                        None => thread::sleep(Duration::from_secs(1)),
+                    }
                     continue;
                 }
             };
+            last_good = Some(selection.clone());
 
             // Inform UI
+            let _ = event_sender.try_send(AudioEvent::HostUpdated(
+                audio_stream.get_host_name().to_string(),
+            ));
             let _ = event_sender.try_send(AudioEvent::DevicesUpdated(
                 Some(audio_stream.get_input_device_name().to_string()),
                 Some(audio_stream.get_output_device_name().to_string()),
             ));
             let _ = event_sender.try_send(AudioEvent::DeviceSwitchComplete);
+            if std::mem::take(&mut devices_chosen) {
+                let devices = DeviceConfig {
+                    host: Some(audio_stream.get_host_name().to_string()),
+                    input_device: Some(audio_stream.get_input_device_name().to_string()),
+                    output_device: Some(audio_stream.get_output_device_name().to_string()),
+                    buffer_size: selection.buffer_size,
+                    latency_compensation_ms: None,
+                    block_size: None,
+                    input_high_pass_hz: None,
+                    stereo_width_percent: None,
+                };
+                if let Err(e) = AppConfig::save_devices(&devices_path, &devices) {
+                    let _ = event_sender.try_send(AudioEvent::Error(LooperError::io(
+                        "save devices to",
+                        devices_path.as_str(),
+                        &e,
+                    )));
+                }
+            }
+            // After the completion notice so it isn't immediately replaced
+            if audio_stream.get_sample_rate() != runtime_config.sample_rate {
+                let _ = event_sender.try_send(AudioEvent::SampleRateConverted {
+                    device_rate: audio_stream.get_sample_rate(),
+                    engine_rate: runtime_config.sample_rate,
+                });
+            }
 
-            // Create a forwarding channel so we can intercept device switch commands
-            let (forward_tx, forward_rx) = channel::unbounded::<LayerCommand>();
-
-            // Forwarder thread: intercept switch commands to update device names and trigger restart
-            let restart_for_forwarder = Arc::clone(&restart_audio_clone);
-            let input_for_forwarder = Arc::clone(&input_device_clone);
-            let output_for_forwarder = Arc::clone(&output_device_clone);
-            let event_sender_for_forwarder = event_sender.clone();
-            let cmd_receiver_for_forwarder = command_receiver.clone();
-            let _forwarder = std::thread::spawn(move || {
-                while let Ok(cmd) = cmd_receiver_for_forwarder.recv() {
-                    match &cmd {
-                        LayerCommand::SwitchInputDevice(new_name) => {
-                            if let Ok(mut name) = input_for_forwarder.lock() {
-                                *name = new_name.clone();
-                            }
-                            let _ = event_sender_for_forwarder
-                                .try_send(AudioEvent::DeviceSwitchRequested);
-                            restart_for_forwarder.store(true, Ordering::Relaxed);
-                        }
-                        LayerCommand::SwitchOutputDevice(new_name) => {
-                            if let Ok(mut name) = output_for_forwarder.lock() {
-                                *name = new_name.clone();
-                            }
-                            let _ = event_sender_for_forwarder
-                                .try_send(AudioEvent::DeviceSwitchRequested);
-                            restart_for_forwarder.store(true, Ordering::Relaxed);
-                        }
-                        _ => {}
-                    }
-                    // Always forward the command to the looper engine
-                    if forward_tx.send(cmd).is_err() {
-                        break;
+            match watch_audio_streams(
+                &audio_stream,
+                &event_sender,
+                &command_receiver,
+                &forward_tx,
+                &mut selection,
+                command_log.as_ref(),
+            ) {
+                // Keep playing until the replacement is ready
+                StreamWatch::Switch => {
+                    running = Some(streams);
+                    devices_chosen = true;
+                    if debug_mode {
+                        println!("Switching audio to new devices...");
                     }
-                    // If a restart was requested, exit to allow streams to be rebuilt
-                    if restart_for_forwarder.load(Ordering::Relaxed) {
-                        break;
+                }
+                StreamWatch::Crashed => {
+                    // Layer buffers live in the engine, so they survive the rebuild
+                    drop(streams);
+                    looper_clone.recover_after_panic();
+                    let _ = event_sender.try_send(AudioEvent::AudioEngineRestarted);
+                    if debug_mode {
+                        println!("Audio thread panicked, rebuilding streams...");
                     }
+                    thread::sleep(Duration::from_millis(500));
                 }
-            });
-
-            // Run the inner thread which owns the active streams
-            if let Err(e) = run_audio_thread_inner(
-                audio_stream,
-                Arc::clone(&looper_clone),
-                forward_rx,
-                event_sender.clone(),
-                debug_mode,
-                Arc::clone(&restart_audio_clone),
-            ) {
-                eprintln!("Audio thread error: {}", e);
-            }
-
-            // Exit if not restarting
-            if !restart_audio_clone.load(Ordering::Relaxed) {
-                break;
-            }
-            restart_audio_clone.store(false, Ordering::Relaxed);
-            if debug_mode {
-                println!("Restarting audio with new devices...");
+                StreamWatch::Quit => break,
             }
         }
     });
 
     // Create and run TUI
     let mut ui = TerminalUI::new(
-        layers,
+        layer_count,
         command_sender,
+        channel_stats,
         event_receiver,
         &input_device_name,
         &output_device_name,
     )
     .map_err(|e| anyhow::anyhow!("UI creation failed: {}", e))?;
+    ui.set_confirm_clear(config.safety.confirm_clear);
+    ui.set_refresh(config.ui.refresh());
+    ui.set_alerts(config.ui.bell, config.ui.desktop_notifications);
+    ui.set_layer_keys(layer_keys);
+    if let Some(mirror) = mirror {
+        ui.set_mirror(mirror);
+    }
+    match announce_path {
+        Some(Some(path)) => {
+            println!(
+                "Announcing status to {} (a FIFO waits for its reader)",
+                path
+            );
+            match Announcer::open(&path) {
+                Ok(announcer) => ui.set_announcer(announcer),
+                Err(e) => {
+                    eprintln!("{:#}", e);
+                    return Ok(());
+                }
+            }
+        }
+        Some(None) => ui.set_announcer(Announcer::stderr()),
+        None => {}
+    }
+    if let Some(log) = event_log {
+        ui.set_event_log(log);
+    }
+    if let Some(directory) = &config.export.directory {
+        ui.set_export_directory(directory);
+    }
+    if let Some(template) = &config.export.name_template {
+        ui.set_export_name_template(template);
+    }
+    if let Some(directory) = &config.import.watch_folder {
+        ui.set_watch_folder(directory);
+    }
+    if run_setup {
+        let path = config_path.unwrap_or_else(|| AppConfig::DEFAULT_PATH.to_string());
+        ui.start_setup_wizard(config, path);
+    }
     ui.run()
         .map_err(|e| anyhow::anyhow!("UI run failed: {}", e))?;
 
@@ -242,27 +899,151 @@ fn main() -> Result<()> {
     Ok(())
 }
 
-fn run_audio_thread_inner(
-    audio_stream: AudioStream,
-    looper_engine: Arc<LooperEngine>,
-    command_receiver: channel::Receiver<LayerCommand>,
-    event_sender: channel::Sender<AudioEvent>,
-    debug_mode: bool,
-    restart_flag: Arc<AtomicBool>,
-) -> Result<()> {
-    let (_input_stream, _output_stream) = audio_stream.start_audio_looper(
-        looper_engine,
-        command_receiver,
-        event_sender,
+/// Diagnostic mode: try buffer sizes from smallest up on the configured devices and
+/// save the first one that runs without dropouts
+fn tune_latency(mut config: AppConfig, path: &str, debug_mode: bool) -> Result<()> {
+    let mut audio_stream = AudioStream::new_with_devices(
+        AudioConfig::default(),
         debug_mode,
+        config.audio.host.clone(),
+        config.audio.input_device.clone(),
+        config.audio.output_device.clone(),
     )?;
+    println!(
+        "Tuning latency on {} -> {} ({}s per buffer size)",
+        audio_stream.get_input_device_name(),
+        audio_stream.get_output_device_name(),
+        TRIAL_DURATION.as_secs()
+    );
 
-    // Keep streams alive and watch for restart
-    loop {
-        if restart_flag.load(Ordering::Relaxed) {
-            break;
+    let mut trials = Vec::new();
+    for frames in TRIAL_BUFFER_SIZES {
+        match audio_stream.probe_buffer_size(frames, TRIAL_DURATION) {
+            Ok(trial) => {
+                println!("  {}", trial);
+                let stable = trial.is_stable();
+                trials.push(trial);
+                // Larger sizes only add delay
+                if stable {
+                    break;
+                }
+            }
+            Err(e) => println!("  {} frames: failed to start ({})", frames, e),
         }
-        thread::sleep(std::time::Duration::from_millis(100));
+    }
+
+    match recommend(&trials) {
+        Some(frames) => {
+            config.audio.buffer_size = Some(frames);
+            config.save(path)?;
+            println!(
+                "Recommended buffer size: {} frames, saved to {}",
+                frames, path
+            );
+        }
+        None => println!("No buffer size ran cleanly; the config was left unchanged"),
     }
     Ok(())
 }
+
+/// Backend, devices and buffer size the audio thread runs on
+#[derive(Debug, Clone)]
+struct DeviceSelection {
+    host: String,
+    input: String,
+    output: String,
+    buffer_size: Option<u32>,
+}
+
+impl DeviceSelection {
+    /// Apply a switch command from the TUI; true when the streams must be reopened
+    fn apply(&mut self, cmd: &LayerCommand, event_sender: &EventSender) -> bool {
+        match cmd {
+            LayerCommand::SwitchInputDevice(name) => self.input = name.clone(),
+            LayerCommand::SwitchOutputDevice(name) => self.output = name.clone(),
+            // The old device names don't exist on another backend
+            LayerCommand::SwitchHost(host) => match default_device_names(Some(host)) {
+                Ok((input, output)) => {
+                    self.host = host.clone();
+                    self.input = input;
+                    self.output = output;
+                }
+                Err(e) => {
+                    let _ = event_sender.try_send(AudioEvent::DeviceSwitchFailed(format!(
+                        "Failed to switch backend: {}",
+                        e
+                    )));
+                    return false;
+                }
+            },
+            LayerCommand::SetBufferSize(frames) => {
+                self.buffer_size = *frames;
+                return true;
+            }
+            _ => return false,
+        }
+        let _ = event_sender.try_send(AudioEvent::DeviceSwitchRequested);
+        true
+    }
+}
+
+/// Why the audio thread stopped watching its running streams
+enum StreamWatch {
+    Switch,  // devices changed; open the new ones
+    Crashed, // a callback panicked; rebuild on the same devices
+    Quit,    // the UI closed its command queue
+}
+
+/// Forward UI commands to the engine while the streams run, until a device switch,
+/// a crashed callback or shutdown. Switches are handled here rather than in a
+/// separate forwarder thread, so nothing is left running between swaps
+fn watch_audio_streams(
+    audio_stream: &AudioStream,
+    event_sender: &EventSender,
+    commands: &channel::Receiver<LayerCommand>,
+    engine_commands: &channel::Sender<LayerCommand>,
+    selection: &mut DeviceSelection,
+    command_log: Option<&Arc<Mutex<EventLog>>>,
+) -> StreamWatch {
+    const POLL: Duration = Duration::from_millis(100);
+    let latency = audio_stream.latency();
+    let callback_panicked = audio_stream.panic_flag();
+    let started = std::time::Instant::now();
+    let mut latency_reported = false;
+    loop {
+        if callback_panicked.load(Ordering::Relaxed) {
+            return StreamWatch::Crashed;
+        }
+        // Device delays settle after the first callbacks (Bluetooth can take a moment)
+        if !latency_reported && started.elapsed() >= Duration::from_secs(1) {
+            let _ = event_sender.try_send(AudioEvent::LatencyMeasured(latency.round_trip()));
+            latency_reported = true;
+        }
+        // A stalled engine leaves commands in the UI queue rather than blocking here,
+        // so a crashed callback is still noticed
+        if engine_commands.is_full() {
+            thread::sleep(POLL);
+            continue;
+        }
+        match commands.recv_timeout(POLL) {
+            Ok(cmd) => {
+                if let Some(log) = command_log
+                    && let Ok(mut log) = log.lock()
+                {
+                    let script = format_command(&cmd);
+                    let _ = log
+                        .log_command(&cmd, script.as_deref())
+                        .and_then(|_| log.flush());
+                }
+                let switching = selection.apply(&cmd, event_sender);
+                // Always forward the command to the looper engine
+                let _ = engine_commands.send(cmd);
+                if switching {
+                    return StreamWatch::Switch;
+                }
+            }
+            Err(channel::RecvTimeoutError::Timeout) => {}
+            Err(channel::RecvTimeoutError::Disconnected) => return StreamWatch::Quit,
+        }
+    }
+}
@@ -0,0 +1,247 @@
+// src/replay.rs
+// Replay of a recorded command stream: an --event-log file (commands at their
+// recorded times) or a plain script of control lines, into the running instance
+// or into an engine with no audio devices
+
+use anyhow::{Context, Result, anyhow};
+use std::time::{Duration, Instant};
+
+use crate::audio::io::STDOUT_PATH;
+use crate::audio::{
+    AudioConfig, AudioEvent, ChannelStats, CommandSender, ExportNormalization, LayerCommand,
+    LooperEngine, LooperError, command_channel, event_channel,
+};
+use crate::control::parse_command;
+
+// Audio rendered after the last command offline, so queued actions and renders finish
+const OFFLINE_TAIL: Duration = Duration::from_secs(2);
+// Background work (exports, freezes) still reporting after the tail
+const OFFLINE_SETTLE: Duration = Duration::from_millis(500);
+
+/// Commands in the order and at the offsets (from the start) they were recorded
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Script {
+    pub commands: Vec<(Duration, LayerCommand)>,
+    pub skipped: usize, // logged commands with no control line (device switches, UI settings)
+}
+
+impl Script {
+    /// Log lines (`{...}`) replay at their `t`; other lines are control commands sent
+    /// right after the one before, with `wait SECS` in between to space them out
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut script = Self::default();
+        let mut at = Duration::ZERO;
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let context = || format!("Line {}", number + 1);
+            if line.starts_with('{') {
+                if string_field(line, "kind").as_deref() != Some("command") {
+                    continue;
+                }
+                let Some(command) = string_field(line, "script") else {
+                    script.skipped += 1;
+                    continue;
+                };
+                let seconds = number_field(line, "t")
+                    .filter(|t| *t >= 0.0)
+                    .ok_or_else(|| anyhow!("no time"))
+                    .with_context(context)?;
+                at = Duration::from_secs_f64(seconds);
+                let command = parse_command(&command).with_context(context)?;
+                script.commands.push((at, command));
+            } else if let Some(seconds) = line.strip_prefix("wait ") {
+                let seconds: f64 = seconds
+                    .trim()
+                    .parse()
+                    .ok()
+                    .filter(|s: &f64| *s >= 0.0)
+                    .ok_or_else(|| anyhow!("wait needs a number of seconds"))
+                    .with_context(context)?;
+                at += Duration::from_secs_f64(seconds);
+            } else {
+                let command = parse_command(line).with_context(context)?;
+                script.commands.push((at, command));
+            }
+        }
+        Ok(script)
+    }
+
+    pub fn load(path: &str) -> Result<Self> {
+        let text =
+            std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path))?;
+        Self::parse(&text).with_context(|| format!("Invalid replay script {}", path))
+    }
+
+    /// Length up to the last command
+    pub fn duration(&self) -> Duration {
+        self.commands.last().map_or(Duration::ZERO, |(at, _)| *at)
+    }
+}
+
+/// Send the commands to a running instance at their offsets from now
+pub fn spawn_replay(script: Script, sender: CommandSender) {
+    std::thread::spawn(move || {
+        let started = Instant::now();
+        for (at, command) in script.commands {
+            if let Some(wait) = at.checked_sub(started.elapsed()) {
+                std::thread::sleep(wait);
+            }
+            // A full queue means the engine is behind; keep the order rather than drop
+            loop {
+                match sender.send(command.clone()) {
+                    Ok(()) => break,
+                    Err(LooperError::BufferFull(_)) => {
+                        std::thread::sleep(Duration::from_millis(10));
+                    }
+                    Err(_) => return, // the instance quit
+                }
+            }
+        }
+    });
+}
+
+/// Run the script through an engine fed silence instead of an input device, as fast
+/// as it renders, printing each event with the script time it arrived at
+/// Status updates sent on a wall-clock timer are left out
+/// `export` then writes the final mix there (`-` for standard output, in which case
+/// events go to standard error so they stay out of the WAV)
+pub fn replay_offline(script: &Script, config: AudioConfig, export: Option<&str>) -> Result<()> {
+    let engine = LooperEngine::new(config.clone());
+    let stats = ChannelStats::new();
+    let (command_sender, command_receiver) = command_channel(&stats);
+    let (event_sender, event_receiver) = event_channel(&stats);
+    engine.set_command_channel(command_receiver);
+    engine.set_event_sender(event_sender);
+
+    let block = config.buffer_size;
+    let input = vec![0.0; block];
+    let mut output = vec![0.0; block];
+    let end = script.duration() + OFFLINE_TAIL;
+    let mut rendered = 0u64; // samples
+    let mut pending = script.commands.iter().peekable();
+    let piped = export == Some(STDOUT_PATH)
+        || script.commands.iter().any(|(_, command)| {
+            matches!(
+                command,
+                LayerCommand::ExportWav(path, _) | LayerCommand::ExportLoop(path, ..)
+                    if path == STDOUT_PATH
+            )
+        });
+    let print = |now: Duration, event: AudioEvent| {
+        if event.is_periodic() {
+            return;
+        }
+        if piped {
+            eprintln!("{:10.6} {:?}", now.as_secs_f64(), event);
+        } else {
+            println!("{:10.6} {:?}", now.as_secs_f64(), event);
+        }
+    };
+
+    loop {
+        let now = Duration::from_secs_f64(rendered as f64 / config.sample_rate as f64);
+        if now >= end {
+            break;
+        }
+        // Each block takes what fits in the queue; the rest waits for the next one
+        while let Some((at, command)) = pending.peek() {
+            if *at > now || command_sender.send(command.clone()).is_err() {
+                break;
+            }
+            pending.next();
+        }
+        engine.process_audio(&input, &mut output);
+        rendered += block as u64;
+        while let Ok(event) = event_receiver.try_recv() {
+            print(now, event);
+        }
+    }
+
+    std::thread::sleep(OFFLINE_SETTLE);
+    while let Ok(event) = event_receiver.try_recv() {
+        print(end, event);
+    }
+    if stats.dropped_events() > 0 {
+        eprintln!("{} events dropped (queue full)", stats.dropped_events());
+    }
+
+    if let Some(path) = export {
+        let path = engine.export_mix(path, ExportNormalization::default())?;
+        if path != STDOUT_PATH {
+            eprintln!("Exported the final mix to {}", path);
+        }
+    }
+    Ok(())
+}
+
+/// `"key":NUMBER` in one of our log lines
+fn number_field(line: &str, key: &str) -> Option<f64> {
+    let start = line.find(&format!("\"{}\":", key))? + key.len() + 3;
+    let value = &line[start..];
+    let end = value.find([',', '}'])?;
+    value[..end].trim().parse().ok()
+}
+
+/// `"key":"STRING"` in one of our log lines, unescaped
+fn string_field(line: &str, key: &str) -> Option<String> {
+    let start = line.find(&format!("\"{}\":\"", key))? + key.len() + 4;
+    let mut value = String::new();
+    let mut chars = line[start..].chars();
+    loop {
+        match chars.next()? {
+            '"' => return Some(value),
+            '\\' => match chars.next()? {
+                'n' => value.push('\n'),
+                'r' => value.push('\r'),
+                't' => value.push('\t'),
+                'u' => {
+                    let code: String = chars.by_ref().take(4).collect();
+                    value.push(char::from_u32(u32::from_str_radix(&code, 16).ok()?)?);
+                }
+                c => value.push(c),
+            },
+            c => value.push(c),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_log_and_plain_script() {
+        let log = r#"{"t":0.000000,"kind":"start","data":"2024-02-29_12-34-56"}
+{"t":0.500000,"kind":"command","script":"import 1 my \"loop\".wav","data":"..."}
+{"t":0.600000,"kind":"event","data":"WavImported(0, \"my loop.wav\")"}
+{"t":1.250000,"kind":"command","data":"StopPreview"}
+{"t":2.000000,"kind":"command","script":"play 1","data":"Tracked { id: 3, cmd: Play(0) }"}"#;
+        let script = Script::parse(log).unwrap();
+        assert_eq!(script.skipped, 1);
+        assert_eq!(
+            script.commands,
+            vec![
+                (
+                    Duration::from_millis(500),
+                    parse_command("import 1 my \"loop\".wav").unwrap()
+                ),
+                (Duration::from_secs(2), LayerCommand::Play(0)),
+            ]
+        );
+
+        let plain = "# count in, then the loop\nrecord 1\nwait 1.5\nstop-record 1\nplay 1\n";
+        let script = Script::parse(plain).unwrap();
+        let times: Vec<f64> = script
+            .commands
+            .iter()
+            .map(|(at, _)| at.as_secs_f64())
+            .collect();
+        assert_eq!(times, vec![0.0, 1.5, 1.5]);
+        assert_eq!(script.duration(), Duration::from_millis(1500));
+
+        assert!(Script::parse("play 1\ndance").is_err());
+    }
+}
@@ -0,0 +1,108 @@
+// src/ui/alerts.rs
+// Terminal bell and desktop notifications for problems that need attention (device
+// lost, CPU overload, failed imports, recording silence) when nobody watches the header
+
+use std::io::Write;
+use std::time::{Duration, Instant};
+
+use crate::audio::{AudioEvent, LooperError};
+
+// Repeats of a storm of errors ring once
+const MIN_INTERVAL: Duration = Duration::from_secs(2);
+
+/// What a critical event says in the notification; `None` for everything else
+pub fn critical_message(event: &AudioEvent) -> Option<String> {
+    match event {
+        AudioEvent::Error(
+            err @ (LooperError::DeviceLost(_)
+            | LooperError::EngineStopped
+            | LooperError::TooLarge { .. }),
+        ) => Some(err.to_string()),
+        AudioEvent::Error(err @ LooperError::IoFailed { action, .. })
+            if action.starts_with("import") || action.starts_with("load session") =>
+        {
+            Some(err.to_string())
+        }
+        AudioEvent::SilentInput(layer_id) => Some(format!(
+            "Layer {}: recording with no input signal",
+            layer_id + 1
+        )),
+        AudioEvent::QualityDegraded(load) => Some(format!(
+            "High CPU load ({:.0}%): audio may drop out",
+            load * 100.0
+        )),
+        _ => None,
+    }
+}
+
+pub struct Alerts {
+    pub bell: bool,
+    pub desktop: bool, // needs the `notifications` feature
+    last: Option<Instant>,
+}
+
+impl Alerts {
+    pub fn new(bell: bool, desktop: bool) -> Self {
+        Self {
+            bell,
+            desktop,
+            last: None,
+        }
+    }
+
+    /// Ring and notify for `message`, unless another alert went out just before
+    pub fn raise(&mut self, message: &str) {
+        if self.last.is_some_and(|last| last.elapsed() < MIN_INTERVAL) {
+            return;
+        }
+        self.last = Some(Instant::now());
+        if self.bell {
+            let mut stdout = std::io::stdout();
+            let _ = stdout.write_all(b"\x07");
+            let _ = stdout.flush();
+        }
+        if self.desktop {
+            notify_desktop(message);
+        }
+    }
+}
+
+#[cfg(feature = "notifications")]
+fn notify_desktop(message: &str) {
+    let message = message.to_string();
+    // Delivery can block on the session bus; the UI loop must not
+    std::thread::spawn(move || {
+        let _ = notify_rust::Notification::new()
+            .summary("Soundlooper")
+            .body(&message)
+            .show();
+    });
+}
+
+#[cfg(not(feature = "notifications"))]
+fn notify_desktop(_message: &str) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_critical_events() {
+        assert!(critical_message(&AudioEvent::Error(LooperError::DeviceLost("output"))).is_some());
+        assert!(critical_message(&AudioEvent::QualityDegraded(0.95)).is_some());
+        let import = LooperError::IoFailed {
+            action: "import WAV",
+            path: "a.wav".to_string(),
+            source: "bad header".to_string(),
+        };
+        assert!(critical_message(&AudioEvent::Error(import)).is_some());
+        let export = LooperError::IoFailed {
+            action: "export WAV",
+            path: "a.wav".to_string(),
+            source: "disk full".to_string(),
+        };
+        assert!(critical_message(&AudioEvent::Error(export)).is_none());
+        assert!(critical_message(&AudioEvent::Error(LooperError::NoMasterLoop)).is_none());
+        assert!(critical_message(&AudioEvent::QualityRestored).is_none());
+    }
+}
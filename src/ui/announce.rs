@@ -0,0 +1,130 @@
+// src/ui/announce.rs
+// Plain-text status lines for screen readers (--announce): one line per important
+// event, always `<subject>: <what happened>`, to stderr or a file / FIFO
+
+use anyhow::{Context, Result};
+use std::fs::OpenOptions;
+use std::io::Write;
+
+use crate::audio::AudioEvent;
+
+/// The line to announce for `event`; meters, positions and other chatter give `None`
+pub fn announcement(event: &AudioEvent) -> Option<String> {
+    let layer = |layer_id: &usize| format!("Layer {}", layer_id + 1);
+    let on_off = |on: &bool| if *on { "on" } else { "off" };
+    let (subject, message) = match event {
+        AudioEvent::LayerPhaseChanged(layer_id, phase) => (layer(layer_id), phase.to_string()),
+        AudioEvent::LayerMuted(layer_id) => (layer(layer_id), "muted".to_string()),
+        AudioEvent::LayerUnmuted(layer_id) => (layer(layer_id), "unmuted".to_string()),
+        AudioEvent::LayerSoloed(layer_id) => (layer(layer_id), "soloed".to_string()),
+        AudioEvent::LayerUnsoloed(layer_id) => (layer(layer_id), "unsoloed".to_string()),
+        AudioEvent::LayerCleared(layer_id, _) => (layer(layer_id), "cleared".to_string()),
+        AudioEvent::LayerLockChanged(layer_id, locked) => (
+            layer(layer_id),
+            if *locked { "locked" } else { "unlocked" }.to_string(),
+        ),
+        AudioEvent::CountInStarted { layer_id, beats } => {
+            (layer(layer_id), format!("count-in, {} beats", beats))
+        }
+        AudioEvent::SilentInput(layer_id) => (layer(layer_id), "no input signal".to_string()),
+        AudioEvent::ImportWarning(layer_id, issue) => {
+            (layer(layer_id), format!("imported file {}", issue))
+        }
+        AudioEvent::ReplacementReady(layer_id) => (layer(layer_id), "new take ready".to_string()),
+        AudioEvent::WavImported(layer_id, path) => (layer(layer_id), format!("imported {}", path)),
+        AudioEvent::AllStopped => ("All layers".to_string(), "stopped".to_string()),
+        AudioEvent::AllPlaying => ("All layers".to_string(), "playing".to_string()),
+        AudioEvent::AllCleared => ("All layers".to_string(), "cleared".to_string()),
+        AudioEvent::BpmChanged(bpm) => ("Tempo".to_string(), format!("{:.1} BPM", bpm)),
+        AudioEvent::TransportPaused(paused) => (
+            "Transport".to_string(),
+            if *paused { "paused" } else { "resumed" }.to_string(),
+        ),
+        AudioEvent::MetronomeToggled(on) => ("Metronome".to_string(), on_off(on).to_string()),
+        AudioEvent::BeatSyncToggled(on) => ("Beat sync".to_string(), on_off(on).to_string()),
+        AudioEvent::WavExported(path) | AudioEvent::ProjectExported(path) => {
+            ("Export".to_string(), format!("saved {}", path))
+        }
+        AudioEvent::LoopPackExported(directory, files) => (
+            "Export".to_string(),
+            format!("saved {} loops in {}", files, directory),
+        ),
+        AudioEvent::SessionSaved(path) => ("Session".to_string(), format!("saved {}", path)),
+        AudioEvent::SessionLoaded(path) => ("Session".to_string(), format!("loaded {}", path)),
+        AudioEvent::JamConnected(peer) => ("Jam".to_string(), format!("connected to {}", peer)),
+        AudioEvent::JamDisconnected => ("Jam".to_string(), "disconnected".to_string()),
+        AudioEvent::QualityDegraded(_) => ("CPU".to_string(), "overloaded".to_string()),
+        AudioEvent::QualityRestored => ("CPU".to_string(), "normal".to_string()),
+        AudioEvent::AudioEngineRestarted => ("Engine".to_string(), "restarted".to_string()),
+        AudioEvent::Error(err)
+        | AudioEvent::CommandAck {
+            result: Err(err), ..
+        } => ("Error".to_string(), err.to_string()),
+        _ => return None,
+    };
+    Some(format!("{}: {}", subject, message))
+}
+
+pub struct Announcer {
+    sink: Box<dyn Write + Send>,
+}
+
+impl Announcer {
+    pub fn stderr() -> Self {
+        Self {
+            sink: Box::new(std::io::stderr()),
+        }
+    }
+
+    /// Append to `path`; opening a FIFO waits until something reads it
+    pub fn open(path: &str) -> Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Failed to open {}", path))?;
+        Ok(Self {
+            sink: Box::new(file),
+        })
+    }
+
+    pub fn announce(&mut self, event: &AudioEvent) {
+        if let Some(line) = announcement(event) {
+            // A reader that went away shouldn't stop the UI
+            let _ = writeln!(self.sink, "{}", line);
+            let _ = self.sink.flush();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::{LayerPhase, LooperError};
+
+    #[test]
+    fn test_announcements() {
+        assert_eq!(
+            announcement(&AudioEvent::LayerPhaseChanged(1, LayerPhase::Recording)).as_deref(),
+            Some("Layer 2: recording")
+        );
+        assert_eq!(
+            announcement(&AudioEvent::BpmChanged(96.0)).as_deref(),
+            Some("Tempo: 96.0 BPM")
+        );
+        assert_eq!(
+            announcement(&AudioEvent::CommandAck {
+                id: 3,
+                result: Err(LooperError::EmptyLayer(0)),
+            })
+            .as_deref(),
+            Some(format!("Error: {}", LooperError::EmptyLayer(0)).as_str())
+        );
+        let ok = AudioEvent::CommandAck {
+            id: 3,
+            result: Ok(()),
+        };
+        assert_eq!(announcement(&ok), None);
+        assert_eq!(announcement(&AudioEvent::PeaksReset), None);
+    }
+}
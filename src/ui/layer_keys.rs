@@ -0,0 +1,113 @@
+// src/ui/layer_keys.rs
+// Direct record/stop/play keys per layer: 1-9 and 0 for layers 1-10, F1-F6 for
+// 11-16, or any keys listed under [keys] in the config
+
+use anyhow::{Result, bail};
+use crossterm::event::KeyCode;
+
+/// One key per layer, in layer order
+#[derive(Debug, Clone, PartialEq)]
+pub struct LayerKeys(Vec<KeyCode>);
+
+impl LayerKeys {
+    pub const DEFAULT: [&str; 16] = [
+        "1", "2", "3", "4", "5", "6", "7", "8", "9", "0", "F1", "F2", "F3", "F4", "F5", "F6",
+    ];
+
+    /// Keys by name: a single character or F1-F12; a key can only be used once
+    pub fn parse<S: AsRef<str>>(names: &[S]) -> Result<Self> {
+        let mut keys = Vec::with_capacity(names.len());
+        for name in names {
+            let name = name.as_ref();
+            let mut chars = name.chars();
+            let key = match (chars.next(), chars.next()) {
+                (Some(c), None) if !c.is_whitespace() => KeyCode::Char(c),
+                _ => match name
+                    .strip_prefix(['F', 'f'])
+                    .and_then(|n| n.parse::<u8>().ok())
+                {
+                    Some(n @ 1..=12) => KeyCode::F(n),
+                    _ => bail!("Unknown layer key {:?} (use one character or F1-F12)", name),
+                },
+            };
+            if keys.contains(&key) {
+                bail!("Layer key {} is listed twice", name);
+            }
+            keys.push(key);
+        }
+        Ok(Self(keys))
+    }
+
+    /// Layer a key plays, records or stops
+    pub fn layer_for(&self, code: KeyCode) -> Option<usize> {
+        self.0.iter().position(|&key| key == code)
+    }
+
+    /// The keys of the first `layers` layers for the footer, runs collapsed
+    /// (`1-9,0,F1-F6`)
+    pub fn summary(&self, layers: usize) -> String {
+        let mut runs: Vec<(KeyCode, KeyCode)> = Vec::new();
+        for &key in self.0.iter().take(layers) {
+            match runs.last_mut() {
+                Some((_, last)) if follows(*last, key) => *last = key,
+                _ => runs.push((key, key)),
+            }
+        }
+        runs.iter()
+            .map(|&(first, last)| {
+                if first == last {
+                    key_name(first)
+                } else {
+                    format!("{}-{}", key_name(first), key_name(last))
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+}
+
+impl Default for LayerKeys {
+    fn default() -> Self {
+        Self::parse(&Self::DEFAULT).unwrap()
+    }
+}
+
+fn follows(previous: KeyCode, key: KeyCode) -> bool {
+    match (previous, key) {
+        (KeyCode::Char(a), KeyCode::Char(b)) => {
+            a.is_ascii_alphanumeric() && b as u32 == a as u32 + 1
+        }
+        (KeyCode::F(a), KeyCode::F(b)) => b == a + 1,
+        _ => false,
+    }
+}
+
+fn key_name(key: KeyCode) -> String {
+    match key {
+        KeyCode::F(n) => format!("F{}", n),
+        KeyCode::Char(c) => c.to_string(),
+        other => format!("{:?}", other),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_layer_keys() {
+        let keys = LayerKeys::default();
+        assert_eq!(keys.layer_for(KeyCode::Char('0')), Some(9));
+        assert_eq!(keys.layer_for(KeyCode::F(6)), Some(15));
+        assert_eq!(keys.layer_for(KeyCode::F(7)), None);
+        assert_eq!(keys.summary(16), "1-9,0,F1-F6");
+        assert_eq!(keys.summary(4), "1-4");
+
+        let keys = LayerKeys::parse(&["a", "s", "d", "f12"]).unwrap();
+        assert_eq!(keys.layer_for(KeyCode::F(12)), Some(3));
+        assert_eq!(keys.summary(4), "a,s,d,F12");
+        assert!(LayerKeys::parse(&["1", "F13"]).is_err());
+        assert!(LayerKeys::parse(&["1", "1"]).is_err());
+        assert!(LayerKeys::parse(&["Shift+1"]).is_err());
+    }
+}
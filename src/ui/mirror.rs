@@ -0,0 +1,108 @@
+// src/ui/mirror.rs
+// Read-only status view for bandmates: every TCP client (nc, telnet) gets the
+// latest status frame as plain ANSI text and anything it sends is ignored
+
+use anyhow::{Context, Result};
+use std::io::Write;
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::{Arc, Condvar, Mutex};
+
+// Home the cursor and clear, so each frame replaces the last one
+const CLEAR_SCREEN: &str = "\x1b[H\x1b[2J";
+
+#[derive(Default)]
+struct Frame {
+    version: u64, // 0 until the first frame
+    text: String,
+}
+
+/// Publishing end of the mirror, held by the UI
+#[derive(Clone)]
+pub struct Mirror {
+    frame: Arc<(Mutex<Frame>, Condvar)>,
+}
+
+impl Mirror {
+    /// Send `lines` to every viewer; unchanged frames are skipped
+    pub fn publish(&self, lines: &[String]) {
+        let text = format!("{}{}\r\n", CLEAR_SCREEN, lines.join("\r\n"));
+        let (lock, ready) = &*self.frame;
+        let Ok(mut frame) = lock.lock() else {
+            return;
+        };
+        if frame.text != text {
+            frame.text = text;
+            frame.version += 1;
+            ready.notify_all();
+        }
+    }
+}
+
+/// Listen for viewers on `addr`; each gets its own thread, so a slow link
+/// only delays its own frames
+pub fn spawn_mirror_server(addr: &str) -> Result<(SocketAddr, Mirror)> {
+    let listener =
+        TcpListener::bind(addr).with_context(|| format!("Failed to listen on {}", addr))?;
+    let local_addr = listener.local_addr()?;
+    let mirror = Mirror {
+        frame: Arc::new((Mutex::new(Frame::default()), Condvar::new())),
+    };
+    let frame = Arc::clone(&mirror.frame);
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let frame = Arc::clone(&frame);
+            std::thread::spawn(move || {
+                let _ = serve_viewer(stream, &frame);
+            });
+        }
+    });
+    Ok((local_addr, mirror))
+}
+
+fn serve_viewer(mut stream: TcpStream, frame: &(Mutex<Frame>, Condvar)) -> std::io::Result<()> {
+    let (lock, ready) = frame;
+    let mut seen = 0;
+    loop {
+        let text = {
+            let mut frame = lock.lock().map_err(|_| std::io::ErrorKind::Other)?;
+            while frame.version == seen {
+                frame = ready.wait(frame).map_err(|_| std::io::ErrorKind::Other)?;
+            }
+            seen = frame.version;
+            frame.text.clone()
+        };
+        // A closed connection fails here and ends the thread
+        stream.write_all(text.as_bytes())?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    #[test]
+    fn test_viewer_gets_latest_frame() {
+        let (addr, mirror) = spawn_mirror_server("127.0.0.1:0").unwrap();
+        mirror.publish(&["Bar 1  Beat 1".to_string()]);
+        mirror.publish(&["Bar 2  Beat 3".to_string(), "L1  PLAY".to_string()]);
+
+        // Joining late starts from the current frame
+        let mut viewer = TcpStream::connect(addr).unwrap();
+        viewer
+            .set_read_timeout(Some(std::time::Duration::from_secs(5)))
+            .unwrap();
+        let expected = format!("{}Bar 2  Beat 3\r\nL1  PLAY\r\n", CLEAR_SCREEN);
+        let mut received = vec![0; expected.len()];
+        viewer.read_exact(&mut received).unwrap();
+        assert_eq!(String::from_utf8(received).unwrap(), expected);
+
+        // Typing on the viewer changes nothing
+        viewer.write_all(b"clear 1\n").unwrap();
+        mirror.publish(&["Bar 3  Beat 1".to_string()]);
+        let expected = format!("{}Bar 3  Beat 1\r\n", CLEAR_SCREEN);
+        let mut received = vec![0; expected.len()];
+        viewer.read_exact(&mut received).unwrap();
+        assert_eq!(String::from_utf8(received).unwrap(), expected);
+    }
+}
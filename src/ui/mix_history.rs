@@ -0,0 +1,86 @@
+// src/ui/mix_history.rs
+// Scrolling loudness / spectrum history of the master mix for the analysis pane
+
+use std::collections::VecDeque;
+
+use crate::audio::AnalysisFrame;
+
+/// Frames kept (10 per second, so ten minutes of a jam)
+const HISTORY_FRAMES: usize = 6000;
+
+/// What the analysis pane shows; cycled with V
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AnalysisView {
+    Off,
+    Loudness,
+    Spectrogram, // loudness plus the octave band spectrogram
+}
+
+impl AnalysisView {
+    pub fn next(self) -> Self {
+        match self {
+            Self::Off => Self::Loudness,
+            Self::Loudness => Self::Spectrogram,
+            Self::Spectrogram => Self::Off,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MixHistory {
+    frames: VecDeque<AnalysisFrame>,
+}
+
+impl MixHistory {
+    pub fn push(&mut self, frame: AnalysisFrame) {
+        if self.frames.len() == HISTORY_FRAMES {
+            self.frames.pop_front();
+        }
+        self.frames.push_back(frame);
+    }
+
+    pub fn clear(&mut self) {
+        self.frames.clear();
+    }
+
+    /// Up to `count` most recent frames, oldest first
+    pub fn recent(&self, count: usize) -> Vec<AnalysisFrame> {
+        let skip = self.frames.len().saturating_sub(count);
+        self.frames.iter().skip(skip).cloned().collect()
+    }
+
+    /// Loudest moment of the whole history (LUFS)
+    pub fn max_loudness(&self) -> Option<f32> {
+        self.frames
+            .iter()
+            .map(|frame| frame.loudness)
+            .max_by(f32::total_cmp)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::ANALYSIS_BANDS;
+
+    #[test]
+    fn test_history_scrolls() {
+        let frame = |loudness| AnalysisFrame {
+            loudness,
+            bands: [-70.0; ANALYSIS_BANDS],
+        };
+        let mut history = MixHistory::default();
+        assert_eq!(history.max_loudness(), None);
+        for i in 0..HISTORY_FRAMES + 5 {
+            history.push(frame(-(i as f32) / 100.0));
+        }
+        let recent = history.recent(3);
+        assert_eq!(recent.len(), 3);
+        assert_eq!(recent[2].loudness, -((HISTORY_FRAMES + 4) as f32) / 100.0);
+        // The first five frames scrolled out
+        assert_eq!(history.max_loudness(), Some(-0.05));
+        assert_eq!(history.recent(HISTORY_FRAMES * 2).len(), HISTORY_FRAMES);
+
+        assert_eq!(AnalysisView::Off.next().next().next(), AnalysisView::Off);
+    }
+}
@@ -1,3 +1,11 @@
+mod alerts;
+pub mod announce;
+pub mod layer_keys;
+pub mod mirror;
+mod mix_history;
+mod recent_files;
+mod setup;
 pub mod terminal;
+mod watch_folder;
 
 pub use terminal::TerminalUI;
@@ -0,0 +1,128 @@
+// src/ui/recent_files.rs
+// Recently imported and exported WAVs, kept between runs so the Recent overlay
+// can re-import or reveal them without walking the file picker again
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::process::Command;
+
+// Oldest entries drop off past this
+const MAX_RECENT: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RecentKind {
+    Imported,
+    Exported,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct RecentFile {
+    pub path: String,
+    pub kind: RecentKind,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct RecentFiles {
+    #[serde(default)]
+    files: Vec<RecentFile>, // newest first
+}
+
+impl RecentFiles {
+    /// Kept next to the config in the working directory
+    pub const DEFAULT_PATH: &str = "soundlooper-recent.toml";
+
+    /// A missing or unreadable list starts empty
+    pub fn load<P: AsRef<Path>>(path: P) -> Self {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|text| toml::from_str(&text).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        let text = toml::to_string_pretty(self).context("Failed to serialize recent files")?;
+        std::fs::write(path, text).with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// Move a file to the top of the list; absolute paths keep entries valid
+    /// when soundlooper is started from another directory
+    pub fn record(&mut self, path: &str, kind: RecentKind) {
+        let path = std::fs::canonicalize(path)
+            .map(|p| p.to_string_lossy().to_string())
+            .unwrap_or_else(|_| path.to_string());
+        self.files.retain(|file| file.path != path);
+        self.files.insert(0, RecentFile { path, kind });
+        self.files.truncate(MAX_RECENT);
+    }
+
+    pub fn remove(&mut self, index: usize) {
+        if index < self.files.len() {
+            self.files.remove(index);
+        }
+    }
+
+    pub fn files(&self) -> &[RecentFile] {
+        &self.files
+    }
+}
+
+/// Show a file in the system file manager (its folder on Linux)
+pub fn reveal(path: &str) -> Result<()> {
+    let mut command = if cfg!(target_os = "macos") {
+        let mut command = Command::new("open");
+        command.arg("-R").arg(path);
+        command
+    } else if cfg!(windows) {
+        let mut command = Command::new("explorer");
+        command.arg(format!("/select,{}", path));
+        command
+    } else {
+        let folder = Path::new(path).parent().unwrap_or(Path::new("."));
+        let mut command = Command::new("xdg-open");
+        command.arg(folder);
+        command
+    };
+    // The file manager outlives this call; don't wait on it
+    command
+        .spawn()
+        .with_context(|| format!("Failed to open the file manager for {}", path))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_and_round_trip() {
+        let mut recent = RecentFiles::default();
+        recent.record("missing/a.wav", RecentKind::Imported);
+        recent.record("missing/b.wav", RecentKind::Exported);
+        recent.record("missing/a.wav", RecentKind::Exported);
+        let paths: Vec<&str> = recent.files().iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(paths, ["missing/a.wav", "missing/b.wav"]);
+        assert_eq!(recent.files()[0].kind, RecentKind::Exported);
+
+        for i in 0..MAX_RECENT + 5 {
+            recent.record(&format!("missing/{}.wav", i), RecentKind::Exported);
+        }
+        assert_eq!(recent.files().len(), MAX_RECENT);
+        assert_eq!(
+            recent.files()[0].path,
+            format!("missing/{}.wav", MAX_RECENT + 4)
+        );
+        recent.remove(0);
+        recent.remove(MAX_RECENT);
+        assert_eq!(recent.files().len(), MAX_RECENT - 1);
+
+        let text = toml::to_string_pretty(&recent).unwrap();
+        assert_eq!(toml::from_str::<RecentFiles>(&text).unwrap(), recent);
+        assert_eq!(
+            RecentFiles::load("missing/soundlooper-recent.toml"),
+            RecentFiles::default()
+        );
+    }
+}
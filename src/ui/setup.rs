@@ -0,0 +1,247 @@
+// src/ui/setup.rs
+// First-run setup wizard: devices, buffer size, tempo and click, saved as the config file
+
+use crate::audio::ClickSound;
+use crate::config::AppConfig;
+
+/// Hardware buffer choices in frames (None = device default)
+const BUFFER_SIZES: [(Option<u32>, &str); 6] = [
+    (None, "Device default"),
+    (Some(64), "64 frames - lowest latency, needs a fast machine"),
+    (Some(128), "128 frames - low latency"),
+    (Some(256), "256 frames - balanced"),
+    (Some(512), "512 frames - safe on most machines"),
+    (Some(1024), "1024 frames - most stable, audible delay"),
+];
+
+const CLICK_SOUNDS: [&str; 4] = ["sample", "beep", "wood", "tick"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SetupStep {
+    InputDevice,
+    OutputDevice,
+    BufferSize,
+    Bpm,
+    ClickSound,
+}
+
+impl SetupStep {
+    pub const COUNT: usize = 5;
+
+    pub fn number(self) -> usize {
+        match self {
+            Self::InputDevice => 1,
+            Self::OutputDevice => 2,
+            Self::BufferSize => 3,
+            Self::Bpm => 4,
+            Self::ClickSound => 5,
+        }
+    }
+
+    pub fn question(self) -> &'static str {
+        match self {
+            Self::InputDevice => "Which device do you record from?",
+            Self::OutputDevice => "Which device do you listen on?",
+            Self::BufferSize => "Buffer size (smaller = less delay, more risk of crackles)",
+            Self::Bpm => "Starting tempo in BPM",
+            Self::ClickSound => "Metronome sound",
+        }
+    }
+}
+
+/// Answers collected so far; `config` holds them and is written to `path` at the end
+#[derive(Debug, Clone, PartialEq)]
+pub struct SetupWizard {
+    pub step: SetupStep,
+    pub path: String,
+    pub config: AppConfig,
+    inputs: Vec<String>,
+    outputs: Vec<String>,
+    pub selected_index: usize, // within the current step's choices
+    pub bpm_text: String,
+}
+
+impl SetupWizard {
+    /// Device lists come from the host; the devices in use are preselected
+    pub fn new(
+        mut config: AppConfig,
+        path: String,
+        inputs: Vec<String>,
+        outputs: Vec<String>,
+        current_input: &str,
+        current_output: &str,
+    ) -> Self {
+        config
+            .audio
+            .input_device
+            .get_or_insert_with(|| current_input.to_string());
+        config
+            .audio
+            .output_device
+            .get_or_insert_with(|| current_output.to_string());
+        let bpm_text = format!("{}", config.tempo.bpm);
+        let mut wizard = Self {
+            step: SetupStep::InputDevice,
+            path,
+            config,
+            inputs,
+            outputs,
+            selected_index: 0,
+            bpm_text,
+        };
+        wizard.preselect();
+        wizard
+    }
+
+    /// Entries to pick from on the current step (empty for the BPM entry)
+    pub fn choices(&self) -> Vec<String> {
+        let with_current = |devices: &[String], current: &Option<String>| {
+            let mut devices = devices.to_vec();
+            if let Some(current) = current
+                && !devices.contains(current)
+            {
+                devices.insert(0, current.clone());
+            }
+            devices
+        };
+        match self.step {
+            SetupStep::InputDevice => with_current(&self.inputs, &self.config.audio.input_device),
+            SetupStep::OutputDevice => {
+                with_current(&self.outputs, &self.config.audio.output_device)
+            }
+            SetupStep::BufferSize => BUFFER_SIZES
+                .iter()
+                .map(|(_, label)| label.to_string())
+                .collect(),
+            SetupStep::Bpm => Vec::new(),
+            SetupStep::ClickSound => {
+                let mut sounds: Vec<String> = CLICK_SOUNDS.iter().map(|s| s.to_string()).collect();
+                // Keep a WAV click from an existing config on offer
+                if let ClickSound::Wav(_) = self.config.metronome.sound {
+                    sounds.push(String::from(self.config.metronome.sound.clone()));
+                }
+                sounds
+            }
+        }
+    }
+
+    pub fn select_previous(&mut self) {
+        self.selected_index = self.selected_index.saturating_sub(1);
+    }
+
+    pub fn select_next(&mut self) {
+        let count = self.choices().len();
+        if self.selected_index + 1 < count {
+            self.selected_index += 1;
+        }
+    }
+
+    /// Store the current answer and move on; Ok(true) once every step is answered
+    pub fn advance(&mut self) -> Result<bool, String> {
+        let choice = self.choices().get(self.selected_index).cloned();
+        match self.step {
+            SetupStep::InputDevice => self.config.audio.input_device = choice,
+            SetupStep::OutputDevice => self.config.audio.output_device = choice,
+            SetupStep::BufferSize => {
+                self.config.audio.buffer_size = BUFFER_SIZES[self.selected_index].0;
+            }
+            SetupStep::Bpm => match self.bpm_text.trim().parse::<f64>() {
+                Ok(bpm) if (20.0..=300.0).contains(&bpm) => self.config.tempo.bpm = bpm,
+                _ => return Err("Enter a BPM between 20 and 300".to_string()),
+            },
+            SetupStep::ClickSound => {
+                if let Some(choice) = choice {
+                    self.config.metronome.sound = ClickSound::from(choice);
+                }
+            }
+        }
+        self.step = match self.step {
+            SetupStep::InputDevice => SetupStep::OutputDevice,
+            SetupStep::OutputDevice => SetupStep::BufferSize,
+            SetupStep::BufferSize => SetupStep::Bpm,
+            SetupStep::Bpm => SetupStep::ClickSound,
+            SetupStep::ClickSound => return Ok(true),
+        };
+        self.preselect();
+        Ok(false)
+    }
+
+    /// Return to the previous step, keeping the answers given so far
+    pub fn back(&mut self) {
+        self.step = match self.step {
+            SetupStep::InputDevice | SetupStep::OutputDevice => SetupStep::InputDevice,
+            SetupStep::BufferSize => SetupStep::OutputDevice,
+            SetupStep::Bpm => SetupStep::BufferSize,
+            SetupStep::ClickSound => SetupStep::Bpm,
+        };
+        self.preselect();
+    }
+
+    /// Highlight the answer already in the config for the current step
+    fn preselect(&mut self) {
+        let choices = self.choices();
+        let position = |value: Option<String>| {
+            value
+                .and_then(|value| choices.iter().position(|choice| *choice == value))
+                .unwrap_or(0)
+        };
+        self.selected_index = match self.step {
+            SetupStep::InputDevice => position(self.config.audio.input_device.clone()),
+            SetupStep::OutputDevice => position(self.config.audio.output_device.clone()),
+            SetupStep::BufferSize => BUFFER_SIZES
+                .iter()
+                .position(|(frames, _)| *frames == self.config.audio.buffer_size)
+                .unwrap_or(0),
+            SetupStep::Bpm => 0,
+            SetupStep::ClickSound => {
+                position(Some(String::from(self.config.metronome.sound.clone())))
+            }
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::ClickFlavor;
+
+    #[test]
+    fn test_wizard_collects_answers() {
+        let mut wizard = SetupWizard::new(
+            AppConfig::default(),
+            "test.toml".to_string(),
+            vec!["Mic".to_string(), "Interface".to_string()],
+            vec!["Speakers".to_string(), "Interface".to_string()],
+            "Mic",
+            "Speakers",
+        );
+        wizard.select_next();
+        assert_eq!(wizard.advance(), Ok(false));
+        assert_eq!(wizard.step, SetupStep::OutputDevice);
+        assert_eq!(wizard.selected_index, 0);
+        wizard.select_next();
+        wizard.select_next(); // stays on the last entry
+        assert_eq!(wizard.advance(), Ok(false));
+        wizard.selected_index = 2;
+        assert_eq!(wizard.advance(), Ok(false));
+        wizard.bpm_text = "abc".to_string();
+        assert!(wizard.advance().is_err());
+        wizard.bpm_text = "96".to_string();
+        assert_eq!(wizard.advance(), Ok(false));
+        wizard.back();
+        assert_eq!(wizard.step, SetupStep::Bpm);
+        assert_eq!(wizard.advance(), Ok(false));
+        wizard.selected_index = 2;
+        assert_eq!(wizard.advance(), Ok(true));
+
+        let audio = &wizard.config.audio;
+        assert_eq!(audio.input_device.as_deref(), Some("Interface"));
+        assert_eq!(audio.output_device.as_deref(), Some("Interface"));
+        assert_eq!(audio.buffer_size, Some(128));
+        assert_eq!(wizard.config.tempo.bpm, 96.0);
+        assert_eq!(
+            wizard.config.metronome.sound,
+            ClickSound::Synth(ClickFlavor::Wood)
+        );
+    }
+}
@@ -18,8 +18,30 @@ use std::{
     time::{Duration, Instant},
 };
 
-use crate::audio::stream::enumerate_device_names;
-use crate::audio::{AudioEvent, AudioLayer, LayerCommand};
+use super::alerts::{Alerts, critical_message};
+use super::announce::Announcer;
+use super::layer_keys::LayerKeys;
+use super::mirror::Mirror;
+use super::mix_history::{AnalysisView, MixHistory};
+use super::recent_files::{RecentFile, RecentFiles, RecentKind, reveal};
+use super::setup::{SetupStep, SetupWizard};
+use super::watch_folder::{POLL_INTERVAL, WatchFolder};
+use crate::audio::analysis::SILENCE_DB;
+use crate::audio::io::{
+    STDIN_PATH, STDOUT_PATH, is_flac, next_snapshot_number, snapshot_file_name,
+};
+use crate::audio::latency::HIGH_LATENCY;
+use crate::audio::stream::{available_host_names, enumerate_device_names, input_device_format};
+use crate::audio::{
+    ANALYSIS_BANDS, AnalysisFrame, AudioEvent, AudioLayer, BAND_CENTRES, ChannelStats,
+    ClickSettings, CommandSender, EventLog, EventReceiver, ExportNormalization, ExportRegion,
+    ImportIssue, ImportMode, LayerCommand, LayerPhase, LayerStats, LayerStatusSnapshot, LoopFix,
+    LooperError, Marker, MixQuantize, OutputBus, Session, SessionMetadata, TransportPosition,
+};
+use crate::config::{AppConfig, ExportConfig, UiConfig};
+
+// Time allowed for the confirming second press of C / X
+const CLEAR_CONFIRM_WINDOW: Duration = Duration::from_secs(1);
 
 #[derive(Debug, Clone, PartialEq)]
 enum InputMode {
@@ -31,7 +53,45 @@ enum InputMode {
         scroll_offset: usize,
     },
     ExportWav,
+    ExportRegion {
+        filename: String,
+    },
+    ExportNormalization {
+        filename: String,
+        region: Option<ExportRegion>, // None = whole layers as recorded
+    },
+    ExportProject,
+    ExportLoopPack,
     SetBpm,
+    AddMarker {
+        layer_id: usize,
+    },
+    SaveSession,
+    LoadSession,
+    Audition {
+        layer_id: usize,
+    },
+    LoopFix {
+        layer_id: usize,
+    },
+    LatencyWarning {
+        round_trip: Duration,
+    },
+    DcOffsetWarning {
+        layer_id: usize,
+    },
+    EditMetadata {
+        field: MetadataField,
+        draft: SessionMetadata,
+    },
+    Notes {
+        text: String,
+    },
+    RecentFiles {
+        layer_id: usize, // re-imports go here
+        selected_index: usize,
+        scroll_offset: usize,
+    },
     DevicePicker {
         inputs: Vec<String>,
         outputs: Vec<String>,
@@ -39,6 +99,23 @@ enum InputMode {
         selected_index: usize, // index within current column
         scroll_offset: usize,  // scroll for the current column
     },
+    InputCheck {
+        device: String,
+        format: Result<(u32, u16), String>, // (sample rate, channels)
+    },
+    Setup(Box<SetupWizard>),
+    LayerInfo {
+        layer_id: usize,
+        stats: LayerStats,
+    },
+}
+
+/// Session details prompted for in order by the metadata editor
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum MetadataField {
+    Title,
+    Author,
+    Tags,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -51,21 +128,64 @@ enum FileEntry {
 enum HeaderStatus {
     InputPrompt(String, String), // (prompt, current_input)
     Success(String),             // message
+    Warning(String),             // message, shown in red
     Cancelled,
 }
 
+/// Input headed for armed and capturing layers, for their `in` meters
+#[derive(Debug, Clone, Copy)]
+struct RecordInput {
+    live_peak: f32,              // live input, shown while armed
+    silent_layer: Option<usize>, // capturing with no signal coming in
+}
+
+/// Transport and engine status values shown in the footer
+#[derive(Debug, Clone)]
+struct FooterStatus {
+    bpm: f64,
+    position: TransportPosition,
+    sync_on: bool,
+    metro_on: bool,
+    click_record_only: bool,
+    tap_locked: bool,
+    transport_paused: bool,
+    swing: f64,
+    count_in_bars: Option<u32>, // None = count-in mode off
+    mix_quantize: MixQuantize,
+    latency_compensation: Duration,
+    quality_degraded: bool,
+    jam_connected: bool,
+    dropped_events: u64,
+    rejected_commands: u64,
+    layer_keys: String, // e.g. "1-9,0,F1-F6"
+}
+
 pub struct TerminalUI {
     terminal: Terminal<CrosstermBackend<io::Stdout>>,
-    layers: Arc<Vec<Arc<Mutex<AudioLayer>>>>,
+    // Layer display state, updated from LayerStatusBatch / LayerMarkers events
+    layers: Vec<LayerStatusSnapshot>,
+    layer_markers: Vec<Vec<Marker>>,
     selected_layer: usize,
-    command_sender: crossbeam::channel::Sender<LayerCommand>,
-    event_receiver: crossbeam::channel::Receiver<AudioEvent>,
+    command_sender: CommandSender,
+    next_command_id: u64, // correlation id for LayerCommand::Tracked
+    channel_stats: Arc<ChannelStats>,
+    event_receiver: EventReceiver,
+    event_log: Option<Arc<Mutex<EventLog>>>, // --event-log: every event received is written here
     is_running: bool,
     last_update: Instant,
+    refresh: Duration,  // shortest time between redraws
+    needs_redraw: bool, // something on screen changed since the last draw
+    alerts: Alerts,
+    big_screen: bool,                  // performer view (^) instead of the tables
+    silent_input_layer: Option<usize>, // capturing with no signal coming in
+    mirror: Option<Mirror>,            // --mirror: read-only status for remote viewers
+    announcer: Option<Announcer>,      // --announce: status lines for screen readers
+    layer_keys: LayerKeys,             // record/stop/play key per layer
     last_key_time: Instant,
     // key_debounce_duration: Duration, // Temporarily disabled for debugging
     input_device_name: String,
     output_device_name: String,
+    host_name: Option<String>, // audio backend, once the audio thread reports it
     // File input state
     input_mode: Option<InputMode>,
     input_buffer: String,
@@ -75,21 +195,65 @@ pub struct TerminalUI {
     status_timer: Option<Instant>,
     // File picker overlay
     file_picker_overlay: bool,
+    // Live input level for the input check screen
+    input_peak: f32,
+    input_peak_hold: f32,
+    // File currently playing on the preview channel
+    previewing: Option<String>,
+    // Clearing content needs a second press within CLEAR_CONFIRM_WINDOW
+    confirm_clear: bool,
+    pending_clear: Option<(Option<usize>, Instant)>, // (layer, None = all; first press)
+    // Title, author, notes and tags of the current session
+    session_metadata: SessionMetadata,
     // Tempo/Sync state
     beat_sync_enabled: bool,
     bpm_display: f64,
-    current_beat: u32,
-    current_measure: usize,
+    tap_locked: bool,
+    transport_paused: bool, // tempo clock frozen
+    tap_rounding: bool,
+    swing: f64,
+    transport_position: TransportPosition,
     metronome_enabled: bool,
+    click_bus: OutputBus,
+    click: ClickSettings,
     count_in_mode_enabled: bool,
+    count_in_measures: u32,
+    count_out_enabled: bool,
     count_in_remaining: Option<(usize, u32)>,
+    mix_quantize: MixQuantize, // mute/solo wait for this boundary when synced
+    // Device round trip and how far new takes are shifted to make up for it
+    device_latency: Duration,
+    latency_compensation: Duration,
+    latency_warned: bool,
+    // Length and level target of the last WAV export
+    export_region: Option<ExportRegion>,
+    export_normalization: ExportNormalization,
+    // Snapshot exports (K): numbered takes in this directory
+    export_directory: String,
+    snapshot_count: u32,
+    // Export name with {session} / {bpm} / {date} / {time} / {n} tokens, from the config
+    export_name_template: Option<String>,
+    // Imported / exported WAVs for the Recent overlay (U), saved between runs
+    recent_files: RecentFiles,
+    // New WAVs here are imported into empty layers and started on the next bar
+    watch_folder: Option<WatchFolder>,
+    watch_polled: Instant,
+    watch_imports: Vec<(usize, String)>, // (layer_id, file_path) still decoding
+    // Loudness / spectrum pane of the master mix
+    analysis_view: AnalysisView,
+    mix_history: MixHistory,
+    // Adaptive quality state
+    quality_degraded: bool,
+    // Address of the instance we're jamming with
+    jam_peer: Option<String>,
 }
 
 impl TerminalUI {
     pub fn new(
-        layers: Arc<Vec<Arc<Mutex<AudioLayer>>>>,
-        command_sender: crossbeam::channel::Sender<LayerCommand>,
-        event_receiver: crossbeam::channel::Receiver<AudioEvent>,
+        layer_count: usize,
+        command_sender: CommandSender,
+        channel_stats: Arc<ChannelStats>,
+        event_receiver: EventReceiver,
         input_device_name: &str,
         output_device_name: &str,
     ) -> Result<Self, Box<dyn std::error::Error>> {
@@ -102,16 +266,29 @@ impl TerminalUI {
 
         Ok(Self {
             terminal,
-            layers,
+            layers: vec![LayerStatusSnapshot::default(); layer_count],
+            layer_markers: vec![Vec::new(); layer_count],
             selected_layer: 0,
             command_sender,
+            next_command_id: 0,
+            channel_stats,
             event_receiver,
+            event_log: None,
             is_running: true,
             last_update: Instant::now(),
+            refresh: UiConfig::default().refresh(),
+            needs_redraw: true,
+            alerts: Alerts::new(true, false),
+            big_screen: false,
+            silent_input_layer: None,
+            mirror: None,
+            announcer: None,
+            layer_keys: LayerKeys::default(),
             last_key_time: Instant::now(),
             // key_debounce_duration: Duration::from_millis(150), // Temporarily disabled for debugging
             input_device_name: input_device_name.to_string(),
             output_device_name: output_device_name.to_string(),
+            host_name: None,
             // File input state
             input_mode: None,
             input_buffer: String::new(),
@@ -121,68 +298,272 @@ impl TerminalUI {
             status_timer: None,
             // File picker overlay
             file_picker_overlay: false,
+            input_peak: 0.0,
+            input_peak_hold: 0.0,
+            previewing: None,
+            confirm_clear: true,
+            pending_clear: None,
+            session_metadata: SessionMetadata::default(),
             // Tempo/Sync state
             beat_sync_enabled: true,
             bpm_display: 120.0,
-            current_beat: 1,
-            current_measure: 0,
+            tap_locked: false,
+            transport_paused: false,
+            tap_rounding: false,
+            swing: 50.0,
+            transport_position: TransportPosition::default(),
             metronome_enabled: false,
+            click_bus: OutputBus::Main,
+            click: ClickSettings::default(),
             count_in_mode_enabled: false,
+            count_in_measures: 1,
+            count_out_enabled: false,
             count_in_remaining: None,
+            mix_quantize: MixQuantize::Off,
+            device_latency: Duration::ZERO,
+            latency_compensation: Duration::ZERO,
+            latency_warned: false,
+            export_region: None,
+            export_normalization: ExportNormalization::Peak,
+            export_directory: ExportConfig::DEFAULT_DIRECTORY.to_string(),
+            snapshot_count: 0,
+            export_name_template: None,
+            recent_files: RecentFiles::load(RecentFiles::DEFAULT_PATH),
+            watch_folder: None,
+            watch_polled: Instant::now(),
+            watch_imports: Vec::new(),
+            analysis_view: AnalysisView::Off,
+            mix_history: MixHistory::default(),
+            quality_degraded: false,
+            jam_peer: None,
         })
     }
 
+    /// Shortest time between redraws; the screen is only redrawn when something changed
+    pub fn set_refresh(&mut self, refresh: Duration) {
+        self.refresh = refresh;
+    }
+
+    /// Ring the terminal bell and/or show a desktop notification on critical errors
+    pub fn set_alerts(&mut self, bell: bool, desktop: bool) {
+        self.alerts = Alerts::new(bell, desktop);
+    }
+
+    /// Publish a plain-text status view to remote viewers on every redraw
+    pub fn set_mirror(&mut self, mirror: Mirror) {
+        self.mirror = Some(mirror);
+    }
+
+    /// Announce important events as plain-text lines for a screen reader
+    pub fn set_announcer(&mut self, announcer: Announcer) {
+        self.announcer = Some(announcer);
+    }
+
+    /// Record/stop/play keys for each layer (`[keys] layers` in the config)
+    pub fn set_layer_keys(&mut self, layer_keys: LayerKeys) {
+        self.layer_keys = layer_keys;
+    }
+
+    /// Where snapshot exports (Shift+K) are written
+    pub fn set_export_directory(&mut self, directory: &str) {
+        self.export_directory = directory.to_string();
+    }
+
+    /// Name for snapshots and the export prompt's suggestion; the engine fills in tokens
+    pub fn set_export_name_template(&mut self, template: &str) {
+        self.export_name_template = Some(template.to_string());
+    }
+
+    /// Log every event received to `log`, alongside the commands main.rs logs
+    pub fn set_event_log(&mut self, log: Arc<Mutex<EventLog>>) {
+        self.event_log = Some(log);
+    }
+
+    /// Import WAVs that show up in `directory` from now on
+    pub fn set_watch_folder(&mut self, directory: &str) {
+        self.watch_folder = Some(WatchFolder::new(directory));
+    }
+
+    /// Whether C/X need a second press before destroying recorded audio (default on)
+    pub fn set_confirm_clear(&mut self, confirm: bool) {
+        self.confirm_clear = confirm;
+    }
+
+    /// Open the setup wizard; finishing it writes `config_path` and applies the answers
+    pub fn start_setup_wizard(&mut self, config: AppConfig, config_path: String) {
+        let (inputs, outputs) =
+            enumerate_device_names(self.host_name.as_deref()).unwrap_or_default();
+        let wizard = SetupWizard::new(
+            config,
+            config_path,
+            inputs,
+            outputs,
+            &self.input_device_name,
+            &self.output_device_name,
+        );
+        self.input_mode = Some(InputMode::Setup(Box::new(wizard)));
+        self.file_picker_overlay = true;
+    }
+
     pub fn run(&mut self) -> Result<(), Box<dyn std::error::Error>> {
         while self.is_running {
-            // Process events
-            self.process_events()?;
+            // Block on the keyboard until the next frame is due; with nothing to redraw,
+            // sleep a whole frame and pick up the audio events that came in meanwhile
+            let timeout = if self.needs_redraw {
+                self.refresh.saturating_sub(self.last_update.elapsed())
+            } else {
+                self.refresh
+            };
+            self.process_events(timeout)?;
 
             // Check status timer
             self.check_status_timer();
 
-            // Update display if enough time has passed
-            if self.last_update.elapsed() >= Duration::from_millis(50) {
+            if self.watch_polled.elapsed() >= POLL_INTERVAL {
+                self.poll_watch_folder();
+                self.watch_polled = Instant::now();
+                self.needs_redraw = true;
+            }
+
+            if self.needs_redraw && self.last_update.elapsed() >= self.refresh {
                 self.draw()?;
+                if let Some(mirror) = &self.mirror {
+                    mirror.publish(&self.mirror_lines());
+                }
                 self.last_update = Instant::now();
+                self.needs_redraw = false;
             }
-
-            // Small sleep to prevent excessive CPU usage
-            std::thread::sleep(Duration::from_millis(1));
         }
 
         Ok(())
     }
 
-    fn process_events(&mut self) -> Result<(), Box<dyn std::error::Error>> {
-        if event::poll(Duration::from_millis(0))?
-            && let Event::Key(key) = event::read()?
-        {
-            // Process key presses
-            if key.kind == KeyEventKind::Press {
-                self.handle_key_event(key)?;
-                self.last_key_time = Instant::now();
+    fn process_events(&mut self, timeout: Duration) -> Result<(), Box<dyn std::error::Error>> {
+        if event::poll(timeout)? {
+            match event::read()? {
+                // Process key presses
+                Event::Key(key) if key.kind == KeyEventKind::Press => {
+                    self.handle_key_event(key)?;
+                    self.last_key_time = Instant::now();
+                    self.needs_redraw = true;
+                }
+                Event::Resize(..) => self.needs_redraw = true,
+                _ => {}
             }
         }
 
         // Process audio events
+        // Locked once per batch; the command forwarder only holds it for a line
+        let event_log = self.event_log.clone();
+        let mut log = event_log.as_ref().and_then(|log| log.lock().ok());
         while let Ok(event) = self.event_receiver.try_recv() {
+            if let Some(log) = log.as_mut() {
+                let _ = log.log_event(&event);
+            }
+            // The periodic updates arrive ~30 times a second even when nothing moves
+            self.needs_redraw |= match &event {
+                AudioEvent::LayerStatusBatch(layers) => *layers != self.layers,
+                AudioEvent::TransportPosition(position) => *position != self.transport_position,
+                AudioEvent::InputLevel { peak, peak_hold } => {
+                    (*peak, *peak_hold) != (self.input_peak, self.input_peak_hold)
+                }
+                _ => true,
+            };
+            if let Some(message) = critical_message(&event) {
+                self.alerts.raise(&message);
+            }
+            if let Some(announcer) = self.announcer.as_mut() {
+                announcer.announce(&event);
+            }
             self.handle_audio_event(event);
         }
+        if let Some(mut log) = log {
+            let _ = log.flush();
+        }
 
         Ok(())
     }
 
     fn handle_key_event(&mut self, key: KeyEvent) -> Result<(), Box<dyn std::error::Error>> {
         // Handle input mode first
-        if let Some(ref input_mode) = self.input_mode {
-            return self.handle_input_key(key, input_mode.clone());
+        if let Some(input_mode) = self.input_mode.clone() {
+            // Pickers and auditions take no text, so transport stops still work behind them
+            let passes_transport = matches!(
+                input_mode,
+                InputMode::FilePicker { .. }
+                    | InputMode::RecentFiles { .. }
+                    | InputMode::DevicePicker { .. }
+                    | InputMode::InputCheck { .. }
+                    | InputMode::LayerInfo { .. }
+                    | InputMode::Audition { .. }
+                    | InputMode::LoopFix { .. }
+                    | InputMode::LatencyWarning { .. }
+                    | InputMode::DcOffsetWarning { .. }
+            );
+            if passes_transport && self.handle_transport_key(key) {
+                return Ok(());
+            }
+            return self.handle_input_key(key, input_mode);
+        }
+
+        // Mapped layer keys win over any other binding on the same key
+        if let Some(layer_id) = self.layer_keys.layer_for(key.code) {
+            self.handle_layer_key(layer_id);
+            return Ok(());
         }
 
         match key.code {
             KeyCode::Char('q') => {
                 self.is_running = false;
             }
-            KeyCode::Char('n') | KeyCode::Char('N') => {
+            KeyCode::Char('Q') => {
+                // Cycle mute/solo quantize: off -> beat -> measure
+                let quantize = match self.mix_quantize {
+                    MixQuantize::Off => MixQuantize::Beat,
+                    MixQuantize::Beat => MixQuantize::Measure,
+                    MixQuantize::Measure => MixQuantize::Off,
+                };
+                let _ = self
+                    .command_sender
+                    .send(LayerCommand::SetMixQuantize(quantize));
+            }
+            KeyCode::Char('N') => {
+                let _ = self
+                    .command_sender
+                    .send(LayerCommand::SetClickBus(self.click_bus.toggled()));
+            }
+            KeyCode::Char('M') => {
+                // Metronome only while recording (count-in still clicks)
+                self.set_click(ClickSettings {
+                    record_only: !self.click.record_only,
+                    ..self.click
+                });
+            }
+            KeyCode::Char(')') => {
+                self.set_click(ClickSettings {
+                    volume: self.click.volume + 0.1,
+                    ..self.click
+                });
+            }
+            KeyCode::Char('(') => {
+                self.set_click(ClickSettings {
+                    volume: self.click.volume - 0.1,
+                    ..self.click
+                });
+            }
+            KeyCode::Char('>') => {
+                self.set_click(ClickSettings {
+                    pan: self.click.pan + 0.25,
+                    ..self.click
+                });
+            }
+            KeyCode::Char('<') => {
+                self.set_click(ClickSettings {
+                    pan: self.click.pan - 0.25,
+                    ..self.click
+                });
+            }
+            KeyCode::Char('n') => {
                 // Toggle metronome
                 self.metronome_enabled = !self.metronome_enabled;
                 let new_state = self.metronome_enabled;
@@ -195,7 +576,12 @@ impl TerminalUI {
                     "Metronome OFF"
                 });
             }
-            KeyCode::Char('b') | KeyCode::Char('B') => {
+            KeyCode::Char('B') => {
+                let _ = self
+                    .command_sender
+                    .send(LayerCommand::SetTapRounding(!self.tap_rounding));
+            }
+            KeyCode::Char('b') => {
                 let _ = self.command_sender.send(LayerCommand::TapTempo);
             }
             KeyCode::Char('t') | KeyCode::Char('T') => {
@@ -225,35 +611,22 @@ impl TerminalUI {
                     "Count-in Mode OFF"
                 });
             }
-            KeyCode::Char('1') => {
-                self.handle_layer_key(0);
-            }
-            KeyCode::Char('2') => {
-                self.handle_layer_key(1);
-            }
-            KeyCode::Char('3') => {
-                self.handle_layer_key(2);
-            }
-            KeyCode::Char('4') => {
-                self.handle_layer_key(3);
-            }
-            KeyCode::Char('5') => {
-                self.handle_layer_key(4);
-            }
-            KeyCode::Char('6') => {
-                self.handle_layer_key(5);
-            }
-            KeyCode::Char('7') => {
-                self.handle_layer_key(6);
-            }
-            KeyCode::Char('8') => {
-                self.handle_layer_key(7);
-            }
-            KeyCode::Char('9') => {
-                self.handle_layer_key(8);
+            KeyCode::Char('u') => {
+                // Cycle pre-roll length: 1 -> 2 -> 4 bars
+                let measures = match self.count_in_measures {
+                    1 => 2,
+                    2 => 4,
+                    _ => 1,
+                };
+                let _ = self
+                    .command_sender
+                    .send(LayerCommand::SetCountInMeasures(measures));
             }
-            KeyCode::Char('0') => {
-                self.handle_layer_key(9);
+            KeyCode::Char('f') => {
+                // Toggle one bar of clicks after recording stops
+                let _ = self
+                    .command_sender
+                    .send(LayerCommand::ToggleCountOut(!self.count_out_enabled));
             }
             KeyCode::Char('r') => {
                 // Record on selected layer
@@ -266,36 +639,17 @@ impl TerminalUI {
                 }
             }
             KeyCode::Char('s') => {
-                // Stop selected layer only (stop recording immediately; stop playback synced if enabled)
-                let (is_recording, _is_playing) = match self.layers[self.selected_layer].lock() {
-                    Ok(layer) => (layer.is_recording, layer.is_playing),
-                    Err(_) => (false, false),
-                };
-                if is_recording {
-                    let _ = self
-                        .command_sender
-                        .send(LayerCommand::StopRecording(self.selected_layer));
-                } else if self.beat_sync_enabled {
-                    let _ = self
-                        .command_sender
-                        .send(LayerCommand::SyncStop(self.selected_layer));
-                } else {
-                    self.stop_selected_layer();
-                }
+                self.stop_layer(self.selected_layer);
             }
             KeyCode::Char(' ') => {
                 // Stop all
                 let _ = self.command_sender.send(LayerCommand::StopAll);
             }
-            KeyCode::Up => {
-                if self.selected_layer > 0 {
-                    self.selected_layer -= 1;
-                }
+            KeyCode::Up if self.selected_layer > 0 => {
+                self.selected_layer -= 1;
             }
-            KeyCode::Down => {
-                if self.selected_layer < self.layers.len() - 1 {
-                    self.selected_layer += 1;
-                }
+            KeyCode::Down if self.selected_layer < self.layers.len() - 1 => {
+                self.selected_layer += 1;
             }
             KeyCode::Char('+') | KeyCode::Char('=') => {
                 self.adjust_volume(0.1);
@@ -303,17 +657,64 @@ impl TerminalUI {
             KeyCode::Char('-') => {
                 self.adjust_volume(-0.1);
             }
+            // BPM nudges for beat-matching
+            KeyCode::Char(']') => {
+                self.set_bpm_relative(self.bpm_display + 1.0);
+            }
+            KeyCode::Char('[') => {
+                self.set_bpm_relative(self.bpm_display - 1.0);
+            }
+            KeyCode::Char('}') => {
+                self.set_bpm_relative(self.bpm_display + 0.1);
+            }
+            KeyCode::Char('{') => {
+                self.set_bpm_relative(self.bpm_display - 0.1);
+            }
+            KeyCode::Char('.') => {
+                // More swing (5% steps; the engine caps it)
+                let _ = self
+                    .command_sender
+                    .send(LayerCommand::SetSwing(self.swing + 5.0));
+            }
+            KeyCode::Char(',') => {
+                let _ = self
+                    .command_sender
+                    .send(LayerCommand::SetSwing(self.swing - 5.0));
+            }
+            KeyCode::Char('*') => {
+                self.set_bpm_relative(self.bpm_display * 2.0);
+            }
+            // Transport: freeze the tempo clock, or re-anchor bar 1 to now
+            KeyCode::Char('\\') => {
+                let _ = self
+                    .command_sender
+                    .send(LayerCommand::PauseTransport(!self.transport_paused));
+            }
+            KeyCode::Char('|') => {
+                let _ = self.command_sender.send(LayerCommand::ResetTransport);
+            }
+            KeyCode::Char('/') => {
+                self.set_bpm_relative(self.bpm_display / 2.0);
+            }
             KeyCode::Char('m') => {
                 self.toggle_mute(self.selected_layer);
             }
             KeyCode::Char('l') => {
                 self.toggle_solo(self.selected_layer);
             }
+            KeyCode::Char('S') => {
+                let _ = self
+                    .command_sender
+                    .send(LayerCommand::ToggleSoloSafe(self.selected_layer));
+            }
+            KeyCode::Char('L') => {
+                let _ = self
+                    .command_sender
+                    .send(LayerCommand::ToggleLock(self.selected_layer));
+            }
             KeyCode::Char('p') => {
                 if self.beat_sync_enabled {
-                    let _ = self
-                        .command_sender
-                        .send(LayerCommand::SyncPlay(self.selected_layer));
+                    self.send_tracked(LayerCommand::SyncPlay(self.selected_layer));
                 } else {
                     self.start_playback(self.selected_layer);
                 }
@@ -323,7 +724,76 @@ impl TerminalUI {
             }
             KeyCode::Char('x') => {
                 // Clear all layers
-                let _ = self.command_sender.send(LayerCommand::ClearAll);
+                // Locked layers are kept, so only unlocked audio needs confirming
+                let has_content = self
+                    .layers
+                    .iter()
+                    .any(|layer| !layer.is_empty() && !layer.is_locked);
+                if self.clear_confirmed(None, has_content) {
+                    let _ = self.command_sender.send(LayerCommand::ClearAll);
+                }
+            }
+            KeyCode::Char('F') => {
+                self.send_tracked(LayerCommand::ToggleFitToMaster(self.selected_layer));
+            }
+            KeyCode::Char('J') => {
+                self.send_tracked(LayerCommand::FreezeLayer(self.selected_layer));
+            }
+            // Denoise: learn room tone from one layer, then clean others with it
+            KeyCode::Char('#') => {
+                self.send_tracked(LayerCommand::LearnNoise(self.selected_layer));
+            }
+            KeyCode::Char('$') => {
+                self.send_tracked(LayerCommand::Denoise(self.selected_layer));
+            }
+            KeyCode::Char('%') => {
+                self.send_tracked(LayerCommand::Declick(self.selected_layer));
+            }
+            KeyCode::Char('&') => {
+                self.send_tracked(LayerCommand::RemoveDcOffset(self.selected_layer));
+            }
+            KeyCode::Char('!') => {
+                let _ = self.command_sender.send(LayerCommand::ResetPeaks);
+            }
+            KeyCode::Char('?') => {
+                self.send_tracked(LayerCommand::AnalyzeLayer(self.selected_layer));
+            }
+            KeyCode::Char('A') => self.toggle_latency_compensation(),
+            KeyCode::Char('V') => self.cycle_analysis_view(),
+            KeyCode::Char('K') => self.export_snapshot(),
+            KeyCode::Char('U') => self.open_recent_files(),
+            KeyCode::Char('D') => self.toggle_overdub(self.selected_layer),
+            // Re-take over the old take (heard on the cue bus), then keep or revert it
+            KeyCode::Char(';') => self.toggle_replacement(self.selected_layer),
+            KeyCode::Char('\'') => {
+                let _ = self
+                    .command_sender
+                    .send(LayerCommand::KeepReplacement(self.selected_layer));
+            }
+            KeyCode::Char('"') => {
+                let _ = self
+                    .command_sender
+                    .send(LayerCommand::RevertReplacement(self.selected_layer));
+            }
+            // Take lanes: play the next take, or keep the one playing
+            KeyCode::Char('`') => self.next_take(self.selected_layer),
+            KeyCode::Char('~') => {
+                let _ = self
+                    .command_sender
+                    .send(LayerCommand::KeepTake(self.selected_layer));
+            }
+            KeyCode::Char('C') => {
+                let _ = self
+                    .command_sender
+                    .send(LayerCommand::CancelPending(self.selected_layer));
+            }
+            KeyCode::Char('X') => {
+                // Forget the master loop; the next recording sets it
+                let _ = self.command_sender.send(LayerCommand::ClearMasterLength);
+            }
+            KeyCode::Char('R') => {
+                // Measure new recordings against the selected layer
+                self.send_tracked(LayerCommand::MasterFromLayer(self.selected_layer));
             }
             KeyCode::Char('a') => {
                 // Play all layers
@@ -337,10 +807,34 @@ impl TerminalUI {
                 // Open device picker
                 self.open_device_picker();
             }
+            KeyCode::Char('O') => {
+                let layer_id = self.selected_layer;
+                let bus = self.layers[layer_id].output_bus.toggled();
+                let _ = self
+                    .command_sender
+                    .send(LayerCommand::SetLayerBus(layer_id, bus));
+            }
+            KeyCode::Char('P') => {
+                // Pre-listen the selected layer on the cue bus (again to stop)
+                self.send_tracked(LayerCommand::ToggleCue(self.selected_layer));
+            }
             KeyCode::Char('e') => {
                 // Export composition as WAV
                 self.export_composition();
             }
+            KeyCode::Char('E') => {
+                // Export stems plus a Reaper project
+                self.start_input_mode(InputMode::ExportProject, "Export DAW project as: ");
+            }
+            KeyCode::Char('^') => {
+                self.big_screen = !self.big_screen;
+            }
+            KeyCode::Char('@') => {
+                // Each layer's loop as a tempo-tagged WAV, into one folder
+                self.start_input_mode(InputMode::ExportLoopPack, "Export loop pack to folder: ");
+                self.input_buffer = self.export_directory.clone();
+                self.update_input_display();
+            }
             KeyCode::Char('z') => {
                 // Undo on selected layer
                 let _ = self
@@ -353,6 +847,48 @@ impl TerminalUI {
                     .command_sender
                     .send(LayerCommand::Redo(self.selected_layer));
             }
+            KeyCode::Char('Z') => {
+                // Undo the last action on any layer
+                let _ = self.command_sender.send(LayerCommand::GlobalUndo);
+            }
+            KeyCode::Char('Y') => {
+                // Redo the last globally undone action
+                let _ = self.command_sender.send(LayerCommand::GlobalRedo);
+            }
+            KeyCode::Char('k') => {
+                // Drop a marker at the selected layer's playback position
+                self.start_input_mode(
+                    InputMode::AddMarker {
+                        layer_id: self.selected_layer,
+                    },
+                    "Marker name: ",
+                );
+            }
+            KeyCode::Char('j') => {
+                self.jump_to_next_marker(self.selected_layer);
+            }
+            KeyCode::Char('w') => {
+                self.start_input_mode(
+                    InputMode::SaveSession,
+                    "Save session as (name.slt saves a template): ",
+                );
+            }
+            KeyCode::Char('W') => {
+                self.start_input_mode(InputMode::LoadSession, "Load session: ");
+            }
+            KeyCode::Char('d') => {
+                // Edit title, author and tags one prompt at a time
+                self.start_metadata_prompt(MetadataField::Title, self.session_metadata.clone());
+            }
+            KeyCode::Char('v') => {
+                self.start_input_mode(
+                    InputMode::Notes {
+                        text: self.session_metadata.notes.clone(),
+                    },
+                    "Session notes - Esc: save & close",
+                );
+                self.file_picker_overlay = true;
+            }
             _ => {}
         }
         Ok(())
@@ -361,22 +897,290 @@ impl TerminalUI {
     fn handle_audio_event(&mut self, _event: AudioEvent) {
         // Provide immediate user feedback on import/export results
         match _event {
+            AudioEvent::LoopLengthMismatch {
+                layer_id,
+                ratio,
+                length,
+                target,
+            } => {
+                // Don't interrupt a prompt that's being typed into
+                if self.input_mode.is_none() {
+                    let offset = (length as f64 / target as f64 - 1.0) * 100.0;
+                    self.start_input_mode(
+                        InputMode::LoopFix { layer_id },
+                        &format!(
+                            "Layer {} is {} of the master {:+.1}% - T/Enter: trim, S: stretch, M: make master, Esc: keep",
+                            layer_id + 1,
+                            ratio,
+                            offset
+                        ),
+                    );
+                } else {
+                    self.show_success(&format!(
+                        "Layer {} doesn't fit the master loop (nearest {})",
+                        layer_id + 1,
+                        ratio
+                    ));
+                }
+            }
+            AudioEvent::LayerFitChanged(layer_id, fitted) => {
+                self.show_success(&format!(
+                    "Layer {} {}",
+                    layer_id + 1,
+                    if fitted {
+                        "fitted to the master loop"
+                    } else {
+                        "plays at its own length"
+                    }
+                ));
+            }
+            AudioEvent::LayerFrozen(layer_id) => {
+                self.show_success(&format!(
+                    "Layer {} frozen (varispeed rendered into its audio)",
+                    layer_id + 1
+                ));
+            }
+            AudioEvent::NoiseLearned(layer_id) => {
+                self.show_success(&format!(
+                    "Noise print learned from Layer {} ($ denoises the selected layer)",
+                    layer_id + 1
+                ));
+            }
+            AudioEvent::DenoiseProgress { layer_id, percent } => {
+                self.show_success(&format!("Denoising Layer {}: {}%", layer_id + 1, percent));
+            }
+            AudioEvent::LayerDenoised(layer_id) => {
+                self.show_success(&format!(
+                    "Layer {} denoised (undo restores it)",
+                    layer_id + 1
+                ));
+            }
+            AudioEvent::LayerDeclicked(layer_id, repaired) => {
+                self.show_success(&match repaired {
+                    0 => format!("No clicks found on Layer {}", layer_id + 1),
+                    1 => format!("Layer {}: 1 click removed (undo restores it)", layer_id + 1),
+                    n => format!(
+                        "Layer {}: {} clicks removed (undo restores them)",
+                        layer_id + 1,
+                        n
+                    ),
+                });
+            }
+            AudioEvent::DcOffsetRemoved(layer_id, offset) => {
+                self.show_success(&format!(
+                    "Layer {}: DC offset of {:+.1}% removed (undo restores it)",
+                    layer_id + 1,
+                    offset * 100.0
+                ));
+            }
+            AudioEvent::ImportWarning(layer_id, issue) => {
+                let message = format!("Layer {}: imported file {}", layer_id + 1, issue);
+                // Offer the fix, unless another prompt is open
+                if matches!(issue, ImportIssue::DcOffset(_)) && self.input_mode.is_none() {
+                    self.start_input_mode(
+                        InputMode::DcOffsetWarning { layer_id },
+                        &format!("{} - Enter: remove it, Esc: keep", message),
+                    );
+                } else {
+                    self.show_warning(&message);
+                }
+            }
+            AudioEvent::MasterLoopChanged(length) => {
+                self.show_success(if length.is_some() {
+                    "Master loop length set"
+                } else {
+                    "Master loop cleared - the next recording sets it"
+                });
+            }
+            AudioEvent::LoopLengthFixed(layer_id, fix) => {
+                self.show_success(&match fix {
+                    LoopFix::Trim => {
+                        format!("Layer {} trimmed to fit the master loop", layer_id + 1)
+                    }
+                    LoopFix::Stretch => {
+                        format!("Layer {} stretched to fit the master loop", layer_id + 1)
+                    }
+                    LoopFix::SetMaster => format!("Layer {} is now the master loop", layer_id + 1),
+                });
+            }
             AudioEvent::WavImported(layer_id, path) => {
                 self.show_success(&format!("Imported to Layer {}: {}", layer_id + 1, path));
+                if path != STDIN_PATH {
+                    self.remember_file(&path, RecentKind::Imported);
+                }
+                if let Some(index) = self
+                    .watch_imports
+                    .iter()
+                    .position(|(id, _)| *id == layer_id)
+                {
+                    // Dropped-in stems join on the next bar (straight away without beat sync)
+                    self.watch_imports.remove(index);
+                    self.send_tracked(LayerCommand::SyncPlay(layer_id));
+                }
+            }
+            AudioEvent::ImportLoopInfo {
+                layer_id,
+                info,
+                stretched_to,
+            } => {
+                let mut found = Vec::new();
+                if info.loop_points.is_some() {
+                    found.push("loop points".to_string());
+                }
+                if let Some(bpm) = info.bpm {
+                    found.push(match stretched_to {
+                        Some(target) => format!("{:.1} BPM (stretched to {:.1})", bpm, target),
+                        None => format!("{:.1} BPM", bpm),
+                    });
+                }
+                self.show_success(&format!(
+                    "Layer {}: {} from file",
+                    layer_id + 1,
+                    found.join(" and ")
+                ));
             }
             AudioEvent::WavExported(path) => {
                 self.show_success(&format!("Exported: {}", path));
+                if path != STDOUT_PATH {
+                    self.remember_file(&path, RecentKind::Exported);
+                }
+            }
+            AudioEvent::UploadProgress { file_path, percent } => {
+                self.show_success(&format!("Uploading {}: {}%", file_path, percent));
+            }
+            AudioEvent::UploadFinished(path) => {
+                self.show_success(&format!("Uploaded: {}", path));
+            }
+            AudioEvent::SilentInput(layer_id) => {
+                self.silent_input_layer = Some(layer_id);
+                self.show_warning(&format!(
+                    "Layer {}: no input signal - check the cable and interface mute",
+                    layer_id + 1
+                ));
+            }
+            AudioEvent::ProjectExported(path) => {
+                self.show_success(&format!("Project exported: {}", path));
+            }
+            AudioEvent::LoopPackExported(directory, files) => {
+                self.show_success(&format!(
+                    "Loop pack exported: {} files in {}",
+                    files, directory
+                ));
+            }
+            AudioEvent::PreviewStarted(path) => {
+                self.show_success(&format!("Previewing: {}", path));
+                self.previewing = Some(path);
+            }
+            AudioEvent::LayerStatusBatch(layers) if layers.len() == self.layers.len() => {
+                // The no-input warning lasts until signal arrives or the take ends
+                if let Some(layer_id) = self.silent_input_layer
+                    && let Some(layer) = layers.get(layer_id)
+                    && (!(layer.is_recording || layer.is_replacing)
+                        || layer.record_peak > AudioLayer::SILENT_INPUT)
+                {
+                    self.silent_input_layer = None;
+                }
+                self.layers = layers;
             }
-            AudioEvent::Error(msg) => {
-                self.show_success(&format!("Error: {}", msg));
+            AudioEvent::MetadataChanged(metadata) => {
+                self.session_metadata = metadata;
             }
+            AudioEvent::LayerMarkers(layer_id, markers) => {
+                if let Some(slot) = self.layer_markers.get_mut(layer_id) {
+                    *slot = markers;
+                }
+            }
+            AudioEvent::InputLevel { peak, peak_hold } => {
+                self.input_peak = peak;
+                self.input_peak_hold = peak_hold;
+            }
+            AudioEvent::PreviewStopped => {
+                self.previewing = None;
+            }
+            AudioEvent::CommandAck { result, .. } => {
+                if let Err(LooperError::EmptyLayer(layer_id)) = result {
+                    self.show_success(&format!("Layer {} is empty, cannot play", layer_id + 1));
+                } else if let Err(LooperError::LayerInMix(layer_id)) = result {
+                    self.show_success(&format!(
+                        "Layer {} is playing in the mix, stop it to cue",
+                        layer_id + 1
+                    ));
+                } else if let Err(err) = result {
+                    self.show_success(&format!("Error: {}", err));
+                }
+            }
+            AudioEvent::Error(err) => match err {
+                LooperError::DeviceLost(_) => {
+                    self.show_success(&format!("{} - choose a device in Options (O)", err));
+                }
+                LooperError::IoFailed { ref path, .. } => {
+                    // A failed decode leaves nothing to audition
+                    if let Some(InputMode::Audition { .. }) = self.input_mode {
+                        self.exit_input_mode();
+                    }
+                    self.watch_imports
+                        .retain(|(_, file_path)| file_path != path);
+                    self.show_success(&format!("Error: {}", err));
+                }
+                LooperError::LayerLocked(_) => {
+                    self.show_success(&format!("{} - unlock with Shift+L", err));
+                }
+                _ => self.show_success(&format!("Error: {}", err)),
+            },
             AudioEvent::BpmChanged(bpm) => {
                 self.bpm_display = bpm;
+                // Tap events re-lock right after; any other change drops the lock
+                self.tap_locked = false;
                 self.show_success(&format!("BPM: {:.1}", bpm));
             }
-            AudioEvent::Beat(beat, measure) => {
-                self.current_beat = beat;
-                self.current_measure = measure;
+            AudioEvent::TransportPaused(paused) => {
+                self.transport_paused = paused;
+                self.show_success(if paused {
+                    "Transport paused"
+                } else {
+                    "Transport running"
+                });
+            }
+            AudioEvent::TransportReset => {
+                self.show_success("Bar 1 starts now");
+            }
+            AudioEvent::TapStatus { taps, locked } => {
+                self.tap_locked = locked;
+                if taps < 2 {
+                    self.show_success("Tap again to set tempo");
+                } else {
+                    self.show_success(&format!(
+                        "Tap BPM: {:.1} ({} taps{})",
+                        self.bpm_display,
+                        taps,
+                        if locked { ", locked" } else { "" }
+                    ));
+                }
+            }
+            AudioEvent::SwingChanged(swing) => {
+                self.swing = swing;
+                self.show_success(&if swing > 50.0 {
+                    format!("Swing: {:.0}%", swing)
+                } else {
+                    "Swing OFF (straight)".to_string()
+                });
+            }
+            AudioEvent::TapRoundingToggled(on) => {
+                self.tap_rounding = on;
+                self.show_success(if on {
+                    "Tap BPM rounding ON"
+                } else {
+                    "Tap BPM rounding OFF"
+                });
+            }
+            AudioEvent::Beat(beat, measure, _) => {
+                // Land on the beat now; ticks and time follow with the status batch
+                self.transport_position.bar = measure + 1;
+                self.transport_position.beat = beat;
+                self.transport_position.tick = 0;
+            }
+            AudioEvent::TransportPosition(position) => {
+                self.transport_position = position;
             }
             AudioEvent::CountInStarted { layer_id, beats } => {
                 self.count_in_remaining = Some((layer_id, beats));
@@ -385,6 +1189,19 @@ impl TerminalUI {
                 self.count_in_remaining = None;
                 self.show_success(&format!("Count-in done L{}", layer_id + 1));
             }
+            AudioEvent::PendingCancelled(layer_id) => {
+                // A cancelled count-in sends no more ticks
+                if self
+                    .count_in_remaining
+                    .is_some_and(|(counting, _)| layer_id.is_none_or(|id| id == counting))
+                {
+                    self.count_in_remaining = None;
+                }
+                self.show_success(&match layer_id {
+                    Some(id) => format!("Queued actions cancelled L{}", id + 1),
+                    None => "Queued actions cancelled".to_string(),
+                });
+            }
             AudioEvent::CountInTick {
                 layer_id,
                 remaining_beats,
@@ -392,6 +1209,18 @@ impl TerminalUI {
                 let _ = layer_id; // we keep for potential per-layer UI later
                 self.count_in_remaining = Some((layer_id, remaining_beats));
             }
+            AudioEvent::CountInMeasuresChanged(measures) => {
+                self.count_in_measures = measures;
+                self.show_success(&format!(
+                    "Pre-roll: {} bar{}",
+                    measures,
+                    if measures == 1 { "" } else { "s" }
+                ));
+            }
+            AudioEvent::CountOutToggled(on) => {
+                self.count_out_enabled = on;
+                self.show_success(if on { "Count-out ON" } else { "Count-out OFF" });
+            }
             AudioEvent::CountInModeToggled(on) => {
                 self.show_success(if on {
                     "Count-in Mode ON"
@@ -405,9 +1234,24 @@ impl TerminalUI {
             AudioEvent::DeviceSwitchComplete => {
                 self.show_success("Device switch complete!");
             }
+            AudioEvent::SampleRateConverted {
+                device_rate,
+                engine_rate,
+            } => {
+                self.show_success(&format!(
+                    "Input runs at {} Hz; converting to the session's {} Hz",
+                    device_rate, engine_rate
+                ));
+            }
+            AudioEvent::AudioEngineRestarted => {
+                self.show_success("Audio engine restarted after a crash (layers kept)");
+            }
             AudioEvent::DeviceSwitchFailed(msg) => {
                 self.show_success(&format!("Device switch failed: {}", msg));
             }
+            AudioEvent::HostUpdated(name) => {
+                self.host_name = Some(name);
+            }
             AudioEvent::DevicesUpdated(input, output) => {
                 match input {
                     Some(name) => self.input_device_name = name,
@@ -419,19 +1263,231 @@ impl TerminalUI {
                 }
                 self.show_success("Devices updated");
             }
+            AudioEvent::JamConnected(peer) => {
+                self.show_success(&format!("Jamming with {}", peer));
+                self.jam_peer = Some(peer);
+            }
+            AudioEvent::JamDisconnected => {
+                self.jam_peer = None;
+                self.show_success("Jam peer disconnected, waiting to reconnect");
+            }
+            AudioEvent::JamLoopReceived(layer_id) => {
+                self.show_success(&format!("Loop from jam peer on layer {}", layer_id + 1));
+            }
+            // Also sent for changes made over the control socket
+            AudioEvent::BeatSyncToggled(on) => {
+                self.beat_sync_enabled = on;
+            }
             AudioEvent::MetronomeToggled(on) => {
+                self.metronome_enabled = on;
                 self.show_success(if on { "Metronome ON" } else { "Metronome OFF" });
             }
+            AudioEvent::LayerBusChanged(layer_id, bus) => {
+                self.show_success(&format!("Layer {} → {} bus", layer_id + 1, bus.label()));
+            }
+            AudioEvent::ReplacementRecording(layer_id) => {
+                self.show_success(&format!(
+                    "Re-taking layer {} (old take on the cue bus)",
+                    layer_id + 1
+                ));
+            }
+            AudioEvent::ReplacementReady(layer_id) => {
+                self.show_success(&format!(
+                    "New take for layer {}: ' keeps it, \" reverts",
+                    layer_id + 1
+                ));
+            }
+            AudioEvent::ReplacementKept(layer_id) => {
+                self.show_success(&format!(
+                    "Layer {} replaced (undo restores it)",
+                    layer_id + 1
+                ));
+            }
+            AudioEvent::TakeSelected(layer_id, take) => {
+                let takes = self.layers.get(layer_id).map_or(0, |l| l.takes);
+                self.show_success(&format!(
+                    "Layer {}: take {}/{} (~ keeps it)",
+                    layer_id + 1,
+                    take + 1,
+                    takes.max(take + 1)
+                ));
+            }
+            AudioEvent::TakeKept(layer_id) => {
+                self.show_success(&format!("Layer {}: other takes dropped", layer_id + 1));
+            }
+            AudioEvent::ReplacementReverted(layer_id) => {
+                self.show_success(&format!("Layer {} kept its old take", layer_id + 1));
+            }
+            AudioEvent::LayerSelected(layer_id) if layer_id < self.layers.len() => {
+                self.selected_layer = layer_id;
+            }
+            AudioEvent::LayerCleared(layer_id, samples) if samples > 0 => {
+                self.show_success(&format!(
+                    "Cleared layer {} ({} samples)",
+                    layer_id + 1,
+                    samples
+                ));
+            }
+            AudioEvent::LatencyMeasured(round_trip) => {
+                self.device_latency = round_trip;
+                // Offer compensation once, when nothing else is being prompted for
+                if round_trip > HIGH_LATENCY
+                    && self.latency_compensation.is_zero()
+                    && !self.latency_warned
+                    && self.input_mode.is_none()
+                {
+                    self.latency_warned = true;
+                    self.start_input_mode(
+                        InputMode::LatencyWarning { round_trip },
+                        &format!(
+                            "High device latency (~{} ms, Bluetooth?) - overdubs will land late. Enter: compensate, Esc: ignore",
+                            round_trip.as_millis()
+                        ),
+                    );
+                }
+            }
+            AudioEvent::MixAnalysis(frame) if self.analysis_view != AnalysisView::Off => {
+                self.mix_history.push(frame);
+            }
+            AudioEvent::MixAnalysis(_) | AudioEvent::MixAnalysisToggled(_) => {}
+            AudioEvent::InputHighPassChanged(cutoff) => match cutoff {
+                Some(hz) => self.show_success(&format!("Input high-pass at {:.0} Hz", hz)),
+                None => self.show_success("Input high-pass off"),
+            },
+            AudioEvent::StereoWidthChanged(0.0) => self.show_success("Main bus folded to mono"),
+            AudioEvent::StereoWidthChanged(width) => {
+                self.show_success(&format!("Stereo width {:.0}%", width * 100.0));
+            }
+            AudioEvent::PeaksReset => {
+                self.show_success("Peak hold and clip indicators reset");
+            }
+            // Don't cover a prompt the user opened meanwhile
+            AudioEvent::LayerStatsReady(layer_id, stats) if self.input_mode.is_none() => {
+                self.input_mode = Some(InputMode::LayerInfo { layer_id, stats });
+                self.file_picker_overlay = true;
+            }
+            AudioEvent::LayerStatsReady(..) => {}
+            AudioEvent::LatencyCompensationChanged(delay) => {
+                self.latency_compensation = delay;
+                if delay.is_zero() {
+                    self.show_success("Latency compensation off");
+                } else {
+                    self.show_success(&format!(
+                        "New takes shifted {} ms earlier",
+                        delay.as_millis()
+                    ));
+                }
+            }
+            AudioEvent::MixQuantizeChanged(quantize) => {
+                self.mix_quantize = quantize;
+                self.show_success(match quantize {
+                    MixQuantize::Off => "Mute/solo apply immediately",
+                    MixQuantize::Beat => "Mute/solo wait for the next beat",
+                    MixQuantize::Measure => "Mute/solo wait for the next measure",
+                });
+            }
+            AudioEvent::LayerSoloSafeChanged(layer_id, safe) => {
+                self.show_success(&format!(
+                    "Layer {} {}",
+                    layer_id + 1,
+                    if safe {
+                        "solo-safe (plays through solos)"
+                    } else {
+                        "no longer solo-safe"
+                    }
+                ));
+            }
+            AudioEvent::LayerLockChanged(layer_id, locked) => {
+                self.show_success(&format!(
+                    "Layer {} {}",
+                    layer_id + 1,
+                    if locked { "locked" } else { "unlocked" }
+                ));
+            }
+            AudioEvent::LayerCued(layer_id) => {
+                self.show_success(&format!("Cueing layer {} (cue bus only)", layer_id + 1));
+            }
+            AudioEvent::ClickBusChanged(bus) => {
+                self.click_bus = bus;
+                self.show_success(&format!("Click → {} bus", bus.label()));
+            }
+            AudioEvent::ClickChanged(click) => {
+                self.click = click;
+                let pan = match click.pan {
+                    pan if pan < 0.0 => format!("L{:.0}", -pan * 100.0),
+                    pan if pan > 0.0 => format!("R{:.0}", pan * 100.0),
+                    _ => "C".to_string(),
+                };
+                self.show_success(&format!(
+                    "Click: volume {:.0}%, pan {}{}",
+                    click.volume * 100.0,
+                    pan,
+                    if click.record_only {
+                        ", only while recording"
+                    } else {
+                        ""
+                    }
+                ));
+            }
+            AudioEvent::QualityDegraded(load) => {
+                self.quality_degraded = true;
+                self.show_success(&format!(
+                    "High CPU load ({:.0}%): reduced quality",
+                    load * 100.0
+                ));
+            }
+            AudioEvent::QualityRestored => {
+                self.quality_degraded = false;
+                self.show_success("CPU load normal: full quality restored");
+            }
+            AudioEvent::GlobalUndoApplied(layer_ids) => {
+                self.show_success(&Self::describe_global_undo("Undo", &layer_ids));
+            }
+            AudioEvent::GlobalRedoApplied(layer_ids) => {
+                self.show_success(&Self::describe_global_undo("Redo", &layer_ids));
+            }
+            AudioEvent::MarkerSet { layer_id, name, .. } => {
+                self.show_success(&format!("Marker {} set on Layer {}", name, layer_id + 1));
+            }
+            AudioEvent::MarkerRemoved { layer_id, name } => {
+                self.show_success(&format!(
+                    "Marker {} removed from Layer {}",
+                    name,
+                    layer_id + 1
+                ));
+            }
+            AudioEvent::SessionSaved(path) => {
+                self.show_success(&format!("Session saved: {}", path));
+            }
+            AudioEvent::LargeSessionHistory(path, bytes) => {
+                self.show_success(&format!(
+                    "Session saved: {} (history adds {} MB; save_history in [session])",
+                    path,
+                    bytes / (1024 * 1024)
+                ));
+            }
+            AudioEvent::TemplateSaved(path) => {
+                self.show_success(&format!("Template saved: {}", path));
+            }
+            AudioEvent::SessionLoaded(path) => {
+                self.show_success(&format!("Session loaded: {}", path));
+            }
             _ => {
                 // no-op
             }
         }
     }
 
+    fn describe_global_undo(action: &str, layer_ids: &[usize]) -> String {
+        match layer_ids {
+            [] => format!("Nothing to {}", action.to_lowercase()),
+            [layer_id] => format!("{}: Layer {}", action, layer_id + 1),
+            _ => format!("{}: {} layers", action, layer_ids.len()),
+        }
+    }
+
     fn toggle_layer_record(&mut self, layer_id: usize) {
-        if layer_id < self.layers.len()
-            && let Ok(layer) = self.layers[layer_id].lock()
-        {
+        if let Some(layer) = self.layers.get(layer_id).copied() {
             if layer.is_recording {
                 // Stop recording (this will automatically start playback)
                 let _ = self
@@ -449,14 +1505,52 @@ impl TerminalUI {
         }
     }
 
+    fn next_take(&mut self, layer_id: usize) {
+        let Some(layer) = self.layers.get(layer_id) else {
+            return;
+        };
+        if layer.takes < 2 {
+            self.show_success(&format!("Layer {} has only one take", layer_id + 1));
+            return;
+        }
+        let take = (layer.current_take + 1) % layer.takes;
+        let _ = self
+            .command_sender
+            .send(LayerCommand::SelectTake(layer_id, take));
+    }
+
+    fn toggle_replacement(&mut self, layer_id: usize) {
+        let Some(layer) = self.layers.get(layer_id) else {
+            return;
+        };
+        let _ = self.command_sender.send(if layer.is_replacing {
+            LayerCommand::StopRecording(layer_id)
+        } else {
+            // The engine refuses empty layers
+            LayerCommand::RecordReplacement(layer_id)
+        });
+    }
+
+    fn toggle_overdub(&mut self, layer_id: usize) {
+        let Some(layer) = self.layers.get(layer_id) else {
+            return;
+        };
+        if layer.phase() == LayerPhase::Overdubbing {
+            let _ = self
+                .command_sender
+                .send(LayerCommand::StopRecording(layer_id));
+        } else {
+            // The engine refuses layers that aren't playing
+            let _ = self.command_sender.send(LayerCommand::Overdub(layer_id));
+        }
+    }
+
     fn handle_layer_key(&mut self, layer_id: usize) {
         if layer_id >= self.layers.len() {
             return;
         }
-        let (is_recording, is_playing) = match self.layers[layer_id].lock() {
-            Ok(layer) => (layer.is_recording, layer.is_playing),
-            Err(_) => return,
-        };
+        let layer = self.layers[layer_id];
+        let (is_recording, is_playing) = (layer.is_recording, layer.is_playing);
 
         if is_recording {
             let _ = self
@@ -478,47 +1572,172 @@ impl TerminalUI {
     }
 
     fn adjust_volume(&mut self, delta: f32) {
-        if let Ok(layer) = self.layers[self.selected_layer].lock() {
-            let new_volume = (layer.volume + delta).clamp(0.0, 1.0);
-            let _ = self
-                .command_sender
-                .send(LayerCommand::SetVolume(self.selected_layer, new_volume));
-        }
+        let layer = self.layers[self.selected_layer];
+        let new_volume = (layer.volume + delta).clamp(0.0, 1.0);
+        let _ = self
+            .command_sender
+            .send(LayerCommand::SetVolume(self.selected_layer, new_volume));
+    }
+
+    fn set_bpm_relative(&mut self, bpm: f64) {
+        // Snap to 0.1 so repeated nudges don't drift; same range as the engine
+        let bpm = ((bpm * 10.0).round() / 10.0).clamp(20.0, 300.0);
+        // Update now so quick repeated presses build on each other
+        self.bpm_display = bpm;
+        let _ = self.command_sender.send(LayerCommand::SetBpm(bpm));
     }
 
     fn toggle_mute(&mut self, layer_id: usize) {
         let _ = self.command_sender.send(LayerCommand::Mute(layer_id));
+        self.show_mix_queued("Mute", layer_id);
     }
 
     fn toggle_solo(&mut self, layer_id: usize) {
         let _ = self.command_sender.send(LayerCommand::Solo(layer_id));
+        self.show_mix_queued("Solo", layer_id);
+    }
+
+    /// The engine holds quantized toggles back, so say when they'll land
+    fn show_mix_queued(&mut self, what: &str, layer_id: usize) {
+        if !self.beat_sync_enabled {
+            return;
+        }
+        let boundary = match self.mix_quantize {
+            MixQuantize::Off => return,
+            MixQuantize::Beat => "beat",
+            MixQuantize::Measure => "measure",
+        };
+        self.show_success(&format!(
+            "{} on layer {} at the next {}",
+            what,
+            layer_id + 1,
+            boundary
+        ));
     }
 
     fn start_playback(&mut self, layer_id: usize) {
         if layer_id < self.layers.len() {
-            let _ = self.command_sender.send(LayerCommand::Play(layer_id));
+            self.send_tracked(LayerCommand::Play(layer_id));
+        }
+    }
+
+    /// Send new metronome volume/pan/mode (the engine clamps and echoes it back)
+    fn set_click(&mut self, click: ClickSettings) {
+        let _ = self.command_sender.send(LayerCommand::SetClick(click));
+    }
+
+    /// Send a command whose failure comes back as a CommandAck
+    fn send_tracked(&mut self, cmd: LayerCommand) {
+        self.next_command_id += 1;
+        let _ = self.command_sender.send(LayerCommand::Tracked {
+            id: self.next_command_id,
+            cmd: Box::new(cmd),
+        });
+    }
+
+    /// Stop keys that stay live while an overlay is open
+    /// Space stops everything, S stops the selected layer, layer keys stop that layer
+    /// Returns true if the key was handled
+    fn handle_transport_key(&mut self, key: KeyEvent) -> bool {
+        if let Some(layer_id) = self.layer_keys.layer_for(key.code) {
+            self.stop_layer(layer_id);
+            return true;
+        }
+        match key.code {
+            KeyCode::Char(' ') => {
+                let _ = self.command_sender.send(LayerCommand::StopAll);
+            }
+            KeyCode::Char('s') => {
+                self.stop_layer(self.selected_layer);
+            }
+            _ => return false,
         }
+        true
     }
 
-    fn stop_selected_layer(&mut self) {
-        if self.selected_layer < self.layers.len() {
+    /// Stop one layer (stop recording immediately; stop playback synced if enabled)
+    fn stop_layer(&mut self, layer_id: usize) {
+        let Some(layer) = self.layers.get(layer_id) else {
+            return;
+        };
+        let is_recording = layer.is_recording;
+        if is_recording {
             let _ = self
                 .command_sender
-                .send(LayerCommand::StopPlaying(self.selected_layer));
+                .send(LayerCommand::StopRecording(layer_id));
+        } else if self.beat_sync_enabled {
+            let _ = self.command_sender.send(LayerCommand::SyncStop(layer_id));
+        } else {
+            let _ = self
+                .command_sender
+                .send(LayerCommand::StopPlaying(layer_id));
         }
     }
 
     fn clear_layer(&mut self, layer_id: usize) {
-        if layer_id < self.layers.len() {
+        // Locked layers go straight to the engine, which refuses them
+        if layer_id < self.layers.len()
+            && (self.layers[layer_id].is_locked
+                || self.clear_confirmed(Some(layer_id), !self.layers[layer_id].is_empty()))
+        {
             let _ = self.command_sender.send(LayerCommand::Clear(layer_id));
         }
     }
 
+    /// Second press of the same clear key within the window (or nothing to lose)
+    fn clear_confirmed(&mut self, target: Option<usize>, has_content: bool) -> bool {
+        if !self.confirm_clear || !has_content {
+            return true;
+        }
+        if let Some((pending, pressed_at)) = self.pending_clear.take()
+            && pending == target
+            && pressed_at.elapsed() < CLEAR_CONFIRM_WINDOW
+        {
+            return true;
+        }
+        self.pending_clear = Some((target, Instant::now()));
+        self.show_success(&match target {
+            Some(layer_id) => format!("Press C again to clear layer {}", layer_id + 1),
+            None => "Press X again to clear ALL layers".to_string(),
+        });
+        false
+    }
+
     fn handle_input_key(
         &mut self,
         key: KeyEvent,
         input_mode: InputMode,
     ) -> Result<(), Box<dyn std::error::Error>> {
+        if let InputMode::Notes { text } = input_mode {
+            self.handle_notes_key(key, text);
+            return Ok(());
+        }
+        if let InputMode::Setup(wizard) = input_mode {
+            self.handle_setup_key(key, *wizard);
+            return Ok(());
+        }
+        if let InputMode::RecentFiles {
+            layer_id,
+            selected_index,
+            scroll_offset,
+        } = input_mode
+        {
+            self.handle_recent_key(key, layer_id, selected_index, scroll_offset);
+            return Ok(());
+        }
+        if let InputMode::InputCheck { .. } = input_mode {
+            if key.code == KeyCode::Esc {
+                self.open_device_picker();
+            }
+            return Ok(());
+        }
+        if let InputMode::LayerInfo { .. } = input_mode {
+            if matches!(key.code, KeyCode::Esc | KeyCode::Enter | KeyCode::Char('?')) {
+                self.exit_input_mode();
+            }
+            return Ok(());
+        }
+
         match key.code {
             KeyCode::Enter => {
                 // Finish input
@@ -584,30 +1803,9 @@ impl TerminalUI {
                                     }
                                 }
                                 FileEntry::WavFile(filename) => {
-                                    let full_path = if current_dir == "." {
-                                        filename.clone()
-                                    } else {
-                                        std::path::Path::new(&current_dir)
-                                            .join(filename)
-                                            .to_string_lossy()
-                                            .to_string()
-                                    };
-
-                                    // Validate the file before importing
-                                    match self.validate_import_file(&full_path) {
-                                        Ok(_) => {
-                                            // Immediately show importing status
-                                            self.show_success(&format!("Importing: {}", full_path));
-                                            let _ =
-                                                self.command_sender.send(LayerCommand::ImportWav(
-                                                    layer_id,
-                                                    full_path.clone(),
-                                                ));
-                                        }
-                                        Err(error) => {
-                                            self.show_success(&format!("Import failed: {}", error));
-                                        }
-                                    }
+                                    let full_path = Self::picker_file_path(&current_dir, filename);
+                                    self.audition_import(layer_id, full_path);
+                                    return Ok(());
                                 }
                             }
                         }
@@ -650,21 +1848,101 @@ impl TerminalUI {
                         return Ok(());
                     }
                     InputMode::ExportWav => {
-                        let filename = self.ensure_wav_extension(self.input_buffer.clone());
+                        let filename = self.ensure_export_extension(self.input_buffer.clone());
+                        let extension = if is_flac(&filename) { "flac" } else { "wav" };
 
-                        // Validate the export path before exporting
-                        match self.validate_export_path(&filename) {
+                        // Validate the export path before asking for the length and level
+                        match self.validate_export_path(&filename, extension) {
+                            Ok(_) => {
+                                self.start_input_mode(
+                                    InputMode::ExportRegion { filename },
+                                    "Length (all, N cycles, bars A-B): ",
+                                );
+                                // Enter keeps the last choice
+                                self.input_buffer = match self.export_region {
+                                    Some(region) => region.to_string(),
+                                    None => "all".to_string(),
+                                };
+                                self.update_input_display();
+                                return Ok(());
+                            }
+                            Err(error) => {
+                                self.show_success(&format!("Export failed: {}", error));
+                            }
+                        }
+                    }
+                    InputMode::ExportRegion { filename } => {
+                        let text = self.input_buffer.trim();
+                        let region = if text.is_empty() || text.eq_ignore_ascii_case("all") {
+                            Ok(None)
+                        } else {
+                            text.parse::<ExportRegion>().map(Some)
+                        };
+                        match region {
+                            Ok(region) => {
+                                self.export_region = region;
+                                self.start_input_mode(
+                                    InputMode::ExportNormalization { filename, region },
+                                    "Normalize to (peak, -14 LUFS, -1 dBTP): ",
+                                );
+                                self.input_buffer = self.export_normalization.to_string();
+                                self.update_input_display();
+                                return Ok(());
+                            }
+                            Err(error) => {
+                                self.show_success(&format!("Export cancelled: {}", error));
+                            }
+                        }
+                    }
+                    InputMode::ExportNormalization { filename, region } => {
+                        match self.input_buffer.parse::<ExportNormalization>() {
+                            Ok(normalization) => {
+                                self.export_normalization = normalization;
+                                let command = match region {
+                                    Some(region) => LayerCommand::ExportLoop(
+                                        filename.clone(),
+                                        region,
+                                        normalization,
+                                    ),
+                                    None => {
+                                        LayerCommand::ExportWav(filename.clone(), normalization)
+                                    }
+                                };
+                                self.send_tracked(command);
+                                let length = region.map(|r| format!(", {}", r)).unwrap_or_default();
+                                self.show_success(&format!(
+                                    "Exporting: {} ({}{})",
+                                    filename, normalization, length
+                                ));
+                            }
+                            Err(error) => {
+                                self.show_success(&format!("Export cancelled: {}", error));
+                            }
+                        }
+                    }
+                    InputMode::ExportProject => {
+                        let filename = self.ensure_rpp_extension(self.input_buffer.clone());
+                        match self.validate_export_path(&filename, "rpp") {
                             Ok(_) => {
                                 let _ = self
                                     .command_sender
-                                    .send(LayerCommand::ExportWav(filename.clone()));
-                                self.show_success(&format!("Exported: {}", filename));
+                                    .send(LayerCommand::ExportProject(filename.clone()));
+                                self.show_success(&format!("Exporting project: {}", filename));
                             }
                             Err(error) => {
                                 self.show_success(&format!("Export failed: {}", error));
                             }
                         }
                     }
+                    InputMode::ExportLoopPack => {
+                        let directory = self.input_buffer.trim().to_string();
+                        if directory.is_empty() {
+                            self.show_success("Export cancelled: no folder given");
+                        } else {
+                            self.send_tracked(LayerCommand::ExportLoopPack(directory.clone()));
+                            self.show_success(&format!("Exporting loop pack: {}", directory));
+                        }
+                    }
                     InputMode::SetBpm => {
                         let text = self.input_buffer.trim();
                         if let Ok(value) = text.parse::<f64>() {
@@ -673,10 +1951,100 @@ impl TerminalUI {
                             self.show_success("Invalid BPM");
                         }
                     }
+                    InputMode::Audition { layer_id } => {
+                        self.confirm_audition(layer_id, ImportMode::Replace);
+                    }
+                    InputMode::LatencyWarning { round_trip } => {
+                        let _ = self
+                            .command_sender
+                            .send(LayerCommand::SetLatencyCompensation(round_trip));
+                    }
+                    InputMode::DcOffsetWarning { layer_id } => {
+                        self.send_tracked(LayerCommand::RemoveDcOffset(layer_id));
+                    }
+                    InputMode::LoopFix { layer_id } => {
+                        let _ = self
+                            .command_sender
+                            .send(LayerCommand::FixLoopLength(layer_id, LoopFix::Trim));
+                    }
+                    InputMode::AddMarker { layer_id } => {
+                        let name = match self.input_buffer.trim() {
+                            "" => self.next_marker_name(layer_id),
+                            text => text.to_string(),
+                        };
+                        let _ = self
+                            .command_sender
+                            .send(LayerCommand::SetMarker { layer_id, name });
+                    }
+                    InputMode::EditMetadata { field, mut draft } => {
+                        let text = self.input_buffer.trim().to_string();
+                        match field {
+                            MetadataField::Title => {
+                                draft.title = text;
+                                self.start_metadata_prompt(MetadataField::Author, draft);
+                                return Ok(());
+                            }
+                            MetadataField::Author => {
+                                draft.author = text;
+                                self.start_metadata_prompt(MetadataField::Tags, draft);
+                                return Ok(());
+                            }
+                            MetadataField::Tags => {
+                                draft.tags = SessionMetadata::parse_tags(&text);
+                                let _ = self.command_sender.send(LayerCommand::SetMetadata(draft));
+                                self.show_success("Session details updated");
+                            }
+                        }
+                    }
+                    InputMode::Notes { .. }
+                    | InputMode::RecentFiles { .. }
+                    | InputMode::InputCheck { .. }
+                    | InputMode::LayerInfo { .. }
+                    | InputMode::Setup(_) => {}
+                    InputMode::SaveSession => {
+                        let filename = self.ensure_toml_extension(self.input_buffer.clone());
+                        let template = Session::is_template(&filename);
+                        let extension = if template {
+                            Session::TEMPLATE_EXTENSION
+                        } else {
+                            "toml"
+                        };
+                        match self.validate_export_path(&filename, extension) {
+                            Ok(_) if template => {
+                                let _ = self
+                                    .command_sender
+                                    .send(LayerCommand::SaveTemplate(filename.clone()));
+                                self.show_success(&format!("Saving template: {}", filename));
+                            }
+                            Ok(_) => {
+                                let _ = self
+                                    .command_sender
+                                    .send(LayerCommand::SaveSession(filename.clone()));
+                                self.show_success(&format!("Saving session: {}", filename));
+                            }
+                            Err(error) => {
+                                self.show_success(&format!("Save failed: {}", error));
+                            }
+                        }
+                    }
+                    InputMode::LoadSession => {
+                        let filename = self.ensure_toml_extension(self.input_buffer.clone());
+                        if std::path::Path::new(&filename).is_file() {
+                            let _ = self
+                                .command_sender
+                                .send(LayerCommand::LoadSession(filename.clone()));
+                            self.show_success(&format!("Loading session: {}", filename));
+                        } else {
+                            self.show_success(&format!("Session not found: {}", filename));
+                        }
+                    }
                 }
                 self.exit_input_mode();
             }
             KeyCode::Esc => {
+                if let InputMode::Audition { .. } = input_mode {
+                    let _ = self.command_sender.send(LayerCommand::CancelAudition);
+                }
                 // Cancel input
                 self.show_cancelled();
                 self.exit_input_mode();
@@ -720,6 +2088,76 @@ impl TerminalUI {
                 self.input_buffer.pop();
                 self.update_input_display();
             }
+            KeyCode::Char('p') if matches!(input_mode, InputMode::FilePicker { .. }) => {
+                // Cue the highlighted WAV before importing it
+                if let InputMode::FilePicker {
+                    current_dir,
+                    entries,
+                    selected_index,
+                    ..
+                } = input_mode
+                    && let Some(FileEntry::WavFile(filename)) = entries.get(selected_index)
+                {
+                    let full_path = Self::picker_file_path(&current_dir, filename);
+                    if self.previewing.as_deref() == Some(full_path.as_str()) {
+                        let _ = self.command_sender.send(LayerCommand::StopPreview);
+                    } else {
+                        match self.validate_import_file(&full_path) {
+                            Ok(_) => {
+                                let _ = self
+                                    .command_sender
+                                    .send(LayerCommand::PreviewWav(full_path));
+                            }
+                            Err(error) => {
+                                self.show_success(&format!("Preview failed: {}", error));
+                            }
+                        }
+                    }
+                }
+            }
+            KeyCode::Char('i') | KeyCode::Char('I')
+                if matches!(input_mode, InputMode::DevicePicker { .. }) =>
+            {
+                self.open_input_check();
+            }
+            KeyCode::Char('h') | KeyCode::Char('H')
+                if matches!(input_mode, InputMode::DevicePicker { .. }) =>
+            {
+                self.cycle_audio_host();
+            }
+            KeyCode::Char(c) if matches!(input_mode, InputMode::Audition { .. }) => {
+                let InputMode::Audition { layer_id } = input_mode else {
+                    return Ok(());
+                };
+                let mode = match c.to_ascii_lowercase() {
+                    'r' => ImportMode::Replace,
+                    'a' => ImportMode::Append,
+                    'i' => ImportMode::InsertAtPlayhead,
+                    _ => return Ok(()),
+                };
+                self.confirm_audition(layer_id, mode);
+                self.exit_input_mode();
+            }
+            KeyCode::Char(_)
+                if matches!(
+                    input_mode,
+                    InputMode::LatencyWarning { .. } | InputMode::DcOffsetWarning { .. }
+                ) => {}
+            KeyCode::Char(c) if matches!(input_mode, InputMode::LoopFix { .. }) => {
+                let InputMode::LoopFix { layer_id } = input_mode else {
+                    return Ok(());
+                };
+                let fix = match c.to_ascii_lowercase() {
+                    't' => LoopFix::Trim,
+                    's' => LoopFix::Stretch,
+                    'm' => LoopFix::SetMaster,
+                    _ => return Ok(()),
+                };
+                let _ = self
+                    .command_sender
+                    .send(LayerCommand::FixLoopLength(layer_id, fix));
+                self.exit_input_mode();
+            }
             KeyCode::Char(c) => {
                 self.input_buffer.push(c);
                 self.update_input_display();
@@ -847,6 +2285,202 @@ impl TerminalUI {
         ));
     }
 
+    fn start_metadata_prompt(&mut self, field: MetadataField, draft: SessionMetadata) {
+        let (prompt, current) = match field {
+            MetadataField::Title => ("Session title: ", draft.title.clone()),
+            MetadataField::Author => ("Author: ", draft.author.clone()),
+            MetadataField::Tags => ("Tags (comma separated): ", draft.tags.join(", ")),
+        };
+        self.start_input_mode(InputMode::EditMetadata { field, draft }, prompt);
+        // Start from the current value so Enter keeps it
+        self.input_buffer = current;
+        self.update_input_display();
+    }
+
+    /// Free text editing in the notes overlay; Esc saves and closes
+    fn handle_notes_key(&mut self, key: KeyEvent, mut text: String) {
+        match key.code {
+            KeyCode::Esc => {
+                if text != self.session_metadata.notes {
+                    let metadata = SessionMetadata {
+                        notes: text,
+                        ..self.session_metadata.clone()
+                    };
+                    let _ = self
+                        .command_sender
+                        .send(LayerCommand::SetMetadata(metadata));
+                    self.show_success("Notes saved");
+                } else {
+                    self.header_status = None;
+                }
+                self.exit_input_mode();
+                return;
+            }
+            KeyCode::Enter => text.push('\n'),
+            KeyCode::Backspace => {
+                text.pop();
+            }
+            KeyCode::Char(c) => text.push(c),
+            _ => {}
+        }
+        self.input_mode = Some(InputMode::Notes { text });
+    }
+
+    /// Validate a WAV, then audition it in sync before importing
+    fn audition_import(&mut self, layer_id: usize, full_path: String) {
+        self.exit_input_mode();
+        match self.validate_import_file(&full_path) {
+            Ok(_) => {
+                let _ = self
+                    .command_sender
+                    .send(LayerCommand::AuditionWav(layer_id, full_path));
+                self.start_input_mode(
+                    InputMode::Audition { layer_id },
+                    "Audition - R/Enter: replace, A: append, I: insert, Esc: cancel",
+                );
+            }
+            Err(error) => {
+                self.show_success(&format!("Import failed: {}", error));
+            }
+        }
+    }
+
+    /// Recent overlay: Enter re-imports (via audition), O reveals, D forgets
+    fn handle_recent_key(
+        &mut self,
+        key: KeyEvent,
+        layer_id: usize,
+        mut selected_index: usize,
+        scroll_offset: usize,
+    ) {
+        let count = self.recent_files.files().len();
+        let selected = self
+            .recent_files
+            .files()
+            .get(selected_index)
+            .map(|file| file.path.clone());
+        match key.code {
+            KeyCode::Esc => {
+                self.show_cancelled();
+                self.exit_input_mode();
+                return;
+            }
+            KeyCode::Enter => {
+                if let Some(path) = selected {
+                    self.audition_import(layer_id, path);
+                }
+                return;
+            }
+            KeyCode::Up => {
+                selected_index = selected_index
+                    .checked_sub(1)
+                    .unwrap_or(count.saturating_sub(1));
+            }
+            KeyCode::Down => {
+                selected_index = if selected_index + 1 < count {
+                    selected_index + 1
+                } else {
+                    0
+                };
+            }
+            KeyCode::Char('o') | KeyCode::Char('O') => {
+                if let Some(path) = selected {
+                    match reveal(&path) {
+                        Ok(()) => self.show_success(&format!("Revealed: {}", path)),
+                        Err(e) => self.show_success(&format!("Reveal failed: {:#}", e)),
+                    }
+                }
+            }
+            KeyCode::Char('d') | KeyCode::Char('D') | KeyCode::Delete => {
+                self.recent_files.remove(selected_index);
+                self.save_recent_files();
+                if self.recent_files.files().is_empty() {
+                    self.exit_input_mode();
+                    self.show_success("Recent files cleared");
+                    return;
+                }
+                selected_index = selected_index.min(self.recent_files.files().len() - 1);
+            }
+            _ => {}
+        }
+        let count = self.recent_files.files().len();
+        self.input_mode = Some(InputMode::RecentFiles {
+            layer_id,
+            selected_index,
+            scroll_offset: self.calculate_scroll_offset(selected_index, scroll_offset, count),
+        });
+    }
+
+    fn handle_setup_key(&mut self, key: KeyEvent, mut wizard: SetupWizard) {
+        match key.code {
+            KeyCode::Esc => {
+                self.exit_input_mode();
+                self.show_success("Setup skipped - run with --setup to start it again");
+                return;
+            }
+            KeyCode::Up => wizard.select_previous(),
+            KeyCode::Down => wizard.select_next(),
+            KeyCode::Left => wizard.back(),
+            KeyCode::Backspace if wizard.step == SetupStep::Bpm => {
+                wizard.bpm_text.pop();
+            }
+            KeyCode::Char(c)
+                if wizard.step == SetupStep::Bpm && (c.is_ascii_digit() || c == '.') =>
+            {
+                wizard.bpm_text.push(c);
+            }
+            KeyCode::Enter => match wizard.advance() {
+                Ok(true) => {
+                    self.finish_setup(wizard);
+                    return;
+                }
+                Ok(false) => self.header_status = None,
+                Err(message) => self.show_success(&message),
+            },
+            _ => {}
+        }
+        self.input_mode = Some(InputMode::Setup(Box::new(wizard)));
+    }
+
+    /// Write the wizard's config file and switch the running engine over to it
+    fn finish_setup(&mut self, wizard: SetupWizard) {
+        self.exit_input_mode();
+        let SetupWizard { path, config, .. } = wizard;
+        let audio = config.audio.clone();
+
+        // Streams are rebuilt with the new buffer size (and again per device switch)
+        let _ = self
+            .command_sender
+            .send(LayerCommand::SetBufferSize(audio.buffer_size));
+        if let Some(input) = audio.input_device
+            && input != self.input_device_name
+        {
+            self.input_device_name = input.clone();
+            let _ = self
+                .command_sender
+                .send(LayerCommand::SwitchInputDevice(input));
+        }
+        if let Some(output) = audio.output_device
+            && output != self.output_device_name
+        {
+            self.output_device_name = output.clone();
+            let _ = self
+                .command_sender
+                .send(LayerCommand::SwitchOutputDevice(output));
+        }
+        let _ = self
+            .command_sender
+            .send(LayerCommand::SetBpm(config.tempo.bpm));
+        let _ = self
+            .command_sender
+            .send(LayerCommand::SetClickSound(config.metronome.sound.clone()));
+
+        match config.save(&path) {
+            Ok(()) => self.show_success(&format!("Setup saved to {}", path)),
+            Err(e) => self.show_success(&format!("Error: {:#}", e)),
+        }
+    }
+
     fn start_file_picker(&mut self, layer_id: usize) -> Result<(), Box<dyn std::error::Error>> {
         // Start from user's home directory for better navigation
         let home_dir = std::env::var("HOME")
@@ -975,12 +2609,8 @@ impl TerminalUI {
             return Err("File must have .wav extension".to_string());
         }
 
-        // Check file size (limit to 100MB)
-        const MAX_FILE_SIZE: u64 = 100 * 1024 * 1024; // 100MB
+        // Length limits are the engine's (from the header), so only empty files stop here
         if let Ok(metadata) = std::fs::metadata(path) {
-            if metadata.len() > MAX_FILE_SIZE {
-                return Err("File too large (max 100MB)".to_string());
-            }
             if metadata.len() == 0 {
                 return Err("File is empty".to_string());
             }
@@ -1012,16 +2642,15 @@ impl TerminalUI {
         Ok(())
     }
 
-    fn validate_export_path(&self, file_path: &str) -> Result<(), String> {
+    fn validate_export_path(&self, file_path: &str, extension: &str) -> Result<(), String> {
         let path = std::path::Path::new(file_path);
 
         // Check file extension
-        if let Some(extension) = path.extension() {
-            if extension.to_string_lossy().to_lowercase() != "wav" {
-                return Err("Export file must have .wav extension".to_string());
-            }
-        } else {
-            return Err("Export file must have .wav extension".to_string());
+        let matches = path
+            .extension()
+            .is_some_and(|ext| ext.to_string_lossy().to_lowercase() == extension);
+        if !matches {
+            return Err(format!("Export file must have .{} extension", extension));
         }
 
         // Check for dangerous paths
@@ -1053,7 +2682,34 @@ impl TerminalUI {
         Ok(())
     }
 
+    fn confirm_audition(&mut self, layer_id: usize, mode: ImportMode) {
+        let action = match mode {
+            ImportMode::Replace => "Replacing",
+            ImportMode::Append => "Appending to",
+            ImportMode::InsertAtPlayhead => "Inserting into",
+        };
+        self.show_success(&format!("{} Layer {}...", action, layer_id + 1));
+        let _ = self
+            .command_sender
+            .send(LayerCommand::ConfirmAudition(mode));
+    }
+
+    fn picker_file_path(current_dir: &str, filename: &str) -> String {
+        if current_dir == "." {
+            filename.to_string()
+        } else {
+            std::path::Path::new(current_dir)
+                .join(filename)
+                .to_string_lossy()
+                .to_string()
+        }
+    }
+
     fn exit_input_mode(&mut self) {
+        // Leaving the picker ends any preview
+        if self.previewing.is_some() {
+            let _ = self.command_sender.send(LayerCommand::StopPreview);
+        }
         self.input_mode = None;
         self.input_buffer.clear();
         self.input_prompt.clear();
@@ -1061,22 +2717,77 @@ impl TerminalUI {
         // Keep header_status for success/cancel messages
     }
 
-    fn ensure_wav_extension(&self, filename: String) -> String {
-        if filename.to_lowercase().ends_with(".wav") {
+    /// Exports are WAV unless named `.flac`
+    fn ensure_export_extension(&self, filename: String) -> String {
+        if filename.to_lowercase().ends_with(".wav") || is_flac(&filename) {
             filename
         } else {
             format!("{}.wav", filename)
         }
     }
 
+    fn ensure_rpp_extension(&self, filename: String) -> String {
+        if filename.to_lowercase().ends_with(".rpp") {
+            filename
+        } else {
+            format!("{}.rpp", filename)
+        }
+    }
+
+    /// Session names get `.toml` unless they already name a session or template file
+    fn ensure_toml_extension(&self, filename: String) -> String {
+        if filename.to_lowercase().ends_with(".toml") || Session::is_template(&filename) {
+            filename
+        } else {
+            format!("{}.toml", filename)
+        }
+    }
+
+    /// First unused single-letter marker name (A, B, ...)
+    fn next_marker_name(&self, layer_id: usize) -> String {
+        let markers = &self.layer_markers[layer_id];
+        ('A'..='Z')
+            .map(|c| c.to_string())
+            .find(|name| markers.iter().all(|m| &m.name != name))
+            .unwrap_or_else(|| format!("M{}", markers.len() + 1))
+    }
+
+    fn jump_to_next_marker(&mut self, layer_id: usize) {
+        // First marker after the playhead, wrapping to the first marker
+        let position = self.layers[layer_id].playback_position;
+        let markers = &self.layer_markers[layer_id];
+        let next = markers
+            .iter()
+            .find(|m| m.position > position)
+            .or_else(|| markers.first())
+            .map(|m| m.name.clone());
+        match next {
+            Some(name) => {
+                self.show_success(&format!("Jump to marker {}", name));
+                let _ = self
+                    .command_sender
+                    .send(LayerCommand::JumpToMarker { layer_id, name });
+            }
+            None => self.show_success("No markers on this layer"),
+        }
+    }
+
     fn update_input_display(&mut self) {
         if let Some(HeaderStatus::InputPrompt(ref prompt, _)) = self.header_status {
-            let display_input = if prompt.starts_with("Set BPM") {
+            // File name prompts preview the extension that will be added
+            let suffix = match self.input_mode {
+                Some(InputMode::ExportWav) => ".wav",
+                Some(InputMode::ExportProject) => ".rpp",
+                Some(InputMode::SaveSession) | Some(InputMode::LoadSession) => ".toml",
+                _ => "",
+            };
+            let display_input = if self.input_buffer.to_lowercase().ends_with(suffix)
+                || (suffix == ".toml" && Session::is_template(&self.input_buffer))
+                || (suffix == ".wav" && is_flac(&self.input_buffer))
+            {
                 self.input_buffer.clone()
-            } else if self.input_buffer.is_empty() {
-                ".wav".to_string()
             } else {
-                format!("{}.wav", self.input_buffer)
+                format!("{}{}", self.input_buffer, suffix)
             };
             self.header_status = Some(HeaderStatus::InputPrompt(prompt.clone(), display_input));
         }
@@ -1087,6 +2798,11 @@ impl TerminalUI {
         self.status_timer = Some(Instant::now());
     }
 
+    fn show_warning(&mut self, message: &str) {
+        self.header_status = Some(HeaderStatus::Warning(message.to_string()));
+        self.status_timer = Some(Instant::now());
+    }
+
     fn show_cancelled(&mut self) {
         self.header_status = Some(HeaderStatus::Cancelled);
         self.status_timer = Some(Instant::now());
@@ -1098,6 +2814,7 @@ impl TerminalUI {
         {
             self.header_status = None;
             self.status_timer = None;
+            self.needs_redraw = true;
         }
     }
 
@@ -1109,10 +2826,100 @@ impl TerminalUI {
 
     fn export_composition(&mut self) {
         self.start_input_mode(InputMode::ExportWav, "Export composition as: ");
+        if let Some(template) = &self.export_name_template {
+            self.input_buffer = template.clone();
+            self.update_input_display();
+        }
+    }
+
+    /// Render the composition to the next numbered take in the export directory,
+    /// without prompts (uses the last normalization target)
+    fn export_snapshot(&mut self) {
+        let directory = std::path::Path::new(&self.export_directory);
+        if let Err(e) = std::fs::create_dir_all(directory) {
+            self.show_success(&format!("Snapshot failed: {}", e));
+            return;
+        }
+        if let Some(template) = &self.export_name_template {
+            // Tokens ({n} included) are filled in as the engine writes the file
+            let path = directory.join(template).to_string_lossy().to_string();
+            self.send_tracked(LayerCommand::ExportWav(path, self.export_normalization));
+            self.show_success("Snapshot exporting");
+            return;
+        }
+        // The previous snapshot may still be rendering, so don't rely on the listing alone
+        let number = next_snapshot_number(directory).max(self.snapshot_count + 1);
+        self.snapshot_count = number;
+        let path = directory.join(snapshot_file_name(number, std::time::SystemTime::now()));
+        let path = path.to_string_lossy().to_string();
+        self.send_tracked(LayerCommand::ExportWav(
+            path.clone(),
+            self.export_normalization,
+        ));
+        self.show_success(&format!("Snapshot {}: {}", number, path));
+    }
+
+    /// Import newly dropped WAVs into the first empty, unlocked layers
+    fn poll_watch_folder(&mut self) {
+        let Some(watch) = self.watch_folder.as_mut() else {
+            return;
+        };
+        let ready = watch.poll();
+        for path in ready {
+            let path = path.to_string_lossy().to_string();
+            let target = self.layers.iter().enumerate().position(|(id, layer)| {
+                layer.is_empty()
+                    && !layer.is_locked
+                    && !self.watch_imports.iter().any(|(pending, _)| *pending == id)
+            });
+            match target {
+                Some(layer_id) => {
+                    self.watch_imports.push((layer_id, path.clone()));
+                    self.send_tracked(LayerCommand::ImportWav(
+                        layer_id,
+                        path.clone(),
+                        ImportMode::Replace,
+                    ));
+                    self.show_success(&format!(
+                        "Watch folder: importing {} to layer {}",
+                        path,
+                        layer_id + 1
+                    ));
+                }
+                None => {
+                    self.show_success(&format!("Watch folder: no empty layer for {}", path));
+                }
+            }
+        }
+    }
+
+    fn open_recent_files(&mut self) {
+        if self.recent_files.files().is_empty() {
+            self.show_success("No recent imports or exports yet");
+            return;
+        }
+        self.input_mode = Some(InputMode::RecentFiles {
+            layer_id: self.selected_layer,
+            selected_index: 0,
+            scroll_offset: 0,
+        });
+        self.file_picker_overlay = true;
+    }
+
+    /// Put a WAV at the top of the Recent list
+    fn remember_file(&mut self, path: &str, kind: RecentKind) {
+        self.recent_files.record(path, kind);
+        self.save_recent_files();
+    }
+
+    fn save_recent_files(&mut self) {
+        if let Err(e) = self.recent_files.save(RecentFiles::DEFAULT_PATH) {
+            self.show_success(&format!("Recent files not saved: {:#}", e));
+        }
     }
 
     fn open_device_picker(&mut self) {
-        match enumerate_device_names() {
+        match enumerate_device_names(self.host_name.as_deref()) {
             Ok((inputs, outputs)) => {
                 // Start focused on inputs
                 let selected = 0usize;
@@ -1126,16 +2933,142 @@ impl TerminalUI {
                 });
                 self.file_picker_overlay = true;
             }
-            Err(e) => {
-                self.show_success(&format!("Error listing devices: {}", e));
+            Err(e) => {
+                self.show_success(&format!("Error listing devices: {}", e));
+            }
+        }
+    }
+
+    /// Compensate new takes for the measured device round trip, or stop doing so
+    fn toggle_latency_compensation(&mut self) {
+        let delay = if self.latency_compensation.is_zero() {
+            self.device_latency
+        } else {
+            Duration::ZERO
+        };
+        if delay.is_zero() && self.latency_compensation.is_zero() {
+            self.show_success("The audio backend reports no device latency");
+            return;
+        }
+        let _ = self
+            .command_sender
+            .send(LayerCommand::SetLatencyCompensation(delay));
+    }
+
+    /// Loudness history, then loudness plus spectrogram, then off
+    fn cycle_analysis_view(&mut self) {
+        let next = self.analysis_view.next();
+        match (self.analysis_view, next) {
+            (AnalysisView::Off, _) => {
+                self.mix_history.clear();
+                let _ = self.command_sender.send(LayerCommand::SetMixAnalysis(true));
+            }
+            (_, AnalysisView::Off) => {
+                let _ = self
+                    .command_sender
+                    .send(LayerCommand::SetMixAnalysis(false));
+            }
+            _ => {}
+        }
+        self.analysis_view = next;
+    }
+
+    /// Move to the next audio backend; the device lists are reloaded for it
+    fn cycle_audio_host(&mut self) {
+        let hosts = available_host_names();
+        if hosts.len() < 2 {
+            self.show_success("No other audio backend available");
+            return;
+        }
+        let current = hosts
+            .iter()
+            .position(|host| Some(host) == self.host_name.as_ref())
+            .unwrap_or(0);
+        let next = hosts[(current + 1) % hosts.len()].clone();
+        let _ = self
+            .command_sender
+            .send(LayerCommand::SwitchHost(next.clone()));
+        self.show_success(&format!("Switching to {}...", next));
+        self.host_name = Some(next);
+        self.open_device_picker();
+    }
+
+    /// Signal check for the active input device (opened from the device picker)
+    fn open_input_check(&mut self) {
+        let device = self.input_device_name.clone();
+        let format =
+            input_device_format(self.host_name.as_deref(), &device).map_err(|e| e.to_string());
+        self.input_mode = Some(InputMode::InputCheck { device, format });
+        self.file_picker_overlay = true;
+    }
+
+    fn title(&self) -> String {
+        match self.session_metadata.summary() {
+            Some(summary) => format!("Soundlooper - {}", summary),
+            None => "Soundlooper".to_string(),
+        }
+    }
+
+    /// Section: the last marker the selected layer has played past
+    fn current_section(&self) -> Option<String> {
+        let position = self.layers[self.selected_layer].playback_position;
+        self.layer_markers[self.selected_layer]
+            .iter()
+            .filter(|m| m.position <= position)
+            .max_by_key(|m| m.position)
+            .map(|m| m.name.clone())
+    }
+
+    /// The status view remote viewers see: no tables or colors, so it reads over nc
+    fn mirror_lines(&self) -> Vec<String> {
+        let position = self.transport_position;
+        let mut heading = format!(
+            "Bar {}  Beat {}  {:.1} BPM",
+            position.bar, position.beat, self.bpm_display
+        );
+        if let Some(section) = self.current_section() {
+            heading.push_str(&format!("  [{}]", section));
+        }
+        let mut lines = vec![self.title(), heading, String::new()];
+        for (layer_id, layer) in self.layers.iter().enumerate() {
+            let state = if layer.queued.record {
+                format!("ARMED {}", layer.queued.beats_left)
+            } else {
+                Self::layer_state_static(layer).0.to_string()
+            };
+            let peak_db = crate::audio::peak_meter::PeakMeter::to_db(layer.peak);
+            let filled = (((peak_db - SILENCE_DB) / -SILENCE_DB).clamp(0.0, 1.0) * 20.0) as usize;
+            let mut line = format!(
+                "L{:<2} {:<8} {}{}",
+                layer_id + 1,
+                state,
+                "#".repeat(filled),
+                ".".repeat(20 - filled)
+            );
+            if layer.peak > 0.001 {
+                line.push_str(&format!(" {:6.1} dB", peak_db));
+            }
+            if layer.is_muted {
+                line.push_str("  MUTED");
+            } else if layer.is_solo {
+                line.push_str("  SOLO");
             }
+            lines.push(line);
         }
+        lines
     }
 
     fn draw(&mut self) -> Result<(), Box<dyn std::error::Error>> {
         let selected_layer = self.selected_layer;
-        let layers = Arc::clone(&self.layers);
+        let layers = self.layers.clone();
+        let selected_markers = self.layer_markers[selected_layer].clone();
         let countdown = self.count_in_remaining;
+        let big_screen = self.big_screen;
+        let record_input = RecordInput {
+            live_peak: self.input_peak,
+            silent_layer: self.silent_input_layer,
+        };
+        let section = self.current_section();
 
         // Extract values to avoid borrow checker issues
         let input_device_name = self.input_device_name.clone();
@@ -1143,34 +3076,95 @@ impl TerminalUI {
         let header_status = self.header_status.clone();
         let file_picker_overlay = self.file_picker_overlay;
         let input_mode = self.input_mode.clone();
+        let recent_files = self.recent_files.files().to_vec();
+        let host_name = self
+            .host_name
+            .clone()
+            .unwrap_or_else(|| "default".to_string());
+        let input_level = (self.input_peak, self.input_peak_hold);
+        let analysis_view = self.analysis_view;
+        // One frame per terminal column is as much as the pane can show
+        let width = self.terminal.size().map(|s| s.width).unwrap_or(200);
+        let analysis_frames = self.mix_history.recent(width as usize);
+        let max_loudness = self.mix_history.max_loudness();
+        let title = self.title();
+        let footer_status = FooterStatus {
+            bpm: self.bpm_display,
+            position: self.transport_position,
+            sync_on: self.beat_sync_enabled,
+            metro_on: self.metronome_enabled,
+            click_record_only: self.click.record_only,
+            tap_locked: self.tap_locked,
+            transport_paused: self.transport_paused,
+            swing: self.swing,
+            count_in_bars: self.count_in_mode_enabled.then_some(self.count_in_measures),
+            mix_quantize: self.mix_quantize,
+            latency_compensation: self.latency_compensation,
+            quality_degraded: self.quality_degraded,
+            jam_connected: self.jam_peer.is_some(),
+            dropped_events: self.channel_stats.dropped_events(),
+            rejected_commands: self.channel_stats.rejected_commands(),
+            layer_keys: self.layer_keys.summary(self.layers.len()),
+        };
 
         self.terminal.draw(|f| {
+            // Prompts and pickers need the normal view
+            if big_screen && input_mode.is_none() {
+                Self::draw_big_screen_static(
+                    f,
+                    f.area(),
+                    &title,
+                    section.as_deref(),
+                    &footer_status,
+                    &layers,
+                );
+                if let Some((layer_id, beats_left)) = countdown {
+                    Self::draw_countdown_overlay_static(f, f.area(), layer_id, beats_left);
+                }
+                return;
+            }
+            let analysis_height = match analysis_view {
+                AnalysisView::Off => 0,
+                AnalysisView::Loudness => 7,
+                AnalysisView::Spectrogram => ANALYSIS_BANDS as u16 + 2,
+            };
             let chunks = Layout::default()
                 .direction(Direction::Vertical)
                 .constraints([
-                    Constraint::Length(3), // Header
-                    Constraint::Min(0),    // Layers
-                    Constraint::Length(5), // Footer (3 lines of content + borders)
+                    Constraint::Length(3),               // Header
+                    Constraint::Min(0),                  // Layers
+                    Constraint::Length(analysis_height), // Mix analysis (V)
+                    Constraint::Length(5),               // Footer (3 lines of content + borders)
                 ])
                 .split(f.area());
 
             Self::draw_header_static(
                 f,
                 chunks[0],
+                &title,
                 &input_device_name,
                 &output_device_name,
                 &header_status,
             );
-            Self::draw_layers_static(f, chunks[1], &layers, selected_layer, countdown);
-            Self::draw_footer_static(
+            Self::draw_layers_static(
                 f,
-                chunks[2],
-                self.bpm_display,
-                self.current_beat,
-                self.current_measure,
-                self.beat_sync_enabled,
-                self.metronome_enabled,
+                chunks[1],
+                &layers,
+                &selected_markers,
+                selected_layer,
+                countdown,
+                record_input,
             );
+            if analysis_view != AnalysisView::Off {
+                Self::draw_analysis_static(
+                    f,
+                    chunks[2],
+                    analysis_view,
+                    &analysis_frames,
+                    max_loudness,
+                );
+            }
+            Self::draw_footer_static(f, chunks[3], &footer_status);
 
             // Draw file picker overlay if active
             if file_picker_overlay {
@@ -1178,11 +3172,56 @@ impl TerminalUI {
                     Some(InputMode::FilePicker { .. }) => {
                         Self::draw_file_picker_overlay_static(f, f.area(), &input_mode);
                     }
+                    Some(InputMode::RecentFiles {
+                        layer_id,
+                        selected_index,
+                        scroll_offset,
+                    }) => {
+                        Self::draw_recent_overlay_static(
+                            f,
+                            f.area(),
+                            &recent_files,
+                            layer_id,
+                            selected_index,
+                            scroll_offset,
+                        );
+                    }
                     Some(InputMode::DevicePicker { .. }) => {
-                        Self::draw_device_picker_overlay_static(f, f.area(), &input_mode);
+                        Self::draw_device_picker_overlay_static(
+                            f,
+                            f.area(),
+                            &input_mode,
+                            &host_name,
+                        );
+                    }
+                    Some(InputMode::Notes { ref text }) => {
+                        Self::draw_notes_overlay_static(f, f.area(), text);
+                    }
+                    Some(InputMode::Setup(ref wizard)) => {
+                        Self::draw_setup_overlay_static(f, f.area(), wizard);
+                    }
+                    Some(InputMode::LayerInfo {
+                        layer_id,
+                        ref stats,
+                    }) => {
+                        Self::draw_layer_info_overlay_static(f, f.area(), layer_id, stats);
+                    }
+                    Some(InputMode::InputCheck {
+                        ref device,
+                        ref format,
+                    }) => {
+                        Self::draw_input_check_overlay_static(
+                            f,
+                            f.area(),
+                            device,
+                            format,
+                            input_level,
+                        );
                     }
                     _ => {}
                 }
+            } else if let Some((layer_id, beats_left)) = countdown {
+                Self::draw_countdown_overlay_static(f, f.area(), layer_id, beats_left);
             }
         })?;
         Ok(())
@@ -1191,6 +3230,7 @@ impl TerminalUI {
     fn draw_header_static(
         f: &mut Frame,
         area: Rect,
+        title: &str,
         input_device_name: &str,
         output_device_name: &str,
         header_status: &Option<HeaderStatus>,
@@ -1202,6 +3242,9 @@ impl TerminalUI {
             Some(HeaderStatus::Success(message)) => {
                 format!("✓ {}", message)
             }
+            Some(HeaderStatus::Warning(message)) => {
+                format!("⚠ {}", message)
+            }
             Some(HeaderStatus::Cancelled) => "✗ Cancelled".to_string(),
             None => {
                 format!(
@@ -1211,15 +3254,304 @@ impl TerminalUI {
             }
         };
 
+        let style = match header_status {
+            Some(HeaderStatus::Warning(_)) => Style::default()
+                .fg(Color::White)
+                .bg(Color::Red)
+                .add_modifier(Modifier::BOLD),
+            _ => Style::default()
+                .fg(Color::White)
+                .add_modifier(Modifier::BOLD),
+        };
         let header = Paragraph::new(header_text)
+            .style(style)
+            .alignment(ratatui::layout::Alignment::Center)
+            .block(Block::default().borders(Borders::ALL).title(title));
+        f.render_widget(header, area);
+    }
+
+    /// Large digits for the count-in, readable from across the room
+    fn big_number_lines(value: u32) -> Vec<String> {
+        const FONT: [[&str; 5]; 10] = [
+            ["███", "█ █", "█ █", "█ █", "███"],
+            [" █ ", "██ ", " █ ", " █ ", "███"],
+            ["███", "  █", "███", "█  ", "███"],
+            ["███", "  █", "███", "  █", "███"],
+            ["█ █", "█ █", "███", "  █", "  █"],
+            ["███", "█  ", "███", "  █", "███"],
+            ["███", "█  ", "███", "█ █", "███"],
+            ["███", "  █", "  █", "  █", "  █"],
+            ["███", "█ █", "███", "█ █", "███"],
+            ["███", "█ █", "███", "  █", "███"],
+        ];
+        let digits: Vec<usize> = value
+            .to_string()
+            .chars()
+            .filter_map(|c| c.to_digit(10).map(|d| d as usize))
+            .collect();
+        (0..5)
+            .map(|row| {
+                digits
+                    .iter()
+                    .map(|&d| {
+                        // Double each cell so digits aren't squashed by tall terminal cells
+                        FONT[d][row]
+                            .chars()
+                            .flat_map(|c| [c, c])
+                            .collect::<String>()
+                    })
+                    .collect::<Vec<_>>()
+                    .join("  ")
+            })
+            .collect()
+    }
+
+    fn draw_countdown_overlay_static(f: &mut Frame, area: Rect, layer_id: usize, beats_left: u32) {
+        let lines = Self::big_number_lines(beats_left);
+        let text_width = lines[0].chars().count() as u16;
+        let overlay_width = (text_width + 8).max(24).min(area.width);
+        let overlay_height = 9.min(area.height);
+        let x = area.x + (area.width - overlay_width) / 2;
+        let y = area.y + (area.height - overlay_height) / 2;
+        let overlay_area = Rect::new(x, y, overlay_width, overlay_height);
+
+        let mut text = vec![String::new()];
+        text.extend(lines);
+        let countdown = Paragraph::new(text.join("\n"))
             .style(
                 Style::default()
-                    .fg(Color::White)
+                    .fg(Color::Yellow)
+                    .bg(Color::Black)
                     .add_modifier(Modifier::BOLD),
             )
             .alignment(ratatui::layout::Alignment::Center)
-            .block(Block::default().borders(Borders::ALL).title("Soundlooper"));
-        f.render_widget(header, area);
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(format!("Count-in L{}", layer_id + 1)),
+            );
+        f.render_widget(ratatui::widgets::Clear, overlay_area);
+        f.render_widget(countdown, overlay_area);
+    }
+
+    fn draw_notes_overlay_static(f: &mut Frame, area: Rect, text: &str) {
+        // Use most of the screen; notes hold set lists and lyrics
+        let overlay_width = area.width.saturating_sub(8).clamp(20, 90).min(area.width);
+        let overlay_height = area.height.saturating_sub(4).clamp(6, 30).min(area.height);
+        let x = area.x + (area.width - overlay_width) / 2;
+        let y = area.y + (area.height - overlay_height) / 2;
+        let overlay_area = Rect::new(x, y, overlay_width, overlay_height);
+
+        // Background
+        for row in y..y + overlay_height {
+            let bg_line = Paragraph::new(" ".repeat(overlay_width as usize))
+                .style(Style::default().bg(Color::Black));
+            f.render_widget(bg_line, Rect::new(x, row, overlay_width, 1));
+        }
+
+        // Keep the end of the text (where typing happens) in view
+        let visible_lines = overlay_height.saturating_sub(3) as usize;
+        let line_count = text.split('\n').count();
+        let scroll = line_count.saturating_sub(visible_lines) as u16;
+        let notes = Paragraph::new(format!("{}_", text))
+            .style(Style::default().fg(Color::White).bg(Color::Black))
+            .wrap(ratatui::widgets::Wrap { trim: false })
+            .scroll((scroll, 0))
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Session Notes"),
+            );
+        f.render_widget(notes, overlay_area);
+
+        let instructions = "Type to edit  Enter: New line  Esc: Save & close";
+        let instructions_area = Rect::new(
+            x + 1,
+            y + overlay_height.saturating_sub(2),
+            overlay_width.saturating_sub(2),
+            1,
+        );
+        let instructions_widget = Paragraph::new(instructions)
+            .style(Style::default().fg(Color::Yellow).bg(Color::Black))
+            .alignment(ratatui::layout::Alignment::Center);
+        f.render_widget(instructions_widget, instructions_area);
+    }
+
+    fn draw_setup_overlay_static(f: &mut Frame, area: Rect, wizard: &SetupWizard) {
+        use ratatui::text::{Line, Span};
+
+        let overlay_width = 64.min(area.width);
+        let overlay_height = 16.min(area.height);
+        let x = area.x + (area.width - overlay_width) / 2;
+        let y = area.y + (area.height - overlay_height) / 2;
+        let overlay_area = Rect::new(x, y, overlay_width, overlay_height);
+
+        let mut lines = vec![
+            Line::from(Span::styled(
+                wizard.step.question(),
+                Style::default().add_modifier(Modifier::BOLD),
+            )),
+            Line::from(""),
+        ];
+        if wizard.step == SetupStep::Bpm {
+            lines.push(Line::from(format!("BPM: {}_", wizard.bpm_text)));
+        } else {
+            // Keep the selection in view on long device lists
+            let visible = overlay_height.saturating_sub(7).max(1) as usize;
+            let first = wizard.selected_index.saturating_sub(visible - 1);
+            for (index, choice) in wizard
+                .choices()
+                .iter()
+                .enumerate()
+                .skip(first)
+                .take(visible)
+            {
+                lines.push(if index == wizard.selected_index {
+                    Line::from(Span::styled(
+                        format!("> {}", choice),
+                        Style::default().fg(Color::Black).bg(Color::Cyan),
+                    ))
+                } else {
+                    Line::from(format!("  {}", choice))
+                });
+            }
+        }
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            "↑↓: Choose, Enter: Next, ←: Back, Esc: Skip setup",
+            Style::default().fg(Color::Yellow),
+        )));
+
+        let title = format!(
+            "Setup {}/{} - saved to {}",
+            wizard.step.number(),
+            SetupStep::COUNT,
+            wizard.path
+        );
+        let setup = Paragraph::new(lines)
+            .style(Style::default().fg(Color::White).bg(Color::Black))
+            .block(Block::default().borders(Borders::ALL).title(title));
+        f.render_widget(ratatui::widgets::Clear, overlay_area);
+        f.render_widget(setup, overlay_area);
+    }
+
+    fn draw_input_check_overlay_static(
+        f: &mut Frame,
+        area: Rect,
+        device: &str,
+        format: &Result<(u32, u16), String>,
+        (peak, peak_hold): (f32, f32),
+    ) {
+        use crate::audio::peak_meter::{MeterColor, PeakMeter};
+        use ratatui::text::{Line, Span};
+
+        let overlay_width = 60.min(area.width);
+        let overlay_height = 10.min(area.height);
+        let x = area.x + (area.width - overlay_width) / 2;
+        let y = area.y + (area.height - overlay_height) / 2;
+        let overlay_area = Rect::new(x, y, overlay_width, overlay_height);
+
+        let format_line = match format {
+            Ok((sample_rate, channels)) => format!(
+                "Format: {} Hz, {} channel{}",
+                sample_rate,
+                channels,
+                if *channels == 1 { "" } else { "s" }
+            ),
+            Err(error) => format!("Format: unavailable ({})", error),
+        };
+
+        // Bar scaled over -60..0 dB
+        let bar_width = overlay_width.saturating_sub(16) as usize;
+        let db = PeakMeter::to_db(peak);
+        let filled = (((db + 60.0) / 60.0).clamp(0.0, 1.0) * bar_width as f32) as usize;
+        let color = match PeakMeter::get_color(peak) {
+            MeterColor::Normal => Color::Green,
+            MeterColor::Warn => Color::Yellow,
+            MeterColor::Hot => Color::Red,
+            MeterColor::Clip => Color::Magenta,
+        };
+        let level_text = |level: f32| {
+            if level > 0.001 {
+                format!("{:.1}dB", PeakMeter::to_db(level))
+            } else {
+                "-∞".to_string()
+            }
+        };
+        let signal = if peak_hold > 0.001 {
+            Span::styled("Signal OK", Style::default().fg(Color::Green))
+        } else {
+            Span::styled("No signal", Style::default().fg(Color::Red))
+        };
+
+        let lines = vec![
+            Line::from(format!("Device: {}", device)),
+            Line::from(format_line),
+            Line::from(""),
+            Line::from(vec![
+                Span::raw("Level "),
+                Span::styled("█".repeat(filled), Style::default().fg(color)),
+                Span::raw("·".repeat(bar_width - filled)),
+                Span::raw(format!(" {}", level_text(peak))),
+            ]),
+            Line::from(vec![
+                Span::raw(format!("Peak hold: {}  ", level_text(peak_hold))),
+                signal,
+            ]),
+            Line::from(""),
+            Line::from(Span::styled(
+                "Esc: Back to devices",
+                Style::default().fg(Color::Yellow),
+            )),
+        ];
+        let check = Paragraph::new(lines)
+            .style(Style::default().fg(Color::White).bg(Color::Black))
+            .block(Block::default().borders(Borders::ALL).title("Input Check"));
+        f.render_widget(ratatui::widgets::Clear, overlay_area);
+        f.render_widget(check, overlay_area);
+    }
+
+    fn draw_layer_info_overlay_static(
+        f: &mut Frame,
+        area: Rect,
+        layer_id: usize,
+        stats: &LayerStats,
+    ) {
+        use ratatui::text::{Line, Span};
+
+        let overlay_width = 44.min(area.width);
+        let overlay_height = 11.min(area.height);
+        let x = area.x + (area.width - overlay_width) / 2;
+        let y = area.y + (area.height - overlay_height) / 2;
+        let overlay_area = Rect::new(x, y, overlay_width, overlay_height);
+
+        let lines = vec![
+            Line::from(format!(
+                "Length: {:.2} s ({:.2} bars)",
+                stats.seconds, stats.bars
+            )),
+            Line::from(format!("Peak: {:.1} dBFS", stats.peak_db)),
+            Line::from(format!("RMS: {:.1} dBFS", stats.rms_db)),
+            Line::from(format!("Crest factor: {:.1} dB", stats.crest_db)),
+            Line::from(match stats.tempo {
+                Some(bpm) => format!("Estimated tempo: {:.1} BPM", bpm),
+                None => "Estimated tempo: no clear beat".to_string(),
+            }),
+            Line::from(""),
+            Line::from(Span::styled(
+                "Esc: Close",
+                Style::default().fg(Color::Yellow),
+            )),
+        ];
+        let info = Paragraph::new(lines)
+            .style(Style::default().fg(Color::White).bg(Color::Black))
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(format!("Layer {} Info", layer_id + 1)),
+            );
+        f.render_widget(ratatui::widgets::Clear, overlay_area);
+        f.render_widget(info, overlay_area);
     }
 
     fn draw_file_picker_overlay_static(f: &mut Frame, area: Rect, input_mode: &Option<InputMode>) {
@@ -1347,7 +3679,7 @@ impl TerminalUI {
             f.render_widget(list, list_area);
 
             // Draw instructions at the bottom
-            let instructions = "↑↓: Navigate | Enter: Select/Open | Esc: Cancel";
+            let instructions = "↑↓: Navigate  P: Preview  Enter: Open  Esc: Cancel";
             let instructions_area = Rect::new(x + 1, y + overlay_height - 1, overlay_width - 2, 1);
             let instructions_widget = Paragraph::new(instructions)
                 .style(Style::default().fg(Color::Yellow))
@@ -1356,10 +3688,76 @@ impl TerminalUI {
         }
     }
 
+    fn draw_recent_overlay_static(
+        f: &mut Frame,
+        area: Rect,
+        files: &[RecentFile],
+        layer_id: usize,
+        selected_index: usize,
+        scroll_offset: usize,
+    ) {
+        const VISIBLE_ITEMS: usize = 15;
+        let overlay_width = area.width.saturating_sub(8).clamp(20, 90).min(area.width);
+        let overlay_height = ((files.len().min(VISIBLE_ITEMS) + 3) as u16).min(area.height);
+        let x = area.x + (area.width - overlay_width) / 2;
+        let y = area.y + (area.height - overlay_height) / 2;
+        let overlay_area = Rect::new(x, y, overlay_width, overlay_height);
+
+        // Solid background so the layers don't show through
+        for row in y..y + overlay_height {
+            let bg_line = Paragraph::new(" ".repeat(overlay_width as usize))
+                .style(Style::default().bg(Color::Black));
+            f.render_widget(bg_line, Rect::new(x, row, overlay_width, 1));
+        }
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(format!("Recent Files (import to Layer {})", layer_id + 1));
+        f.render_widget(block, overlay_area);
+
+        let items: Vec<ListItem> = files
+            .iter()
+            .enumerate()
+            .skip(scroll_offset)
+            .take(VISIBLE_ITEMS)
+            .map(|(i, file)| {
+                let (icon, color) = match file.kind {
+                    RecentKind::Imported => ("⬇ in ", Color::Cyan),
+                    RecentKind::Exported => ("⬆ out", Color::Green),
+                };
+                let style = if i == selected_index {
+                    Style::default().bg(Color::Blue).fg(Color::White)
+                } else {
+                    Style::default().fg(color)
+                };
+                ListItem::new(format!("{} {}", icon, file.path)).style(style)
+            })
+            .collect();
+        let list_area = Rect::new(
+            x + 1,
+            y + 1,
+            overlay_width.saturating_sub(2),
+            overlay_height.saturating_sub(3),
+        );
+        f.render_widget(List::new(items), list_area);
+
+        let instructions = "↑↓: Navigate  Enter: Import  O: Reveal  D: Forget  Esc: Close";
+        let instructions_area = Rect::new(
+            x + 1,
+            y + overlay_height.saturating_sub(2),
+            overlay_width.saturating_sub(2),
+            1,
+        );
+        let instructions_widget = Paragraph::new(instructions)
+            .style(Style::default().fg(Color::Yellow))
+            .alignment(ratatui::layout::Alignment::Center);
+        f.render_widget(instructions_widget, instructions_area);
+    }
+
     fn draw_device_picker_overlay_static(
         f: &mut Frame,
         area: Rect,
         input_mode: &Option<InputMode>,
+        host_name: &str,
     ) {
         if let Some(InputMode::DevicePicker {
             inputs,
@@ -1499,8 +3897,23 @@ impl TerminalUI {
                 *scroll_offset,
             );
 
+            // Active backend, on the spare row under the lists
+            let host_line = format!(
+                "Backend: {}  (H: next of {})",
+                host_name,
+                available_host_names().join(", ")
+            );
+            let host_area = Rect::new(x + 1, y + overlay_height - 3, overlay_width - 2, 1);
+            f.render_widget(
+                Paragraph::new(host_line)
+                    .style(Style::default().fg(Color::Cyan))
+                    .alignment(ratatui::layout::Alignment::Center),
+                host_area,
+            );
+
             // Instructions
-            let instructions = "↑↓: Navigate  Tab: Switch  Enter: Select  Esc: Close";
+            let instructions =
+                "↑↓: Navigate  Tab: Switch  Enter: Select  I: Input check  Esc: Close";
             let instructions_area = Rect::new(x + 1, y + overlay_height - 2, overlay_width - 2, 1);
             let instructions_widget = Paragraph::new(instructions)
                 .style(Style::default().fg(Color::Yellow))
@@ -1512,25 +3925,198 @@ impl TerminalUI {
     fn draw_layers_static(
         f: &mut Frame,
         area: Rect,
-        layers: &Arc<Vec<Arc<Mutex<AudioLayer>>>>,
+        layers: &[LayerStatusSnapshot],
+        selected_markers: &[Marker],
         selected_layer: usize,
         countdown: Option<(usize, u32)>,
+        record_input: RecordInput,
     ) {
         let chunks = Layout::default()
             .direction(Direction::Horizontal)
             .constraints([Constraint::Percentage(70), Constraint::Percentage(30)])
             .split(area);
 
-        Self::draw_layer_list_static(f, chunks[0], layers, selected_layer, countdown);
-        Self::draw_layer_details_static(f, chunks[1], layers, selected_layer);
+        Self::draw_layer_list_static(
+            f,
+            chunks[0],
+            layers,
+            selected_layer,
+            countdown,
+            record_input,
+        );
+        Self::draw_layer_details_static(f, chunks[1], layers, selected_markers, selected_layer);
+    }
+
+    /// A layer's state word and color, as the layer list and the big screen show it
+    fn layer_state_static(layer: &LayerStatusSnapshot) -> (&'static str, Color) {
+        if layer.is_replacing {
+            ("RETAKE", Color::Red)
+        } else if layer.has_replacement {
+            ("NEW?", Color::LightYellow)
+        } else if layer.phase() == LayerPhase::Overdubbing {
+            ("DUB", Color::LightRed)
+        } else if layer.is_recording {
+            ("REC", Color::Red)
+        } else if layer.is_cueing {
+            ("CUE", Color::Magenta)
+        } else if layer.is_playing {
+            ("PLAY", Color::Green)
+        } else if !layer.is_empty() {
+            ("PAUSE", Color::Yellow)
+        } else {
+            ("EMPTY", Color::Gray)
+        }
+    }
+
+    fn meter_color_static(level: f32) -> Color {
+        match crate::audio::peak_meter::PeakMeter::get_color(level) {
+            crate::audio::peak_meter::MeterColor::Normal => Color::Green,
+            crate::audio::peak_meter::MeterColor::Warn => Color::Yellow,
+            crate::audio::peak_meter::MeterColor::Hot => Color::Red,
+            crate::audio::peak_meter::MeterColor::Clip => Color::Magenta,
+        }
+    }
+
+    /// Performer-facing view (^) for a second monitor: session and section, a large
+    /// bar and beat, and one big state and meter per layer in use; no tables
+    fn draw_big_screen_static(
+        f: &mut Frame,
+        area: Rect,
+        title: &str,
+        section: Option<&str>,
+        status: &FooterStatus,
+        layers: &[LayerStatusSnapshot],
+    ) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(3), // Session, section and tempo
+                Constraint::Length(8), // Bar and beat
+                Constraint::Min(0),    // Layers
+            ])
+            .split(area);
+
+        let mut heading = vec![title.to_string()];
+        heading.extend(section.map(str::to_string));
+        heading.push(format!("{:.1} BPM", status.bpm));
+        let header = Paragraph::new(heading.join("  |  "))
+            .style(
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD),
+            )
+            .alignment(ratatui::layout::Alignment::Center)
+            .block(Block::default().borders(Borders::ALL));
+        f.render_widget(header, chunks[0]);
+
+        let counter = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+            .split(chunks[1]);
+        // The downbeat stands out so the one is easy to find
+        let beat_color = if status.position.beat == 1 {
+            Color::Yellow
+        } else {
+            Color::White
+        };
+        for (area, label, value, color) in [
+            (counter[0], "BAR", status.position.bar as u32, Color::White),
+            (counter[1], "BEAT", status.position.beat, beat_color),
+        ] {
+            let mut text = vec![String::new()];
+            text.extend(Self::big_number_lines(value));
+            let digits = Paragraph::new(text.join("\n"))
+                .style(Style::default().fg(color).add_modifier(Modifier::BOLD))
+                .alignment(ratatui::layout::Alignment::Center)
+                .block(Block::default().borders(Borders::ALL).title(label));
+            f.render_widget(digits, area);
+        }
+
+        let active: Vec<(usize, &LayerStatusSnapshot)> = layers
+            .iter()
+            .enumerate()
+            .filter(|(_, layer)| !layer.is_empty() || layer.is_recording || layer.queued.any())
+            .collect();
+        if active.is_empty() {
+            let idle = Paragraph::new("No loops yet")
+                .style(
+                    Style::default()
+                        .fg(Color::Gray)
+                        .add_modifier(Modifier::BOLD),
+                )
+                .alignment(ratatui::layout::Alignment::Center)
+                .block(Block::default().borders(Borders::ALL));
+            f.render_widget(idle, chunks[2]);
+            return;
+        }
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(vec![
+                Constraint::Ratio(1, active.len() as u32);
+                active.len()
+            ])
+            .split(chunks[2]);
+        for ((layer_id, layer), row) in active.into_iter().zip(rows.iter()) {
+            let (state, mut color) = Self::layer_state_static(layer);
+            let mut state = state.to_string();
+            if layer.queued.record {
+                state = format!("ARMED {}", layer.queued.beats_left);
+                color = Color::LightMagenta;
+            } else if layer.is_muted {
+                state.push_str(" (MUTED)");
+                color = Color::DarkGray;
+            }
+            // Capturing layers show the input they're taking in
+            let level = if layer.is_recording || layer.is_replacing {
+                layer.record_peak
+            } else {
+                layer.peak
+            };
+            let peak_db = crate::audio::peak_meter::PeakMeter::to_db(level);
+            let meter = Gauge::default()
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title(format!("L{}  {}", layer_id + 1, state))
+                        .title_style(Style::default().fg(color).add_modifier(Modifier::BOLD)),
+                )
+                .gauge_style(Style::default().fg(Self::meter_color_static(level)))
+                .ratio(((peak_db - SILENCE_DB) / -SILENCE_DB).clamp(0.0, 1.0) as f64)
+                .label(if level > 0.001 {
+                    format!("{:.1} dB", peak_db)
+                } else {
+                    "-∞".to_string()
+                });
+            f.render_widget(meter, *row);
+        }
+    }
+
+    /// Input level bar for the Samples column: `in` and 7 cells over -60..0 dBFS
+    fn record_meter_cell(level: f32) -> ratatui::widgets::Cell<'static> {
+        use ratatui::text::{Line, Span};
+        const CELLS: usize = 7;
+        let db = crate::audio::peak_meter::PeakMeter::to_db(level);
+        let filled = (((db + 60.0) / 60.0).clamp(0.0, 1.0) * CELLS as f32).round() as usize;
+        ratatui::widgets::Cell::from(Line::from(vec![
+            Span::styled("in ", Style::default().fg(Color::Red)),
+            Span::styled(
+                "▮".repeat(filled),
+                Style::default().fg(Self::meter_color_static(level)),
+            ),
+            Span::styled(
+                "▯".repeat(CELLS - filled),
+                Style::default().fg(Color::DarkGray),
+            ),
+        ]))
     }
 
     fn draw_layer_list_static(
         f: &mut Frame,
         area: Rect,
-        layers: &Arc<Vec<Arc<Mutex<AudioLayer>>>>,
+        layers: &[LayerStatusSnapshot],
         selected_layer: usize,
         countdown: Option<(usize, u32)>,
+        record_input: RecordInput,
     ) {
         use ratatui::text::Span;
         use ratatui::widgets::{Cell, Row, Table};
@@ -1539,29 +4125,23 @@ impl TerminalUI {
         let rows: Vec<Row> = layers
             .iter()
             .enumerate()
-            .map(|(i, layer_arc)| {
-                let layer = layer_arc.lock().unwrap();
-
+            .map(|(i, layer)| {
                 // Determine status and color; inject count-in countdown if relevant
-                let mut status_text = if layer.is_recording {
-                    "[REC]".to_string()
-                } else if layer.is_playing {
-                    "[PLAY]".to_string()
-                } else if !layer.is_empty() {
-                    "[PAUSE]".to_string()
-                } else {
-                    "[EMPTY]".to_string()
-                };
-
-                let mut status_color = if status_text == "[REC]" {
-                    Color::Red
-                } else if status_text == "[PLAY]" {
-                    Color::Green
-                } else if status_text == "[PAUSE]" {
-                    Color::Yellow
-                } else {
-                    Color::Gray
-                };
+                let (state, state_color) = Self::layer_state_static(layer);
+                let mut status_text = format!("[{}]", state);
+                let mut status_color = state_color;
+                // Armed record or queued play/stop: beats until the next downbeat
+                // (Shift+C cancels)
+                let mut flash = false;
+                if layer.queued.record {
+                    status_text = format!("[ARMED {}]", layer.queued.beats_left);
+                    status_color = Color::LightMagenta;
+                    // Flashes on every other beat up to the punch-in
+                    flash = layer.queued.beats_left % 2 == 1;
+                } else if layer.queued.play || layer.queued.stop {
+                    status_text = format!("[QUEUED {}]", layer.queued.beats_left);
+                    status_color = Color::LightBlue;
+                }
                 if let Some((layer_id, beats_left)) = countdown
                     && layer_id == i
                 {
@@ -1569,46 +4149,72 @@ impl TerminalUI {
                     status_text = format!("[{}]", beats_left);
                     status_color = Color::Cyan;
                 }
+                if record_input.silent_layer == Some(i) {
+                    status_text = "[NO INPUT]".to_string();
+                    status_color = Color::Red;
+                    flash = true;
+                }
 
                 // Create status cell with color
-                let status_cell = Cell::from(Span::styled(
-                    status_text.to_string(),
-                    Style::default()
-                        .fg(status_color)
-                        .add_modifier(Modifier::BOLD),
-                ));
+                let mut status_style = Style::default()
+                    .fg(status_color)
+                    .add_modifier(Modifier::BOLD);
+                if flash {
+                    status_style = status_style.add_modifier(Modifier::REVERSED);
+                }
+                let status_cell = Cell::from(Span::styled(status_text.to_string(), status_style));
 
                 // Volume cell
                 let volume_text = format!("{:.0}%", layer.volume * 100.0);
                 let volume_cell = Cell::from(volume_text);
 
-                // Samples cell
-                let samples_text = if layer.is_empty() {
-                    "0".to_string()
+                // Samples cell; armed or capturing, the input level headed for the layer
+                // instead (live input while armed, what's written once it captures)
+                let capturing = layer.is_recording || layer.is_replacing;
+                let samples_cell = if capturing || layer.queued.record {
+                    let level = if capturing {
+                        layer.record_peak
+                    } else {
+                        record_input.live_peak
+                    };
+                    Self::record_meter_cell(level)
+                } else if layer.is_empty() {
+                    Cell::from("0")
                 } else {
-                    layer.get_buffer_length().to_string()
+                    Cell::from(layer.buffer_len.to_string())
                 };
-                let samples_cell = Cell::from(samples_text);
 
                 // Mute/Solo cell
-                let mute_solo_text = if layer.is_muted {
+                let mut mute_solo_text = if layer.is_muted {
                     "MUTED".to_string()
                 } else if layer.is_solo {
                     "SOLO".to_string()
                 } else {
                     "".to_string()
                 };
+                if layer.is_solo_safe && !layer.is_solo {
+                    mute_solo_text.push_str(" SAFE");
+                }
+                if layer.output_bus == OutputBus::Cue {
+                    mute_solo_text.push_str(" CUE");
+                }
+                if layer.is_fitted {
+                    mute_solo_text.push_str(" FIT");
+                }
+                if layer.takes > 1 {
+                    mute_solo_text.push_str(&format!(
+                        " T{}/{}",
+                        layer.current_take + 1,
+                        layer.takes
+                    ));
+                }
+
                 let mute_solo_cell = Cell::from(mute_solo_text);
 
                 // Peak meter cell
-                let peak_level = layer.meter.get_peak();
+                let peak_level = layer.peak;
                 let peak_db = crate::audio::peak_meter::PeakMeter::to_db(peak_level);
-                let peak_color = match crate::audio::peak_meter::PeakMeter::get_color(peak_level) {
-                    crate::audio::peak_meter::MeterColor::Normal => Color::Green,
-                    crate::audio::peak_meter::MeterColor::Warn => Color::Yellow,
-                    crate::audio::peak_meter::MeterColor::Hot => Color::Red,
-                    crate::audio::peak_meter::MeterColor::Clip => Color::Magenta,
-                };
+                let peak_color = Self::meter_color_static(peak_level);
 
                 let peak_text = if peak_level > 0.001 {
                     format!("{:.1}dB", peak_db)
@@ -1621,15 +4227,9 @@ impl TerminalUI {
                 ));
 
                 // Peak hold cell
-                let peak_hold_level = layer.meter.get_peak_hold();
+                let peak_hold_level = layer.peak_hold;
                 let peak_hold_db = crate::audio::peak_meter::PeakMeter::to_db(peak_hold_level);
-                let peak_hold_color =
-                    match crate::audio::peak_meter::PeakMeter::get_color(peak_hold_level) {
-                        crate::audio::peak_meter::MeterColor::Normal => Color::Green,
-                        crate::audio::peak_meter::MeterColor::Warn => Color::Yellow,
-                        crate::audio::peak_meter::MeterColor::Hot => Color::Red,
-                        crate::audio::peak_meter::MeterColor::Clip => Color::Magenta,
-                    };
+                let peak_hold_color = Self::meter_color_static(peak_hold_level);
 
                 let peak_hold_text = if peak_hold_level > 0.001 {
                     format!("{:.1}dB", peak_hold_db)
@@ -1652,8 +4252,15 @@ impl TerminalUI {
                     Style::default().fg(Color::White)
                 };
 
+                // Padlock marks layers that refuse record/clear/import/volume
+                let name = if layer.is_locked {
+                    format!("Layer {} 🔒", i + 1)
+                } else {
+                    format!("Layer {}", i + 1)
+                };
+
                 Row::new(vec![
-                    Cell::from(format!("Layer {}", i + 1)),
+                    Cell::from(name),
                     status_cell,
                     volume_cell,
                     samples_cell,
@@ -1669,11 +4276,11 @@ impl TerminalUI {
         let table = Table::new(
             rows,
             &[
-                Constraint::Length(8),  // Layer
-                Constraint::Length(8),  // Status
+                Constraint::Length(11), // Layer (+ padlock)
+                Constraint::Length(11), // Status (+ beats until a queued action)
                 Constraint::Length(8),  // Volume
                 Constraint::Length(10), // Samples
-                Constraint::Length(10), // Mute/Solo
+                Constraint::Length(23), // Mute/Solo (+ SAFE/CUE/FIT/take)
                 Constraint::Length(10), // Peak
                 Constraint::Length(10), // Peak Hold
             ],
@@ -1723,10 +4330,21 @@ impl TerminalUI {
     fn draw_layer_details_static(
         f: &mut Frame,
         area: Rect,
-        layers: &Arc<Vec<Arc<Mutex<AudioLayer>>>>,
+        layers: &[LayerStatusSnapshot],
+        markers: &[Marker],
         selected_layer: usize,
     ) {
-        let layer = layers[selected_layer].lock().unwrap();
+        let layer = layers[selected_layer];
+        let dbfs = |level: f32| {
+            if level > 0.0 {
+                format!(
+                    "{:.1} dBFS",
+                    crate::audio::peak_meter::PeakMeter::to_db(level)
+                )
+            } else {
+                "-inf dBFS".to_string()
+            }
+        };
 
         let volume_gauge = Gauge::default()
             .block(Block::default().borders(Borders::ALL).title("Volume"))
@@ -1739,22 +4357,46 @@ impl TerminalUI {
             Buffer: {} samples\n\
             Loop: {} - {}\n\
             Position: {}\n\
+            Level: {} peak / {} RMS\n\
+            Max peak: {}{}\n\
             Muted: {}\n\
-            Solo: {}",
+            Solo: {}\n\
+            Locked: {}\n\
+            Markers: {}",
             selected_layer + 1,
             if layer.is_recording {
                 "Recording"
+            } else if layer.is_cueing {
+                "Cueing"
             } else if layer.is_playing {
                 "Playing"
             } else {
                 "Stopped"
             },
-            layer.get_buffer_length(),
+            layer.buffer_len,
             layer.loop_start,
             layer.loop_end,
             layer.playback_position,
+            dbfs(layer.peak),
+            dbfs(layer.rms),
+            dbfs(layer.max_peak),
+            if layer.max_peak >= 1.0 {
+                " CLIP (! resets)"
+            } else {
+                ""
+            },
             layer.is_muted,
-            layer.is_solo
+            layer.is_solo,
+            layer.is_locked,
+            if markers.is_empty() {
+                "none".to_string()
+            } else {
+                markers
+                    .iter()
+                    .map(|m| format!("{}@{}", m.name, m.position))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            }
         ))
         .block(Block::default().borders(Borders::ALL).title("Details"));
 
@@ -1767,16 +4409,102 @@ impl TerminalUI {
         f.render_widget(details, chunks[1]);
     }
 
-    fn draw_footer_static(
+    /// Loudness history of the master mix, with the octave band spectrogram beside it
+    fn draw_analysis_static(
         f: &mut Frame,
         area: Rect,
-        bpm: f64,
-        beat: u32,
-        measure: usize,
-        sync_on: bool,
-        metro_on: bool,
+        view: AnalysisView,
+        frames: &[AnalysisFrame],
+        max_loudness: Option<f32>,
     ) {
         use ratatui::text::{Line, Span};
+        use ratatui::widgets::{Sparkline, SparklineBar};
+
+        // Streaming platforms normalize to about -14 LUFS; well above that is too hot
+        let loudness_color = |lufs: f32| {
+            if lufs > -9.0 {
+                Color::Red
+            } else if lufs > -14.0 {
+                Color::Yellow
+            } else {
+                Color::Green
+            }
+        };
+        // Newest frames that fit, oldest first
+        let visible = |width: u16| &frames[frames.len().saturating_sub(width as usize)..];
+
+        let chunks = if view == AnalysisView::Spectrogram {
+            Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+                .split(area)
+        } else {
+            Layout::default()
+                .constraints([Constraint::Percentage(100)])
+                .split(area)
+        };
+
+        let title = match (frames.last(), max_loudness) {
+            (Some(frame), Some(max)) => format!(
+                " Loudness {:.1} LUFS (max {:.1}) - V: cycle view ",
+                frame.loudness, max
+            ),
+            _ => " Loudness - waiting for audio ".to_string(),
+        };
+        let block = Block::default().borders(Borders::ALL).title(title);
+        let inner = block.inner(chunks[0]);
+        // Bars span -60 LUFS (empty) to 0 LUFS (full height)
+        let bars: Vec<SparklineBar> = visible(inner.width)
+            .iter()
+            .map(|frame| {
+                SparklineBar::from((frame.loudness + 60.0).clamp(0.0, 60.0) as u64)
+                    .style(Some(Style::default().fg(loudness_color(frame.loudness))))
+            })
+            .collect();
+        f.render_widget(
+            Sparkline::default().block(block).data(bars).max(60),
+            chunks[0],
+        );
+
+        if view != AnalysisView::Spectrogram {
+            return;
+        }
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(" Spectrum (octave bands) ");
+        let inner = block.inner(chunks[1]);
+        let columns = visible(inner.width.saturating_sub(5));
+        // Highest band on top
+        let rows: Vec<Line> = (0..ANALYSIS_BANDS)
+            .rev()
+            .map(|band| {
+                let centre = BAND_CENTRES[band];
+                let label = if centre >= 1000.0 {
+                    format!("{:>4} ", format!("{}k", centre / 1000.0))
+                } else {
+                    format!("{:>4} ", centre)
+                };
+                let mut spans = vec![Span::styled(label, Style::default().fg(Color::DarkGray))];
+                spans.extend(columns.iter().map(|frame| {
+                    // 12 dB per shade from -60 dB up
+                    let level = frame.bands[band].max(SILENCE_DB);
+                    let (cell, color) = match ((level + 60.0) / 12.0).floor() as i32 {
+                        i32::MIN..=0 => (" ", Color::Reset),
+                        1 => ("░", Color::Blue),
+                        2 => ("▒", Color::Cyan),
+                        3 => ("▓", Color::Yellow),
+                        _ => ("█", Color::Red),
+                    };
+                    Span::styled(cell, Style::default().fg(color))
+                }));
+                Line::from(spans)
+            })
+            .collect();
+        f.render_widget(Paragraph::new(rows).block(block), chunks[1]);
+    }
+
+    fn draw_footer_static(f: &mut Frame, area: Rect, status: &FooterStatus) {
+        use ratatui::text::{Line, Span};
 
         // Define colors for syntax highlighting
         let key_color = Color::Yellow;
@@ -1802,7 +4530,7 @@ impl TerminalUI {
         let mut line1_spans = Vec::new();
         line1_spans.extend(key_desc("↑↓", "Select"));
         line1_spans.push(separator());
-        line1_spans.extend(key_desc("1-9/0", "Record/Stop"));
+        line1_spans.extend(key_desc(&status.layer_keys, "Record/Stop"));
         line1_spans.push(separator());
         line1_spans.extend(key_desc("R", "Record"));
         line1_spans.push(separator());
@@ -1840,33 +4568,118 @@ impl TerminalUI {
         line3_spans.push(separator());
         line3_spans.extend(key_desc("T", "BPM"));
         line3_spans.push(separator());
-        line3_spans.extend(key_desc("G", if sync_on { "Sync On" } else { "Sync Off" }));
+        line3_spans.extend(key_desc(
+            "G",
+            if status.sync_on {
+                "Sync On"
+            } else {
+                "Sync Off"
+            },
+        ));
         line3_spans.push(separator());
-        line3_spans.extend(key_desc("H", "Count-in"));
+        let count_in_desc = match status.count_in_bars {
+            Some(bars) => format!("Count-in {}", bars),
+            None => "Count-in".to_string(),
+        };
+        line3_spans.extend(key_desc("H", &count_in_desc));
         line3_spans.push(separator());
         line3_spans.extend(key_desc(
             "N",
-            if metro_on {
+            if status.metro_on && status.click_record_only {
+                "Metronome On (Rec)"
+            } else if status.metro_on {
                 "Metronome On"
             } else {
                 "Metronome Off"
             },
         ));
 
-        let status_line = Line::from(vec![
+        let mut status_spans = vec![
             Span::styled(
-                format!(" BPM: {:.1} ", bpm),
+                format!(" BPM: {:.1} ", status.bpm),
                 Style::default()
                     .fg(Color::Cyan)
                     .add_modifier(Modifier::BOLD),
             ),
             Span::styled(
-                format!(" Beat: {}/{} ", beat, measure + 1),
+                format!(
+                    " {}  {:02}:{:02}{} ",
+                    status.position,
+                    status.position.elapsed.as_secs() / 60,
+                    status.position.elapsed.as_secs() % 60,
+                    if status.transport_paused {
+                        " PAUSED"
+                    } else {
+                        ""
+                    }
+                ),
                 Style::default()
-                    .fg(Color::Green)
+                    .fg(if status.transport_paused {
+                        Color::Yellow
+                    } else {
+                        Color::Green
+                    })
                     .add_modifier(Modifier::BOLD),
             ),
-        ]);
+        ];
+        if status.swing > 50.0 {
+            status_spans.push(Span::styled(
+                format!(" Swing: {:.0}% ", status.swing),
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            ));
+        }
+        if !status.latency_compensation.is_zero() {
+            status_spans.push(Span::styled(
+                format!(" LAT -{}ms ", status.latency_compensation.as_millis()),
+                Style::default().fg(Color::Cyan),
+            ));
+        }
+        if status.mix_quantize != MixQuantize::Off {
+            status_spans.push(Span::styled(
+                match status.mix_quantize {
+                    MixQuantize::Beat => " MIX Q: BEAT ",
+                    _ => " MIX Q: BAR ",
+                }
+                .to_string(),
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            ));
+        }
+        if status.tap_locked {
+            status_spans.push(Span::styled(
+                " TAP LOCKED ".to_string(),
+                Style::default()
+                    .fg(Color::Magenta)
+                    .add_modifier(Modifier::BOLD),
+            ));
+        }
+        if status.jam_connected {
+            status_spans.push(Span::styled(
+                " JAM ".to_string(),
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD),
+            ));
+        }
+        if status.quality_degraded {
+            status_spans.push(Span::styled(
+                " LOW QUALITY (CPU) ".to_string(),
+                Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+            ));
+        }
+        if status.dropped_events > 0 || status.rejected_commands > 0 {
+            status_spans.push(Span::styled(
+                format!(
+                    " QUEUE FULL: {} events dropped, {} commands rejected ",
+                    status.dropped_events, status.rejected_commands
+                ),
+                Style::default().fg(Color::Red),
+            ));
+        }
+        let status_line = Line::from(status_spans);
 
         let help_text = vec![
             Line::from(line1_spans),
@@ -0,0 +1,98 @@
+// src/ui/watch_folder.rs
+// Polls a directory for WAVs dropped in during a jam (e.g. stems airdropped
+// by a collaborator) so they can be imported without the file picker
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// How often the directory is listed
+pub const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug)]
+pub struct WatchFolder {
+    directory: PathBuf,
+    seen: HashSet<PathBuf>,         // there at startup or already handed out
+    copying: HashMap<PathBuf, u64>, // new files and their size at the last poll
+}
+
+impl WatchFolder {
+    /// Files already in the directory are left alone
+    pub fn new<P: AsRef<Path>>(directory: P) -> Self {
+        let directory = directory.as_ref().to_path_buf();
+        let seen = wav_files(&directory).into_keys().collect();
+        Self {
+            directory,
+            seen,
+            copying: HashMap::new(),
+        }
+    }
+
+    /// New WAVs whose size held steady since the previous poll, so files still
+    /// being copied or synced in aren't imported half-written
+    pub fn poll(&mut self) -> Vec<PathBuf> {
+        let mut ready = Vec::new();
+        let mut copying = HashMap::new();
+        for (path, size) in wav_files(&self.directory) {
+            if self.seen.contains(&path) {
+                continue;
+            }
+            if size > 0 && self.copying.get(&path) == Some(&size) {
+                self.seen.insert(path.clone());
+                ready.push(path);
+            } else {
+                copying.insert(path, size);
+            }
+        }
+        self.copying = copying;
+        ready.sort();
+        ready
+    }
+}
+
+fn wav_files(directory: &Path) -> HashMap<PathBuf, u64> {
+    let Ok(entries) = std::fs::read_dir(directory) else {
+        return HashMap::new();
+    };
+    entries
+        .flatten()
+        .filter(|entry| {
+            entry
+                .path()
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("wav"))
+        })
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok()?;
+            metadata.is_file().then(|| (entry.path(), metadata.len()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_files_once_settled() {
+        let dir = std::env::temp_dir().join(format!("soundlooper-watch-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("old.wav"), b"RIFF").unwrap();
+
+        let mut watch = WatchFolder::new(&dir);
+        assert!(watch.poll().is_empty());
+
+        std::fs::write(dir.join("stem.WAV"), b"RIFF").unwrap();
+        std::fs::write(dir.join("notes.txt"), b"hello").unwrap();
+        // First sighting: may still be copying
+        assert!(watch.poll().is_empty());
+        // Still growing
+        std::fs::write(dir.join("stem.WAV"), b"RIFFWAVE").unwrap();
+        assert!(watch.poll().is_empty());
+        assert_eq!(watch.poll(), vec![dir.join("stem.WAV")]);
+        // Handed out only once
+        assert!(watch.poll().is_empty());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,501 @@
+// tests/looper_engine.rs
+// Engine-level command semantics: commands go through `send_command` and audio
+// through `process_audio` with synthetic input, as the audio callback drives it
+
+use std::sync::Arc;
+
+use soundlooper::audio::{
+    AudioConfig, AudioEvent, ChannelStats, EventReceiver, LayerCommand, LayerPhase, LooperEngine,
+    LooperError, QuantizedBuffer, event_channel,
+};
+
+const BLOCK: usize = 256;
+
+struct Harness {
+    engine: LooperEngine,
+    events: EventReceiver,
+}
+
+impl Harness {
+    fn new() -> Self {
+        let engine = LooperEngine::new(AudioConfig {
+            sample_rate: 48_000,
+            buffer_size: BLOCK,
+            max_layers: 4,
+        });
+        let (event_sender, events) = event_channel(&ChannelStats::new());
+        engine.set_event_sender(event_sender);
+        Self { engine, events }
+    }
+
+    fn send(&self, command: LayerCommand) {
+        self.engine.send_command(command).unwrap();
+    }
+
+    /// Run `input` through the engine a block at a time; returns the output
+    fn process(&self, input: &[f32]) -> Vec<f32> {
+        let mut output = vec![0.0; input.len()];
+        for (input, output) in input.chunks(BLOCK).zip(output.chunks_mut(BLOCK)) {
+            self.engine.process_audio(input, output);
+        }
+        output
+    }
+
+    fn silence(&self, samples: usize) -> Vec<f32> {
+        self.process(&vec![0.0; samples])
+    }
+
+    /// Record `input` on a layer; it plays from the top once recording stops
+    fn record(&self, layer_id: usize, input: &[f32]) {
+        self.send(LayerCommand::Record(layer_id));
+        self.process(input);
+        self.send(LayerCommand::StopRecording(layer_id));
+    }
+
+    fn layer_audio(&self, layer_id: usize) -> Vec<f32> {
+        let layer = self.engine.get_layer(layer_id).unwrap();
+        let layer = layer.lock().unwrap();
+        layer.audio().to_vec()
+    }
+
+    /// Events since the last call, without the periodic status updates
+    fn events(&self) -> Vec<AudioEvent> {
+        self.events
+            .try_iter()
+            .filter(|event| {
+                !matches!(
+                    event,
+                    AudioEvent::LayerStatusBatch(_)
+                        | AudioEvent::InputLevel { .. }
+                        | AudioEvent::TransportPosition(_)
+                        | AudioEvent::LayerMarkers(..)
+                        | AudioEvent::Beat(..)
+                )
+            })
+            .collect()
+    }
+}
+
+/// A ramp that never repeats a value within a loop, so misplaced samples show
+fn ramp(samples: usize, level: f32) -> Vec<f32> {
+    (0..samples)
+        .map(|i| level * (i + 1) as f32 / samples as f32)
+        .collect()
+}
+
+fn assert_close(actual: &[f32], expected: &[f32]) {
+    assert_eq!(actual.len(), expected.len());
+    for (i, (a, e)) in actual.iter().zip(expected).enumerate() {
+        assert!((a - e).abs() < 1e-4, "sample {}: {} != {}", i, a, e);
+    }
+}
+
+#[test]
+fn test_recorded_buffer_and_loop_playback() {
+    let harness = Harness::new();
+    let take = ramp(16 * BLOCK, 0.5);
+    harness.record(0, &take);
+
+    assert_eq!(harness.layer_audio(0), take);
+    assert_eq!(harness.engine.get_master_loop_length(), Some(take.len()));
+    let layer = harness.engine.get_layer(0).unwrap();
+    assert_eq!(
+        layer.lock().unwrap().phase(),
+        LayerPhase::Playing,
+        "a finished take plays"
+    );
+
+    // Two passes of the loop, back to back
+    let output = harness.silence(2 * take.len());
+    assert_close(&output[..take.len()], &take);
+    assert_close(&output[take.len()..], &take);
+}
+
+#[test]
+fn test_large_input_blocks_are_recorded_whole() {
+    let harness = Harness::new();
+    // Far past the configured buffer size, as some devices deliver
+    let take = ramp(8192, 0.5);
+    harness.send(LayerCommand::Record(0));
+    harness
+        .engine
+        .process_audio(&take, &mut vec![0.0; take.len()]);
+    harness.send(LayerCommand::StopRecording(0));
+    assert_eq!(harness.layer_audio(0), take);
+
+    // Larger still once reserved
+    let take = ramp(20_000, 0.5);
+    harness.engine.reserve_input(take.len());
+    harness.send(LayerCommand::Record(1));
+    harness
+        .engine
+        .process_audio(&take, &mut vec![0.0; take.len()]);
+    harness.send(LayerCommand::StopRecording(1));
+    assert_eq!(harness.layer_audio(1).len(), take.len());
+}
+
+#[test]
+fn test_event_order() {
+    let harness = Harness::new();
+    harness.send(LayerCommand::Record(1));
+    harness.process(&ramp(8 * BLOCK, 0.5));
+    harness.send(LayerCommand::StopRecording(1));
+    // Phase changes (the automatic playback) are published from the next block
+    harness.silence(BLOCK);
+
+    let events = harness.events();
+    let names: Vec<String> = events
+        .iter()
+        .map(|event| format!("{:?}", event))
+        .map(|debug| debug.split(['(', ' ']).next().unwrap().to_string())
+        .collect();
+    assert_eq!(
+        names,
+        [
+            "LayerRecording",
+            "LayerPhaseChanged",
+            "LayerStopped",
+            "MasterLoopChanged",
+            "LayerPhaseChanged",
+        ]
+    );
+    assert_eq!(
+        events[1],
+        AudioEvent::LayerPhaseChanged(1, LayerPhase::Recording)
+    );
+    assert_eq!(events[3], AudioEvent::MasterLoopChanged(Some(8 * BLOCK)));
+    assert_eq!(
+        events[4],
+        AudioEvent::LayerPhaseChanged(1, LayerPhase::Playing)
+    );
+}
+
+#[test]
+fn test_mute_and_solo() {
+    let harness = Harness::new();
+    let length = 8 * BLOCK;
+    harness.record(0, &vec![0.25; length]);
+    harness.record(1, &vec![0.125; length]);
+    // Layer 0 played on while layer 1 recorded; restart both at the loop top
+    harness.send(LayerCommand::StopAll);
+    harness.send(LayerCommand::PlayAll);
+
+    // Settle past any gain ramps, then measure the last block
+    let level = |harness: &Harness| *harness.silence(length).last().unwrap();
+    assert!((level(&harness) - 0.375).abs() < 1e-4);
+
+    harness.send(LayerCommand::Mute(0));
+    assert!((level(&harness) - 0.125).abs() < 1e-4);
+    harness.send(LayerCommand::Mute(0));
+
+    // Solo silences the others; solo-safe layers keep playing
+    harness.send(LayerCommand::Solo(0));
+    assert!((level(&harness) - 0.25).abs() < 1e-4);
+    harness.send(LayerCommand::ToggleSoloSafe(1));
+    assert!((level(&harness) - 0.375).abs() < 1e-4);
+
+    harness.send(LayerCommand::SetVolume(0, 0.5));
+    assert!((level(&harness) - 0.25).abs() < 1e-4);
+    let events = harness.events();
+    assert!(events.contains(&AudioEvent::LayerMuted(0)));
+    assert!(events.contains(&AudioEvent::LayerUnmuted(0)));
+    assert!(events.contains(&AudioEvent::LayerSoloed(0)));
+    assert!(events.contains(&AudioEvent::VolumeChanged(0, 0.5)));
+}
+
+#[test]
+fn test_overdub_keeps_length_and_undo_restores() {
+    let harness = Harness::new();
+    let take = ramp(8 * BLOCK, 0.25);
+    harness.record(0, &take);
+    harness.send(LayerCommand::StopPlaying(0));
+    harness.send(LayerCommand::Play(0));
+
+    // One full pass of a constant overdub, from the loop top
+    harness.send(LayerCommand::Overdub(0));
+    harness.process(&vec![0.125; take.len()]);
+    harness.send(LayerCommand::StopRecording(0));
+
+    let overdubbed = harness.layer_audio(0);
+    let expected: Vec<f32> = take.iter().map(|s| s + 0.125).collect();
+    assert_close(&overdubbed, &expected);
+    assert_eq!(harness.engine.get_master_loop_length(), Some(take.len()));
+
+    harness.send(LayerCommand::Undo(0));
+    assert_eq!(harness.layer_audio(0), take);
+    harness.send(LayerCommand::Redo(0));
+    assert_close(&harness.layer_audio(0), &expected);
+}
+
+#[test]
+fn test_refused_commands() {
+    let harness = Harness::new();
+    assert_eq!(
+        harness.engine.send_command(LayerCommand::Record(9)),
+        Err(LooperError::InvalidLayer(9))
+    );
+    // Queued stops run on the audio thread, so a bad id must not get that far
+    assert_eq!(
+        harness.engine.send_command(LayerCommand::SyncStop(99)),
+        Err(LooperError::InvalidLayer(99))
+    );
+    harness.silence(48_000 * 3);
+    assert_eq!(
+        harness.engine.send_command(LayerCommand::Play(0)),
+        Err(LooperError::EmptyLayer(0))
+    );
+    assert!(matches!(
+        harness.engine.send_command(LayerCommand::Overdub(0)),
+        Err(LooperError::InvalidTransition { layer_id: 0, .. })
+    ));
+
+    // Locked layers refuse to be cleared
+    harness.record(2, &ramp(4 * BLOCK, 0.5));
+    harness.send(LayerCommand::ToggleLock(2));
+    assert!(harness.engine.send_command(LayerCommand::Clear(2)).is_err());
+    assert_eq!(harness.layer_audio(2).len(), 4 * BLOCK);
+    // Held elsewhere (a save, the parking thread) it can't be checked, so it's refused
+    let layer = harness.engine.get_layer(2).unwrap();
+    let guard = layer.lock().unwrap();
+    assert_eq!(
+        harness.engine.send_command(LayerCommand::Clear(2)),
+        Err(LooperError::Busy("Layer"))
+    );
+    assert_eq!(
+        harness.engine.send_command(LayerCommand::SetVolume(2, 0.5)),
+        Err(LooperError::Busy("Layer"))
+    );
+    // Nor can an export tell whether it's mid-take
+    assert_eq!(
+        harness.engine.send_command(LayerCommand::ExportWav(
+            "unused.wav".into(),
+            Default::default()
+        )),
+        Err(LooperError::Busy("Layer"))
+    );
+    drop(guard);
+    // Nor can its audio be changed through the undo history
+    for command in [
+        LayerCommand::Undo(2),
+        LayerCommand::Redo(2),
+        LayerCommand::GlobalUndo,
+    ] {
+        assert_eq!(
+            harness.engine.send_command(command),
+            Err(LooperError::LayerLocked(2))
+        );
+    }
+    assert_eq!(harness.layer_audio(2).len(), 4 * BLOCK);
+
+    harness.send(LayerCommand::ToggleLock(2));
+    harness.send(LayerCommand::Clear(2));
+    assert!(harness.layer_audio(2).is_empty());
+    assert!(
+        harness
+            .events()
+            .contains(&AudioEvent::LayerCleared(2, 4 * BLOCK))
+    );
+}
+
+#[test]
+fn test_global_undo_refuses_busy_layers() {
+    let harness = Harness::new();
+    let take = ramp(4 * BLOCK, 0.5);
+    harness.record(0, &take);
+    harness.record(1, &take);
+    // Fold the takes into the log
+    harness.send(LayerCommand::GlobalUndo);
+    harness.send(LayerCommand::GlobalRedo);
+    assert_eq!(harness.layer_audio(1), take);
+
+    // A layer held elsewhere refuses the whole transaction and leaves it undoable
+    let layer = harness.engine.get_layer(1).unwrap();
+    let guard = layer.lock().unwrap();
+    assert_eq!(
+        harness.engine.send_command(LayerCommand::GlobalUndo),
+        Err(LooperError::Busy("Layer"))
+    );
+    drop(guard);
+    assert_eq!(harness.layer_audio(1), take);
+
+    harness.send(LayerCommand::GlobalUndo);
+    assert_ne!(harness.layer_audio(1), take);
+    harness.send(LayerCommand::GlobalRedo);
+    assert_eq!(harness.layer_audio(1), take);
+}
+
+#[test]
+fn test_loop_pack_trims_each_layer_to_its_loop() {
+    let harness = Harness::new();
+    harness.record(1, &ramp(8 * BLOCK, 0.5));
+    let directory = std::env::temp_dir().join(format!("soundlooper_pack_{}", std::process::id()));
+    harness.send(LayerCommand::ExportLoopPack(
+        directory.to_string_lossy().to_string(),
+    ));
+
+    // Written on a background thread
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+    let exported = loop {
+        let events = harness.events();
+        if let Some(event) = events
+            .into_iter()
+            .find(|e| matches!(e, AudioEvent::LoopPackExported(..)))
+        {
+            break event;
+        }
+        assert!(std::time::Instant::now() < deadline, "no LoopPackExported");
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    };
+    assert!(matches!(exported, AudioEvent::LoopPackExported(_, 1)));
+
+    let files: Vec<_> = std::fs::read_dir(&directory)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    assert_eq!(files.len(), 1);
+    let name = files[0].file_name().unwrap().to_string_lossy().to_string();
+    assert!(name.starts_with("session_layer-2_"), "{}", name);
+    assert!(name.ends_with("bpm.wav"), "{}", name);
+    let reader = hound::WavReader::open(&files[0]).unwrap();
+    assert_eq!(reader.duration() as usize, 8 * BLOCK);
+    let _ = std::fs::remove_dir_all(&directory);
+}
+
+#[test]
+fn test_silent_input_warns_once() {
+    let harness = Harness::new();
+    harness.send(LayerCommand::Record(0));
+    harness.silence(48_000 * 3 / 2);
+    let warnings = |events: Vec<AudioEvent>| {
+        events
+            .iter()
+            .filter(|e| **e == AudioEvent::SilentInput(0))
+            .count()
+    };
+    assert_eq!(warnings(harness.events()), 1);
+
+    // Signal clears it; a real take never warns
+    harness.send(LayerCommand::StopRecording(0));
+    harness.record(1, &ramp(48_000 * 2, 0.5));
+    assert_eq!(
+        harness
+            .events()
+            .iter()
+            .filter(|e| matches!(e, AudioEvent::SilentInput(_)))
+            .count(),
+        0
+    );
+}
+
+#[test]
+fn test_background_edits_see_changes_made_meanwhile() {
+    let harness = Harness::new();
+    let take = ramp(4 * BLOCK, 0.5);
+    harness.record(0, &take);
+    let layer = harness.engine.get_layer(0).unwrap();
+
+    // Mix changes leave the audio a job works on as it was
+    let source = layer.lock().unwrap().edit_source();
+    assert_eq!(*source, take);
+    harness.send(LayerCommand::SetVolume(0, 0.5));
+    assert!(layer.lock().unwrap().is_unchanged_since(&source));
+
+    // An overdub that lands meanwhile isn't overwritten
+    harness.send(LayerCommand::Overdub(0));
+    harness.process(&vec![0.125; take.len()]);
+    harness.send(LayerCommand::StopRecording(0));
+    assert!(!layer.lock().unwrap().is_unchanged_since(&source));
+    // Nor is an undo back to audio the job didn't start from
+    let source = layer.lock().unwrap().edit_source();
+    harness.send(LayerCommand::Undo(0));
+    assert!(!layer.lock().unwrap().is_unchanged_since(&source));
+}
+
+#[test]
+fn test_stereo_width_is_clamped() {
+    let harness = Harness::new();
+    assert_eq!(harness.engine.stereo_width(), Some(1.0));
+
+    harness.send(LayerCommand::SetStereoWidth(0.0));
+    assert_eq!(harness.engine.stereo_width(), Some(0.0));
+    harness.send(LayerCommand::SetStereoWidth(4.0));
+    assert_eq!(harness.engine.stereo_width(), Some(1.5));
+    assert!(
+        harness
+            .events()
+            .contains(&AudioEvent::StereoWidthChanged(1.5))
+    );
+}
+
+#[test]
+fn test_parked_layers_play_their_16_bit_copy() {
+    let harness = Harness::new();
+    let take = ramp(4 * BLOCK, 0.5);
+    harness.record(0, &take);
+    harness.send(LayerCommand::StopPlaying(0));
+    let layer = harness.engine.get_layer(0).unwrap();
+
+    // As the parking thread does it: encoded without holding the layer
+    let source = layer.lock().unwrap().park_source().unwrap();
+    let quantized = Arc::new(QuantizedBuffer::encode(&source));
+    assert!(layer.lock().unwrap().park(&source, quantized));
+    drop(source);
+    {
+        let layer = layer.lock().unwrap();
+        assert!(layer.is_parked());
+        assert!(layer.buffer.is_empty());
+        // Only the 16-bit copy is left
+        assert_eq!(layer.undo_history.memory_bytes(), take.len() * 2);
+    }
+    assert_close(&harness.layer_audio(0), &take);
+
+    // Played straight from the 16-bit copy, before the parking thread unparks it
+    harness.send(LayerCommand::Play(0));
+    let output = harness.silence(take.len());
+    assert_close(&output, &take);
+
+    // Mixing without a scratch buffer (the fallback when it's busy) reads it too
+    let mut output = vec![0.0; take.len()];
+    {
+        let mut layer = layer.lock().unwrap();
+        layer.playback_position = 0;
+        layer.mix_next_samples(&mut output, 1.0);
+    }
+    assert_close(&output, &take);
+
+    // An edit brings the audio back at full size
+    harness.send(LayerCommand::SetVolume(0, 0.5));
+    let mut layer = layer.lock().unwrap();
+    let source = layer.edit_source();
+    assert!(!layer.is_parked());
+    assert_close(&source, &take);
+    assert!(layer.is_unchanged_since(&source));
+}
+
+#[test]
+fn test_autosnapshot_compares_without_copying() {
+    let harness = Harness::new();
+    let take = ramp(4 * BLOCK, 0.5);
+    harness.record(0, &take);
+    let layer = harness.engine.get_layer(0).unwrap();
+    let mut layer = layer.lock().unwrap();
+
+    // A change that wasn't checkpointed is saved sharing the audio
+    layer.volume = 0.5;
+    let source = layer.checkpoint_source().unwrap();
+    assert_eq!(layer.audio_matches(&source, 0..BLOCK), Some(true));
+    assert!(layer.checkpoint_unchanged(&source));
+    assert!(Arc::ptr_eq(&layer.checkpoint_source().unwrap(), &source));
+
+    // Audio changed behind the history's back is found a range at a time
+    layer.buffer[2 * BLOCK] = 0.0;
+    assert_eq!(layer.audio_matches(&source, 0..BLOCK), Some(true));
+    assert_eq!(
+        layer.audio_matches(&source, 2 * BLOCK..3 * BLOCK),
+        Some(false)
+    );
+    // Once it's been checkpointed, the old audio is no longer the one to match
+    layer.checkpoint();
+    assert_eq!(layer.audio_matches(&source, 0..BLOCK), None);
+    assert!(!layer.checkpoint_unchanged(&source));
+}